@@ -0,0 +1,70 @@
+//! Astronomical time computations.
+//!
+//! These functions use UTC in place of UT1, which differs from it by less
+//! than a second, and are accurate to about 0.1 seconds of time within a
+//! few centuries of 2000.
+
+use div_mod::div_mod;
+use tick;
+use instant::Instant;
+
+/// Get the number of days since the J2000.0 epoch, noon on January 1,
+/// 2000, split into whole days and a fraction to preserve precision.
+fn j2000_days(t: Instant) -> (i64, f64) {
+    let (day, rem) = div_mod(t.ticks() - 12 * tick::HOUR, tick::DAY);
+    (day, rem as f64 / tick::DAY as f64)
+}
+
+/// Reduce an angle in hours to the range 0 to 24.
+fn normalize_hours(h: f64) -> f64 {
+    let h = h % 24.0;
+    if h < 0.0 { h + 24.0 } else { h }
+}
+
+/// Get the Greenwich mean sidereal time, in hours from 0 to 24.
+pub fn gmst_hours(t: Instant) -> f64 {
+    let (day, frac) = j2000_days(t);
+    let d = day as f64 + frac;
+    let c = d / 36525.0;
+    // The whole days contribute whole multiples of 24 hours, which are
+    // dropped before they can swamp the fraction.
+    normalize_hours(18.697374558 + 24.0 * frac + 0.06570982441908 * d +
+                    0.000026 * c * c)
+}
+
+/// Get the Greenwich apparent sidereal time, in hours from 0 to 24.  This
+/// is the mean sidereal time corrected by the equation of the equinoxes,
+/// using the two largest terms of the nutation in longitude.
+pub fn gast_hours(t: Instant) -> f64 {
+    let (day, frac) = j2000_days(t);
+    let d = day as f64 + frac;
+    let omega = (125.04 - 0.052954 * d).to_radians();
+    let l = (280.47 + 0.98565 * d).to_radians();
+    let epsilon = (23.4393 - 0.0000004 * d).to_radians();
+    let dpsi = -0.000319 * omega.sin() - 0.000024 * (2.0 * l).sin();
+    normalize_hours(gmst_hours(t) + dpsi * epsilon.cos())
+}
+
+/// Convert an angle in hours to degrees.
+pub fn hours_to_degrees(h: f64) -> f64 {
+    h * 15.0
+}
+
+#[test]
+fn sidereal() {
+    fn close(x: f64, y: f64) -> bool {
+        (x - y).abs() < 1e-9
+    }
+    let noon = Instant::from_ticks(12 * tick::HOUR);
+    assert!(close(gmst_hours(noon), 18.697374558));
+    let next = Instant::from_ticks(36 * tick::HOUR);
+    assert!(close(gmst_hours(next), 18.697374558 + 0.06570982441908));
+    let prev = Instant::from_ticks(-12 * tick::HOUR);
+    assert!(close(gmst_hours(prev), 18.697374558 - 0.06570982441908));
+    // Half a sidereal day after noon, the sidereal time has advanced by
+    // twelve hours.
+    let half = Instant::from_ticks(12 * tick::HOUR + 430820452654);
+    assert!((gmst_hours(half) - 6.697374558).abs() < 1e-6);
+    assert!((gast_hours(noon) - gmst_hours(noon)).abs() < 0.0003);
+    assert_eq!(hours_to_degrees(6.0), 90.0);
+}
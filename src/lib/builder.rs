@@ -0,0 +1,202 @@
+//! Construct instants from calendar fields.
+
+use tick;
+use calendar_iso8601;
+use instant::Instant;
+
+/// A field of a date and time.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum Field {
+    YearField,
+    MonthField,
+    DayField,
+    HourField,
+    MinuteField,
+    SecondField,
+    SubsecondField,
+    OffsetField
+}
+
+/// The reason a DateTimeBuilder could not build an instant.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum BuildError {
+    /// A required field was not set.
+    MissingField(Field),
+    /// A field was set to a value outside its valid range.
+    InvalidField(Field),
+    /// The fields are valid, but the result cannot be represented.
+    OutOfRange
+}
+
+/// Builds an instant from calendar fields in the ISO 8601 calendar, one
+/// field at a time.  The year is required.  The month and day default to
+/// 1, the time of day defaults to midnight, and the UTC offset defaults to
+/// zero.
+///
+/// ```ignore
+/// let t = DateTimeBuilder::new().year(2024).month(5).day(12)
+///     .hour(9).offset_minutes(120).build();
+/// ```
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct DateTimeBuilder {
+    year: Option<int>,
+    month: int,
+    day: int,
+    hour: int,
+    minute: int,
+    second: int,
+    subsec_ticks: i64,
+    offset_minutes: int
+}
+
+impl DateTimeBuilder {
+    /// Create a builder with no year and all other fields set to their
+    /// defaults.
+    pub fn new() -> DateTimeBuilder {
+        DateTimeBuilder {
+            year: None,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            subsec_ticks: 0,
+            offset_minutes: 0
+        }
+    }
+
+    /// Set the year, using astronomical year numbering.
+    pub fn year(self, year: int) -> DateTimeBuilder {
+        DateTimeBuilder { year: Some(year), ..self }
+    }
+
+    /// Set the month, from 1 to 12.
+    pub fn month(self, month: int) -> DateTimeBuilder {
+        DateTimeBuilder { month: month, ..self }
+    }
+
+    /// Set the day of the month, starting from 1.
+    pub fn day(self, day: int) -> DateTimeBuilder {
+        DateTimeBuilder { day: day, ..self }
+    }
+
+    /// Set the hour, from 0 to 23.
+    pub fn hour(self, hour: int) -> DateTimeBuilder {
+        DateTimeBuilder { hour: hour, ..self }
+    }
+
+    /// Set the minute, from 0 to 59.
+    pub fn minute(self, minute: int) -> DateTimeBuilder {
+        DateTimeBuilder { minute: minute, ..self }
+    }
+
+    /// Set the second, from 0 to 59.
+    pub fn second(self, second: int) -> DateTimeBuilder {
+        DateTimeBuilder { second: second, ..self }
+    }
+
+    /// Set the number of ticks after the second.
+    pub fn subsec_ticks(self, ticks: i64) -> DateTimeBuilder {
+        DateTimeBuilder { subsec_ticks: ticks, ..self }
+    }
+
+    /// Set the offset from UTC, in minutes, which is positive east of
+    /// Greenwich.  The fields are local time at this offset.
+    pub fn offset_minutes(self, offset: int) -> DateTimeBuilder {
+        DateTimeBuilder { offset_minutes: offset, ..self }
+    }
+
+    /// Validate the fields and build an instant.
+    pub fn build(&self) -> Result<Instant, BuildError> {
+        let year = match self.year {
+            Some(y) => y,
+            None => return Err(MissingField(YearField))
+        };
+        if year < -calendar_iso8601::MAX_YEAR ||
+            year > calendar_iso8601::MAX_YEAR {
+            return Err(OutOfRange);
+        }
+        if self.month < 1 || self.month > 12 {
+            return Err(InvalidField(MonthField));
+        }
+        if self.day < 1 ||
+            self.day > calendar_iso8601::days_in_month(year, self.month) {
+            return Err(InvalidField(DayField));
+        }
+        if self.hour < 0 || self.hour > 23 {
+            return Err(InvalidField(HourField));
+        }
+        if self.minute < 0 || self.minute > 59 {
+            return Err(InvalidField(MinuteField));
+        }
+        if self.second < 0 || self.second > 59 {
+            return Err(InvalidField(SecondField));
+        }
+        if self.subsec_ticks < 0 || self.subsec_ticks >= tick::SECOND {
+            return Err(InvalidField(SubsecondField));
+        }
+        if self.offset_minutes <= -24 * 60 || self.offset_minutes >= 24 * 60 {
+            return Err(InvalidField(OffsetField));
+        }
+        let cjd = calendar_iso8601::to_cjd(year, self.month, self.day);
+        let time = self.hour as i64 * tick::HOUR +
+            self.minute as i64 * tick::MINUTE +
+            self.second as i64 * tick::SECOND +
+            self.subsec_ticks -
+            self.offset_minutes as i64 * tick::MINUTE;
+        match (cjd as i64).checked_mul(&tick::DAY)
+                          .and_then(|n| n.checked_add(&time)) {
+            Some(n) => Ok(Instant { ticks: n }),
+            None => Err(OutOfRange)
+        }
+    }
+}
+
+#[test]
+fn build() {
+    fn test(b: DateTimeBuilder, ticks: i64) {
+        match b.build() {
+            Ok(x) => assert_eq!(x.ticks, ticks),
+            Err(e) => fail!("builder: {}, error: {}", b, e)
+        }
+    }
+
+    let y2k = DateTimeBuilder::new().year(2000);
+    test(y2k, 0);
+    test(y2k.day(2), tick::DAY);
+    test(y2k.hour(9).minute(30).second(15).subsec_ticks(5),
+         9 * tick::HOUR + 30 * tick::MINUTE + 15 * tick::SECOND + 5);
+    test(y2k.hour(2).offset_minutes(120), 0);
+    test(y2k.offset_minutes(-60), tick::HOUR);
+    test(DateTimeBuilder::new().year(1999).month(12).day(31).hour(23),
+         -tick::HOUR);
+    test(DateTimeBuilder::new().year(2024).month(2).day(29),
+         tick::DAY * calendar_iso8601::to_cjd(2024, 2, 29) as i64);
+}
+
+#[test]
+fn build_fail() {
+    fn test(b: DateTimeBuilder, err: BuildError) {
+        match b.build() {
+            Ok(x) => fail!("builder: {}, output: {}", b, x),
+            Err(e) => assert_eq!(e, err)
+        }
+    }
+
+    let y2k = DateTimeBuilder::new().year(2000);
+    test(DateTimeBuilder::new(), MissingField(YearField));
+    test(DateTimeBuilder::new().month(13), MissingField(YearField));
+    test(y2k.month(0), InvalidField(MonthField));
+    test(y2k.month(13), InvalidField(MonthField));
+    test(y2k.day(0), InvalidField(DayField));
+    test(y2k.month(4).day(31), InvalidField(DayField));
+    test(y2k.year(2001).month(2).day(29), InvalidField(DayField));
+    test(y2k.hour(24), InvalidField(HourField));
+    test(y2k.minute(-1), InvalidField(MinuteField));
+    test(y2k.second(60), InvalidField(SecondField));
+    test(y2k.subsec_ticks(tick::SECOND), InvalidField(SubsecondField));
+    test(y2k.offset_minutes(24 * 60), InvalidField(OffsetField));
+    test(y2k.year(40000), OutOfRange);
+    test(y2k.year(-30000), OutOfRange);
+    test(y2k.year(1 << 40), OutOfRange);
+}
@@ -0,0 +1,603 @@
+//! Business days, holiday calendars, and market tenors.
+//!
+//! A holiday calendar says which days are business days: those which are
+//! neither on the weekend nor holidays.  Roll conventions move a date which
+//! is not a business day to a nearby business day, and tenors such as "ON"
+//! or "3M" give the length of a deposit, swap, or other instrument, so
+//! that maturity dates can be computed with `Date::add_tenor`.  IMM dates,
+//! the third Wednesday of March, June, September, and December, are the
+//! quarterly dates used by futures and many swaps.
+//!
+//! Holidays can be loaded from text files maintained outside the program,
+//! such as exchange calendars.  Each line has a date in the form
+//! "YYYY-MM-DD", optionally followed by a comma, semicolon, tab, or space
+//! and the name of the holiday, as in "2014-12-25,Christmas Day".  Names
+//! may be in double quotes, as in CSV files.  Blank lines and lines
+//! starting with "#" are ignored, and so is a first line which does not
+//! start with a digit, such as a CSV header.
+
+use std::from_str::FromStr;
+use std::io::{Reader, IoResult, IoError, InvalidInput, File};
+use std::num;
+use std::str;
+use calendar_iso8601;
+use format_iso8601::{Date, YearMonthDay, Weekday, Wednesday, Saturday,
+                     Sunday};
+use format_iso8601::date_to_cjd;
+use parseutil;
+use period::Period;
+
+/// The days of the week which are not business days, and a list of
+/// holidays.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct HolidayCalendar {
+    /// Bit n is set if weekday n is on the weekend.
+    weekend: u8,
+    /// The chronological Julian days and names of the holidays, in order.
+    holidays: Vec<(int, String)>
+}
+
+impl HolidayCalendar {
+    /// Create a calendar with Saturday and Sunday as the weekend and no
+    /// holidays.
+    pub fn new() -> HolidayCalendar {
+        HolidayCalendar::with_weekend([Saturday, Sunday])
+    }
+
+    /// Create a calendar with the given weekend and no holidays.  If every
+    /// day is on the weekend, there are no business days, and every
+    /// search for one fails.
+    pub fn with_weekend(weekend: &[Weekday]) -> HolidayCalendar {
+        let mask = weekend.iter().fold(0u8, |m, &w| m | 1 << (w as uint));
+        HolidayCalendar { weekend: mask, holidays: Vec::new() }
+    }
+
+    /// Add a holiday without a name.  Returns false for reduced precision
+    /// dates and for dates which do not exist.
+    pub fn add_holiday(&mut self, date: &Date) -> bool {
+        self.add_named_holiday(date, "")
+    }
+
+    /// Add a holiday with a name, such as "Christmas Day".  If the date is
+    /// already a holiday, its name is replaced.  Returns false for reduced
+    /// precision dates and for dates which do not exist.
+    pub fn add_named_holiday(&mut self, date: &Date, name: &str) -> bool {
+        let cjd = match date_to_cjd(date) {
+            Some(x) => x, None => return false
+        };
+        let name = String::from_str(name);
+        match self.find_holiday(cjd) {
+            Some(i) => *self.holidays.get_mut(i) = (cjd, name),
+            None => {
+                let i = self.holidays.iter().position(|&(h, _)| h > cjd)
+                    .unwrap_or(self.holidays.len());
+                self.holidays.insert(i, (cjd, name));
+            }
+        }
+        true
+    }
+
+    /// Get the name of a holiday, which is empty if it was added without
+    /// one.  Returns None if the date is not a holiday.
+    pub fn holiday_name<'a>(&'a self, date: &Date) -> Option<&'a str> {
+        date_to_cjd(date)
+            .and_then(|cjd| self.find_holiday(cjd))
+            .map(|i| {
+                let (_, ref name) = *self.holidays.get(i);
+                name.as_slice()
+            })
+    }
+
+    /// Read holidays from text in the format described in the module
+    /// documentation and add them to the calendar.  Returns an error of
+    /// kind InvalidInput, giving the line number, if a line is invalid.
+    /// Holidays on lines before the invalid line are still added.
+    pub fn read_holidays<R: Reader>(&mut self, reader: &mut R)
+                                    -> IoResult<()> {
+        let text = try!(reader.read_to_string());
+        let mut first = true;
+        for (n, line) in text.as_slice().lines_any().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("#") {
+                continue;
+            }
+            let c = line.as_bytes()[0];
+            let header = first && (c < '0' as u8 || c > '9' as u8);
+            first = false;
+            if header {
+                continue;
+            }
+            match read_holiday_line(line) {
+                Some((date, name)) => {
+                    self.add_named_holiday(&date, name.as_slice());
+                }
+                None => return Err(IoError {
+                    kind: InvalidInput,
+                    desc: "invalid holiday",
+                    detail: Some(format!("line {}", n + 1))
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a calendar from a file in the format described in the module
+    /// documentation, with Saturday and Sunday as the weekend.
+    pub fn load_file(path: &Path) -> IoResult<HolidayCalendar> {
+        let mut calendar = HolidayCalendar::new();
+        try!(calendar.read_holidays(&mut try!(File::open(path))));
+        Ok(calendar)
+    }
+
+    /// Test whether a complete date is a business day.  Returns false for
+    /// reduced precision dates and for dates which do not exist.
+    pub fn is_business_day(&self, date: &Date) -> bool {
+        match date_to_cjd(date) {
+            Some(cjd) => self.is_business_cjd(cjd),
+            None => false
+        }
+    }
+
+    /// Move a complete date by a number of business days, forwards if n
+    /// is positive and backwards if it is negative.  The date need not be
+    /// a business day itself, and is returned unchanged if n is zero.
+    /// Returns None for reduced precision dates, for dates which do not
+    /// exist, and if there are no business days.
+    pub fn add_business_days(&self, date: &Date, n: int) -> Option<Date> {
+        let mut cjd = match date_to_cjd(date) {
+            Some(x) => x, None => return None
+        };
+        let step = if n < 0 { -1 } else { 1 };
+        for _ in range(0, num::abs(n)) {
+            cjd = match self.find_business_cjd(cjd + step, step) {
+                Some(x) => x, None => return None
+            };
+        }
+        Some(cjd_to_date(cjd))
+    }
+
+    fn is_business_cjd(&self, cjd: int) -> bool {
+        let w = calendar_iso8601::weekday(cjd);
+        self.weekend & 1 << (w as uint) == 0 &&
+            self.find_holiday(cjd).is_none()
+    }
+
+    /// Find the index of a holiday by its chronological Julian day.
+    fn find_holiday(&self, cjd: int) -> Option<uint> {
+        self.holidays.as_slice().bsearch(|&(h, _)| h.cmp(&cjd))
+    }
+
+    /// Find the first business day starting at a chronological Julian day
+    /// and moving in the direction of step, which is 1 or -1.
+    fn find_business_cjd(&self, cjd: int, step: int) -> Option<int> {
+        if self.weekend & 0xfe == 0xfe {
+            return None;
+        }
+        let mut cjd = cjd;
+        while !self.is_business_cjd(cjd) {
+            cjd += step;
+        }
+        Some(cjd)
+    }
+}
+
+/// Read a line giving a holiday.  Returns the date and the name.
+fn read_holiday_line(line: &str) -> Option<(Date, String)> {
+    let (date, rem) = match parseutil::read_calendar_date(line.as_bytes()) {
+        Some(x) => x, None => return None
+    };
+    let rem = str::from_utf8(rem).unwrap();
+    if rem.is_empty() {
+        return Some((date, String::new()));
+    }
+    match rem.char_at(0) {
+        ',' | ';' | ' ' | '\t' => (),
+        _ => return None
+    }
+    let name = rem.slice_from(1).trim();
+    let name = if name.len() >= 2 && name.starts_with("\"") &&
+        name.ends_with("\"") {
+        name.slice(1, name.len() - 1).replace("\"\"", "\"")
+    } else {
+        String::from_str(name)
+    };
+    Some((date, name))
+}
+
+fn cjd_to_date(cjd: int) -> Date {
+    let (y, m, d) = calendar_iso8601::from_cjd(cjd);
+    YearMonthDay(y, m, d)
+}
+
+/// Test whether two chronological Julian days are in the same month.
+fn same_month(a: int, b: int) -> bool {
+    let (ay, am, _) = calendar_iso8601::from_cjd(a);
+    let (by, bm, _) = calendar_iso8601::from_cjd(b);
+    ay == by && am == bm
+}
+
+/// Conventions for moving a date which is not a business day.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum RollConvention {
+    /// Keep the date, even if it is not a business day.
+    Unadjusted,
+    /// Use the next business day.
+    Following,
+    /// Use the next business day, unless it is in the next month, in which
+    /// case use the previous business day.
+    ModifiedFollowing,
+    /// Use the previous business day.
+    Preceding,
+    /// Use the previous business day, unless it is in the previous month,
+    /// in which case use the next business day.
+    ModifiedPreceding
+}
+
+impl RollConvention {
+    /// Adjust a complete date to a business day in the calendar.  The
+    /// result is a calendar date.  Returns None for reduced precision
+    /// dates, for dates which do not exist, and if there are no business
+    /// days.
+    pub fn adjust(&self, date: &Date, calendar: &HolidayCalendar)
+                  -> Option<Date> {
+        let cjd = match date_to_cjd(date) {
+            Some(x) => x, None => return None
+        };
+        let (step, modified) = match *self {
+            Unadjusted => return Some(cjd_to_date(cjd)),
+            Following => (1, false),
+            ModifiedFollowing => (1, true),
+            Preceding => (-1, false),
+            ModifiedPreceding => (-1, true)
+        };
+        let result = match calendar.find_business_cjd(cjd, step) {
+            Some(x) if modified && !same_month(x, cjd) =>
+                calendar.find_business_cjd(cjd, -step),
+            r => r
+        };
+        result.map(|x| cjd_to_date(x))
+    }
+}
+
+/// Get the IMM date in a month, which is its third Wednesday.  Returns None
+/// unless the month is March, June, September, or December.
+pub fn imm_date(year: int, month: int) -> Option<Date> {
+    if month % 3 != 0 {
+        return None;
+    }
+    Date::nth_weekday(year, month, Wednesday, 3)
+}
+
+/// Get the first IMM date after a complete date.  Returns None for reduced
+/// precision dates and for dates which do not exist.
+pub fn next_imm_date(after: &Date) -> Option<Date> {
+    let cjd = match date_to_cjd(after) {
+        Some(x) => x, None => return None
+    };
+    let (y, m, _) = calendar_iso8601::from_cjd(cjd);
+    let month = (m + 2) / 3 * 3;
+    let date = imm_date(y, month).unwrap();
+    if date_to_cjd(&date).unwrap() > cjd {
+        Some(date)
+    } else if month == 12 {
+        imm_date(y + 1, 3)
+    } else {
+        imm_date(y, month + 3)
+    }
+}
+
+/// The number of business days from the trade date to the spot date,
+/// which is where "SN" starts.  This is the usual lag for deposits and
+/// foreign exchange, though a few currencies settle sooner.
+pub static SPOT_DAYS: int = 2;
+
+/// The length of a money market or swap instrument, as quoted in the
+/// market.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum Tenor {
+    /// "ON", one business day starting on the trade date.
+    Overnight,
+    /// "TN", one business day starting on the next business day.
+    TomorrowNext,
+    /// "SN", one business day starting on the spot date, `SPOT_DAYS`
+    /// business days after the trade date.
+    SpotNext,
+    /// A number of days, weeks, months, or years, such as "3M".  Weeks are
+    /// seven days.
+    Term(Period)
+}
+
+impl Tenor {
+    /// Parse a tenor, which is "ON", "TN", "SN", or a number followed by
+    /// "D", "W", "M", or "Y", as in "1W", "3M", or "10Y".  Letters are
+    /// uppercase.
+    pub fn parse(s: &str) -> Option<Tenor> {
+        match s {
+            "ON" => return Some(Overnight),
+            "TN" => return Some(TomorrowNext),
+            "SN" => return Some(SpotNext),
+            _ => ()
+        }
+        if s.len() < 2 {
+            return None;
+        }
+        let unit = s.as_bytes()[s.len() - 1] as char;
+        let digits = match unit {
+            'D' | 'W' | 'M' | 'Y' => s.slice_to(s.len() - 1),
+            _ => return None
+        };
+        if !digits.bytes().all(|c| c >= '0' as u8 && c <= '9' as u8) {
+            return None;
+        }
+        let n: int = match from_str(digits) {
+            Some(n) => n, None => return None
+        };
+        let period = match unit {
+            'D' => Some(Period::new(0, 0, n)),
+            'W' => n.checked_mul(&7).map(|d| Period::new(0, 0, d)),
+            'M' => Some(Period::new(0, n, 0)),
+            _ => Some(Period::new(n, 0, 0))
+        };
+        period.map(|p| Term(p))
+    }
+
+    /// Get the period of a tenor given in days, weeks, months, or years.
+    /// Returns None for tenors given in business days.
+    pub fn to_period(&self) -> Option<Period> {
+        match *self {
+            Term(p) => Some(p),
+            _ => None
+        }
+    }
+}
+
+impl FromStr for Tenor {
+    fn from_str(s: &str) -> Option<Tenor> {
+        Tenor::parse(s)
+    }
+}
+
+#[test]
+fn test_business_days() {
+    use format_iso8601::{Year, YearDay, YearWeekDay, Monday, Tuesday,
+                         Wednesday, Thursday, Friday};
+    let mut cal = HolidayCalendar::new();
+    assert!(cal.add_holiday(&YearMonthDay(2014, 6, 13)));
+    assert!(cal.add_holiday(&YearMonthDay(2014, 6, 13)));
+    assert!(cal.add_holiday(&YearMonthDay(2014, 6, 11)));
+    assert!(!cal.add_holiday(&YearMonthDay(2014, 2, 30)));
+    assert!(!cal.add_holiday(&Year(2014)));
+    assert!(!cal.add_holiday(&YearMonthDay(2014, 0, 13)));
+    assert!(!cal.add_holiday(&YearMonthDay(2014, 13, 13)));
+    assert!(!cal.add_holiday(&YearMonthDay(2014, 6, 0)));
+    assert!(!cal.add_holiday(&YearDay(2014, 0)));
+    assert!(!cal.add_holiday(&YearWeekDay(2014, 0, 1)));
+    assert!(!cal.add_holiday(&YearWeekDay(2014, 1, 8)));
+    assert!(!cal.is_business_day(&YearMonthDay(2014, 13, 1)));
+    assert_eq!(Following.adjust(&YearMonthDay(2014, 0, 1), &cal), None);
+    assert_eq!(next_imm_date(&YearMonthDay(2014, 13, 1)), None);
+    assert!(cal.is_business_day(&YearMonthDay(2014, 6, 10)));
+    assert!(!cal.is_business_day(&YearMonthDay(2014, 6, 11)));
+    assert!(!cal.is_business_day(&YearMonthDay(2014, 6, 13)));
+    assert!(!cal.is_business_day(&YearMonthDay(2014, 6, 14)));
+    assert!(!cal.is_business_day(&Year(2014)));
+
+    fn test(cal: &HolidayCalendar, date: Date, n: int, expected: Date) {
+        let out = cal.add_business_days(&date, n);
+        if out != Some(expected) {
+            fail!("input: {} {}, expected: {}, output: {}",
+                  date, n, expected, out);
+        }
+    }
+    test(&cal, YearMonthDay(2014, 6, 10), 1, YearMonthDay(2014, 6, 12));
+    test(&cal, YearMonthDay(2014, 6, 10), 2, YearMonthDay(2014, 6, 16));
+    test(&cal, YearMonthDay(2014, 6, 16), -2, YearMonthDay(2014, 6, 10));
+    test(&cal, YearMonthDay(2014, 6, 14), 1, YearMonthDay(2014, 6, 16));
+    test(&cal, YearMonthDay(2014, 6, 14), -1, YearMonthDay(2014, 6, 12));
+    test(&cal, YearMonthDay(2014, 6, 14), 0, YearMonthDay(2014, 6, 14));
+
+    let never = HolidayCalendar::with_weekend([Monday, Tuesday, Wednesday,
+                                               Thursday, Friday, Saturday,
+                                               Sunday]);
+    assert_eq!(never.add_business_days(&YearMonthDay(2014, 6, 10), 1),
+               None);
+    let friday = HolidayCalendar::with_weekend([Friday]);
+    test(&friday, YearMonthDay(2014, 6, 12), 1, YearMonthDay(2014, 6, 14));
+}
+
+#[test]
+fn test_roll() {
+    use format_iso8601::{YearMonth, YearDay};
+    let mut cal = HolidayCalendar::new();
+    cal.add_holiday(&YearMonthDay(2014, 6, 13));
+    fn test(cal: &HolidayCalendar, roll: RollConvention, date: Date,
+            expected: Date) {
+        let out = roll.adjust(&date, cal);
+        if out != Some(expected) {
+            fail!("input: {} {}, expected: {}, output: {}",
+                  roll, date, expected, out);
+        }
+    }
+    // Saturday, the last day of May.
+    let sat = YearMonthDay(2014, 5, 31);
+    test(&cal, Unadjusted, sat, sat);
+    test(&cal, Following, sat, YearMonthDay(2014, 6, 2));
+    test(&cal, ModifiedFollowing, sat, YearMonthDay(2014, 5, 30));
+    test(&cal, Preceding, sat, YearMonthDay(2014, 5, 30));
+    test(&cal, ModifiedPreceding, sat, YearMonthDay(2014, 5, 30));
+    // Sunday, the first day of June.
+    let sun = YearMonthDay(2014, 6, 1);
+    test(&cal, Following, sun, YearMonthDay(2014, 6, 2));
+    test(&cal, ModifiedFollowing, sun, YearMonthDay(2014, 6, 2));
+    test(&cal, Preceding, sun, YearMonthDay(2014, 5, 30));
+    test(&cal, ModifiedPreceding, sun, YearMonthDay(2014, 6, 2));
+    // A Friday holiday.
+    test(&cal, Following, YearMonthDay(2014, 6, 13),
+         YearMonthDay(2014, 6, 16));
+    test(&cal, Preceding, YearMonthDay(2014, 6, 13),
+         YearMonthDay(2014, 6, 12));
+    test(&cal, Following, YearDay(2014, 161), YearMonthDay(2014, 6, 10));
+    assert_eq!(Following.adjust(&YearMonth(2014, 6), &cal), None);
+}
+
+#[test]
+fn test_parse_tenor() {
+    fn test(s: &str, expected: Option<Tenor>) {
+        let out = Tenor::parse(s);
+        if out != expected {
+            fail!("input: {}, expected: {}, output: {}", s, expected, out);
+        }
+    }
+    test("ON", Some(Overnight));
+    test("TN", Some(TomorrowNext));
+    test("SN", Some(SpotNext));
+    test("2D", Some(Term(Period::new(0, 0, 2))));
+    test("1W", Some(Term(Period::new(0, 0, 7))));
+    test("3M", Some(Term(Period::new(0, 3, 0))));
+    test("18M", Some(Term(Period::new(0, 18, 0))));
+    test("10Y", Some(Term(Period::new(10, 0, 0))));
+    test("", None);
+    test("M", None);
+    test("3", None);
+    test("3m", None);
+    test("on", None);
+    test("3X", None);
+    test("3\u00e9", None);
+    test("-3M", None);
+    test("+3M", None);
+    test("1Y6M", None);
+    test("99999999999999999999Y", None);
+    assert_eq!(Overnight.to_period(), None);
+    assert_eq!(Term(Period::new(0, 3, 0)).to_period(),
+               Some(Period::new(0, 3, 0)));
+}
+
+#[test]
+fn test_add_tenor() {
+    let mut cal = HolidayCalendar::new();
+    cal.add_holiday(&YearMonthDay(2014, 6, 13));
+    fn test(cal: &HolidayCalendar, date: Date, tenor: &str,
+            roll: RollConvention, expected: Option<Date>) {
+        let out = date.add_tenor(&Tenor::parse(tenor).unwrap(), roll, cal);
+        if out != expected {
+            fail!("input: {} {} {}, expected: {}, output: {}",
+                  date, tenor, roll, expected, out);
+        }
+    }
+    let d = YearMonthDay(2014, 6, 10);
+    test(&cal, d, "1W", Following, Some(YearMonthDay(2014, 6, 17)));
+    test(&cal, d, "3D", Following, Some(YearMonthDay(2014, 6, 16)));
+    test(&cal, d, "3D", Preceding, Some(YearMonthDay(2014, 6, 12)));
+    test(&cal, d, "10Y", Following, Some(YearMonthDay(2024, 6, 10)));
+    // The 13th is a holiday, so the day after the 12th is the 16th.
+    let d = YearMonthDay(2014, 6, 12);
+    test(&cal, d, "ON", Following, Some(YearMonthDay(2014, 6, 16)));
+    test(&cal, d, "TN", Preceding, Some(YearMonthDay(2014, 6, 17)));
+    test(&cal, d, "SN", Following, Some(YearMonthDay(2014, 6, 18)));
+    // A trade date on the weekend starts counting from the next business
+    // day, as add_business_days() does.
+    let d = YearMonthDay(2014, 6, 14);
+    test(&cal, d, "ON", Following, Some(YearMonthDay(2014, 6, 16)));
+    test(&cal, d, "TN", Following, Some(YearMonthDay(2014, 6, 17)));
+    test(&cal, d, "SN", Following, Some(YearMonthDay(2014, 6, 18)));
+    // The end of May is on a weekend.
+    let d = YearMonthDay(2014, 3, 31);
+    test(&cal, d, "2M", Unadjusted, Some(YearMonthDay(2014, 5, 31)));
+    test(&cal, d, "2M", Following, Some(YearMonthDay(2014, 6, 2)));
+    test(&cal, d, "2M", ModifiedFollowing, Some(YearMonthDay(2014, 5, 30)));
+    test(&cal, YearMonthDay(2014, 2, 30), "1M", Following, None);
+    test(&cal, d, "999999999Y", Following, None);
+}
+
+#[test]
+fn test_imm_date() {
+    assert_eq!(imm_date(2014, 3), Some(YearMonthDay(2014, 3, 19)));
+    assert_eq!(imm_date(2014, 6), Some(YearMonthDay(2014, 6, 18)));
+    assert_eq!(imm_date(2014, 9), Some(YearMonthDay(2014, 9, 17)));
+    assert_eq!(imm_date(2014, 12), Some(YearMonthDay(2014, 12, 17)));
+    assert_eq!(imm_date(2015, 3), Some(YearMonthDay(2015, 3, 18)));
+    assert_eq!(imm_date(2014, 4), None);
+    assert_eq!(imm_date(2014, 0), None);
+    assert_eq!(imm_date(2014, 15), None);
+
+    fn test(after: Date, expected: Option<Date>) {
+        let out = next_imm_date(&after);
+        if out != expected {
+            fail!("input: {}, expected: {}, output: {}",
+                  after, expected, out);
+        }
+    }
+    test(YearMonthDay(2014, 6, 10), Some(YearMonthDay(2014, 6, 18)));
+    test(YearMonthDay(2014, 6, 17), Some(YearMonthDay(2014, 6, 18)));
+    test(YearMonthDay(2014, 6, 18), Some(YearMonthDay(2014, 9, 17)));
+    test(YearMonthDay(2014, 4, 1), Some(YearMonthDay(2014, 6, 18)));
+    test(YearMonthDay(2014, 3, 31), Some(YearMonthDay(2014, 6, 18)));
+    test(YearMonthDay(2014, 12, 17), Some(YearMonthDay(2015, 3, 18)));
+    test(YearMonthDay(2014, 12, 31), Some(YearMonthDay(2015, 3, 18)));
+    test(YearMonthDay(2014, 1, 1), Some(YearMonthDay(2014, 3, 19)));
+    test(YearMonthDay(2014, 2, 30), None);
+}
+
+#[test]
+fn test_read_holidays() {
+    use std::io::MemReader;
+    fn read(s: &str) -> IoResult<HolidayCalendar> {
+        let mut cal = HolidayCalendar::new();
+        let mut r = MemReader::new(Vec::from_slice(s.as_bytes()));
+        try!(cal.read_holidays(&mut r));
+        Ok(cal)
+    }
+    let cal = read("Date,Name\n\
+                    # Exchange closures\n\
+                    2014-12-25,Christmas Day\r\n\
+                    \n\
+                    2014-12-26;\"Boxing Day, St. Stephen's\"\n\
+                    2015-01-01\tNew Year's Day\n\
+                    2015-04-03 Good Friday\n\
+                    2015-04-06\n").unwrap();
+    fn name<'a>(cal: &'a HolidayCalendar, date: Date) -> Option<&'a str> {
+        cal.holiday_name(&date)
+    }
+    assert_eq!(name(&cal, YearMonthDay(2014, 12, 25)), Some("Christmas Day"));
+    assert_eq!(name(&cal, YearMonthDay(2014, 12, 26)),
+               Some("Boxing Day, St. Stephen's"));
+    assert_eq!(name(&cal, YearMonthDay(2015, 1, 1)), Some("New Year's Day"));
+    assert_eq!(name(&cal, YearMonthDay(2015, 4, 3)), Some("Good Friday"));
+    assert_eq!(name(&cal, YearMonthDay(2015, 4, 6)), Some(""));
+    assert_eq!(name(&cal, YearMonthDay(2015, 4, 7)), None);
+    assert_eq!(cal.add_business_days(&YearMonthDay(2014, 12, 24), 1),
+               Some(YearMonthDay(2014, 12, 29)));
+
+    let cal = read("\"a \"\"quoted\"\" name\"\n2014-06-10,\"a \"\"b\"\"\"")
+        .unwrap();
+    assert_eq!(cal.holiday_name(&YearMonthDay(2014, 6, 10)),
+               Some("a \"b\""));
+
+    fn error(s: &str, line: uint) {
+        match read(s) {
+            Err(e) => {
+                assert_eq!(e.kind, InvalidInput);
+                assert_eq!(e.detail, Some(format!("line {}", line)));
+            }
+            Ok(_) => fail!("input: {}, expected an error", s)
+        }
+    }
+    error("2014-12-25\nChristmas", 2);
+    error("2014-02-30,Invalid", 1);
+    error("Date,Name\n2014-12-25x", 2);
+    error("14-12-25", 1);
+}
+
+#[test]
+fn test_holiday_names() {
+    use format_iso8601::YearMonth;
+    let mut cal = HolidayCalendar::new();
+    assert!(cal.add_named_holiday(&YearMonthDay(2014, 12, 25), "Christmas"));
+    assert!(cal.add_holiday(&YearMonthDay(2014, 12, 24)));
+    assert!(cal.add_named_holiday(&YearMonthDay(2014, 12, 24), "Eve"));
+    assert_eq!(cal.holiday_name(&YearMonthDay(2014, 12, 24)), Some("Eve"));
+    assert_eq!(cal.holiday_name(&YearMonthDay(2014, 12, 25)),
+               Some("Christmas"));
+    assert!(!cal.is_business_day(&YearMonthDay(2014, 12, 24)));
+    assert!(!cal.add_named_holiday(&YearMonth(2014, 12), "December"));
+}
@@ -0,0 +1,315 @@
+//! The Chinese lunisolar calendar, from 1900 to 2100.
+//!
+//! Months begin on the day of a new moon, as observed in China, and have
+//! 29 or 30 days.  A year has twelve months, or thirteen when a leap month
+//! is inserted after one of them.  Years are numbered here by the
+//! Gregorian year in which they begin, and also have a name in the
+//! sixty-year cycle.  The calendar is not computed astronomically; the
+//! lengths of the months come from a table covering the years 1900 to
+//! 2100, and dates outside that range are rejected.
+
+use div_mod::div_mod;
+
+/// The first year in the table.
+pub static FIRST_YEAR: int = 1900;
+/// The last year in the table.
+pub static LAST_YEAR: int = 2100;
+
+/// The chronological Julian day of the first day of FIRST_YEAR, which is
+/// 1900-01-31.
+static FIRST_CJD: int = -36494;
+
+/// The months of each year.  Bit 16 - m is set if month m has 30 days,
+/// bits 0-3 give the month followed by a leap month, or zero, and bit 16
+/// is set if the leap month has 30 days.
+static YEAR_INFO: [u32, ..201] = [
+    0x04bd8, 0x04ae0, 0x0a570, 0x054d5, 0x0d260, // 1900
+    0x0d950, 0x16554, 0x056a0, 0x09ad0, 0x055d2, // 1905
+    0x04ae0, 0x0a5b6, 0x0a4d0, 0x0d250, 0x1d255, // 1910
+    0x0b540, 0x0d6a0, 0x0ada2, 0x095b0, 0x14977, // 1915
+    0x04970, 0x0a4b0, 0x0b4b5, 0x06a50, 0x06d40, // 1920
+    0x1ab54, 0x02b60, 0x09570, 0x052f2, 0x04970, // 1925
+    0x06566, 0x0d4a0, 0x0ea50, 0x16a95, 0x05ad0, // 1930
+    0x02b60, 0x186e3, 0x092e0, 0x1c8d7, 0x0c950, // 1935
+    0x0d4a0, 0x1d8a6, 0x0b550, 0x056a0, 0x1a5b4, // 1940
+    0x025d0, 0x092d0, 0x0d2b2, 0x0a950, 0x0b557, // 1945
+    0x06ca0, 0x0b550, 0x15355, 0x04da0, 0x0a5b0, // 1950
+    0x14573, 0x052b0, 0x0a9a8, 0x0e950, 0x06aa0, // 1955
+    0x0aea6, 0x0ab50, 0x04b60, 0x0aae4, 0x0a570, // 1960
+    0x05260, 0x0f263, 0x0d950, 0x05b57, 0x056a0, // 1965
+    0x096d0, 0x04dd5, 0x04ad0, 0x0a4d0, 0x0d4d4, // 1970
+    0x0d250, 0x0d558, 0x0b540, 0x0b6a0, 0x195a6, // 1975
+    0x095b0, 0x049b0, 0x0a974, 0x0a4b0, 0x0b27a, // 1980
+    0x06a50, 0x06d40, 0x0af46, 0x0ab60, 0x09570, // 1985
+    0x04af5, 0x04970, 0x064b0, 0x074a3, 0x0ea50, // 1990
+    0x06b58, 0x05ac0, 0x0ab60, 0x096d5, 0x092e0, // 1995
+    0x0c960, 0x0d954, 0x0d4a0, 0x0da50, 0x07552, // 2000
+    0x056a0, 0x0abb7, 0x025d0, 0x092d0, 0x0cab5, // 2005
+    0x0a950, 0x0b4a0, 0x0baa4, 0x0ad50, 0x055d9, // 2010
+    0x04ba0, 0x0a5b0, 0x15176, 0x052b0, 0x0a930, // 2015
+    0x07954, 0x06aa0, 0x0ad50, 0x05b52, 0x04b60, // 2020
+    0x0a6e6, 0x0a4e0, 0x0d260, 0x0ea65, 0x0d530, // 2025
+    0x05aa0, 0x076a3, 0x096d0, 0x04afb, 0x04ad0, // 2030
+    0x0a4d0, 0x1d0b6, 0x0d250, 0x0d520, 0x0dd45, // 2035
+    0x0b5a0, 0x056d0, 0x055b2, 0x049b0, 0x0a577, // 2040
+    0x0a4b0, 0x0aa50, 0x1b255, 0x06d20, 0x0ada0, // 2045
+    0x14b63, 0x09370, 0x049f8, 0x04970, 0x064b0, // 2050
+    0x168a6, 0x0ea50, 0x06b20, 0x1a6c4, 0x0aae0, // 2055
+    0x092e0, 0x0d2e3, 0x0c960, 0x0d557, 0x0d4a0, // 2060
+    0x0da50, 0x05d55, 0x056a0, 0x0a6d0, 0x055d4, // 2065
+    0x052d0, 0x0a9b8, 0x0a950, 0x0b4a0, 0x0b6a6, // 2070
+    0x0ad50, 0x055a0, 0x0aba4, 0x0a5b0, 0x052b0, // 2075
+    0x0b273, 0x06930, 0x07337, 0x06aa0, 0x0ad50, // 2080
+    0x14b55, 0x04b60, 0x0a570, 0x054e4, 0x0d160, // 2085
+    0x0e968, 0x0d520, 0x0daa0, 0x16aa6, 0x056d0, // 2090
+    0x04ae0, 0x0a9d4, 0x0a2d0, 0x0d150, 0x0f252, // 2095
+    0x0d520                                      // 2100
+];
+
+/// A date in the Chinese calendar.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct ChineseDate {
+    /// The Gregorian year in which the Chinese year begins.
+    pub year: int,
+    /// The month, from 1 to 12.
+    pub month: int,
+    /// Whether this is the leap month which follows the month.
+    pub leap_month: bool,
+    /// The day of the month, from 1 to 30.
+    pub day: int
+}
+
+impl ChineseDate {
+    /// Get the position of the year in the sixty-year cycle, from 1 to 60.
+    /// Year 1 of the cycle, jiazi, began in 1984.
+    pub fn cycle_year(&self) -> int {
+        let (_, n) = div_mod(self.year - 1984, 60);
+        n + 1
+    }
+
+    /// Get the celestial stem of the year, from 1 (jia) to 10 (gui).
+    pub fn stem(&self) -> int {
+        (self.cycle_year() - 1) % 10 + 1
+    }
+
+    /// Get the terrestrial branch of the year, from 1 (zi, the rat) to 12
+    /// (hai, the pig).
+    pub fn branch(&self) -> int {
+        (self.cycle_year() - 1) % 12 + 1
+    }
+}
+
+fn month_days(info: u32, month: int) -> int {
+    if info & (0x10000 >> month as uint) != 0 { 30 } else { 29 }
+}
+
+fn leap_month(info: u32) -> int {
+    (info & 0xf) as int
+}
+
+fn leap_month_days(info: u32) -> int {
+    if leap_month(info) == 0 {
+        0
+    } else if info & 0x10000 != 0 {
+        30
+    } else {
+        29
+    }
+}
+
+fn year_days(info: u32) -> int {
+    range(1, 13).fold(leap_month_days(info), |n, m| n + month_days(info, m))
+}
+
+/// Get the number of the leap month in a year, which follows the month
+/// with the same number, or None if the year has no leap month or is
+/// outside the table.
+pub fn leap_month_in_year(year: int) -> Option<int> {
+    if year < FIRST_YEAR || year > LAST_YEAR {
+        return None;
+    }
+    match leap_month(YEAR_INFO[(year - FIRST_YEAR) as uint]) {
+        0 => None,
+        m => Some(m)
+    }
+}
+
+/// Get the number of days in a month, or None if the month does not
+/// exist.
+pub fn days_in_month(year: int, month: int, leap: bool) -> Option<int> {
+    if year < FIRST_YEAR || year > LAST_YEAR || month < 1 || month > 12 {
+        return None;
+    }
+    let info = YEAR_INFO[(year - FIRST_YEAR) as uint];
+    if !leap {
+        Some(month_days(info, month))
+    } else if leap_month(info) == month {
+        Some(leap_month_days(info))
+    } else {
+        None
+    }
+}
+
+/// Convert a date to a chronological Julian day.  Returns None if the
+/// date does not exist or is outside the table.
+pub fn to_cjd(date: &ChineseDate) -> Option<int> {
+    let length = match days_in_month(date.year, date.month, date.leap_month) {
+        Some(n) => n, None => return None
+    };
+    if date.day < 1 || date.day > length {
+        return None;
+    }
+    let index = (date.year - FIRST_YEAR) as uint;
+    let info = YEAR_INFO[index];
+    let mut cjd = YEAR_INFO.slice_to(index).iter()
+        .fold(FIRST_CJD, |n, &info| n + year_days(info));
+    for m in range(1, date.month) {
+        cjd += month_days(info, m);
+        if m == leap_month(info) {
+            cjd += leap_month_days(info);
+        }
+    }
+    if date.leap_month {
+        cjd += month_days(info, date.month);
+    }
+    Some(cjd + date.day - 1)
+}
+
+/// Convert a chronological Julian day to a date.  Returns None if the day
+/// is outside the table.
+pub fn from_cjd(cjd: int) -> Option<ChineseDate> {
+    let mut start = FIRST_CJD;
+    if cjd < start {
+        return None;
+    }
+    for (i, &info) in YEAR_INFO.iter().enumerate() {
+        let length = year_days(info);
+        if cjd >= start + length {
+            start += length;
+            continue;
+        }
+        let year = FIRST_YEAR + i as int;
+        let mut day = cjd - start;
+        for m in range(1, 13) {
+            let n = month_days(info, m);
+            if day < n {
+                return Some(ChineseDate {
+                    year: year, month: m, leap_month: false, day: day + 1
+                });
+            }
+            day -= n;
+            if m == leap_month(info) {
+                let n = leap_month_days(info);
+                if day < n {
+                    return Some(ChineseDate {
+                        year: year, month: m, leap_month: true, day: day + 1
+                    });
+                }
+                day -= n;
+            }
+        }
+        unreachable!();
+    }
+    None
+}
+
+/// Get the chronological Julian day of the new year, the first day of the
+/// first month, or None if the year is outside the table.
+pub fn new_year(year: int) -> Option<int> {
+    to_cjd(&ChineseDate { year: year, month: 1, leap_month: false, day: 1 })
+}
+
+#[test]
+fn conversions() {
+    use calendar_iso8601;
+    fn test(y: int, m: int, d: int, year: int, month: int, leap: bool,
+            day: int) {
+        let cjd = calendar_iso8601::to_cjd(y, m, d);
+        let date = ChineseDate {
+            year: year, month: month, leap_month: leap, day: day
+        };
+        assert_eq!(from_cjd(cjd), Some(date));
+        assert_eq!(to_cjd(&date), Some(cjd));
+    }
+    test(1900, 1, 31, 1900, 1, false, 1);
+    test(2000, 1, 1, 1999, 11, false, 25);
+    test(2014, 1, 31, 2014, 1, false, 1);
+    test(2014, 6, 10, 2014, 5, false, 13);
+    test(2014, 9, 8, 2014, 8, false, 15);
+    test(2020, 5, 23, 2020, 4, true, 1);
+    test(2023, 3, 21, 2023, 2, false, 30);
+    test(2023, 3, 22, 2023, 2, true, 1);
+    test(2023, 4, 20, 2023, 3, false, 1);
+    test(2024, 2, 9, 2023, 12, false, 30);
+    test(2033, 12, 22, 2033, 11, true, 1);
+    test(2034, 1, 20, 2033, 12, false, 1);
+    test(2101, 1, 28, 2100, 12, false, 29);
+    assert_eq!(from_cjd(calendar_iso8601::to_cjd(1900, 1, 30)), None);
+    assert_eq!(from_cjd(calendar_iso8601::to_cjd(2101, 1, 29)), None);
+}
+
+#[test]
+fn invalid_dates() {
+    fn test(year: int, month: int, leap: bool, day: int) {
+        let date = ChineseDate {
+            year: year, month: month, leap_month: leap, day: day
+        };
+        assert_eq!(to_cjd(&date), None);
+    }
+    test(1899, 12, false, 1);
+    test(2101, 1, false, 1);
+    test(2014, 0, false, 1);
+    test(2014, 13, false, 1);
+    test(2014, 1, false, 0);
+    test(2014, 1, true, 1);
+    test(2023, 2, true, 30);
+    test(2023, 12, false, 31);
+}
+
+#[test]
+fn new_years() {
+    use calendar_iso8601;
+    fn test(year: int, m: int, d: int) {
+        assert_eq!(new_year(year), Some(calendar_iso8601::to_cjd(year, m, d)));
+    }
+    test(1949, 1, 29);
+    test(1980, 2, 16);
+    test(2000, 2, 5);
+    test(2008, 2, 7);
+    test(2020, 1, 25);
+    test(2024, 2, 10);
+    test(2026, 2, 17);
+    test(2050, 1, 23);
+    test(2100, 2, 9);
+    assert_eq!(new_year(1899), None);
+    assert_eq!(leap_month_in_year(2023), Some(2));
+    assert_eq!(leap_month_in_year(2024), None);
+    assert_eq!(leap_month_in_year(2033), Some(11));
+}
+
+#[test]
+fn cycle() {
+    fn test(year: int, cycle: int, stem: int, branch: int) {
+        let date = ChineseDate {
+            year: year, month: 1, leap_month: false, day: 1
+        };
+        assert_eq!(date.cycle_year(), cycle);
+        assert_eq!(date.stem(), stem);
+        assert_eq!(date.branch(), branch);
+    }
+    test(1984, 1, 1, 1);
+    test(2014, 31, 1, 7);
+    test(2024, 41, 1, 5);
+    test(1900, 37, 7, 1);
+    test(2043, 60, 10, 12);
+}
+
+#[test]
+fn round_trip() {
+    let end = FIRST_CJD + YEAR_INFO.iter().fold(0, |n, &i| n + year_days(i));
+    for cjd in range(FIRST_CJD, end) {
+        let date = from_cjd(cjd).unwrap();
+        assert_eq!(to_cjd(&date), Some(cjd));
+    }
+    assert_eq!(from_cjd(end), None);
+}
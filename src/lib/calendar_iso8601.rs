@@ -11,23 +11,87 @@ use std::num::div_rem;
 use div_mod::div_mod;
 
 static EPOCH_LEN: int = 146097;
-static MONTHDAY: [int, ..12] = [
-    0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334
+
+/// The largest year, positive or negative, accepted when building dates
+/// and times from fields, which keeps day numbers from overflowing.
+/// Instants only reach years of about 30000, so any larger year is out of
+/// range.
+pub static MAX_YEAR: int = 1000000;
+
+/// The first and last years handled by the fast paths, in which every
+/// fourth year is a leap year.
+static FAST_FIRST_YEAR: int = 1970;
+static FAST_LAST_YEAR: int = 2099;
+
+/// The chronological Julian days of 1970-01-01 and 2100-01-01, the range
+/// of days handled by the fast path in from_cjd().
+static FAST_FIRST_CJD: int = -10957;
+static FAST_END_CJD: int = 36525;
+
+/// The chronological Julian day of 1968-01-01, the leap year starting the
+/// four-year cycle which contains FAST_FIRST_YEAR.
+static FAST_BASE_CJD: int = -11688;
+
+/// The number of days in a year before each month, for common years and
+/// for leap years.
+static MONTH_START: [[int, ..12], ..2] = [
+    [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334],
+    [0, 31, 60, 91, 121, 152, 182, 213, 244, 274, 305, 335]
 ];
 
+/// Get the number of days in a 400-year cycle starting in 2000 before the
+/// given year of the cycle, from 0 to 399.  The first year of the cycle is
+/// a leap year, so each leap year is counted from the year after it.
+fn cycle_year_start(a: int) -> int {
+    365 * a + (a + 3) / 4 - (a + 99) / 100 + (a + 399) / 400
+}
+
 /// Convert an ISO 8601 date to a chronological Julian day.
+#[inline]
 pub fn to_cjd(year: int, month: int, day: int) -> int {
-    let y = year - 2000;
-    let (b, a) = div_mod(y, 400);
-    let mut leap = 1 + a / 4 - a / 100;
-    if month <= 2 && (a % 4) == 0 && ((a % 100) != 0 || a == 0) {
-        leap -= 1;
+    if year >= FAST_FIRST_YEAR && year <= FAST_LAST_YEAR {
+        let y = year - 1968;
+        let leap = (y & 3) == 0;
+        FAST_BASE_CJD + 365 * y + (y + 3) / 4 +
+            MONTH_START[leap as uint][(month - 1) as uint] + day - 1
+    } else {
+        to_cjd_general(year, month, day)
     }
-    146097 * b + 365 * a + MONTHDAY[(month - 1) as uint] + day - 1 + leap
+}
+
+/// Convert an ISO 8601 date to a chronological Julian day, for any year.
+fn to_cjd_general(year: int, month: int, day: int) -> int {
+    let (b, a) = div_mod(year - 2000, 400);
+    let leap = (a % 4) == 0 && ((a % 100) != 0 || a == 0);
+    EPOCH_LEN * b + cycle_year_start(a) +
+        MONTH_START[leap as uint][(month - 1) as uint] + day - 1
 }
 
 /// Convert a chronological Julian day to an ISO 8601 date.
+#[inline]
 pub fn from_cjd(cjd: int) -> (int, int, int) {
+    if cjd < FAST_FIRST_CJD || cjd >= FAST_END_CJD {
+        return from_cjd_general(cjd);
+    }
+    // Split the days into four-year cycles, each starting with a leap year.
+    let n = cjd - FAST_BASE_CJD;
+    let (q, r) = (n / 1461, n % 1461);
+    let (y, yday, leap) = if r < 366 {
+        (q * 4, r, 1u)
+    } else {
+        (q * 4 + (r - 1) / 365, (r - 1) % 365, 0u)
+    };
+    // Dividing by 32 gives the month or the one before it.
+    let starts = &MONTH_START[leap];
+    let mut m = (yday / 32) as uint;
+    if m < 11 && yday >= starts[m + 1] {
+        m += 1;
+    }
+    (1968 + y, m as int + 1, yday - starts[m] + 1)
+}
+
+/// Convert a chronological Julian day to an ISO 8601 date, for any day.
+fn from_cjd_general(cjd: int) -> (int, int, int) {
     let (t1, d) = div_mod(cjd, EPOCH_LEN);
     let (t2, d) = div_rem(d - 1, 36524);
     let (t3, d) = div_rem(d + 1, 1461);
@@ -35,11 +99,11 @@ pub fn from_cjd(cjd: int) -> (int, int, int) {
     let y = 2000 + t1 * 400 + t2 * 100 + t3 * 4 + t4;
 
     let mut m = d / 29 + 1;
-    if m > 12 || (m > 1 && d < MONTHDAY[(m - 1) as uint]) {
+    if m > 12 || (m > 1 && d < MONTH_START[0][(m - 1) as uint]) {
         m -= 1;
     }
 
-    let mut d = d - MONTHDAY[(m - 1) as uint] + 1;
+    let mut d = d - MONTH_START[0][(m - 1) as uint] + 1;
     if t4 == 0 && (t3 != 0 || t2 == 0) && m <= 2 {
         d += 1;
         if d == 32 {
@@ -51,6 +115,64 @@ pub fn from_cjd(cjd: int) -> (int, int, int) {
     (y, m, d)
 }
 
+/// Test whether a year is a leap year in the ISO 8601 calendar.
+pub fn is_leap_year(year: int) -> bool {
+    (year % 4) == 0 && ((year % 100) != 0 || (year % 400) == 0)
+}
+
+/// Get the number of days in a month.
+pub fn days_in_month(year: int, month: int) -> int {
+    static LENGTHS: [int, ..12] = [
+        31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31
+    ];
+    if month == 2 && is_leap_year(year) {
+        29
+    } else {
+        LENGTHS[(month - 1) as uint]
+    }
+}
+
+/// Get the number of days in a year.
+pub fn days_in_year(year: int) -> int {
+    if is_leap_year(year) { 366 } else { 365 }
+}
+
+/// Get the ISO 8601 day of the week for a chronological Julian day,
+/// from 1 (Monday) to 7 (Sunday).
+pub fn weekday(cjd: int) -> int {
+    // 2000-01-01 was a Saturday.
+    let (_, d) = div_mod(cjd + 5, 7);
+    d + 1
+}
+
+/// Convert an ISO 8601 week date to a chronological Julian day.
+pub fn week_to_cjd(year: int, week: int, day: int) -> int {
+    // Week 1 is the week containing January 4.
+    let jan4 = to_cjd(year, 1, 4);
+    jan4 - weekday(jan4) + (week - 1) * 7 + day
+}
+
+/// Convert a chronological Julian day to an ISO 8601 ordinal date.
+pub fn to_ordinal(cjd: int) -> (int, int) {
+    let (y, _, _) = from_cjd(cjd);
+    (y, cjd - to_cjd(y, 1, 1) + 1)
+}
+
+/// Convert a chronological Julian day to an ISO 8601 week date.
+pub fn to_week_date(cjd: int) -> (int, int, int) {
+    // The week belongs to the year containing its Thursday.
+    let d = weekday(cjd);
+    let thursday = cjd - d + 4;
+    let (y, _, _) = from_cjd(thursday);
+    (y, (thursday - week_to_cjd(y, 1, 4)) / 7 + 1, d)
+}
+
+/// Get the number of ISO 8601 weeks in a year, either 52 or 53.
+pub fn weeks_in_year(year: int) -> int {
+    // December 28 is always in the last week of the year.
+    (to_cjd(year, 12, 28) - week_to_cjd(year, 1, 1)) / 7 + 1
+}
+
 #[test]
 fn cjd_point() {
     fn test(cjd: int, y: int, m: int, d: int) {
@@ -74,6 +196,136 @@ fn cjd_point() {
     test(-146097, 1600, 1, 1);
 }
 
+#[test]
+fn week_date() {
+    fn test(cjd: int, y: int, w: int, d: int) {
+        let out_cjd = week_to_cjd(y, w, d);
+        if out_cjd != cjd {
+            fail!("{:04d}-W{:02d}-{}: expected {}, got {}",
+                  y, w, d, cjd, out_cjd);
+        }
+        if weekday(cjd) != d {
+            fail!("{}: expected weekday {}, got {}", cjd, d, weekday(cjd));
+        }
+        let (y2, w2, d2) = to_week_date(cjd);
+        if y != y2 || w != w2 || d != d2 {
+            fail!("{}: expected {:04d}-W{:02d}-{}, got {:04d}-W{:02d}-{}",
+                  cjd, y, w, d, y2, w2, d2);
+        }
+    }
+
+    test(0, 1999, 52, 6);
+    test(2, 2000, 1, 1);
+    test(to_cjd(2008, 12, 29), 2009, 1, 1);
+    test(to_cjd(2010, 1, 3), 2009, 53, 7);
+    test(to_cjd(2024, 5, 15), 2024, 20, 3);
+
+    test(to_cjd(2004, 12, 31), 2004, 53, 5);
+    test(to_cjd(2005, 1, 2), 2004, 53, 7);
+
+    assert_eq!(weeks_in_year(2004), 53);
+    assert_eq!(weeks_in_year(2009), 53);
+    assert_eq!(weeks_in_year(2010), 52);
+    assert_eq!(weeks_in_year(2024), 52);
+}
+
+#[test]
+fn ordinal_date() {
+    assert_eq!(to_ordinal(0), (2000, 1));
+    assert_eq!(to_ordinal(-1), (1999, 365));
+    assert_eq!(to_ordinal(to_cjd(2000, 12, 31)), (2000, 366));
+    assert_eq!(to_ordinal(to_cjd(2024, 5, 14)), (2024, 135));
+}
+
+#[test]
+fn cjd_years() {
+    // Check the start of each year against the length of the year before.
+    let mut cjd = to_cjd(-1200, 1, 1);
+    assert_eq!(cjd, -146097 * 8);
+    for year in range(-1200, 2801) {
+        let out = to_cjd(year, 1, 1);
+        if out != cjd {
+            fail!("{}-01-01: expected {}, got {}", year, cjd, out);
+        }
+        for month in range(1, 12) {
+            let next = to_cjd(year, month + 1, 1);
+            if next - to_cjd(year, month, 1) != days_in_month(year, month) {
+                fail!("{}-{:02d}: wrong length", year, month);
+            }
+        }
+        cjd += days_in_year(year);
+    }
+}
+
+#[test]
+fn cjd_fast_path() {
+    // Compare the fast paths with the general algorithms, with a margin of
+    // a few years on each side.
+    for cjd in range(FAST_FIRST_CJD - 2000, FAST_END_CJD + 2000) {
+        let date = from_cjd(cjd);
+        if date != from_cjd_general(cjd) {
+            fail!("{}: expected {}, got {}", cjd, from_cjd_general(cjd), date);
+        }
+        let (y, m, d) = date;
+        if to_cjd(y, m, d) != cjd || to_cjd_general(y, m, d) != cjd {
+            fail!("{}: expected {}, got {}", date, cjd, to_cjd(y, m, d));
+        }
+    }
+    assert_eq!(to_cjd(1970, 1, 1), FAST_FIRST_CJD);
+    assert_eq!(to_cjd(2100, 1, 1), FAST_END_CJD);
+    assert_eq!(to_cjd_general(1968, 1, 1), FAST_BASE_CJD);
+}
+
+#[bench]
+fn bench_from_cjd(b: &mut ::test::Bencher) {
+    b.iter(|| {
+        let mut total = 0;
+        for cjd in range(-1000, 1000) {
+            let (y, m, d) = from_cjd(cjd * 13);
+            total += y + m + d;
+        }
+        total
+    });
+}
+
+#[bench]
+fn bench_from_cjd_general(b: &mut ::test::Bencher) {
+    b.iter(|| {
+        let mut total = 0;
+        for cjd in range(-1000, 1000) {
+            let (y, m, d) = from_cjd_general(cjd * 13);
+            total += y + m + d;
+        }
+        total
+    });
+}
+
+#[bench]
+fn bench_to_cjd_general(b: &mut ::test::Bencher) {
+    b.iter(|| {
+        let mut total = 0;
+        for year in range(1990, 2030) {
+            for month in range(1, 13) {
+                total += to_cjd_general(year, month, 15);
+            }
+        }
+        total
+    });
+}
+
+#[bench]
+fn bench_to_cjd(b: &mut ::test::Bencher) {
+    b.iter(|| {
+        let mut total = 0;
+        for year in range(1990, 2030) {
+            for month in range(1, 13) {
+                total += to_cjd(year, month, 15);
+            }
+        }
+        total
+    });
+}
+
 #[test]
 fn cjd_range() {
     static MONTHS: [int, ..12] = [
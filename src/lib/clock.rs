@@ -0,0 +1,404 @@
+//! Reading the system clocks.
+//!
+//! The clocks are read with clock_gettime, using the clock IDs defined by
+//! Linux, so this module is only available on Linux.
+
+use std::io::timer;
+use std::sync::Arc;
+#[cfg(target_word_size = "64")]
+use std::sync::atomics::{AtomicInt, Relaxed};
+#[cfg(not(target_word_size = "64"))]
+use std::sync::Mutex;
+use libc::c_int;
+use libc::types::os::common::posix01::timespec;
+use tick;
+use duration::Duration;
+use instant::{Instant, UNIX_EPOCH};
+
+// Versions of glibc before 2.17 define clock_gettime in librt.
+#[link(name = "rt")]
+extern {
+    fn clock_gettime(clk_id: c_int, tp: *mut timespec) -> c_int;
+    fn clock_getres(clk_id: c_int, res: *mut timespec) -> c_int;
+}
+
+/// A system clock which can be read with clock_gettime.  The clock IDs are
+/// those used by Linux.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum PosixClock {
+    /// The wall clock, which measures time since the Unix epoch.
+    Realtime,
+    /// A faster but less precise version of the wall clock.
+    RealtimeCoarse,
+    /// A monotonic clock, which is adjusted by NTP to run at the correct
+    /// rate but never jumps, and which stops while the system is
+    /// suspended.
+    Monotonic,
+    /// A monotonic clock which is not adjusted by NTP.
+    MonotonicRaw,
+    /// A faster but less precise version of the monotonic clock.
+    MonotonicCoarse,
+    /// A monotonic clock which includes time spent suspended.
+    Boottime
+}
+
+impl PosixClock {
+    /// Get the clock ID passed to clock_gettime.
+    fn id(&self) -> c_int {
+        match *self {
+            Realtime => 0,
+            Monotonic => 1,
+            MonotonicRaw => 4,
+            RealtimeCoarse => 5,
+            MonotonicCoarse => 6,
+            Boottime => 7
+        }
+    }
+
+    /// Test whether the clock measures wall time, rather than time since
+    /// an unspecified epoch.
+    pub fn is_wall_clock(&self) -> bool {
+        match *self {
+            Realtime | RealtimeCoarse => true,
+            _ => false
+        }
+    }
+
+    /// Read a wall clock.  Returns None if this is not a wall clock.
+    pub fn read_instant(&self) -> Option<Instant> {
+        if self.is_wall_clock() {
+            Some(Instant::from_ticks(read_ticks(*self) + UNIX_EPOCH.ticks()))
+        } else {
+            None
+        }
+    }
+
+    /// Read a monotonic clock.  Returns None if this is a wall clock.  Only
+    /// readings of the same clock can be meaningfully compared.
+    pub fn read_monotonic(&self) -> Option<MonotonicInstant> {
+        if self.is_wall_clock() {
+            None
+        } else {
+            Some(MonotonicInstant { ticks: read_ticks(*self) })
+        }
+    }
+
+    /// Get the clock's resolution, the smallest difference between two
+    /// readings.  Resolutions finer than a tick are rounded up to one tick.
+    /// Fails if the resolution cannot be read.
+    pub fn resolution(&self) -> Duration {
+        let mut ts = timespec { tv_sec: 0, tv_nsec: 0 };
+        let r = unsafe { clock_getres(self.id(), &mut ts) };
+        if r != 0 {
+            fail!("clock_getres failed");
+        }
+        // Nanoseconds are hundredths of a tick.
+        let ticks = ts.tv_sec as i64 * tick::SECOND +
+            tick::to_larger_unit_ceil(ts.tv_nsec as i64, 100);
+        Duration { ticks: ticks }
+    }
+}
+
+/// Read a clock with clock_gettime, returning the number of ticks since
+/// the clock's epoch.  Fails if the clock cannot be read.
+fn read_ticks(clock: PosixClock) -> i64 {
+    let mut ts = timespec { tv_sec: 0, tv_nsec: 0 };
+    let r = unsafe { clock_gettime(clock.id(), &mut ts) };
+    if r != 0 {
+        fail!("clock_gettime failed");
+    }
+    match tick::from_sec_nsec(ts.tv_sec as i64, ts.tv_nsec as i64) {
+        Some(n) => n,
+        None => fail!("clock_gettime out of range")
+    }
+}
+
+/// Read the wall clock.  The result jumps if the system time is changed.
+pub fn now() -> Instant {
+    Instant::from_ticks(read_ticks(Realtime) + UNIX_EPOCH.ticks())
+}
+
+/// A reading of a monotonic clock.  Monotonic instants can only be compared
+/// to each other, since the clock's epoch is unspecified, but unlike the
+/// wall clock they never jump backwards.
+#[deriving(PartialEq, PartialOrd, Ord, Eq, Clone, Show, Hash)]
+pub struct MonotonicInstant {
+    ticks: i64
+}
+
+impl Add<Duration, MonotonicInstant> for MonotonicInstant {
+    fn add(&self, rhs: &Duration) -> MonotonicInstant {
+        MonotonicInstant { ticks: self.ticks + rhs.ticks }
+    }
+}
+
+impl Sub<Duration, MonotonicInstant> for MonotonicInstant {
+    fn sub(&self, rhs: &Duration) -> MonotonicInstant {
+        MonotonicInstant { ticks: self.ticks - rhs.ticks }
+    }
+}
+
+impl MonotonicInstant {
+    /// Read the monotonic clock.
+    pub fn now() -> MonotonicInstant {
+        MonotonicInstant { ticks: read_ticks(Monotonic) }
+    }
+
+    /// Get the duration from an earlier reading to this one.
+    pub fn duration_since(&self, earlier: MonotonicInstant) -> Duration {
+        Duration { ticks: self.ticks - earlier.ticks }
+    }
+
+    /// Get the duration since this reading was taken.
+    pub fn elapsed(&self) -> Duration {
+        MonotonicInstant::now().duration_since(*self)
+    }
+}
+
+/// A guard which measures how long it is alive, and passes the duration
+/// to a callback when it is dropped.  This makes it easy to time a block:
+///
+/// ```ignore
+/// let _t = TimedScope::new(|d| println!("took {}", Scaled(d)));
+/// ```
+pub struct TimedScope<'a> {
+    start: MonotonicInstant,
+    callback: |Duration|: 'a
+}
+
+impl<'a> TimedScope<'a> {
+    /// Start timing, calling the callback with the elapsed time when the
+    /// guard is dropped.
+    pub fn new(callback: |Duration|: 'a) -> TimedScope<'a> {
+        TimedScope { start: MonotonicInstant::now(), callback: callback }
+    }
+
+    /// Get the time elapsed so far.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for TimedScope<'a> {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        (self.callback)(elapsed);
+    }
+}
+
+/// A cached reading of the wall clock, for programs which need the time
+/// so often that reading the system clock each time is too expensive,
+/// and which can tolerate timestamps that lag by a short interval.  The
+/// cached time is only changed by calling update(), either directly, such
+/// as once per iteration of an event loop, or from a refresher task.
+///
+/// Clocks can be shared between tasks.  The time is stored in an atomic
+/// integer where int has 64 bits, and behind a lock otherwise.
+pub struct CoarseClock {
+    ticks: SharedTicks
+}
+
+/// A tick count which can be read and written from several tasks.
+#[cfg(target_word_size = "64")]
+struct SharedTicks {
+    value: AtomicInt
+}
+
+#[cfg(target_word_size = "64")]
+impl SharedTicks {
+    fn new(ticks: i64) -> SharedTicks {
+        SharedTicks { value: AtomicInt::new(ticks as int) }
+    }
+
+    #[inline]
+    fn load(&self) -> i64 {
+        self.value.load(Relaxed) as i64
+    }
+
+    fn store(&self, ticks: i64) {
+        self.value.store(ticks as int, Relaxed);
+    }
+}
+
+/// A tick count which can be read and written from several tasks.
+#[cfg(not(target_word_size = "64"))]
+struct SharedTicks {
+    value: Mutex<i64>
+}
+
+#[cfg(not(target_word_size = "64"))]
+impl SharedTicks {
+    fn new(ticks: i64) -> SharedTicks {
+        SharedTicks { value: Mutex::new(ticks) }
+    }
+
+    #[inline]
+    fn load(&self) -> i64 {
+        *self.value.lock()
+    }
+
+    fn store(&self, ticks: i64) {
+        *self.value.lock() = ticks;
+    }
+}
+
+impl CoarseClock {
+    /// Create a coarse clock, reading the wall clock.
+    pub fn new() -> CoarseClock {
+        CoarseClock { ticks: SharedTicks::new(now().ticks()) }
+    }
+
+    /// Create a coarse clock shared with a task which updates it at the
+    /// given interval, rounded up to whole milliseconds.  The task exits
+    /// once it holds the last reference to the clock.  Fails if the
+    /// interval is not positive.
+    pub fn spawn_refresher(interval: Duration) -> Arc<CoarseClock> {
+        let millis = match interval.to_timeout_millis() {
+            Some(n) if n > 0 => n,
+            _ => fail!("refresh interval must be positive")
+        };
+        let clock = Arc::new(CoarseClock::new());
+        let weak = clock.downgrade();
+        spawn(proc() {
+            loop {
+                timer::sleep(millis);
+                match weak.upgrade() {
+                    Some(clock) => clock.update(),
+                    None => break
+                }
+            }
+        });
+        clock
+    }
+
+    /// Get the cached time.
+    #[inline]
+    pub fn now(&self) -> Instant {
+        Instant::from_ticks(self.ticks.load())
+    }
+
+    /// Read the wall clock, updating the cached time.
+    pub fn update(&self) {
+        self.ticks.store(now().ticks());
+    }
+}
+
+/// Detects when the wall clock is stepped, for example by NTP or by an
+/// administrator, by comparing how far it moves against the monotonic
+/// clock.  Each check compares the clocks to their readings at the
+/// previous check.
+pub struct StepDetector {
+    wall: Instant,
+    monotonic: MonotonicInstant,
+    threshold: Duration
+}
+
+impl StepDetector {
+    /// Create a detector which reports steps larger than the threshold.
+    pub fn new(threshold: Duration) -> StepDetector {
+        StepDetector::new_at(threshold, now(), MonotonicInstant::now())
+    }
+
+    /// Create a detector from given readings of the wall and monotonic
+    /// clocks.
+    pub fn new_at(threshold: Duration, wall: Instant,
+                  monotonic: MonotonicInstant) -> StepDetector {
+        StepDetector {
+            wall: wall,
+            monotonic: monotonic,
+            threshold: threshold
+        }
+    }
+
+    /// Read the clocks and check for a step.  Returns the size of the
+    /// step, which is negative if the wall clock moved backwards, or None
+    /// if the clocks moved together to within the threshold.
+    pub fn check(&mut self) -> Option<Duration> {
+        self.check_at(now(), MonotonicInstant::now())
+    }
+
+    /// Check for a step using given readings of the wall and monotonic
+    /// clocks.
+    pub fn check_at(&mut self, wall: Instant, monotonic: MonotonicInstant)
+                    -> Option<Duration> {
+        let step = (wall.ticks() - self.wall.ticks()) -
+            (monotonic.ticks - self.monotonic.ticks);
+        self.wall = wall;
+        self.monotonic = monotonic;
+        let size = if step < 0 { -step } else { step };
+        if size > self.threshold.ticks {
+            Some(Duration { ticks: step })
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn monotonic() {
+    let a = MonotonicInstant::now();
+    let b = MonotonicInstant::now();
+    assert!(a <= b);
+    assert!(b.duration_since(a).ticks >= 0);
+    assert_eq!((a + Duration { ticks: 5 }).duration_since(a).ticks, 5);
+}
+
+#[test]
+fn timed_scope() {
+    let mut result = None;
+    {
+        let _t = TimedScope::new(|d| result = Some(d));
+    }
+    assert!(result.unwrap().ticks >= 0);
+}
+
+#[test]
+fn posix_clocks() {
+    // June 10, 2014.
+    let past = Instant::from_ticks(tick::DAY * 5274);
+    assert!(Realtime.read_instant().unwrap() > past);
+    assert!(RealtimeCoarse.read_instant().unwrap() > past);
+    assert!(Realtime.read_monotonic().is_none());
+    for &clock in [Monotonic, MonotonicRaw, MonotonicCoarse, Boottime]
+        .iter() {
+        let a = clock.read_monotonic().unwrap();
+        let b = clock.read_monotonic().unwrap();
+        assert!(a <= b);
+        assert!(clock.read_instant().is_none());
+        let res = clock.resolution();
+        assert!(res.ticks > 0 && res.ticks <= tick::SECOND);
+    }
+}
+
+#[test]
+fn coarse() {
+    // June 10, 2014.
+    let past = Instant::from_ticks(tick::DAY * 5274);
+    let clock = CoarseClock::new();
+    let a = clock.now();
+    assert!(a > past);
+    assert_eq!(clock.now(), a);
+    clock.update();
+    assert!(clock.now() >= a);
+    let shared = CoarseClock::spawn_refresher(Duration { ticks: 1 });
+    assert!(shared.now() > past);
+}
+
+#[test]
+fn step_detector() {
+    let mono = |t: i64| MonotonicInstant { ticks: t };
+    let wall = |t: i64| Instant::from_ticks(t + tick::DAY * 5274);
+    let mut d = StepDetector::new_at(Duration { ticks: tick::SECOND },
+                                     wall(0), mono(100));
+    assert_eq!(d.check_at(wall(tick::MINUTE), mono(100 + tick::MINUTE)),
+               None);
+    assert_eq!(d.check_at(wall(tick::MINUTE * 2 + tick::SECOND),
+                          mono(100 + tick::MINUTE * 2)), None);
+    assert_eq!(d.check_at(wall(tick::HOUR), mono(100 + tick::MINUTE * 3)),
+               Some(Duration { ticks: tick::MINUTE * 57 - tick::SECOND }));
+    assert_eq!(d.check_at(wall(0), mono(100 + tick::MINUTE * 4)),
+               Some(Duration { ticks: -tick::MINUTE * 61 }));
+    let mut d = StepDetector::new(Duration { ticks: tick::HOUR });
+    assert_eq!(d.check(), None);
+}
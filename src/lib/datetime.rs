@@ -0,0 +1,320 @@
+//! Civil dates and times, as shown on a calendar and a clock.
+//!
+//! A DateTime has no time zone, so it does not identify an instant until
+//! it is interpreted in UTC or in some zone.  The conversions here are
+//! equivalents of the C functions timegm and mktime.
+
+use std::num::{Bounded, Saturating};
+use div_mod::div_mod;
+use tick;
+use calendar_iso8601;
+use instant::Instant;
+use zone::{Zone, Single, Ambiguous, Skipped};
+
+/// A date and time of day in the ISO 8601 calendar.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct DateTime {
+    /// The year, using astronomical year numbering.
+    pub year: int,
+    /// The month, from 1 to 12.
+    pub month: int,
+    /// The day of the month, starting from 1.
+    pub day: int,
+    /// The hour, from 0 to 23.
+    pub hour: int,
+    /// The minute, from 0 to 59.
+    pub minute: int,
+    /// The second, from 0 to 59.
+    pub second: int,
+    /// The number of ticks after the second.
+    pub subsec_ticks: i64
+}
+
+/// How to treat fields outside their usual ranges, such as month 13 or
+/// second 90.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum FieldPolicy {
+    /// Reject the date and time.
+    Strict,
+    /// Clamp each field to its range, so month 13 is December, and
+    /// February 30 is the last day of February.
+    Lenient,
+    /// Normalize the fields, as mktime does, so month 13 is January of
+    /// the next year, and second 90 is one minute and 30 seconds.
+    Overflowing
+}
+
+/// Clamp a number to a range.
+fn clamp<T: Ord>(x: T, lo: T, hi: T) -> T {
+    if x < lo { lo } else if x > hi { hi } else { x }
+}
+
+impl DateTime {
+    /// Create a date and time from its fields.  The fields are not
+    /// checked.
+    pub fn new(year: int, month: int, day: int, hour: int, minute: int,
+               second: int, subsec_ticks: i64) -> DateTime {
+        DateTime {
+            year: year,
+            month: month,
+            day: day,
+            hour: hour,
+            minute: minute,
+            second: second,
+            subsec_ticks: subsec_ticks
+        }
+    }
+
+    /// Test whether every field is in its usual range.
+    fn is_valid(&self) -> bool {
+        self.month >= 1 && self.month <= 12 && self.day >= 1 &&
+            self.day <= calendar_iso8601::days_in_month(self.year,
+                                                        self.month) &&
+            self.hour >= 0 && self.hour <= 23 &&
+            self.minute >= 0 && self.minute <= 59 &&
+            self.second >= 0 && self.second <= 59 &&
+            self.subsec_ticks >= 0 && self.subsec_ticks < tick::SECOND
+    }
+
+    /// Bring every field into its usual range using the given policy.
+    /// Returns None if the policy is Strict and any field is out of range,
+    /// or if the policy is Overflowing and the result is out of range.
+    pub fn resolve(&self, policy: FieldPolicy) -> Option<DateTime> {
+        match policy {
+            Strict => if self.is_valid() { Some(*self) } else { None },
+            Lenient => {
+                let month = clamp(self.month, 1, 12);
+                let last = calendar_iso8601::days_in_month(self.year, month);
+                Some(DateTime {
+                    year: self.year,
+                    month: month,
+                    day: clamp(self.day, 1, last),
+                    hour: clamp(self.hour, 0, 23),
+                    minute: clamp(self.minute, 0, 59),
+                    second: clamp(self.second, 0, 59),
+                    subsec_ticks: clamp(self.subsec_ticks, 0,
+                                        tick::SECOND - 1)
+                })
+            }
+            Overflowing => self.to_local_ticks()
+                .map(|n| Instant::from_ticks(n).to_datetime())
+        }
+    }
+
+    /// Get the same time on a different date.  Returns None if the date
+    /// does not exist.
+    fn with_date(&self, year: int, month: int, day: int)
+                 -> Option<DateTime> {
+        if month < 1 || month > 12 || day < 1 ||
+            day > calendar_iso8601::days_in_month(year, month) {
+            return None;
+        }
+        Some(DateTime { year: year, month: month, day: day, ..*self })
+    }
+
+    /// Get the same date and time in a different year.  Returns None if
+    /// the date does not exist in that year, such as February 29.
+    pub fn with_year(&self, year: int) -> Option<DateTime> {
+        self.with_date(year, self.month, self.day)
+    }
+
+    /// Get the same date and time in a different month.  Returns None if
+    /// the month is not from 1 to 12 or the day does not exist in it.
+    pub fn with_month(&self, month: int) -> Option<DateTime> {
+        self.with_date(self.year, month, self.day)
+    }
+
+    /// Get the same time on a different day of the month.  Returns None if
+    /// the day does not exist in the month.
+    pub fn with_day(&self, day: int) -> Option<DateTime> {
+        self.with_date(self.year, self.month, day)
+    }
+
+    /// Get the same date and time with a different hour.  Returns None if
+    /// the hour is not from 0 to 23.
+    pub fn with_hour(&self, hour: int) -> Option<DateTime> {
+        if hour < 0 || hour > 23 {
+            return None;
+        }
+        Some(DateTime { hour: hour, ..*self })
+    }
+
+    /// Get the same date and time with a different minute.  Returns None
+    /// if the minute is not from 0 to 59.
+    pub fn with_minute(&self, minute: int) -> Option<DateTime> {
+        if minute < 0 || minute > 59 {
+            return None;
+        }
+        Some(DateTime { minute: minute, ..*self })
+    }
+
+    /// Get the same date and time with a different second.  Returns None
+    /// if the second is not from 0 to 59.
+    pub fn with_second(&self, second: int) -> Option<DateTime> {
+        if second < 0 || second > 59 {
+            return None;
+        }
+        Some(DateTime { second: second, ..*self })
+    }
+
+    /// Get the number of ticks since the epoch on the local time scale.
+    /// Fields outside their usual ranges are normalized, as mktime does,
+    /// so month 13 is January of the next year, and day 0 is the last day
+    /// of the previous month.  Returns None on overflow.
+    fn to_local_ticks(&self) -> Option<i64> {
+        let (dy, m0) = div_mod(self.month as i64 - 1, 12);
+        let year = (self.year as i64).saturating_add(dy);
+        let max_year = calendar_iso8601::MAX_YEAR as i64;
+        if year < -max_year || year > max_year {
+            return None;
+        }
+        let parts = [(self.day as i64 - 1, tick::DAY),
+                     (self.hour as i64, tick::HOUR),
+                     (self.minute as i64, tick::MINUTE),
+                     (self.second as i64, tick::SECOND),
+                     (self.subsec_ticks, 1)];
+        let cjd = calendar_iso8601::to_cjd(year as int, m0 as int + 1, 1);
+        let mut total = match (cjd as i64).checked_mul(&tick::DAY) {
+            Some(n) => n, None => return None
+        };
+        for &(n, unit) in parts.iter() {
+            total = match n.checked_mul(&unit)
+                           .and_then(|x| total.checked_add(&x)) {
+                Some(n) => n, None => return None
+            };
+        }
+        Some(total)
+    }
+}
+
+/// Convert a date and time in UTC to an instant, like timegm.  Fields
+/// outside their usual ranges are normalized.  Returns None if the result
+/// is out of range.
+pub fn civil_to_instant_utc(dt: &DateTime) -> Option<Instant> {
+    dt.to_local_ticks().map(|n| Instant::from_ticks(n))
+}
+
+/// Convert a local date and time in a zone to an instant, like mktime.
+/// Fields outside their usual ranges are normalized.  When the local time
+/// occurs twice, the earlier instant is chosen.  When the local time is
+/// skipped, it is moved forward by the length of the skipped interval, so
+/// 02:30 on a day when clocks move from 02:00 to 03:00 gives 03:30.
+/// Returns None if the result is out of range.
+pub fn civil_to_instant_local(dt: &DateTime, zone: &Zone)
+                              -> Option<Instant> {
+    let local = match dt.to_local_ticks() {
+        Some(n) => n, None => return None
+    };
+    let min: Instant = Bounded::min_value();
+    let max: Instant = Bounded::max_value();
+    // Offsets are less than a day, so this avoids overflow below.
+    if local <= min.ticks() + tick::DAY || local >= max.ticks() - tick::DAY {
+        return None;
+    }
+    Some(match zone.from_local(local) {
+        Single(t) => t,
+        Ambiguous(t, _) => t,
+        Skipped(t) => t
+    })
+}
+
+#[test]
+fn utc() {
+    fn test(dt: DateTime, expected: &str) {
+        let out = civil_to_instant_utc(&dt).map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != Some(expected) {
+            fail!("input: {}, expected: {}, output: {}", dt, expected, out);
+        }
+    }
+    test(DateTime::new(2014, 6, 10, 11, 12, 13, 0), "2014-06-10T11:12:13Z");
+    test(DateTime::new(2014, 13, 1, 0, 0, 0, 0), "2015-01-01T00:00:00Z");
+    test(DateTime::new(2014, 0, 1, 0, 0, 0, 0), "2013-12-01T00:00:00Z");
+    test(DateTime::new(2014, -12, 1, 0, 0, 0, 0), "2012-12-01T00:00:00Z");
+    test(DateTime::new(2014, 3, 0, 0, 0, 0, 0), "2014-02-28T00:00:00Z");
+    test(DateTime::new(2014, 2, 29, 0, 0, 0, 0), "2014-03-01T00:00:00Z");
+    test(DateTime::new(2014, 1, 1, 0, 0, 90, 0), "2014-01-01T00:01:30Z");
+    test(DateTime::new(2014, 1, 1, -1, 0, 0, 0), "2013-12-31T23:00:00Z");
+    test(DateTime::new(2014, 1, 1, 0, 0, 0, -tick::SECOND),
+         "2013-12-31T23:59:59Z");
+    assert!(civil_to_instant_utc(&DateTime::new(40000, 1, 1, 0, 0, 0, 0))
+            .is_none());
+    assert!(civil_to_instant_utc(&DateTime::new(2014, 1, 1, 0, 0, 0,
+                                                Bounded::max_value()))
+            .is_none());
+}
+
+#[test]
+fn resolve() {
+    fn test(dt: DateTime, policy: FieldPolicy, expected: Option<DateTime>) {
+        let out = dt.resolve(policy);
+        if out != expected {
+            fail!("input: {}, policy: {}, expected: {}, output: {}",
+                  dt, policy, expected, out);
+        }
+    }
+    let dt = DateTime::new(2014, 6, 10, 11, 12, 13, 5);
+    test(dt, Strict, Some(dt));
+    test(dt, Lenient, Some(dt));
+    test(dt, Overflowing, Some(dt));
+    let dt = DateTime::new(2014, 13, 1, 0, 0, 90, 0);
+    test(dt, Strict, None);
+    test(dt, Lenient, Some(DateTime::new(2014, 12, 1, 0, 0, 59, 0)));
+    test(dt, Overflowing, Some(DateTime::new(2015, 1, 1, 0, 1, 30, 0)));
+    let dt = DateTime::new(2014, 2, 30, 24, -5, 0, tick::SECOND);
+    test(dt, Strict, None);
+    test(dt, Lenient,
+         Some(DateTime::new(2014, 2, 28, 23, 0, 0, tick::SECOND - 1)));
+    test(dt, Overflowing, Some(DateTime::new(2014, 3, 2, 23, 55, 1, 0)));
+    test(DateTime::new(2012, 2, 30, 0, 0, 0, 0), Lenient,
+         Some(DateTime::new(2012, 2, 29, 0, 0, 0, 0)));
+    test(DateTime::new(2014, 0, 0, 0, 0, 0, 0), Lenient,
+         Some(DateTime::new(2014, 1, 1, 0, 0, 0, 0)));
+    test(DateTime::new(40000, 1, 1, 0, 0, 0, 0), Overflowing, None);
+    test(DateTime::new(2014, 1, 1, 0, 0, 0, -1), Strict, None);
+}
+
+#[test]
+fn with_fields() {
+    let dt = DateTime::new(2012, 2, 29, 11, 12, 13, 5);
+    assert_eq!(dt.with_year(2016),
+               Some(DateTime::new(2016, 2, 29, 11, 12, 13, 5)));
+    assert_eq!(dt.with_year(2014), None);
+    assert_eq!(dt.with_month(3),
+               Some(DateTime::new(2012, 3, 29, 11, 12, 13, 5)));
+    assert_eq!(dt.with_month(0), None);
+    assert_eq!(dt.with_day(1),
+               Some(DateTime::new(2012, 2, 1, 11, 12, 13, 5)));
+    assert_eq!(dt.with_day(30), None);
+    assert_eq!(dt.with_hour(0),
+               Some(DateTime::new(2012, 2, 29, 0, 12, 13, 5)));
+    assert_eq!(dt.with_hour(24), None);
+    assert_eq!(dt.with_minute(59),
+               Some(DateTime::new(2012, 2, 29, 11, 59, 13, 5)));
+    assert_eq!(dt.with_minute(-1), None);
+    assert_eq!(dt.with_second(0),
+               Some(DateTime::new(2012, 2, 29, 11, 12, 0, 5)));
+    assert_eq!(dt.with_second(60), None);
+}
+
+#[test]
+fn local() {
+    use zone;
+    let z = zone::test_zone();
+    fn test(z: &Zone, dt: DateTime, expected: &str) {
+        let out = civil_to_instant_local(&dt, z).map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != Some(expected) {
+            fail!("input: {}, expected: {}, output: {}", dt, expected, out);
+        }
+    }
+    test(&z, DateTime::new(2014, 1, 10, 12, 0, 0, 0), "2014-01-10T11:00:00Z");
+    test(&z, DateTime::new(2014, 6, 10, 12, 0, 0, 0), "2014-06-10T10:00:00Z");
+    // Skipped, moved forward to 03:30 CEST.
+    test(&z, DateTime::new(2014, 3, 30, 2, 30, 0, 0), "2014-03-30T01:30:00Z");
+    // Repeated, the first is 02:30 CEST.
+    test(&z, DateTime::new(2014, 10, 26, 2, 30, 0, 0),
+         "2014-10-26T00:30:00Z");
+    // Normalized into summer time.
+    test(&z, DateTime::new(2014, 3, 29, 36, 0, 0, 0), "2014-03-30T10:00:00Z");
+    assert!(civil_to_instant_local(&DateTime::new(-40000, 1, 1, 0, 0, 0, 0),
+                                   &z).is_none());
+}
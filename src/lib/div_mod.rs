@@ -1,13 +1,130 @@
+//! Integer division with rounding other than toward zero.
+//!
+//! The / and % operators round toward zero, which is rarely what date
+//! arithmetic needs: the day containing one tick before the epoch is day
+//! -1, not day 0.  These functions work for any sign of either operand,
+//! and fail on division by zero, like the operators.
+
+use std::i64;
 use std::num::one;
+use wide::{mul_wide, div_wide, magnitude};
 
-/// Compute integer division and modulus, rounding down.
-/// Contrast with div_rem.
+/// Compute integer division and modulus, rounding down.  The modulus has
+/// the same sign as the divisor.  Contrast with div_rem.
 pub fn div_mod<T: Signed>(x: T, y: T) -> (T, T) {
     let quot = x / y;
     let rem = x % y;
-    if rem.is_negative() {
+    if !rem.is_zero() && rem.is_negative() != y.is_negative() {
         (quot - one(), rem + y)
     } else {
         (quot, rem)
     }
 }
+
+/// Compute integer division, rounding up.
+pub fn div_ceil<T: Signed>(x: T, y: T) -> T {
+    let quot = x / y;
+    let rem = x % y;
+    if !rem.is_zero() && rem.is_negative() == y.is_negative() {
+        quot + one()
+    } else {
+        quot
+    }
+}
+
+/// Compute integer division, rounding to the nearest integer, and to the
+/// even integer if there are two.
+pub fn div_round<T: Signed>(x: T, y: T) -> T {
+    let (quot, rem) = div_mod(x, y);
+    if rem.is_zero() {
+        return quot;
+    }
+    // The remainder and the rest of the divisor have the same sign as the
+    // divisor, so neither subtraction nor comparison can overflow.
+    let rest = y - rem;
+    let (rem, rest) = if y.is_negative() { (rest, rem) } else { (rem, rest) };
+    let two: T = one::<T>() + one();
+    if rem > rest || (rem == rest && !(quot % two).is_zero()) {
+        quot + one()
+    } else {
+        quot
+    }
+}
+
+/// Compute x * y / z, rounding down, without overflow in the product.
+/// Returns None if the result is out of range.
+pub fn mul_div(x: i64, y: i64, z: i64) -> Option<i64> {
+    if z == 0 {
+        fail!("attempted to divide by zero");
+    }
+    let (hi, lo) = mul_wide(magnitude(x), magnitude(y));
+    let (quot, rem) = match div_wide(hi, lo, magnitude(z)) {
+        Some(r) => r, None => return None
+    };
+    if ((x < 0) != (y < 0)) != (z < 0) {
+        // Rounding down moves a negative result away from zero.
+        let limit = magnitude(i64::MIN);
+        if quot > limit || (quot == limit && rem != 0) {
+            return None;
+        }
+        let quot = if rem != 0 { quot + 1 } else { quot };
+        Some((!quot + 1) as i64)
+    } else if quot > i64::MAX as u64 {
+        None
+    } else {
+        Some(quot as i64)
+    }
+}
+
+#[test]
+fn signs() {
+    for x in range(-30i, 31) {
+        for y in range(-7i, 8) {
+            if y == 0 {
+                continue;
+            }
+            let (q, r) = div_mod(x, y);
+            assert_eq!(q * y + r, x);
+            assert!(r == 0 || (r < 0) == (y < 0));
+            assert!(r.abs() < y.abs());
+            let c = div_ceil(x, y);
+            let (f, _) = div_mod(-x, y);
+            assert_eq!(c, -f);
+            assert!(c == q || c == q + 1);
+            let n = div_round(x, y);
+            let d = (x - n * y).abs() * 2;
+            assert!(d <= y.abs());
+            if d == y.abs() {
+                assert_eq!(n % 2, 0);
+            }
+            for z in range(-7i64, 8) {
+                if z == 0 {
+                    continue;
+                }
+                let (p, _) = div_mod((x * y) as i64, z);
+                assert_eq!(mul_div(x as i64, y as i64, z), Some(p));
+            }
+        }
+    }
+}
+
+#[test]
+fn extremes() {
+    assert_eq!(div_mod(i64::MIN, 1), (i64::MIN, 0));
+    assert_eq!(div_mod(i64::MIN, i64::MAX), (-2, i64::MAX - 1));
+    assert_eq!(div_round(i64::MAX, i64::MIN), -1);
+    assert_eq!(div_round(i64::MIN, i64::MAX), -1);
+    assert_eq!(div_round(5i64, 2), 2);
+    assert_eq!(div_round(7i64, 2), 4);
+    assert_eq!(div_round(-5i64, 2), -2);
+    assert_eq!(mul_div(i64::MAX, i64::MAX, i64::MAX), Some(i64::MAX));
+    assert_eq!(mul_div(i64::MIN, i64::MAX, i64::MAX), Some(i64::MIN));
+    assert_eq!(mul_div(i64::MIN, i64::MIN, i64::MIN), Some(i64::MIN));
+    assert_eq!(mul_div(i64::MIN, -1, 1), None);
+    assert_eq!(mul_div(i64::MIN, 1, -1), None);
+    assert_eq!(mul_div(i64::MAX, 2, 1), None);
+    assert_eq!(mul_div(i64::MAX, 3, 2), None);
+    assert_eq!(mul_div(i64::MAX, 2, 3), Some(i64::MAX / 3 * 2));
+    assert_eq!(mul_div(i64::MAX, -2, 3), Some(-(i64::MAX / 3 * 2) - 1));
+    assert_eq!(mul_div(0, 5, -3), Some(0));
+}
@@ -1,11 +1,75 @@
 use libc::types::os::common::posix01::{timespec, timeval};
-use std::num::{Bounded, pow};
-use std::u64;
-use std::io::{MemWriter, IoResult};
+use std::num::{Bounded, FromPrimitive, ToPrimitive, pow};
+use std::cmp::min;
+use std::i64;
+use std::io::{Writer, MemWriter, BufWriter, IoResult};
 use std::fmt::{Show, Formatter, FormatError, WriteError};
 use std::from_str::FromStr;
 use tick;
 use fmtutil;
+use fmtutil::{Number, Text};
+use parseutil;
+use parse::{ParseOptions, DecimalPoint};
+use instant::Instant;
+use wide;
+
+/// The length of the longest duration written by Show and format_into().
+pub static FORMATTED_LEN: uint = 24;
+
+/// A unit of time which can be used to measure durations.  Minutes, hours,
+/// days, and weeks are standard units with a fixed number of seconds.
+#[deriving(PartialEq, Eq, Clone, Show, Hash)]
+pub enum TimeUnit {
+    Tick,
+    Microsecond,
+    Millisecond,
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week
+}
+
+impl TimeUnit {
+    /// Get the length of the unit, in ticks.
+    pub fn ticks(&self) -> i64 {
+        match *self {
+            Tick => 1,
+            Microsecond => tick::MICROSECOND,
+            Millisecond => tick::MILLISECOND,
+            Second => tick::SECOND,
+            Minute => tick::MINUTE,
+            Hour => tick::HOUR,
+            Day => tick::DAY,
+            Week => tick::DAY * 7
+        }
+    }
+}
+
+/// The direction to round when converting to a larger unit.
+#[deriving(PartialEq, Eq, Clone, Show, Hash)]
+pub enum RoundingMode {
+    /// Round to the nearest integer, with ties going to even.
+    Nearest,
+    /// Round towards negative infinity.
+    Floor,
+    /// Round towards positive infinity.
+    Ceiling,
+    /// Round towards zero.
+    TowardZero
+}
+
+/// The reason a POSIX timespec or timeval structure could not be
+/// converted.
+#[deriving(PartialEq, Eq, Clone, Show, Hash)]
+pub enum PosixTimeError {
+    /// The tv_nsec field is outside the range 0 to 999,999,999.
+    InvalidNanoseconds,
+    /// The tv_usec field is outside the range 0 to 999,999.
+    InvalidMicroseconds,
+    /// The fields are valid, but the result cannot be represented.
+    PosixTimeOverflow
+}
 
 /// An absolute amount of time, independent of time zones and calendars.
 /// A duration can express the positive or negative difference between two
@@ -62,25 +126,307 @@ impl Bounded for Duration {
     }
 }
 
+/// Conversion from a number of ticks.
+impl FromPrimitive for Duration {
+    fn from_i64(n: i64) -> Option<Duration> {
+        Some(Duration { ticks: n })
+    }
+
+    fn from_u64(n: u64) -> Option<Duration> {
+        n.to_i64().map(|n| Duration { ticks: n })
+    }
+}
+
+/// Conversion to a number of ticks.
+impl ToPrimitive for Duration {
+    fn to_i64(&self) -> Option<i64> {
+        Some(self.ticks)
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.ticks.to_u64()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.ticks as f64)
+    }
+}
+
 impl FromStr for Duration {
     fn from_str(s: &str) -> Option<Duration> {
-        // We accept strings of the format "PT<N>S", where <N> is a decimal
-        // number, possibly negative, possibly using a comma, and the
-        // remaining characters are case-insensitive.
+        Duration::parse_with(s, &ParseOptions::strict())
+    }
+}
 
-        let minval: i64 = Bounded::min_value();
-        let maxval: i64 = Bounded::max_value();
+/// The designators accepted in ISO 8601 durations, in the order they must
+/// appear, with the length of each unit in ticks and whether the
+/// component belongs after the "T".
+static DESIGNATORS: [(char, i64, bool), ..5] = [
+    ('W', 7 * tick::DAY, false),
+    ('D', tick::DAY, false),
+    ('H', tick::HOUR, true),
+    ('M', tick::MINUTE, true),
+    ('S', tick::SECOND, true)
+];
+
+/// A wrapper which formats a negative duration with the sign before the
+/// designators, as in "-PT1S", rather than the default "PT-1S".  Many
+/// ISO 8601 consumers only accept the leading sign.
+pub struct LeadingSign(pub Duration);
+
+/// A wrapper which formats a duration using the most suitable SI unit,
+/// as in "1.234ms", "56.7µs", or "3.21s", which is convenient for
+/// reporting measurements.  The formatter's precision gives the number of
+/// significant digits, which defaults to four, and trailing zeros after
+/// the decimal point are removed.  Durations of a second or longer are
+/// always reported in seconds.
+pub struct Scaled(pub Duration);
+
+impl Show for Duration {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        // Uses the ISO-8601 format for durations.
+        // See: http://en.wikipedia.org/wiki/ISO_8601#Durations
+        let datavec = match self.to_utf8_io(f.precision, false) {
+            Ok(x) => x,
+            Err(_) => return Err(WriteError)
+        };
+        fmtutil::write_padded(f, datavec.as_slice())
+    }
+}
 
-        let r = s;
-        let r = match r.slice_shift_char() {
+impl Show for LeadingSign {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        let LeadingSign(ref duration) = *self;
+        let datavec = match duration.to_utf8_io(f.precision, true) {
+            Ok(x) => x,
+            Err(_) => return Err(WriteError)
+        };
+        fmtutil::write_padded(f, datavec.as_slice())
+    }
+}
+
+impl Show for Scaled {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        let Scaled(ref duration) = *self;
+        let datavec = match duration.to_scaled_utf8_io(f.precision) {
+            Ok(x) => x,
+            Err(_) => return Err(WriteError)
+        };
+        fmtutil::write_padded(f, datavec.as_slice())
+    }
+}
+
+impl Duration {
+    /// Parse an ISO 8601 duration using the given options, such as
+    /// "PT1H30M" or "P1DT12H".  Weeks and days are standard weeks and
+    /// days, and years and months are not accepted, since they do not
+    /// have a fixed length.  Designators are case-insensitive, and the
+    /// sign may be written before the designators, as in "-PT1S", or
+    /// before the first component, as in "PT-1S".
+    ///
+    /// Strict parsing enforces the ISO 8601 rules: the components appear
+    /// in order, hours, minutes, and seconds follow a "T", at least one
+    /// component is present after "P" and after "T", only the last
+    /// component has a fraction, and weeks are not combined with other
+    /// components.  Lenient parsing accepts components in any order, hours
+    /// and seconds without the "T", an empty duration, such as "PT", and
+    /// fractions on any component, so "PT1.5H30M" is two hours.
+    pub fn parse_with(s: &str, opts: &ParseOptions) -> Option<Duration> {
+        match Duration::read(s, opts) {
+            Some((d, rem)) if rem.is_empty() => Some(d),
+            _ => None
+        }
+    }
+
+    /// Parse an ISO 8601 duration at the beginning of a string, using the
+    /// given options, and return it with the number of bytes it occupies,
+    /// so that it can be part of a larger grammar.  Parsing stops at the
+    /// first character which cannot continue the duration.
+    pub fn parse_prefix(s: &str, opts: &ParseOptions)
+                        -> Option<(Duration, uint)> {
+        Duration::read(s, opts).map(|(d, rem)| (d, s.len() - rem.len()))
+    }
+
+    /// Read a duration from the beginning of a string.  Returns the
+    /// duration and the remainder of the string.
+    fn read<'a>(s: &'a str, opts: &ParseOptions)
+                -> Option<(Duration, &'a str)> {
+        let (leading_negative, r) = match s.slice_shift_char() {
+            (Some('-'), r) => (true, r),
+            _ => (false, s)
+        };
+        let mut r = match r.slice_shift_char() {
             (Some(c), r) if c == 'P' || c == 'p' => r,
             _ => return None
         };
-        let r = match r.slice_shift_char() {
-            (Some(c), r) if c == 'T' || c == 't' => r,
+        let mut negative = leading_negative;
+        let mut in_time = false;
+        let mut seen = [false, ..5];
+        let mut last: Option<uint> = None;
+        let mut components = 0u;
+        let mut time_components = 0u;
+        let mut after_fraction = false;
+        let mut total = 0u64;
+        // Signed components are summed with 128 bits, so that only the
+        // final sum must be in range.
+        let mut signed = (0i64, 0u64);
+        loop {
+            match r.slice_shift_char() {
+                (Some(c), rem) if !in_time && (c == 'T' || c == 't') => {
+                    in_time = true;
+                    r = rem;
+                    continue;
+                }
+                // Anything else which cannot start a component ends the
+                // duration.
+                (Some(c), _) if c == '-' || (c >= '0' && c <= '9') => (),
+                _ => break
+            }
+            let (neg, ticks, has_fraction, index, rem) =
+                match Duration::read_component(r, opts) {
+                    Some(x) => x, None => return None
+                };
+            let (designator, _, is_time) = DESIGNATORS[index];
+            let misplaced_sign = !opts.signed_components &&
+                neg && (negative || components > 0);
+            if seen[index] || misplaced_sign ||
+                (!is_time && in_time) ||
+                (is_time && !in_time && (!opts.lenient || designator == 'M')) {
+                return None;
+            }
+            if !opts.lenient {
+                let in_order = match last {
+                    Some(i) => i < index, None => true
+                };
+                if !in_order || after_fraction ||
+                    (components > 0 && (designator == 'W' || seen[0])) {
+                    return None;
+                }
+            }
+            if opts.signed_components {
+                // A leading sign negates every component.
+                let value = if neg != negative {
+                    wide::neg_signed(0, ticks)
+                } else {
+                    (0, ticks)
+                };
+                signed = match wide::checked_add_signed(signed, value) {
+                    Some(n) => n, None => return None
+                };
+            } else {
+                total = match total.checked_add(&ticks) {
+                    Some(n) => n, None => return None
+                };
+                negative = negative || neg;
+            }
+            seen[index] = true;
+            last = Some(index);
+            components += 1;
+            if in_time {
+                time_components += 1;
+            }
+            after_fraction = after_fraction || has_fraction;
+            r = rem;
+        }
+        if !opts.lenient &&
+            (components == 0 || (in_time && time_components == 0)) {
+            return None;
+        }
+        if opts.signed_components {
+            let (hi, lo) = signed;
+            wide::narrow_signed(hi, lo).map(|n| (Duration { ticks: n }, r))
+        } else {
+            Duration::from_magnitude(negative, total).map(|d| (d, r))
+        }
+    }
+
+    /// Read one component of a duration, such as "1.5H", from the
+    /// beginning of a string.  The number may have a minus sign.  Returns
+    /// whether it is negative, its magnitude in ticks, whether it has a
+    /// fraction, the index of its designator in DESIGNATORS, and the
+    /// remainder of the string.
+    fn read_component<'a>(r: &'a str, opts: &ParseOptions)
+                          -> Option<(bool, u64, bool, uint, &'a str)> {
+        let (negative, r) = match r.slice_shift_char() {
+            (Some('-'), rem) => (true, rem),
+            _ => (false, r)
+        };
+        let len = match r.find(|c: char| !(c >= '0' && c <= '9')) {
+            Some(i) => i, None => return None
+        };
+        if len == 0 {
+            return None;
+        }
+        let n = match from_str::<u64>(r.slice_to(len)) {
+            Some(n) => n, None => return None
+        };
+        let r = r.slice_from(len);
+        let b = r.as_bytes();
+        let has_fraction =
+            parseutil::starts_with_fraction(b, opts.decimal_mark);
+        let frac_len = if has_fraction {
+            1 + b.slice_from(1).iter()
+                .take_while(|&&c| c >= '0' as u8 && c <= '9' as u8)
+                .count()
+        } else {
+            0
+        };
+        let (index, rem) = match r.slice_from(frac_len).slice_shift_char() {
+            (Some(c), rem) => {
+                let c = c.to_uppercase();
+                match DESIGNATORS.iter().position(|&(d, _, _)| d == c) {
+                    Some(i) => (i, rem), None => return None
+                }
+            }
             _ => return None
         };
+        let (_, unit, _) = DESIGNATORS[index];
+        let frac = if has_fraction {
+            match parseutil::read_fraction(b, unit, opts.decimal_mark) {
+                Some((n, _)) => n as u64, None => return None
+            }
+        } else {
+            0
+        };
+        n.checked_mul(&(unit as u64))
+            .and_then(|n| n.checked_add(&frac))
+            .map(|n| (negative, n, has_fraction, index, rem))
+    }
+
+    /// Get the number of ticks as an i64.
+    #[inline]
+    pub fn as_ticks_i64(&self) -> i64 {
+        self.ticks
+    }
+
+    /// Get the number of ticks as an f64, which may round durations longer
+    /// than about 28 years.
+    #[inline]
+    pub fn as_ticks_f64(&self) -> f64 {
+        self.ticks as f64
+    }
+
+    /// Parse a number of seconds written as a plain decimal number, such
+    /// as "12.345" or "-0.5", without the ISO 8601 designators.  Only a
+    /// decimal point is accepted, and the number is rounded to the nearest
+    /// tick in the same way as the ISO 8601 parser.
+    pub fn from_decimal_seconds_str(s: &str) -> Option<Duration> {
+        let opts = ParseOptions {
+            decimal_mark: DecimalPoint,
+            ..ParseOptions::strict()
+        };
+        match Duration::read_seconds(s, &opts) {
+            Some((negative, ticks, r)) if r.is_empty() =>
+                Duration::from_magnitude(negative, ticks),
+            _ => None
+        }
+    }
 
+    /// Read a decimal number of seconds, possibly negative, from the
+    /// beginning of a string.  Returns whether it is negative, its
+    /// magnitude in ticks, and the remainder of the string.
+    fn read_seconds<'a>(r: &'a str, opts: &ParseOptions)
+                        -> Option<(bool, u64, &'a str)> {
         let (negative, r) = match r.slice_shift_char() {
             (Some(c), rem) => if c == '-' { (true, rem) } else { (false, r) },
             _ => return None
@@ -88,7 +434,7 @@ impl FromStr for Duration {
 
         let (sec_part, r) = {
             let len = match r.find(|c: char| !(c >= '0' && c <= '9')) {
-                Some(i) => i, None => return None
+                Some(i) => i, None => r.len()
             };
             if len == 0 {
                 return None
@@ -102,46 +448,31 @@ impl FromStr for Duration {
             (n, r.slice_from(len))
         };
 
-        let (tick_part, r) = match r.slice_shift_char() {
-            (Some(c), rem) if c == '.' || c == ',' => {
-                let len = match rem.find(|c: char| !(c >= '0' && c <= '9')) {
-                    Some(i) => i, None => return None
-                };
-                if len == 0 {
-                    return None
+        let (tick_part, r) = {
+            let b = r.as_bytes();
+            if parseutil::starts_with_fraction(b, opts.decimal_mark) {
+                match parseutil::read_fraction(b, tick::SECOND,
+                                               opts.decimal_mark) {
+                    Some((n, rem)) =>
+                        (n as u64, r.slice_from(r.len() - rem.len())),
+                    None => return None
                 }
-                let tick_part = if len <= 7 {
-                    match from_str::<u64>(rem.slice_to(len)) {
-                        Some(n) => n * pow(10u64, 7 - len),
-                        None => return None
-                    }
-                } else {
-                    let n = match from_str::<u64>(rem.slice_to(7)) {
-                        Some(n) => n, None => return None
-                    };
-                    if rem.char_at(7) == '5' &&
-                        rem.slice(8, len).chars().all(|c| c == '0') {
-                        n + (n & 1)
-                    } else if rem.char_at(7) >= '5' {
-                        n + 1
-                    } else {
-                        n
-                    }
-                };
-                (tick_part, rem.slice_from(len))
+            } else {
+                (0u64, r)
             }
-            _ => (0u64, r)
         };
 
-        match r.slice_shift_char() {
-            (Some(c), r) if (c == 'S' || c == 's') && r.is_empty() => {}
-            _ => return None
+        match sec_part.checked_add(&tick_part) {
+            Some(n) => Some((negative, n, r)),
+            None => None
         }
+    }
 
-        let ticks = match sec_part.checked_add(&tick_part) {
-            Some(n) => n, None => return None
-        };
-
+    /// Create a duration from a sign and a magnitude in ticks.  Returns
+    /// None if the result is out of range.
+    fn from_magnitude(negative: bool, ticks: u64) -> Option<Duration> {
+        let minval: i64 = Bounded::min_value();
+        let maxval: i64 = Bounded::max_value();
         if negative {
             if ticks > minval as u64 {
                 None
@@ -156,26 +487,20 @@ impl FromStr for Duration {
             }
         }
     }
-}
-
-impl Show for Duration {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
-        // Uses the ISO-8601 format for durations.
-        // See: http://en.wikipedia.org/wiki/ISO_8601#Durations
-        let datavec = match self.to_utf8_io(f.precision) {
-            Ok(x) => x,
-            Err(_) => return Err(WriteError)
-        };
-        fmtutil::write_field(f, datavec.as_slice())
-    }
-}
 
-impl Duration {
     /// Convert a duration to a UTF-8 vector, used to implement Show.
-    /// The IoResult is a convenience so we can use try!().
-    fn to_utf8_io(&self, precision: Option<uint>) -> IoResult<Vec<u8>> {
+    /// The IoResult is a convenience so we can use try!().  If
+    /// leading_sign is set, the sign is written before the designators.
+    fn to_utf8_io(&self, precision: Option<uint>, leading_sign: bool)
+                  -> IoResult<Vec<u8>> {
         let mut w = MemWriter::with_capacity(32);
-        try!(w.write_str("PT"))
+        try!(self.write_utf8(&mut w, precision, leading_sign));
+        Ok(w.unwrap())
+    }
+
+    /// Write a duration as UTF-8, as for to_utf8_io().
+    fn write_utf8(&self, w: &mut Writer, precision: Option<uint>,
+                  leading_sign: bool) -> IoResult<()> {
         let (negative, mag) = if self.ticks >= 0 {
             (false, self.ticks as u64)
         } else {
@@ -206,76 +531,322 @@ impl Duration {
                 }
             }
         };
-        if negative && significand > 0 {
+        if negative && significand > 0 && leading_sign {
+            try!(w.write_char('-'));
+        }
+        try!(w.write_str("PT"))
+        if negative && significand > 0 && !leading_sign {
             try!(w.write_char('-'));
         }
-        try!(u64::to_str_bytes(significand, 10, |v| {
-            if v.len() <= dotpos {
-                try!(w.write_str("0.0000000".slice_to(2 + dotpos - v.len())));
-                try!(w.write(v));
+        let scale = pow(10u64, dotpos);
+        let segments = [Number(significand / scale, 1, '0'), Text("."),
+                        Number(significand % scale, dotpos, '0')];
+        let count = if dotpos > 0 { 3 } else { 1 };
+        try!(fmtutil::write_segments(w, segments.slice_to(count)));
+        for _ in range(0, zeroes) {
+            try!(w.write_char('0'));
+        }
+        w.write_char('S')
+    }
+
+    /// Write the duration in the format used by Show, such as "PT1.5S",
+    /// into a buffer without allocating.  Returns the number of bytes
+    /// written, or None if the buffer is too small, in which case its
+    /// contents are unspecified.  A buffer of FORMATTED_LEN bytes is large
+    /// enough for any duration.
+    pub fn format_into(&self, buf: &mut [u8]) -> Option<uint> {
+        let mut w = BufWriter::new(buf);
+        match self.write_utf8(&mut w, None, false) {
+            Ok(()) => w.tell().ok().map(|n| n as uint),
+            Err(_) => None
+        }
+    }
+
+    /// Convert a duration to a UTF-8 vector using an SI unit, used to
+    /// implement Show for Scaled.
+    fn to_scaled_utf8_io(&self, precision: Option<uint>)
+                         -> IoResult<Vec<u8>> {
+        // The name of each unit, its length, and the number of digits
+        // after the decimal point which are needed to show a single unit
+        // of the value.  Nanoseconds are measured in hundredths of a tick.
+        static UNITS: [(&'static str, u64, uint), ..4] = [
+            ("ns", 1, 0),
+            ("µs", tick::MICROSECOND as u64, 1),
+            ("ms", tick::MILLISECOND as u64, 4),
+            ("s", tick::SECOND as u64, 7)
+        ];
+        let mut w = MemWriter::with_capacity(32);
+        let sig = match precision {
+            Some(p) => if p > 0 { p } else { 1 },
+            None => 4
+        };
+        let (negative, mag) = if self.ticks >= 0 {
+            (false, self.ticks as u64)
+        } else {
+            (true, -self.ticks as u64)
+        };
+        if mag == 0 {
+            try!(w.write_str("0s"));
+            return Ok(w.unwrap());
+        }
+        let mut i = if mag < tick::MICROSECOND as u64 {
+            0
+        } else if mag < tick::MILLISECOND as u64 {
+            1
+        } else if mag < tick::SECOND as u64 {
+            2
+        } else {
+            3
+        };
+        loop {
+            let (name, per_unit, max_dec) = UNITS[i];
+            let value = if i == 0 { mag * 100 } else { mag };
+            let mut ival = value / per_unit;
+            let mut intdigits = 1u;
+            let mut n = ival / 10;
+            while n > 0 {
+                intdigits += 1;
+                n /= 10;
+            }
+            let dec = if sig > intdigits {
+                min(sig - intdigits, max_dec)
             } else {
-                try!(w.write(v.slice_to(v.len() - dotpos)));
-                if dotpos > 0 {
-                    try!(w.write_char('.'));
-                    try!(w.write(v.slice_from(v.len() - dotpos)));
+                0
+            };
+            let scale = pow(10u64, dec);
+            let fracnum = (value % per_unit) * scale;
+            let mut frac = fracnum / per_unit;
+            let rem = fracnum % per_unit;
+            let odd = if dec > 0 { frac & 1 } else { ival & 1 };
+            if rem * 2 > per_unit || (rem * 2 == per_unit && odd == 1) {
+                frac += 1;
+                if frac == scale {
+                    frac = 0;
+                    ival += 1;
                 }
             }
-            for _ in range(0, zeroes) {
-                try!(w.write_char('0'));
+            if ival >= 1000 && i < UNITS.len() - 1 {
+                i += 1;
+                continue;
             }
-            Ok(())
-        }));
-        try!(w.write_char('S'));
-        Ok(w.unwrap())
+            if negative {
+                try!(w.write_char('-'));
+            }
+            try!(write!(w, "{}", ival));
+            let mut digits = [0u8, ..7];
+            for j in range(0, dec).rev() {
+                digits[j] = '0' as u8 + (frac % 10) as u8;
+                frac /= 10;
+            }
+            let mut len = dec;
+            while len > 0 && digits[len - 1] == '0' as u8 {
+                len -= 1;
+            }
+            if len > 0 {
+                try!(w.write_char('.'));
+                try!(w.write(digits.slice_to(len)));
+            }
+            try!(w.write_str(name));
+            return Ok(w.unwrap());
+        }
+    }
+
+    /// Create a duration from a number of standard days, standard hours,
+    /// standard minutes, seconds, and ticks.  The components may have
+    /// different signs.  Returns None if any component or the total
+    /// overflows.
+    pub fn new(days: i64, hours: i64, minutes: i64, seconds: i64,
+               ticks: i64) -> Option<Duration> {
+        let parts = [(days, tick::DAY), (hours, tick::HOUR),
+                     (minutes, tick::MINUTE), (seconds, tick::SECOND),
+                     (ticks, 1)];
+        let mut total: i64 = 0;
+        for &(n, unit) in parts.iter() {
+            total = match n.checked_mul(&unit)
+                           .and_then(|x| total.checked_add(&x)) {
+                Some(x) => x, None => return None
+            };
+        }
+        Some(Duration { ticks: total })
+    }
+
+    /// Get the duration from one instant to another, which is negative if
+    /// the second instant is earlier, so that a + Duration::between(a, b)
+    /// is b.  Overflow is only detected by a debug assertion.  Use
+    /// checked_between() to handle it.
+    pub fn between(a: Instant, b: Instant) -> Duration {
+        debug_assert!(Duration::checked_between(a, b).is_some());
+        Duration { ticks: b.ticks() - a.ticks() }
+    }
+
+    /// Get the duration from one instant to another.  Returns None on
+    /// overflow, which is only possible for instants more than about
+    /// 29,000 years apart.
+    pub fn checked_between(a: Instant, b: Instant) -> Option<Duration> {
+        b.ticks().checked_sub(&a.ticks()).map(|x| Duration { ticks: x })
     }
 
-    /// Convert from microseconds to a duration.  No overflow protection.
+    /// Create a duration from a number of standard hours, standard
+    /// minutes, and seconds.  Returns None on overflow.
+    pub fn from_hms(hours: i64, minutes: i64, seconds: i64)
+                    -> Option<Duration> {
+        Duration::new(0, hours, minutes, seconds, 0)
+    }
+
+    /// Convert from nanoseconds to a duration, rounding to the nearest
+    /// tick, with ties going to even.  This cannot overflow.
+    pub fn from_nanoseconds(n: i64) -> Duration {
+        Duration { ticks: tick::from_nsec(n) }
+    }
+
+    /// Convert from microseconds to a duration.  Overflow is only detected by
+    /// a debug assertion.  Use checked_from_microseconds() to handle it.
     pub fn from_microseconds(n: i64) -> Duration {
+        debug_assert!(Duration::checked_from_microseconds(n).is_some());
         Duration { ticks: tick::MICROSECOND * n }
     }
 
-    /// Convert from milliseconds to a duration.  No overflow protection.
+    /// Convert from microseconds to a duration.  Returns None on overflow.
+    pub fn checked_from_microseconds(n: i64) -> Option<Duration> {
+        Duration::from_unit(n, Microsecond)
+    }
+
+    /// Convert from milliseconds to a duration.  Overflow is only detected by
+    /// a debug assertion.  Use checked_from_milliseconds() to handle it.
     pub fn from_milliseconds(n: i64) -> Duration {
+        debug_assert!(Duration::checked_from_milliseconds(n).is_some());
         Duration { ticks: tick::MILLISECOND * n }
     }
 
-    /// Convert from seconds to a duration.  No overflow protection.
+    /// Convert from milliseconds to a duration.  Returns None on overflow.
+    pub fn checked_from_milliseconds(n: i64) -> Option<Duration> {
+        Duration::from_unit(n, Millisecond)
+    }
+
+    /// Convert from seconds to a duration.  Overflow is only detected by a
+    /// debug assertion.  Use checked_from_seconds() to handle it.
     pub fn from_seconds(n: i64) -> Duration {
+        debug_assert!(Duration::checked_from_seconds(n).is_some());
         Duration { ticks: tick::SECOND * n }
     }
 
-    /// Convert from standard minutes to a duration.  No overflow protection.
+    /// Convert from seconds to a duration.  Returns None on overflow.
+    pub fn checked_from_seconds(n: i64) -> Option<Duration> {
+        Duration::from_unit(n, Second)
+    }
+
+    /// Convert from standard minutes to a duration.  Overflow is only detected
+    /// by a debug assertion.  Use checked_from_minutes() to handle it.
     pub fn from_minutes(n: i64) -> Duration {
+        debug_assert!(Duration::checked_from_minutes(n).is_some());
         Duration { ticks: tick::MINUTE * n }
     }
 
-    /// Convert from standard hours to a duration.  No overflow protection.
+    /// Convert from standard minutes to a duration.  Returns None on overflow.
+    pub fn checked_from_minutes(n: i64) -> Option<Duration> {
+        Duration::from_unit(n, Minute)
+    }
+
+    /// Convert from standard hours to a duration.  Overflow is only detected
+    /// by a debug assertion.  Use checked_from_hours() to handle it.
     pub fn from_hours(n: i64) -> Duration {
+        debug_assert!(Duration::checked_from_hours(n).is_some());
         Duration { ticks: tick::HOUR * n }
     }
 
-    /// Convert from standard days to a duration.  No overflow protection.
+    /// Convert from standard hours to a duration.  Returns None on overflow.
+    pub fn checked_from_hours(n: i64) -> Option<Duration> {
+        Duration::from_unit(n, Hour)
+    }
+
+    /// Convert from standard days to a duration.  Overflow is only detected
+    /// by a debug assertion.  Use checked_from_standard_days() to handle it.
     pub fn from_standard_days(n: i64) -> Duration {
+        debug_assert!(Duration::checked_from_standard_days(n).is_some());
         Duration { ticks: tick::DAY * n }
     }
 
-    /// Convert from standard weeks to a duration.  No overflow protection.
+    /// Convert from standard days to a duration.  Returns None on overflow.
+    pub fn checked_from_standard_days(n: i64) -> Option<Duration> {
+        Duration::from_unit(n, Day)
+    }
+
+    /// Convert from standard weeks to a duration.  Overflow is only detected
+    /// by a debug assertion.  Use checked_from_standard_weeks() to handle it.
     pub fn from_standard_weeks(n: i64) -> Duration {
+        debug_assert!(Duration::checked_from_standard_weeks(n).is_some());
         Duration { ticks: tick::DAY * 7 * n }
     }
 
-    /// Convert from a POSIX timeval structure.
+    /// Convert from standard weeks to a duration.  Returns None on overflow.
+    pub fn checked_from_standard_weeks(n: i64) -> Option<Duration> {
+        Duration::from_unit(n, Week)
+    }
+
+    /// Convert from a number of the given units to a duration.  Returns
+    /// None on overflow.
+    pub fn from_unit(n: i64, unit: TimeUnit) -> Option<Duration> {
+        n.checked_mul(&unit.ticks()).map(|x| Duration { ticks: x })
+    }
+
+    /// Multiply by an integer.  Returns None on overflow.
+    pub fn checked_mul_i64(&self, n: i64) -> Option<Duration> {
+        self.ticks.checked_mul(&n).map(|x| Duration { ticks: x })
+    }
+
+    /// Convert to a whole number of the given units, rounding in the given
+    /// direction.
+    pub fn to_unit(&self, unit: TimeUnit, mode: RoundingMode) -> i64 {
+        let u = unit.ticks();
+        match mode {
+            Nearest => tick::to_larger_unit(self.ticks, u),
+            Floor => tick::to_larger_unit_floor(self.ticks, u),
+            Ceiling => tick::to_larger_unit_ceil(self.ticks, u),
+            TowardZero => self.ticks / u
+        }
+    }
+
+    /// Convert from a POSIX timespec structure measuring a span of time.
+    /// Nanoseconds are rounded to the nearest tick, with ties going to
+    /// even.  Returns None if the result is out of range.
     pub fn from_timespec(value: &timespec) -> Option<Duration> {
         tick::from_sec_nsec(value.tv_sec, value.tv_nsec)
             .map(|n| Duration { ticks: n })
     }
 
-    /// Convert from a POSIX timespec structure.
+    /// Convert from a POSIX timeval structure measuring a span of time.
+    /// Returns None if the result is out of range.
     pub fn from_timeval(value: &timeval) -> Option<Duration> {
         tick::from_sec_usec(value.tv_sec, value.tv_usec as i64)
             .map(|n| Duration { ticks: n })
     }
 
+    /// Convert from a POSIX timespec structure, checking that the
+    /// nanoseconds are in range.
+    pub fn try_from_timespec(value: &timespec)
+                             -> Result<Duration, PosixTimeError> {
+        if value.tv_nsec < 0 || value.tv_nsec >= 1000000000 {
+            return Err(InvalidNanoseconds);
+        }
+        Duration::from_timespec(value).ok_or(PosixTimeOverflow)
+    }
+
+    /// Convert from a POSIX timeval structure, checking that the
+    /// microseconds are in range.
+    pub fn try_from_timeval(value: &timeval)
+                            -> Result<Duration, PosixTimeError> {
+        if value.tv_usec < 0 || value.tv_usec >= 1000000 {
+            return Err(InvalidMicroseconds);
+        }
+        Duration::from_timeval(value).ok_or(PosixTimeOverflow)
+    }
+
+    /// Convert to nanoseconds, which is exact.  Returns None if the result
+    /// is out of range, which is about ±292 years.
+    pub fn to_nanoseconds(&self) -> Option<i64> {
+        tick::to_nsec(self.ticks)
+    }
+
     /// Convert to whole seconds, with rounding.
     pub fn to_seconds(&self) -> i64 {
         tick::to_sec(self.ticks)
@@ -291,6 +862,59 @@ impl Duration {
         tick::to_usec(self.ticks)
     }
 
+    /// Convert to whole seconds, rounding down.
+    pub fn to_seconds_floor(&self) -> i64 {
+        tick::to_sec_floor(self.ticks)
+    }
+
+    /// Convert to whole seconds, rounding up.  This is appropriate for
+    /// timeouts, which must not expire early.
+    pub fn to_seconds_ceil(&self) -> i64 {
+        tick::to_sec_ceil(self.ticks)
+    }
+
+    /// Convert to whole milliseconds, rounding down.
+    pub fn to_milliseconds_floor(&self) -> i64 {
+        tick::to_msec_floor(self.ticks)
+    }
+
+    /// Convert to whole milliseconds, rounding up.
+    pub fn to_milliseconds_ceil(&self) -> i64 {
+        tick::to_msec_ceil(self.ticks)
+    }
+
+    /// Convert to whole microseconds, rounding down.
+    pub fn to_microseconds_floor(&self) -> i64 {
+        tick::to_usec_floor(self.ticks)
+    }
+
+    /// Convert to whole microseconds, rounding up.
+    pub fn to_microseconds_ceil(&self) -> i64 {
+        tick::to_usec_ceil(self.ticks)
+    }
+
+    /// Convert to a timeout in whole milliseconds, for APIs which take an
+    /// unsigned number of milliseconds.  Partial milliseconds are rounded
+    /// up, so the timeout never expires early.  Returns None if the
+    /// duration is negative.
+    pub fn to_timeout_millis(&self) -> Option<u64> {
+        if self.ticks < 0 {
+            None
+        } else {
+            Some(tick::to_msec_ceil(self.ticks) as u64)
+        }
+    }
+
+    /// Create a duration from a timeout in milliseconds.  Returns None if
+    /// the result is out of range.
+    pub fn from_timeout_millis(n: u64) -> Option<Duration> {
+        if n > i64::MAX as u64 {
+            return None;
+        }
+        (n as i64).checked_mul(&tick::MILLISECOND)
+            .map(|ticks| Duration { ticks: ticks })
+    }
+
     /// Convert to a POSIX timespec structure.
     pub fn to_timespec(&self) -> timespec {
         let (sec, nsec) = tick::to_sec_nsec(self.ticks);
@@ -304,6 +928,97 @@ impl Duration {
     }
 }
 
+/// A duration which is never negative, for quantities such as timeouts
+/// and expiry intervals.  Every unsigned duration is also a valid duration.
+#[deriving(PartialEq, PartialOrd, Ord, Eq, Clone, Zero, Default, Hash)]
+pub struct UnsignedDuration {
+    ticks: i64
+}
+
+/// Addition saturates at the largest duration, so that the result is never
+/// negative.  Use checked_add() to detect overflow.
+impl Add<UnsignedDuration, UnsignedDuration> for UnsignedDuration {
+    fn add(&self, rhs: &UnsignedDuration) -> UnsignedDuration {
+        self.saturating_add(*rhs)
+    }
+}
+
+impl Bounded for UnsignedDuration {
+    fn min_value() -> UnsignedDuration {
+        UnsignedDuration { ticks: 0 }
+    }
+
+    fn max_value() -> UnsignedDuration {
+        UnsignedDuration { ticks: Bounded::max_value() }
+    }
+}
+
+impl Show for UnsignedDuration {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        self.to_duration().fmt(f)
+    }
+}
+
+impl UnsignedDuration {
+    /// Convert a duration.  Returns None if it is negative.
+    pub fn new(d: Duration) -> Option<UnsignedDuration> {
+        if d.ticks < 0 {
+            None
+        } else {
+            Some(UnsignedDuration { ticks: d.ticks })
+        }
+    }
+
+    /// Convert a duration, replacing negative durations with zero.
+    pub fn clamped(d: Duration) -> UnsignedDuration {
+        UnsignedDuration { ticks: if d.ticks < 0 { 0 } else { d.ticks } }
+    }
+
+    /// Get the number of ticks.
+    #[inline]
+    pub fn ticks(&self) -> u64 {
+        self.ticks as u64
+    }
+
+    /// Convert to a duration.
+    #[inline]
+    pub fn to_duration(&self) -> Duration {
+        Duration { ticks: self.ticks }
+    }
+
+    /// Add two durations.  Returns None on overflow.
+    pub fn checked_add(&self, rhs: UnsignedDuration)
+                       -> Option<UnsignedDuration> {
+        self.ticks.checked_add(&rhs.ticks)
+            .map(|n| UnsignedDuration { ticks: n })
+    }
+
+    /// Add two durations, stopping at the largest duration.
+    pub fn saturating_add(&self, rhs: UnsignedDuration) -> UnsignedDuration {
+        self.checked_add(rhs).unwrap_or(Bounded::max_value())
+    }
+
+    /// Subtract a duration.  Returns None if the result would be negative.
+    pub fn checked_sub(&self, rhs: UnsignedDuration)
+                       -> Option<UnsignedDuration> {
+        if rhs.ticks > self.ticks {
+            None
+        } else {
+            Some(UnsignedDuration { ticks: self.ticks - rhs.ticks })
+        }
+    }
+
+    /// Subtract a duration, stopping at zero.
+    pub fn saturating_sub(&self, rhs: UnsignedDuration) -> UnsignedDuration {
+        self.checked_sub(rhs).unwrap_or(UnsignedDuration { ticks: 0 })
+    }
+
+    /// Convert to a timeout in whole milliseconds, rounding up.
+    pub fn to_timeout_millis(&self) -> u64 {
+        tick::to_msec_ceil(self.ticks) as u64
+    }
+}
+
 #[cfg(test)]
 fn test_format_1(d: i64, s: &str) -> () {
     let out = format!("{}", Duration { ticks: d });
@@ -350,6 +1065,27 @@ fn test_format() {
     test_format_1(Bounded::min_value(), "PT-922337203685.4775808S");
 }
 
+#[test]
+fn test_format_into() {
+    fn test(d: i64) {
+        let expected = format!("{}", Duration { ticks: d });
+        let mut buf = [0u8, ..FORMATTED_LEN];
+        let n = Duration { ticks: d }.format_into(&mut buf).unwrap();
+        assert_eq!(buf.slice_to(n), expected.as_bytes());
+        let mut short = [0u8, ..40];
+        assert_eq!(Duration { ticks: d }.format_into(short.mut_slice_to(n)),
+                   Some(n));
+        assert_eq!(Duration { ticks: d }
+                   .format_into(short.mut_slice_to(n - 1)), None);
+    }
+    test(0);
+    test(-1);
+    test(tick::DAY * 7);
+    test(Bounded::max_value());
+    test(Bounded::min_value());
+    assert_eq!(Duration { ticks: 0 }.format_into(&mut []), None);
+}
+
 #[cfg(test)]
 fn test_roundtrip_1(n: Duration) {
     let s = format!("{}", n);
@@ -433,6 +1169,10 @@ fn test_parsefail() {
     test_parsefail_1("P0S");
     test_parsefail_1("T0S");
     test_parsefail_1("PT0");
+    test_parsefail_1("-PT-1S");
+    test_parsefail_1("--PT1S");
+    test_parsefail_1("+PT1S");
+    test_parsefail_1("-P");
 
     // Overflow causes parse failure
     test_parsefail_1("PT922337203685.4775808S");
@@ -462,6 +1202,10 @@ fn test_parse() {
     test_parse_1("PT-0S", 0);
     test_parse_1("pt0s", 0);
     test_parse_1("PT0.0000000S", 0);
+    test_parse_1("-PT0S", 0);
+    test_parse_1("-PT1S", -tick::SECOND);
+    test_parse_1("-pt1.5s", -tick::SECOND * 3 / 2);
+    test_parse_1("-PT922337203685.4775808S", Bounded::min_value());
     test_parse_1("PT0.000000000000000000S", 0);
 
     // Test rounding digits beyond the precision actually stored
@@ -475,3 +1219,481 @@ fn test_parse() {
     test_parse_1("PT0.000000149999999999999999999999999999999999S", 1);
     test_parse_1("PT0.00000015S", 2);
 }
+
+#[test]
+fn test_parse_designators() {
+    test_parse_1("PT1H", tick::HOUR);
+    test_parse_1("PT1H30M", tick::HOUR + tick::MINUTE * 30);
+    test_parse_1("PT1M1.5S", tick::MINUTE + tick::SECOND * 3 / 2);
+    test_parse_1("P1D", tick::DAY);
+    test_parse_1("p1dt12h", tick::DAY + tick::HOUR * 12);
+    test_parse_1("P2W", tick::DAY * 14);
+    test_parse_1("PT0.5H", tick::MINUTE * 30);
+    test_parse_1("PT1H0.5M", tick::HOUR + tick::SECOND * 30);
+    test_parse_1("P1.5D", tick::HOUR * 36);
+    test_parse_1("-PT1H30M", -tick::HOUR - tick::MINUTE * 30);
+    test_parse_1("PT-1H30M", -tick::HOUR - tick::MINUTE * 30);
+
+    test_parsefail_1("P");
+    test_parsefail_1("PT");
+    test_parsefail_1("P1DT");
+    test_parsefail_1("P1Y");
+    test_parsefail_1("P1M");
+    test_parsefail_1("P1H");
+    test_parsefail_1("PT1D");
+    test_parsefail_1("PT30M1H");
+    test_parsefail_1("PT1H1H");
+    test_parsefail_1("PT1HT1M");
+    test_parsefail_1("PT1H-30M");
+    test_parsefail_1("PT1.5H0.5M");
+    test_parsefail_1("PT1.5H30M");
+    test_parsefail_1("P1.5DT1H");
+    test_parsefail_1("PT0.5M0S");
+    test_parsefail_1("P1W1D");
+    test_parsefail_1("P1WT1H");
+    test_parsefail_1("PT1H30");
+    test_parsefail_1("PT1HM");
+}
+
+#[test]
+fn test_parse_lenient() {
+    let lenient = ParseOptions::lenient();
+    fn test(opts: &ParseOptions, s: &str, d: Option<i64>) {
+        let out = Duration::parse_with(s, opts).map(|d| d.ticks);
+        if out != d {
+            fail!("input: '{}', expected: {}, output: {}", s, d, out);
+        }
+    }
+    test(&lenient, "PT1S", Some(tick::SECOND));
+    test(&lenient, "PT30M1H", Some(tick::HOUR + tick::MINUTE * 30));
+    test(&lenient, "P1H", Some(tick::HOUR));
+    test(&lenient, "P1S", Some(tick::SECOND));
+    test(&lenient, "P1W1D", Some(tick::DAY * 8));
+    test(&lenient, "PT1.5H0.5M", Some(tick::MINUTE * 90 + tick::SECOND * 30));
+    test(&lenient, "PT1.5H30M", Some(tick::HOUR * 2));
+    test(&lenient, "P0.5DT1H", Some(tick::HOUR * 13));
+    test(&lenient, "PT", Some(0));
+    test(&lenient, "P", Some(0));
+    test(&lenient, "P1M", None);
+    test(&lenient, "PT1H1H", None);
+    test(&lenient, "PT1D", None);
+    test(&lenient, "-PT-1S", None);
+    test(&ParseOptions::strict(), "PT30M1H", None);
+}
+
+#[test]
+fn test_checked_from_units() {
+    let max: i64 = Bounded::max_value();
+    let min: i64 = Bounded::min_value();
+    assert_eq!(Duration::checked_from_seconds(90),
+               Some(Duration { ticks: tick::SECOND * 90 }));
+    assert_eq!(Duration::checked_from_standard_weeks(-1),
+               Some(Duration { ticks: -tick::DAY * 7 }));
+    assert_eq!(Duration::checked_from_microseconds(max / 10),
+               Some(Duration { ticks: max / 10 * 10 }));
+    assert_eq!(Duration::checked_from_microseconds(max / 10 + 1), None);
+    assert_eq!(Duration::checked_from_milliseconds(max), None);
+    assert_eq!(Duration::checked_from_seconds(max / 1000), None);
+    assert_eq!(Duration::checked_from_minutes(min), None);
+    assert_eq!(Duration::checked_from_hours(1 << 40), None);
+    assert_eq!(Duration::checked_from_standard_days(-(1 << 40)), None);
+    assert_eq!(Duration::checked_from_standard_weeks(1 << 40), None);
+}
+
+#[test]
+fn test_nanoseconds() {
+    assert_eq!(Duration::from_nanoseconds(1500), Duration { ticks: 15 });
+    assert_eq!(Duration::from_nanoseconds(50), Duration { ticks: 0 });
+    assert_eq!(Duration::from_nanoseconds(150), Duration { ticks: 2 });
+    assert_eq!(Duration::from_nanoseconds(-150), Duration { ticks: -2 });
+    assert_eq!(Duration::from_seconds(3).to_nanoseconds(),
+               Some(3000000000));
+    assert_eq!(Duration { ticks: -7 }.to_nanoseconds(), Some(-700));
+    let max: Duration = Bounded::max_value();
+    assert_eq!(max.to_nanoseconds(), None);
+    let d = Duration::from_nanoseconds(i64::MAX);
+    assert_eq!(d.to_nanoseconds(), Some(i64::MAX / 100 * 100));
+}
+
+#[test]
+fn test_parse_prefix() {
+    fn test(s: &str, lenient: bool, expected: Option<(&str, uint)>) {
+        let opts = if lenient {
+            ParseOptions::lenient()
+        } else {
+            ParseOptions::strict()
+        };
+        let out = Duration::parse_prefix(s, &opts);
+        let expected = expected.map(|(d, n)| (from_str(d).unwrap(), n));
+        if out != expected {
+            fail!("input: '{}', expected: {}, output: {}",
+                  s, expected, out);
+        }
+    }
+    test("PT1H30M", false, Some(("PT1H30M", 7)));
+    test("PT1H30M timeout", false, Some(("PT1H30M", 7)));
+    test("P1DT2H,", false, Some(("P1DT2H", 6)));
+    test("-PT1.5S)", false, Some(("-PT1.5S", 7)));
+    test("PT5Sx", false, Some(("PT5S", 4)));
+    test("P1D and", false, Some(("P1D", 3)));
+    test("P", false, None);
+    test("PT ", false, None);
+    test("P ", true, Some(("PT0S", 1)));
+    test("PT1X", false, None);
+    test("x", false, None);
+}
+
+#[test]
+fn test_between() {
+    let a: Instant = from_str("2014-06-10T11:12:13Z").unwrap();
+    let b: Instant = from_str("2014-06-11T12:12:13.5Z").unwrap();
+    let d = Duration::between(a, b);
+    assert_eq!(d, Duration::new(1, 1, 0, 0, tick::SECOND / 2).unwrap());
+    assert_eq!(a + d, b);
+    assert_eq!(Duration::between(b, a), -d);
+    assert_eq!(Duration::between(a, a), Duration { ticks: 0 });
+    let min: Instant = Bounded::min_value();
+    let max: Instant = Bounded::max_value();
+    assert_eq!(Duration::checked_between(min, max), None);
+    assert_eq!(Duration::checked_between(max, min), None);
+    assert_eq!(Duration::checked_between(a, max).map(|d| a + d), Some(max));
+}
+
+#[test]
+fn test_checked_mul() {
+    let d = Duration::from_hours(1);
+    assert_eq!(d.checked_mul_i64(-3), Some(Duration::from_hours(-3)));
+    assert_eq!(d.checked_mul_i64(1 << 30), None);
+    let min: Duration = Bounded::min_value();
+    assert_eq!(min.checked_mul_i64(1), Some(min));
+    assert_eq!(min.checked_mul_i64(-1), None);
+}
+
+#[test]
+fn test_parse_decimal_mark() {
+    use parse::{DecimalPoint, DecimalComma};
+
+    let point = ParseOptions { decimal_mark: DecimalPoint,
+                               ..ParseOptions::strict() };
+    let comma = ParseOptions { decimal_mark: DecimalComma,
+                               ..ParseOptions::strict() };
+    let half = Some(Duration { ticks: tick::SECOND / 2 });
+
+    assert_eq!(Duration::parse_with("PT0.5S", &point), half);
+    assert_eq!(Duration::parse_with("PT0,5S", &point), None);
+    assert_eq!(Duration::parse_with("PT0.5S", &comma), None);
+    assert_eq!(Duration::parse_with("PT0,5S", &comma), half);
+    assert_eq!(from_str::<Duration>("PT0,5S"), half);
+}
+
+#[test]
+fn test_parse_signed_components() {
+    let signed = ParseOptions { signed_components: true,
+                                ..ParseOptions::strict() };
+    fn test(s: &str, opts: &ParseOptions, expected: Option<i64>) {
+        let out = Duration::parse_with(s, opts).map(|d| d.ticks);
+        if out != expected {
+            fail!("input: '{}', expected: {}, output: {}", s, expected, out);
+        }
+    }
+
+    test("PT1M-30S", &signed, Some(30 * tick::SECOND));
+    test("PT1M-30S", &ParseOptions::strict(), None);
+    test("-PT1M-30S", &signed, Some(-30 * tick::SECOND));
+    test("PT-1H30M", &signed, Some(-30 * tick::MINUTE));
+    test("PT-1H30M", &ParseOptions::strict(), Some(-90 * tick::MINUTE));
+    test("P1DT-1H", &signed, Some(23 * tick::HOUR));
+    test("P-1DT-1H-1M", &signed,
+         Some(-tick::DAY - tick::HOUR - tick::MINUTE));
+    test("-PT-1S", &signed, Some(tick::SECOND));
+    test("PT-0.5S", &signed, Some(-tick::SECOND / 2));
+    test("PT--1S", &signed, None);
+    test("PT1S-1M", &signed, None);
+    test("PT-922337203685.4775808S", &signed, Some(Bounded::min_value()));
+    test("-PT922337203685.4775808S", &signed, Some(Bounded::min_value()));
+    test("PT922337203685.4775808S", &signed, None);
+    test("PT1M922337203685S", &signed, None);
+    test("PT-1M922337203685S",
+         &signed, Some(922337203685 * tick::SECOND - tick::MINUTE));
+    // Only the final sum must be in range.
+    test("P10675199DT3H-30M", &signed,
+         Some(10675199 * tick::DAY + (3 * tick::HOUR - 30 * tick::MINUTE)));
+    test("P10000000DT200000H-300000M", &signed,
+         Some(10000000 * tick::DAY + 200000 * tick::HOUR -
+              300000 * tick::MINUTE));
+    test("PT-1M922337203685.4775808S", &signed,
+         Some(i64::MAX - tick::MINUTE + 1));
+    test("P-10675199DT-3H30M", &signed,
+         Some(-10675199 * tick::DAY - (3 * tick::HOUR - 30 * tick::MINUTE)));
+    test("P10675199DT3H", &signed, None);
+}
+
+#[test]
+fn test_format_leading_sign() {
+    fn test(d: i64, s: &str) {
+        let out = format!("{}", LeadingSign(Duration { ticks: d }));
+        if out.as_slice() != s {
+            fail!("input: {}, expected: '{}', output: '{}'", d, s, out);
+        }
+        assert_eq!(from_str::<Duration>(out.as_slice()),
+                   Some(Duration { ticks: d }));
+    }
+
+    test(0, "PT0S");
+    test(tick::SECOND, "PT1S");
+    test(-tick::SECOND, "-PT1S");
+    test(-1, "-PT0.0000001S");
+    test(Bounded::min_value(), "-PT922337203685.4775808S");
+
+    assert_eq!(format!("{:.2}", LeadingSign(Duration { ticks: -50000 })),
+               "PT0.00S".to_string());
+    assert_eq!(format!("{:.2}", LeadingSign(Duration { ticks: -50001 })),
+               "-PT0.01S".to_string());
+}
+
+#[test]
+fn test_format_scaled() {
+    fn test(prec: Option<uint>, d: i64, s: &str) {
+        let out = match prec {
+            None => format!("{}", Scaled(Duration { ticks: d })),
+            Some(p) => format!("{:.*}", p, Scaled(Duration { ticks: d }))
+        };
+        if out.as_slice() != s {
+            fail!("precision: {}, input: {}, expected: '{}', output: '{}'",
+                  prec, d, s, out);
+        }
+    }
+
+    test(None, 0, "0s");
+    test(None, 1, "100ns");
+    test(None, -1, "-100ns");
+    test(None, 9, "900ns");
+    test(None, 10, "1µs");
+    test(None, 567, "56.7µs");
+    test(None, 12340, "1.234ms");
+    test(None, -12340, "-1.234ms");
+    test(None, 32100000, "3.21s");
+    test(None, tick::MINUTE, "60s");
+    test(None, Bounded::max_value(), "922337203685s");
+    test(None, Bounded::min_value(), "-922337203685s");
+
+    test(Some(2), 12340, "1.2ms");
+    test(Some(2), 12500, "1.2ms");
+    test(Some(2), 12501, "1.3ms");
+    test(Some(2), 13500, "1.4ms");
+    test(Some(0), 12340, "1ms");
+    test(Some(6), 12345, "1.2345ms");
+    test(Some(9), tick::SECOND + 1, "1.0000001s");
+
+    // Rounding may carry into the next unit.
+    test(Some(3), 9999, "1ms");
+    test(Some(3), 9999999, "1s");
+    test(Some(4), 9999, "999.9µs");
+}
+
+#[test]
+fn test_new() {
+    assert_eq!(Duration::new(0, 0, 0, 0, 0), Some(Duration { ticks: 0 }));
+    assert_eq!(Duration::new(1, 2, 30, 0, 0),
+               Some(Duration { ticks: tick::DAY + 2 * tick::HOUR +
+                                      30 * tick::MINUTE }));
+    assert_eq!(Duration::new(0, 1, -30, 0, 5),
+               Some(Duration { ticks: 30 * tick::MINUTE + 5 }));
+    assert_eq!(Duration::new(0, 0, 0, 922337203685, 4775807),
+               Some(Bounded::max_value()));
+    assert_eq!(Duration::new(0, 0, 0, -922337203685, -4775808),
+               Some(Bounded::min_value()));
+    assert_eq!(Duration::new(0, 0, 0, 922337203685, 4775808), None);
+    assert_eq!(Duration::new(10675200, 0, 0, 0, 0), None);
+    assert_eq!(Duration::new(0, 0, 0, 0, 0), Duration::from_hms(0, 0, 0));
+
+    assert_eq!(Duration::from_hms(1, 2, 3),
+               Some(Duration { ticks: tick::HOUR + 2 * tick::MINUTE +
+                                      3 * tick::SECOND }));
+    assert_eq!(Duration::from_hms(-1, 0, 0), Some(-Duration::from_hours(1)));
+    assert_eq!(Duration::from_hms(256204779, 0, 0), None);
+    assert_eq!(Duration::from_hms(0, 0, 1 << 62), None);
+}
+
+#[test]
+fn test_unit() {
+    static UNITS: [TimeUnit, ..8] = [
+        Tick, Microsecond, Millisecond, Second, Minute, Hour, Day, Week
+    ];
+    for &unit in UNITS.iter() {
+        let d = Duration::from_unit(3, unit).unwrap();
+        assert_eq!(d.ticks, 3 * unit.ticks());
+        assert_eq!(d.to_unit(unit, Nearest), 3);
+        assert_eq!(Duration::from_unit(-3, unit).unwrap().to_unit(unit, Floor),
+                   -3);
+    }
+    assert_eq!(Duration::from_unit(5, Minute),
+               Some(Duration::from_minutes(5)));
+    assert_eq!(Duration::from_unit(2, Week),
+               Some(Duration::from_standard_weeks(2)));
+    assert_eq!(Duration::from_unit(1 << 62, Second), None);
+    assert_eq!(Duration::from_unit(-1 << 62, Second), None);
+
+    fn test(ticks: i64, unit: TimeUnit,
+            nearest: i64, floor: i64, ceiling: i64, toward_zero: i64) {
+        let d = Duration { ticks: ticks };
+        assert_eq!(d.to_unit(unit, Nearest), nearest);
+        assert_eq!(d.to_unit(unit, Floor), floor);
+        assert_eq!(d.to_unit(unit, Ceiling), ceiling);
+        assert_eq!(d.to_unit(unit, TowardZero), toward_zero);
+    }
+
+    test(0, Second, 0, 0, 0, 0);
+    test(15000000, Second, 2, 1, 2, 1);
+    test(25000000, Second, 2, 2, 3, 2);
+    test(-15000000, Second, -2, -2, -1, -1);
+    test(-25000000, Second, -2, -3, -2, -2);
+    test(tick::HOUR + 1, Hour, 1, 1, 2, 1);
+    test(-tick::HOUR - 1, Hour, -1, -2, -1, -1);
+    test(tick::DAY * 10, Week, 1, 1, 2, 1);
+}
+
+#[test]
+fn test_floor_ceil() {
+    let d = Duration { ticks: tick::SECOND * 5 / 2 };
+    assert_eq!((d.to_seconds(), d.to_seconds_floor(), d.to_seconds_ceil()),
+               (2, 2, 3));
+    let d = -d;
+    assert_eq!((d.to_seconds(), d.to_seconds_floor(), d.to_seconds_ceil()),
+               (-2, -3, -2));
+    let d = Duration { ticks: 1 };
+    assert_eq!((d.to_milliseconds_floor(), d.to_milliseconds_ceil()), (0, 1));
+    assert_eq!((d.to_microseconds_floor(), d.to_microseconds_ceil()), (0, 1));
+    let d = Duration { ticks: -1 };
+    assert_eq!((d.to_milliseconds_floor(), d.to_milliseconds_ceil()),
+               (-1, 0));
+    assert_eq!((d.to_microseconds_floor(), d.to_microseconds_ceil()),
+               (-1, 0));
+}
+
+#[test]
+fn test_timeout_millis() {
+    fn test(ticks: i64, expected: Option<u64>) {
+        assert_eq!(Duration { ticks: ticks }.to_timeout_millis(), expected);
+    }
+    test(0, Some(0));
+    test(1, Some(1));
+    test(tick::MILLISECOND, Some(1));
+    test(tick::MILLISECOND + 1, Some(2));
+    test(-1, None);
+    test(Bounded::max_value(), Some(922337203685478));
+
+    assert_eq!(Duration::from_timeout_millis(1500),
+               Some(Duration { ticks: 1500 * tick::MILLISECOND }));
+    assert_eq!(Duration::from_timeout_millis(922337203685477),
+               Some(Duration { ticks: 922337203685477 * tick::MILLISECOND }));
+    assert_eq!(Duration::from_timeout_millis(922337203685478), None);
+    assert_eq!(Duration::from_timeout_millis(-1i64 as u64), None);
+}
+
+#[test]
+fn test_decimal_seconds() {
+    fn test(s: &str, expected: Option<i64>) {
+        let out = Duration::from_decimal_seconds_str(s).map(|d| d.ticks);
+        if out != expected {
+            fail!("input: '{}', expected: {}, output: {}",
+                  s, expected, out);
+        }
+    }
+    test("12.345", Some(123450000));
+    test("0", Some(0));
+    test("-0.5", Some(-5000000));
+    test("1.00000005", Some(10000000));
+    test("1.00000015", Some(10000002));
+    test("922337203685.4775807", Some(9223372036854775807));
+    test("922337203685.4775808", None);
+    test("-922337203685.4775808", Some(Bounded::min_value()));
+    test("12,345", None);
+    test("12.", None);
+    test(".5", None);
+    test("", None);
+    test("-", None);
+    test("12s", None);
+    test(" 12", None);
+    test("--1", None);
+}
+
+#[test]
+fn test_primitive() {
+    use std::num::{from_i32, from_u64};
+    let d = Duration { ticks: -5 };
+    assert_eq!(d.to_i64(), Some(-5));
+    assert_eq!(d.to_u64(), None);
+    assert_eq!(d.to_i8(), Some(-5));
+    assert_eq!(d.to_f64(), Some(-5.0));
+    assert_eq!(d.as_ticks_i64(), -5);
+    assert_eq!(d.as_ticks_f64(), -5.0);
+    assert_eq!(from_i32::<Duration>(7), Some(Duration { ticks: 7 }));
+    assert_eq!(from_u64::<Duration>(1 << 63), None);
+    let max: Duration = Bounded::max_value();
+    assert_eq!(max.to_i32(), None);
+}
+
+#[test]
+fn test_unsigned() {
+    let d = Duration { ticks: 15000 };
+    let u = UnsignedDuration::new(d).unwrap();
+    assert_eq!(u.to_duration(), d);
+    assert_eq!(u.ticks(), 15000);
+    assert_eq!(UnsignedDuration::new(-d), None);
+    assert_eq!(UnsignedDuration::clamped(-d).ticks(), 0);
+    assert_eq!(UnsignedDuration::clamped(d), u);
+    assert_eq!((u + u).ticks(), 30000);
+    assert_eq!(u.checked_sub(u + u), None);
+    assert_eq!(u.saturating_sub(u + u).ticks(), 0);
+    assert_eq!((u + u).checked_sub(u), Some(u));
+    let max: UnsignedDuration = Bounded::max_value();
+    assert_eq!(max.checked_add(u), None);
+    assert_eq!(max.saturating_add(u), max);
+    assert_eq!(max + u, max);
+    assert_eq!((max + max).ticks(), max.ticks());
+    assert_eq!(max.ticks(), 0x7fffffffffffffff);
+    assert_eq!(u.to_timeout_millis(), 2);
+    assert_eq!(format!("{}", u).as_slice(), "PT0.0015S");
+}
+
+#[bench]
+fn bench_format(b: &mut ::test::Bencher) {
+    let d = Duration { ticks: -123456789012345 };
+    b.iter(|| format!("{}", d));
+}
+
+#[test]
+fn test_from_posix() {
+    let ts = |sec: i64, nsec: i64| timespec { tv_sec: sec, tv_nsec: nsec };
+    let d = Duration { ticks: -12345678901 };
+    assert_eq!(Duration::from_timespec(&d.to_timespec()), Some(d));
+    assert_eq!(Duration::from_timeval(&d.to_timeval()),
+               Some(Duration { ticks: -12345678900 }));
+    assert_eq!(Duration::from_timespec(&ts(0, 250)),
+               Some(Duration { ticks: 2 }));
+    assert_eq!(Duration::from_timespec(&ts(0, 350)),
+               Some(Duration { ticks: 4 }));
+    assert_eq!(Duration::from_timespec(&ts(1 << 40, 0)), None);
+    assert_eq!(Duration::from_timespec(&ts(-(1 << 40), 0)), None);
+}
+
+#[test]
+fn test_try_from_posix() {
+    let ts = |sec: i64, nsec: i64| timespec { tv_sec: sec, tv_nsec: nsec };
+    assert_eq!(Duration::try_from_timespec(&ts(2, 500)),
+               Ok(Duration { ticks: 20000005 }));
+    assert_eq!(Duration::try_from_timespec(&ts(-1, 999999999)),
+               Ok(Duration { ticks: 0 }));
+    assert_eq!(Duration::try_from_timespec(&ts(0, -1)),
+               Err(InvalidNanoseconds));
+    assert_eq!(Duration::try_from_timespec(&ts(0, 1000000000)),
+               Err(InvalidNanoseconds));
+    assert_eq!(Duration::try_from_timespec(&ts(1 << 62, 0)),
+               Err(PosixTimeOverflow));
+    let tv = timeval { tv_sec: 1, tv_usec: 1000000 };
+    assert_eq!(Duration::try_from_timeval(&tv), Err(InvalidMicroseconds));
+    let tv = timeval { tv_sec: 1, tv_usec: 5 };
+    assert_eq!(Duration::try_from_timeval(&tv),
+               Ok(Duration { ticks: 10000050 }));
+}
@@ -0,0 +1,111 @@
+//! Epochs for interpreting tick counts and durations from other systems.
+//!
+//! Many systems store time as a count of units since some fixed instant.
+//! An epoch converts between such counts and instants, so each system's
+//! reference point is written down in one place.  Leap seconds are not
+//! accounted for, even for epochs whose time scales count them.
+
+use instant;
+use instant::Instant;
+use duration::Duration;
+
+/// A reference instant from which time is measured.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct Epoch {
+    instant: Instant
+}
+
+/// This library's epoch: January 1, 2000.
+pub static LIBRARY: Epoch = Epoch {
+    instant: Instant { ticks: 0 }
+};
+
+/// The Unix epoch: January 1, 1970.
+pub static UNIX: Epoch = Epoch {
+    instant: instant::UNIX_EPOCH
+};
+
+/// The Windows NT epoch: January 1, 1601.
+pub static NT: Epoch = Epoch {
+    instant: instant::WINDOWS_EPOCH
+};
+
+/// The J2000 astronomical epoch: noon on January 1, 2000.  This is
+/// nominally in Terrestrial Time, but here it is taken as noon UTC.
+pub static J2000: Epoch = Epoch {
+    instant: instant::J2000_EPOCH
+};
+
+/// The NTP epoch: January 1, 1900.
+pub static NTP: Epoch = Epoch {
+    instant: instant::NTP_EPOCH
+};
+
+/// The .NET epoch: January 1, 0001.
+pub static DOTNET: Epoch = Epoch {
+    instant: instant::DOTNET_EPOCH
+};
+
+/// The GPS epoch: January 6, 1980.  GPS time does not include the leap
+/// seconds added since then, and no correction is made for them.
+pub static GPS: Epoch = Epoch {
+    instant: instant::GPS_EPOCH
+};
+
+impl Epoch {
+    /// Create an epoch starting at the given instant.
+    pub fn new(instant: Instant) -> Epoch {
+        Epoch { instant: instant }
+    }
+
+    /// Get the instant at which the epoch starts.
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+
+    /// Get the instant at the given duration after the epoch.  Returns
+    /// None if the result is out of range.
+    pub fn to_instant(&self, since: Duration) -> Option<Instant> {
+        self.instant.ticks.checked_add(&since.ticks)
+            .map(|n| Instant { ticks: n })
+    }
+
+    /// Get the duration from the epoch to the given instant.  Returns None
+    /// if the result is out of range.
+    pub fn from_instant(&self, instant: Instant) -> Option<Duration> {
+        instant.ticks.checked_sub(&self.instant.ticks)
+            .map(|n| Duration { ticks: n })
+    }
+
+    /// Get the instant a number of ticks after the epoch.
+    pub fn from_ticks(&self, ticks: i64) -> Option<Instant> {
+        self.to_instant(Duration { ticks: ticks })
+    }
+
+    /// Get the number of ticks from the epoch to the given instant.
+    pub fn to_ticks(&self, instant: Instant) -> Option<i64> {
+        self.from_instant(instant).map(|d| d.ticks)
+    }
+}
+
+#[test]
+fn epochs() {
+    use std::num::Bounded;
+    let unix_ns: i64 = 1400000000 * 1000000000;
+    let t = UNIX.from_ticks(unix_ns / 100).unwrap();
+    assert_eq!(format!("{}", t).as_slice(), "2014-05-13T16:53:20Z");
+    assert_eq!(UNIX.to_ticks(t), Some(unix_ns / 100));
+    assert_eq!(LIBRARY.to_ticks(t), Some(t.ticks));
+    assert_eq!(NT.to_ticks(UNIX.instant()), Some(116444736000000000));
+    assert_eq!(J2000.to_ticks(LIBRARY.instant()), Some(-432000000000));
+    assert_eq!(format!("{}", GPS.instant()).as_slice(),
+               "1980-01-06T00:00:00Z");
+
+    let e = Epoch::new(Instant { ticks: 5 });
+    assert_eq!(e.to_instant(Duration { ticks: 2 }),
+               Some(Instant { ticks: 7 }));
+    assert_eq!(e.from_instant(Instant { ticks: 2 }),
+               Some(Duration { ticks: -3 }));
+    assert_eq!(e.to_instant(Bounded::max_value()), None);
+    assert_eq!(e.to_ticks(Bounded::min_value()), None);
+}
@@ -0,0 +1,121 @@
+//! Timestamps from file metadata.
+//!
+//! `std::io::fs::stat` reports the times of a file as milliseconds since
+//! the POSIX epoch, stored in a u64.  Times before 1970 wrap around, so
+//! they are read back as signed numbers here.  The platform's own `stat`
+//! structure gives seconds and nanoseconds instead, such as `st_mtime` and
+//! `st_mtime_nsec` on Linux, which from_stat_fields() converts without
+//! losing precision.
+//!
+//! On Unix, the creation time reported by `std::io::fs::stat` is the time
+//! of the last status change, `st_ctime`.
+
+use std::io::{FileStat, IoResult, IoError, InvalidInput};
+use std::io::fs;
+use instant::{Instant, UNIX_EPOCH};
+use tick;
+
+/// Convert a time from `std::io::FileStat`, in milliseconds since the
+/// POSIX epoch.  Returns None if the result is out of range.
+pub fn from_file_time(ms: u64) -> Option<Instant> {
+    (ms as i64).checked_mul(&tick::MILLISECOND)
+        .and_then(|n| n.checked_add(&UNIX_EPOCH.ticks()))
+        .map(|n| Instant::from_ticks(n))
+}
+
+/// Convert a time from the fields of a platform `stat` structure, in
+/// seconds and nanoseconds since the POSIX epoch.  The result is rounded
+/// to the nearest tick.  Returns None if the result is out of range.
+pub fn from_stat_fields(sec: i64, nsec: i64) -> Option<Instant> {
+    tick::from_sec_nsec(sec, nsec)
+        .and_then(|n| n.checked_add(&UNIX_EPOCH.ticks()))
+        .map(|n| Instant::from_ticks(n))
+}
+
+/// The times recorded in the metadata of a file.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct FileTimes {
+    /// When the contents were last modified.
+    pub modified: Instant,
+    /// When the file was last accessed.
+    pub accessed: Instant,
+    /// When the file was created, or on Unix, when its status last
+    /// changed.
+    pub created: Instant
+}
+
+impl FileTimes {
+    /// Get the times from the result of `std::io::fs::stat`.  Returns None
+    /// if any time is out of range.
+    pub fn from_stat(stat: &FileStat) -> Option<FileTimes> {
+        match (from_file_time(stat.modified), from_file_time(stat.accessed),
+               from_file_time(stat.created)) {
+            (Some(m), Some(a), Some(c)) => Some(FileTimes {
+                modified: m,
+                accessed: a,
+                created: c
+            }),
+            _ => None
+        }
+    }
+
+    /// Read the times of a file.  Returns an error of kind InvalidInput if
+    /// any time is out of range.
+    pub fn stat(path: &Path) -> IoResult<FileTimes> {
+        let stat = try!(fs::stat(path));
+        FileTimes::from_stat(&stat).ok_or(IoError {
+            kind: InvalidInput,
+            desc: "file time out of range",
+            detail: None
+        })
+    }
+}
+
+#[test]
+fn test_from_file_time() {
+    fn test(ms: u64, expected: Option<&str>) {
+        let out = from_file_time(ms).map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != expected {
+            fail!("input: {}, expected: {}, output: {}", ms, expected, out);
+        }
+    }
+    test(0, Some("1970-01-01T00:00:00Z"));
+    test(1402399933250, Some("2014-06-10T11:32:13.25Z"));
+    test(-1000i64 as u64, Some("1969-12-31T23:59:59Z"));
+    test((-86400000i64 * 365) as u64, Some("1969-01-01T00:00:00Z"));
+    test(1u64 << 62, None);
+    test(-1i64 as u64 << 62, None);
+}
+
+#[test]
+fn test_from_stat_fields() {
+    fn test(sec: i64, nsec: i64, expected: Option<&str>) {
+        let out = from_stat_fields(sec, nsec).map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != expected {
+            fail!("input: {} {}, expected: {}, output: {}",
+                  sec, nsec, expected, out);
+        }
+    }
+    test(0, 0, Some("1970-01-01T00:00:00Z"));
+    test(1402399933, 123456789, Some("2014-06-10T11:32:13.1234568Z"));
+    test(-1, 999999940, Some("1969-12-31T23:59:59.9999999Z"));
+    test(1i64 << 62, 0, None);
+}
+
+#[test]
+fn test_stat() {
+    use std::io::File;
+    use std::os;
+    let path = os::tmpdir().join(format!("datetime-file-time-{}",
+                                         os::getpid()));
+    File::create(&path).write_str("test").unwrap();
+    // Even numbers of seconds, since some file systems store times with a
+    // resolution of 2 seconds.
+    let result = fs::change_file_times(&path, 1402399930000, 1402399934000)
+        .and_then(|()| FileTimes::stat(&path));
+    fs::unlink(&path).unwrap();
+    let times = result.unwrap();
+    assert_eq!(times.accessed, from_stat_fields(1402399930, 0).unwrap());
+    assert_eq!(times.modified, from_stat_fields(1402399934, 0).unwrap());
+    assert!(FileTimes::stat(&path).is_err());
+}
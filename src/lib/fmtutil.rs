@@ -1,14 +1,140 @@
-use std::fmt::{Formatter, FormatError};
+use std::str;
+use std::fmt::{Show, Formatter, FormatError, WriteError};
 use std::fmt::rt::AlignLeft;
+use std::io::{Writer, IoResult, MemWriter};
+
+/// The two-digit decimal representations of 00 through 99, concatenated,
+/// so that numbers can be written two digits at a time.
+static DIGIT_PAIRS: &'static str =
+    "00010203040506070809101112131415161718192021222324252627282930313233\
+     34353637383940414243444546474849505152535455565758596061626364656667\
+     6869707172737475767778798081828384858687888990919293949596979899";
+
+/// Get the two digits of a number from 0 to 99.
+#[inline]
+fn digit_pair(n: uint) -> &'static [u8] {
+    DIGIT_PAIRS.as_bytes().slice(n * 2, n * 2 + 2)
+}
+
+/// Write a number from 0 to 99 with two digits.
+#[inline]
+pub fn write_2digits(w: &mut Writer, n: uint) -> IoResult<()> {
+    w.write(digit_pair(n))
+}
+
+/// Write a number from 0 to 999 with three digits.
+pub fn write_3digits(w: &mut Writer, n: uint) -> IoResult<()> {
+    try!(w.write_u8('0' as u8 + (n / 100) as u8));
+    write_2digits(w, n % 100)
+}
+
+/// Write a year in ISO 8601 format, like IsoYear.  Years from 0000 to 9999,
+/// which are by far the most common, are written without the formatting
+/// machinery.
+pub fn write_year(w: &mut Writer, y: int) -> IoResult<()> {
+    if y >= 0 && y <= 9999 {
+        try!(write_2digits(w, (y / 100) as uint));
+        write_2digits(w, (y % 100) as uint)
+    } else {
+        write!(w, "{}", IsoYear(y))
+    }
+}
+
+/// Convert an unsigned integer to decimal digits, using the end of the
+/// given buffer.  Returns the digits.
+pub fn u64_digits<'a>(n: u64, buf: &'a mut [u8, ..20]) -> &'a [u8] {
+    let mut n = n;
+    let mut pos = buf.len();
+    while n >= 100 {
+        pos -= 2;
+        let pair = digit_pair((n % 100) as uint);
+        buf[pos] = pair[0];
+        buf[pos + 1] = pair[1];
+        n /= 100;
+    }
+    if n >= 10 {
+        pos -= 2;
+        let pair = digit_pair(n as uint);
+        buf[pos] = pair[0];
+        buf[pos + 1] = pair[1];
+    } else {
+        pos -= 1;
+        buf[pos] = '0' as u8 + n as u8;
+    }
+    buf.slice_from(pos)
+}
+
+/// Write a non-negative number with at least the given number of digits,
+/// padded on the left with the given character.
+pub fn write_number(w: &mut Writer, n: u64, width: uint, pad: char)
+                    -> IoResult<()> {
+    let mut buf = [0u8, ..20];
+    let digits = u64_digits(n, &mut buf);
+    for _ in range(digits.len(), width) {
+        try!(w.write_char(pad));
+    }
+    w.write(digits)
+}
+
+/// A piece of a formatted value, so that each number can have its own
+/// width and padding.
+pub enum Segment<'a> {
+    /// Text which is written as is.
+    Text(&'a str),
+    /// A non-negative number with at least the given number of digits,
+    /// padded on the left with the given character.
+    Number(u64, uint, char)
+}
+
+/// Write a sequence of segments.
+pub fn write_segments(w: &mut Writer, segments: &[Segment]) -> IoResult<()> {
+    for segment in segments.iter() {
+        match *segment {
+            Text(s) => try!(w.write_str(s)),
+            Number(n, width, pad) => try!(write_number(w, n, width, pad))
+        }
+    }
+    Ok(())
+}
+
+/// Write a sequence of segments to a formatter as a single field, with the
+/// formatter's padding and alignment applied to the whole.
+pub fn write_segments_field(f: &mut Formatter, segments: &[Segment])
+                            -> Result<(), FormatError> {
+    let mut w = MemWriter::with_capacity(32);
+    match write_segments(&mut w, segments) {
+        Ok(()) => write_field(f, w.get_ref()),
+        Err(_) => Err(WriteError)
+    }
+}
 
 /// Write a single field to a formatter with the selected padding and
-/// alignment.  The field must already be converted to a slice of UTF-8 data.
+/// alignment.  The precision, if any, is the maximum number of characters,
+/// and the field is truncated on the right to fit, as for strings.  The
+/// field must already be converted to a slice of UTF-8 data.
 pub fn write_field(f: &mut Formatter, data: &[u8]) -> Result<(), FormatError> {
+    let data = match (f.precision, str::from_utf8(data)) {
+        (Some(max), Some(s)) => match s.char_indices().nth(max) {
+            Some((pos, _)) => data.slice_to(pos),
+            None => data
+        },
+        (Some(max), None) if max < data.len() => data.slice_to(max),
+        _ => data
+    };
+    write_padded(f, data)
+}
+
+/// Write a single field to a formatter with the selected padding and
+/// alignment, ignoring the precision.  This is for types which use the
+/// precision for something else, such as the digits of a duration.
+pub fn write_padded(f: &mut Formatter, data: &[u8])
+                    -> Result<(), FormatError> {
+    let chars = match str::from_utf8(data) {
+        Some(s) => s.char_len(),
+        None => data.len()
+    };
     let padding = match f.width {
-        Some(width) => {
-            let sz = data.len();
-            if width > sz { width - sz } else { 0 }
-        }
+        Some(width) => if width > chars { width - chars } else { 0 },
         None => 0
     };
 
@@ -30,3 +156,120 @@ pub fn write_field(f: &mut Formatter, data: &[u8]) -> Result<(), FormatError> {
     }
     Ok(())
 }
+
+/// A wrapper for formatting a year in ISO 8601 format.  Years from 0000 to
+/// 9999 are written with four digits.  Other years use the expanded
+/// representation, which always has a sign, as in "+10000" or "-0044".
+pub struct IsoYear(pub int);
+
+impl Show for IsoYear {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        let IsoYear(y) = *self;
+        if y < 0 {
+            write!(f, "-{:04d}", -y)
+        } else if y > 9999 {
+            write!(f, "+{:04d}", y)
+        } else {
+            write!(f, "{:04d}", y)
+        }
+    }
+}
+
+#[test]
+fn iso_year() {
+    fn test(y: int, s: &str) {
+        let out = format!("{}", IsoYear(y));
+        if out.as_slice() != s {
+            fail!("year: {}, expected: '{}', output: '{}'", y, s, out);
+        }
+    }
+
+    test(0, "0000");
+    test(44, "0044");
+    test(2014, "2014");
+    test(9999, "9999");
+    test(10000, "+10000");
+    test(-1, "-0001");
+    test(-43, "-0043");
+    test(-27228, "-27228");
+}
+
+#[test]
+fn digits() {
+    use std::io::MemWriter;
+    use std::u64;
+    fn test(n: u64) {
+        let mut buf = [0u8, ..20];
+        let out = u64_digits(n, &mut buf);
+        assert_eq!(out, n.to_string().as_bytes());
+    }
+    for n in range(0u64, 1001) {
+        test(n);
+    }
+    test(123456789);
+    test(u64::MAX);
+
+    let mut w = MemWriter::new();
+    write_2digits(&mut w, 7).unwrap();
+    write_3digits(&mut w, 42).unwrap();
+    for &y in [0, 44, 2014, 9999, 10000, -1].iter() {
+        write_year(&mut w, y).unwrap();
+        w.write_char(' ').unwrap();
+    }
+    assert_eq!(String::from_utf8(w.unwrap()).unwrap().as_slice(),
+               "07042 0000 0044 2014 9999 +10000 -0001 ");
+}
+
+#[test]
+fn segments() {
+    let mut w = MemWriter::new();
+    write_segments(&mut w, &[Number(7, 2, '0'), Text(":"),
+                             Number(123, 2, '0'), Text(" "),
+                             Number(5, 3, ' '), Number(0, 0, '0')]).unwrap();
+    assert_eq!(String::from_utf8(w.unwrap()).unwrap().as_slice(),
+               "07:123   50");
+    struct Clock(u64, u64);
+    impl Show for Clock {
+        fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+            let Clock(h, m) = *self;
+            write_segments_field(f, &[Number(h, 1, '0'), Text(":"),
+                                      Number(m, 2, '0')])
+        }
+    }
+    assert_eq!(format!("[{:>6}]", Clock(9, 5)).as_slice(), "[  9:05]");
+    assert_eq!(format!("[{:<6}]", Clock(12, 30)).as_slice(), "[12:30 ]");
+}
+
+#[test]
+fn field() {
+    struct Field(&'static str);
+    impl Show for Field {
+        fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+            let Field(s) = *self;
+            write_field(f, s.as_bytes())
+        }
+    }
+    assert_eq!(format!("[{:.4}]", Field("2014-06-10")).as_slice(), "[2014]");
+    assert_eq!(format!("[{:<8.4}]", Field("2014-06-10")).as_slice(),
+               "[2014    ]");
+    assert_eq!(format!("[{:8.4}]", Field("2014-06-10")).as_slice(),
+               "[    2014]");
+    assert_eq!(format!("[{:.20}]", Field("2014-06-10")).as_slice(),
+               "[2014-06-10]");
+    assert_eq!(format!("[{:>5.3}]", Field("1.5µs")).as_slice(),
+               "[  1.5]");
+    assert_eq!(format!("[{:>6}]", Field("1.5µs")).as_slice(), "[ 1.5µs]");
+    assert_eq!(format!("[{:.4}]", Field("1.5µs")).as_slice(), "[1.5µ]");
+}
+
+#[bench]
+fn bench_u64_digits(b: &mut ::test::Bencher) {
+    let mut buf = [0u8, ..20];
+    b.iter(|| u64_digits(1234567890123456789, &mut buf).len());
+}
+
+#[bench]
+fn bench_u64_to_str_bytes(b: &mut ::test::Bencher) {
+    use std::u64;
+    b.iter(|| u64::to_str_bytes(1234567890123456789, 10, |v| v.len()));
+}
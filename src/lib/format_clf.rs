@@ -0,0 +1,145 @@
+//! Timestamps in the Common Log Format used by Apache and NCSA web server
+//! access logs, such as "[10/Oct/2000:13:55:36 -0700]".
+
+use div_mod::div_mod;
+use tick;
+use calendar_iso8601;
+use instant::Instant;
+use format_iso8601::{YearMonthDay, Parsed};
+use parseutil;
+use parseutil::starts_with;
+
+/// Read a separator byte followed by a two-digit field.
+fn read_field<'a>(s: &'a [u8], sep: char) -> Option<(int, &'a [u8])> {
+    if !starts_with(s, sep) {
+        return None;
+    }
+    parseutil::read_digits(s.slice_from(1), 2)
+}
+
+/// Parse the fields of a Common Log Format timestamp, with or without the
+/// surrounding brackets.  The month abbreviation is case-insensitive.
+pub fn parse_fields(s: &str) -> Option<Parsed> {
+    let s = s.as_bytes();
+    let s = if starts_with(s, '[') {
+        if s.len() < 2 || s[s.len() - 1] != ']' as u8 {
+            return None;
+        }
+        s.slice(1, s.len() - 1)
+    } else {
+        s
+    };
+    let (day, rem) = match parseutil::read_digits(s, 2) {
+        Some(x) => x, None => return None
+    };
+    if !starts_with(rem, '/') {
+        return None;
+    }
+    let (month, rem) =
+        match parseutil::read_month_abbreviation(rem.slice_from(1)) {
+            Some(x) => x, None => return None
+        };
+    if !starts_with(rem, '/') {
+        return None;
+    }
+    let (year, rem) = match parseutil::read_digits(rem.slice_from(1), 4) {
+        Some(x) => x, None => return None
+    };
+    if day < 1 || day > calendar_iso8601::days_in_month(year, month) {
+        return None;
+    }
+    let (hour, rem) = match read_field(rem, ':') {
+        Some(x) => x, None => return None
+    };
+    let (minute, rem) = match read_field(rem, ':') {
+        Some(x) => x, None => return None
+    };
+    let (second, rem) = match read_field(rem, ':') {
+        Some(x) => x, None => return None
+    };
+    if hour > 23 || minute > 59 || second > 59 || !starts_with(rem, ' ') {
+        return None;
+    }
+    let rem = rem.slice_from(1);
+    let negative = match rem.head() {
+        Some(&c) if c == '+' as u8 => false,
+        Some(&c) if c == '-' as u8 => true,
+        _ => return None
+    };
+    let (hhmm, rem) = match parseutil::read_digits(rem.slice_from(1), 4) {
+        Some(x) => x, None => return None
+    };
+    let (offset_hour, offset_minute) = (hhmm / 100, hhmm % 100);
+    if !rem.is_empty() || offset_hour > 23 || offset_minute > 59 {
+        return None;
+    }
+    let time = hour as i64 * tick::HOUR + minute as i64 * tick::MINUTE +
+        second as i64 * tick::SECOND;
+    let offset = offset_hour as i64 * tick::HOUR +
+        offset_minute as i64 * tick::MINUTE;
+    Some(Parsed {
+        date: Some(YearMonthDay(year, month, day)),
+        time: Some(time),
+        offset: Some(if negative { -offset } else { offset })
+    })
+}
+
+/// Parse a Common Log Format timestamp, with or without the surrounding
+/// brackets.
+pub fn parse_timestamp(s: &str) -> Option<Instant> {
+    parse_fields(s).and_then(|p| p.to_instant())
+}
+
+/// Format an instant as a Common Log Format timestamp without brackets,
+/// such as "10/Oct/2000:13:55:36 -0700", using the given UTC offset in
+/// ticks.  The offset is rounded down to whole minutes, and fractions of a
+/// second are truncated.  Returns None if the local year is outside the
+/// range 0 to 9999.
+pub fn format_timestamp(t: Instant, offset: i64) -> Option<String> {
+    let (minutes, _) = div_mod(offset, tick::MINUTE);
+    let local = match t.ticks().checked_add(&(minutes * tick::MINUTE)) {
+        Some(n) => n, None => return None
+    };
+    let (cjd, time) = div_mod(local, tick::DAY);
+    let (y, m, d) = calendar_iso8601::from_cjd(cjd as int);
+    if y < 0 || y > 9999 {
+        return None;
+    }
+    let sec = time / tick::SECOND;
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = if minutes < 0 { -minutes } else { minutes };
+    Some(format!("{:02d}/{}/{:04d}:{:02d}:{:02d}:{:02d} {}{:02d}{:02d}",
+                 d, parseutil::MONTH_ABBREVIATIONS[(m - 1) as uint], y,
+                 sec / 3600, sec / 60 % 60, sec % 60,
+                 sign, minutes / 60, minutes % 60))
+}
+
+/// Format an instant as a bracketed Common Log Format timestamp, such as
+/// "[10/Oct/2000:13:55:36 -0700]".
+pub fn format_bracketed(t: Instant, offset: i64) -> Option<String> {
+    format_timestamp(t, offset).map(|s| format!("[{}]", s))
+}
+
+#[test]
+fn test_clf() {
+    // 2000-10-10T20:55:36Z
+    let t = Instant::from_ticks(tick::DAY * 283 + tick::HOUR * 20 +
+                                tick::MINUTE * 55 + tick::SECOND * 36);
+    let offset = -tick::HOUR * 7;
+    assert_eq!(parse_timestamp("[10/Oct/2000:13:55:36 -0700]"), Some(t));
+    assert_eq!(parse_timestamp("10/oct/2000:13:55:36 -0700"), Some(t));
+    assert_eq!(parse_fields("10/Oct/2000:13:55:36 -0700").unwrap().offset,
+               Some(offset));
+    assert_eq!(parse_timestamp("[10/Oct/2000:13:55:36 -0700"), None);
+    assert_eq!(parse_timestamp("31/Sep/2000:13:55:36 -0700"), None);
+    assert_eq!(parse_timestamp("10/Oct/2000:13:55:36"), None);
+    assert_eq!(parse_timestamp("10/Oct/2000 13:55:36 -0700"), None);
+    assert_eq!(parse_timestamp("10/Okt/2000:13:55:36 -0700"), None);
+
+    assert_eq!(format_bracketed(t, offset),
+               Some("[10/Oct/2000:13:55:36 -0700]".to_string()));
+    assert_eq!(format_timestamp(t, 0),
+               Some("10/Oct/2000:20:55:36 +0000".to_string()));
+    assert_eq!(format_timestamp(t, tick::HOUR * 11 / 2),
+               Some("11/Oct/2000:02:25:36 +0530".to_string()));
+}
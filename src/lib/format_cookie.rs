@@ -0,0 +1,173 @@
+//! Dates in HTTP cookies.
+//!
+//! Cookie expiration dates are written as "Wdy, DD-Mon-YYYY HH:MM:SS GMT",
+//! but browsers accept a wide variety of other formats.  The parser
+//! implements the algorithm in section 5.1.1 of RFC 6265, which is more
+//! forgiving than the RFC 2822 and HTTP-date grammars.
+
+use div_mod::div_mod;
+use tick;
+use calendar_iso8601;
+use instant::Instant;
+use parseutil;
+
+/// Test whether a byte is a delimiter between date tokens.
+fn is_delimiter(c: u8) -> bool {
+    c == 0x09 || (c >= 0x20 && c <= 0x2f) || (c >= 0x3b && c <= 0x40) ||
+        (c >= 0x5b && c <= 0x60) || (c >= 0x7b && c <= 0x7e)
+}
+
+fn is_digit(c: u8) -> bool {
+    c >= '0' as u8 && c <= '9' as u8
+}
+
+/// Read an integer with between min and max digits, which must not be
+/// followed by another digit.  Returns the integer and the remainder of the
+/// token.
+fn read_number<'a>(s: &'a [u8], min: uint, max: uint)
+                   -> Option<(int, &'a [u8])> {
+    let len = s.iter().take_while(|&&c| is_digit(c)).count();
+    if len < min || len > max {
+        return None;
+    }
+    let value = s.slice_to(len).iter()
+        .fold(0, |n, &c| n * 10 + (c - '0' as u8) as int);
+    Some((value, s.slice_from(len)))
+}
+
+/// Match a token against the time production, "hh:mm:ss" where each field
+/// has one or two digits, followed by anything that is not a digit.
+fn match_time(s: &[u8]) -> Option<(int, int, int)> {
+    let (hour, rem) = match read_number(s, 1, 2) {
+        Some(x) => x, None => return None
+    };
+    if !parseutil::starts_with(rem, ':') {
+        return None;
+    }
+    let (minute, rem) = match read_number(rem.slice_from(1), 1, 2) {
+        Some(x) => x, None => return None
+    };
+    if !parseutil::starts_with(rem, ':') {
+        return None;
+    }
+    match read_number(rem.slice_from(1), 1, 2) {
+        Some((second, _)) => Some((hour, minute, second)),
+        None => None
+    }
+}
+
+/// Parse a cookie date using the algorithm in RFC 6265.  Returns None if
+/// the date is invalid.
+pub fn parse_cookie_date(s: &str) -> Option<Instant> {
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+    for token in s.as_bytes().split(|&c| is_delimiter(c)) {
+        if token.is_empty() {
+            continue;
+        }
+        if time.is_none() {
+            time = match_time(token);
+            if time.is_some() {
+                continue;
+            }
+        }
+        if day.is_none() {
+            day = read_number(token, 1, 2).map(|(n, _)| n);
+            if day.is_some() {
+                continue;
+            }
+        }
+        if month.is_none() {
+            month = parseutil::read_month_abbreviation(token)
+                .map(|(n, _)| n);
+            if month.is_some() {
+                continue;
+            }
+        }
+        if year.is_none() {
+            year = read_number(token, 2, 4).map(|(n, _)| n);
+        }
+    }
+    let (hour, minute, second) = match time {
+        Some(x) => x, None => return None
+    };
+    let (day, month, year) = match (day, month, year) {
+        (Some(d), Some(m), Some(y)) => (d, m, y),
+        _ => return None
+    };
+    let year = if year >= 70 && year <= 99 {
+        year + 1900
+    } else if year <= 69 {
+        year + 2000
+    } else {
+        year
+    };
+    if day < 1 || day > calendar_iso8601::days_in_month(year, month) ||
+        year < 1601 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let cjd = calendar_iso8601::to_cjd(year, month, day) as i64;
+    Some(Instant::from_ticks(cjd * tick::DAY + hour as i64 * tick::HOUR +
+                             minute as i64 * tick::MINUTE +
+                             second as i64 * tick::SECOND))
+}
+
+/// Format an instant as a cookie expiration date, such as
+/// "Wed, 09-Jun-2021 10:18:14 GMT".  Fractions of a second are truncated.
+/// Returns None if the year is outside the range 1601 to 9999.
+pub fn format_cookie_date(t: Instant) -> Option<String> {
+    let (cjd, time) = div_mod(t.ticks(), tick::DAY);
+    let cjd = cjd as int;
+    let (y, m, d) = calendar_iso8601::from_cjd(cjd);
+    if y < 1601 || y > 9999 {
+        return None;
+    }
+    let wd = calendar_iso8601::weekday(cjd);
+    let sec = time / tick::SECOND;
+    Some(format!("{}, {:02d}-{}-{:04d} {:02d}:{:02d}:{:02d} GMT",
+                 parseutil::WEEKDAY_ABBREVIATIONS[(wd - 1) as uint], d,
+                 parseutil::MONTH_ABBREVIATIONS[(m - 1) as uint], y,
+                 sec / 3600, sec / 60 % 60, sec % 60))
+}
+
+#[test]
+fn test_parse() {
+    // 2021-06-09T10:18:14Z
+    let t = Instant::from_ticks(tick::DAY * 7830 + tick::HOUR * 10 +
+                                tick::MINUTE * 18 + tick::SECOND * 14);
+    fn test(s: &str, expected: Option<Instant>) {
+        let out = parse_cookie_date(s);
+        if out != expected {
+            fail!("input: '{}', expected: {}, output: {}",
+                  s, expected, out);
+        }
+    }
+    test("Wed, 09-Jun-2021 10:18:14 GMT", Some(t));
+    test("Wed, 09 Jun 2021 10:18:14 GMT", Some(t));
+    test("Wednesday, 09-Jun-21 10:18:14 GMT", Some(t));
+    test("Wed Jun 9 10:18:14 2021", Some(t));
+    test("9 june 2021 10:18:14", Some(t));
+    test("Wed, 09-Jun-2021 10:18:14", Some(t));
+    test("2021 Jun 09 10:18:14 UTC", Some(t));
+    test("Wed, 09-Jun-2021", None);
+    test("Wed, 31-Jun-2021 10:18:14 GMT", None);
+    test("Wed, 09-Jun-1600 10:18:14 GMT", None);
+    test("Wed, 09-Jun-2021 24:18:14 GMT", None);
+    test("Thu, 01-Jan-70 00:00:00 GMT",
+         Some(Instant::from_ticks(tick::DAY * -10957)));
+    test("Sat, 01-Jan-00 00:00:00 GMT", Some(Instant::from_ticks(0)));
+}
+
+#[test]
+fn test_format() {
+    let t = Instant::from_ticks(tick::DAY * 7830 + tick::HOUR * 10 +
+                                tick::MINUTE * 18 + tick::SECOND * 14 + 1);
+    assert_eq!(format_cookie_date(t),
+               Some("Wed, 09-Jun-2021 10:18:14 GMT".to_string()));
+    assert_eq!(format_cookie_date(Instant::from_ticks(0)),
+               Some("Sat, 01-Jan-2000 00:00:00 GMT".to_string()));
+    assert_eq!(format_cookie_date(Instant::from_ticks(tick::DAY * -146097)),
+               None);
+}
@@ -1,4 +1,21 @@
+use std::cmp::min;
 use std::num::Bounded;
+use std::fmt::{Show, Formatter, FormatError};
+use std::from_str::FromStr;
+use tick;
+use calendar_iso8601;
+use calendar_iso8601::MAX_YEAR;
+use div_mod::div_mod;
+use instant::Instant;
+use period::{Period, AnniversaryPolicy};
+use business::{HolidayCalendar, RollConvention, Tenor, Overnight,
+               TomorrowNext, SpotNext, Term, SPOT_DAYS};
+use parse::{ParseOptions, AstronomicalYear, NoYearZero,
+            BuddhistEraYear, BUDDHIST_ERA_OFFSET};
+use parseutil;
+use fmtutil;
+use fmtutil::IsoYear;
+use iter;
 
 #[deriving(PartialEq, PartialOrd, Ord, Eq, Clone, Hash, Rand)]
 pub enum Date {
@@ -10,6 +27,483 @@ pub enum Date {
     YearWeekDay(int, int, int)
 }
 
+/// A day of the week, numbered from Monday as in ISO 8601.
+#[deriving(PartialEq, Eq, Clone, Show, FromPrimitive)]
+pub enum Weekday {
+    Monday = 1,
+    Tuesday = 2,
+    Wednesday = 3,
+    Thursday = 4,
+    Friday = 5,
+    Saturday = 6,
+    Sunday = 7
+}
+
+/// Conventions for numbering the weeks of a year.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum WeekNumbering {
+    /// ISO 8601 weeks, which start on Monday.  Week 1 is the week
+    /// containing January 4, so the first days of January may belong to
+    /// the last week of the previous year, and the last days of December
+    /// to week 1 of the next year.
+    IsoWeeks,
+    /// The convention common in the United States and used by spreadsheet
+    /// WEEKNUM functions.  Weeks start on Sunday, and week 1 is the week
+    /// containing January 1.
+    UsWeeks,
+    /// Weeks start on Sunday, and week 1 starts on the first Sunday of the
+    /// year.  Earlier days are in week 0.  This is strftime's `%U`.
+    SundayWeeks,
+    /// Weeks start on Monday, and week 1 starts on the first Monday of the
+    /// year.  Earlier days are in week 0.  This is strftime's `%W`.
+    MondayWeeks
+}
+
+/// Conventions for writing the year when formatting a date.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum EraStyle {
+    /// Astronomical year numbering, where the year before 1 is 0.
+    Astronomical,
+    /// Years before 1 are counted backwards and marked "BC", so year 0 is
+    /// 1 BC and year -43 is 44 BC.
+    BcAd,
+    /// Like BcAd, but using the designator "BCE".
+    BceCe,
+    /// Years of the Thai Buddhist era, marked "BE", so 2014 AD is 2557 BE.
+    BuddhistEra
+}
+
+impl Date {
+    /// Get the year, using astronomical year numbering.
+    pub fn year(&self) -> int {
+        match *self {
+            Year(y) | YearMonth(y, _) | YearMonthDay(y, _, _) |
+            YearDay(y, _) | YearWeek(y, _) | YearWeekDay(y, _, _) => y
+        }
+    }
+
+    /// Get the same date with a different year, without checking that it
+    /// exists.
+    fn replace_year(&self, y: int) -> Date {
+        match *self {
+            Year(_) => Year(y),
+            YearMonth(_, m) => YearMonth(y, m),
+            YearMonthDay(_, m, d) => YearMonthDay(y, m, d),
+            YearDay(_, d) => YearDay(y, d),
+            YearWeek(_, w) => YearWeek(y, w),
+            YearWeekDay(_, w, d) => YearWeekDay(y, w, d)
+        }
+    }
+
+    /// Get the same date in a different year, keeping its representation.
+    /// Returns None if the date does not exist in that year, such as
+    /// February 29 or week 53.
+    pub fn with_year(&self, y: int) -> Option<Date> {
+        let date = self.replace_year(y);
+        match date {
+            YearWeek(y, w) if w > calendar_iso8601::weeks_in_year(y) => None,
+            Year(_) | YearMonth(_, _) | YearWeek(_, _) => Some(date),
+            _ => date_to_cjd(&date).map(|_| date)
+        }
+    }
+
+    /// Get the same day of a different month.  A complete date is
+    /// converted to a calendar date, and a year becomes a year and month.
+    /// Returns None if the month is not from 1 to 12, if the day does not
+    /// exist in that month, or for dates given by week.
+    pub fn with_month(&self, m: int) -> Option<Date> {
+        if m < 1 || m > 12 {
+            return None;
+        }
+        match *self {
+            Year(y) | YearMonth(y, _) => Some(YearMonth(y, m)),
+            YearWeek(_, _) => None,
+            _ => date_to_cjd(self).and_then(|cjd| {
+                let (y, _, d) = calendar_iso8601::from_cjd(cjd);
+                checked_calendar_date(y, m, d)
+            })
+        }
+    }
+
+    /// Get a different day of the same month.  A complete date is
+    /// converted to a calendar date, and a year and month becomes a
+    /// calendar date.  Returns None if the day does not exist in that
+    /// month, or if the date has no month.
+    pub fn with_day(&self, d: int) -> Option<Date> {
+        match *self {
+            YearMonth(y, m) => checked_calendar_date(y, m, d),
+            Year(_) | YearWeek(_, _) => None,
+            _ => date_to_cjd(self).and_then(|cjd| {
+                let (y, m, _) = calendar_iso8601::from_cjd(cjd);
+                checked_calendar_date(y, m, d)
+            })
+        }
+    }
+
+    /// Add a period to a complete date.  Years and months are added first,
+    /// and the day is clamped to the length of the resulting month, so
+    /// January 31 plus one month is the last day of February.  Days are
+    /// added last.  The result is a calendar date.  Returns None for
+    /// reduced precision dates, for dates which do not exist, and if the
+    /// result is out of range.
+    pub fn add_period(&self, p: &Period) -> Option<Date> {
+        let cjd = match date_to_cjd(self) {
+            Some(x) => x, None => return None
+        };
+        let (y, m, d) = calendar_iso8601::from_cjd(cjd);
+        let months = match p.years.checked_mul(&12)
+            .and_then(|n| n.checked_add(&p.months))
+            .and_then(|n| n.checked_add(&(m - 1))) {
+            Some(n) => n, None => return None
+        };
+        let (dy, m0) = div_mod(months, 12);
+        let y = match y.checked_add(&dy) {
+            Some(y) if y >= -MAX_YEAR && y <= MAX_YEAR => y,
+            _ => return None
+        };
+        let d = min(d, calendar_iso8601::days_in_month(y, m0 + 1));
+        let cjd = match calendar_iso8601::to_cjd(y, m0 + 1, d)
+            .checked_add(&p.days) {
+            Some(n) if n >= -MAX_YEAR * 366 && n <= MAX_YEAR * 366 => n,
+            _ => return None
+        };
+        let (y, m, d) = calendar_iso8601::from_cjd(cjd);
+        Some(YearMonthDay(y, m, d))
+    }
+
+    /// Add a number of years to a complete date, placing the anniversary
+    /// of February 29 according to the policy.  The result is a calendar
+    /// date.  Returns None for reduced precision dates, for dates which do
+    /// not exist, if the result is out of range, and for February 29 in a
+    /// common year with the `Skip` policy.
+    pub fn add_years(&self, years: int, policy: AnniversaryPolicy)
+                     -> Option<Date> {
+        let (y, m, d) = match date_to_cjd(self) {
+            Some(cjd) => calendar_iso8601::from_cjd(cjd),
+            None => return None
+        };
+        match y.checked_add(&years) {
+            Some(y) if y >= -MAX_YEAR && y <= MAX_YEAR =>
+                policy.resolve(y, m, d),
+            _ => None
+        }
+    }
+
+    /// Add a tenor to a complete date, such as a start date, to get a
+    /// maturity date.  Tenors given in days, weeks, months, or years are
+    /// added as by `add_period`, and the result is adjusted to a business
+    /// day in the calendar using the roll convention.  For "ON", "TN", and
+    /// "SN" the date is the trade date, and the tenor is one business day
+    /// starting on the trade date, the next business day, or the spot date
+    /// respectively, which needs no adjustment.  Returns None for reduced
+    /// precision dates, for dates which do not exist, if the result is out
+    /// of range, and if the calendar has no business days.
+    pub fn add_tenor(&self, tenor: &Tenor, roll: RollConvention,
+                     calendar: &HolidayCalendar) -> Option<Date> {
+        match *tenor {
+            Term(ref p) =>
+                self.add_period(p).and_then(|d| roll.adjust(&d, calendar)),
+            Overnight => calendar.add_business_days(self, 1),
+            TomorrowNext => calendar.add_business_days(self, 2),
+            SpotNext => calendar.add_business_days(self, SPOT_DAYS + 1)
+        }
+    }
+
+    /// Get the chronological Julian day of the first day of the date.
+    /// Reduced precision dates start on the first day of the year or
+    /// month, or on the Monday of the week.  Fails if a field is out of
+    /// range, since the date would otherwise be folded into another one.
+    fn first_cjd(&self) -> int {
+        if !fields_in_range(self) {
+            fail!("date field out of range: {}", self);
+        }
+        match *self {
+            Year(y) => calendar_iso8601::to_cjd(y, 1, 1),
+            YearMonth(y, m) => calendar_iso8601::to_cjd(y, m, 1),
+            YearMonthDay(y, m, d) => calendar_iso8601::to_cjd(y, m, d),
+            YearDay(y, d) => calendar_iso8601::to_cjd(y, 1, 1) + d - 1,
+            YearWeek(y, w) => calendar_iso8601::week_to_cjd(y, w, 1),
+            YearWeekDay(y, w, d) => calendar_iso8601::week_to_cjd(y, w, d)
+        }
+    }
+
+    /// Test whether this date starts earlier than another date.  Reduced
+    /// precision dates are compared using their first day, and dates
+    /// written in different representations are compared on the calendar.
+    /// Fails if either date has a field out of range, such as month 13 or
+    /// day 0.
+    pub fn is_before(&self, other: Date) -> bool {
+        self.first_cjd() < other.first_cjd()
+    }
+
+    /// Test whether this date starts later than another date.  Fails like
+    /// is_before().
+    pub fn is_after(&self, other: Date) -> bool {
+        self.first_cjd() > other.first_cjd()
+    }
+
+    /// Test whether this date starts in the half-open interval from the
+    /// start of one date to the start of another, which includes start but
+    /// not end.  Fails like is_before().
+    pub fn is_between(&self, start: Date, end: Date) -> bool {
+        let cjd = self.first_cjd();
+        start.first_cjd() <= cjd && cjd < end.first_cjd()
+    }
+
+    /// Iterate over each day from the first day of start up to but not
+    /// including the first day of end.
+    pub fn range(start: Date, end: Date) -> iter::DateRange {
+        iter::date_range(start.first_cjd(), end.first_cjd())
+    }
+
+    /// Iterate over the first day of each month, starting with the month
+    /// containing the first day of this date.
+    pub fn months_iter(&self) -> iter::Periods {
+        let (y, m, _) = calendar_iso8601::from_cjd(self.first_cjd());
+        iter::periods(y, m, 1, 1)
+    }
+
+    /// Iterate over the same day of each month, starting with the first
+    /// day of this date.  In months which are too short, the last day of
+    /// the month is used instead.
+    pub fn months_iter_clamped(&self) -> iter::Periods {
+        let (y, m, d) = calendar_iso8601::from_cjd(self.first_cjd());
+        iter::periods(y, m, d, 1)
+    }
+
+    /// Iterate over the first day of each year, starting with the year
+    /// containing the first day of this date.
+    pub fn years_iter(&self) -> iter::Periods {
+        let (y, _, _) = calendar_iso8601::from_cjd(self.first_cjd());
+        iter::periods(y, 1, 1, 12)
+    }
+
+    /// Iterate over the same day of each year, starting with the first day
+    /// of this date.  February 29 becomes February 28 in common years.
+    pub fn years_iter_clamped(&self) -> iter::Periods {
+        let (y, m, d) = calendar_iso8601::from_cjd(self.first_cjd());
+        iter::periods(y, m, d, 12)
+    }
+
+    /// Iterate over the anniversaries of the first day of this date,
+    /// starting with that day, and placing the anniversary of February 29
+    /// according to the policy.
+    pub fn anniversaries(&self, policy: AnniversaryPolicy)
+                         -> iter::Anniversaries {
+        let (y, m, d) = calendar_iso8601::from_cjd(self.first_cjd());
+        iter::anniversaries(y, m, d, policy)
+    }
+
+    /// Get the week of the year containing a complete date, using the
+    /// given convention.  Returns the year the week belongs to, which only
+    /// differs from the calendar year for ISO 8601 weeks, and the week
+    /// number.  Returns None for reduced precision dates and for dates
+    /// which do not exist.
+    pub fn week_of_year(&self, numbering: WeekNumbering)
+                        -> Option<(int, int)> {
+        date_to_cjd(self).map(|cjd| {
+            let (y, yday) = calendar_iso8601::to_ordinal(cjd);
+            // Days since the start of the week, with weeks starting on
+            // Sunday or Monday.
+            let since_sunday = calendar_iso8601::weekday(cjd) % 7;
+            let since_monday = calendar_iso8601::weekday(cjd) - 1;
+            match numbering {
+                IsoWeeks => {
+                    let (y, w, _) = calendar_iso8601::to_week_date(cjd);
+                    (y, w)
+                }
+                UsWeeks => (y, week_number_at(cjd, Sunday)),
+                SundayWeeks => (y, (yday - 1 + 7 - since_sunday) / 7),
+                MondayWeeks => (y, (yday - 1 + 7 - since_monday) / 7)
+            }
+        })
+    }
+
+    /// Get the first day of the week containing a complete date, with
+    /// weeks starting on the given day.  Returns None for reduced
+    /// precision dates and for dates which do not exist.
+    pub fn start_of_week(&self, first_day: Weekday) -> Option<Date> {
+        date_to_cjd(self).map(|cjd| {
+            let wday = calendar_iso8601::weekday(cjd);
+            let back = (wday - first_day as int + 7) % 7;
+            let (y, m, d) = calendar_iso8601::from_cjd(cjd - back);
+            YearMonthDay(y, m, d)
+        })
+    }
+
+    /// Get the week of the year containing a complete date, with weeks
+    /// starting on the given day and week 1 containing January 1.  With
+    /// weeks starting on Sunday, this is the same as UsWeeks.  Returns
+    /// None for reduced precision dates and for dates which do not exist.
+    pub fn week_number(&self, first_day: Weekday) -> Option<int> {
+        date_to_cjd(self).map(|cjd| week_number_at(cjd, first_day))
+    }
+
+    /// Get the weeks shown by a calendar for a month, with weeks starting
+    /// on the given day.  Each row has seven dates, and the first and last
+    /// rows include days from the neighboring months, so there are four
+    /// to six rows.  Returns None if the month is not from 1 to 12.
+    pub fn month_grid(year: int, month: int, first_day: Weekday)
+                      -> Option<Vec<[Date, ..7]>> {
+        if month < 1 || month > 12 {
+            return None;
+        }
+        let first = calendar_iso8601::to_cjd(year, month, 1);
+        let last = first + calendar_iso8601::days_in_month(year, month) - 1;
+        let lead = (calendar_iso8601::weekday(first) - first_day as int + 7)
+            % 7;
+        let mut cjd = first - lead;
+        let mut rows = Vec::with_capacity(6);
+        while cjd <= last {
+            let mut row = [Year(0), ..7];
+            for date in row.mut_iter() {
+                let (y, m, d) = calendar_iso8601::from_cjd(cjd);
+                *date = YearMonthDay(y, m, d);
+                cjd += 1;
+            }
+            rows.push(row);
+        }
+        Some(rows)
+    }
+
+    /// Get the nth occurrence of a day of the week in a month, such as the
+    /// third Wednesday.  If n is negative, occurrences are counted from
+    /// the end of the month, so -1 is the last.  Returns None if the month
+    /// is not from 1 to 12, if n is zero, or if the month does not have n
+    /// such days.
+    pub fn nth_weekday(year: int, month: int, weekday: Weekday, n: int)
+                       -> Option<Date> {
+        if month < 1 || month > 12 || n == 0 {
+            return None;
+        }
+        let len = calendar_iso8601::days_in_month(year, month);
+        let day = if n > 0 {
+            let first = calendar_iso8601::to_cjd(year, month, 1);
+            let w = calendar_iso8601::weekday(first);
+            1 + (weekday as int - w + 7) % 7 + (n - 1) * 7
+        } else {
+            let last = calendar_iso8601::to_cjd(year, month, len);
+            let w = calendar_iso8601::weekday(last);
+            len - (w - weekday as int + 7) % 7 + (n + 1) * 7
+        };
+        if day < 1 || day > len {
+            None
+        } else {
+            Some(YearMonthDay(year, month, day))
+        }
+    }
+
+    /// Format the date with the given convention for the year.  With an
+    /// era designator, years before 1 are written without a sign and
+    /// followed by the designator, as in "44-03-15 BC", and other years are
+    /// written without padding.  Years of the Buddhist era are always
+    /// followed by "BE", as in "2557-06-10 BE", and have a sign before 1 BE,
+    /// as in "-57 BE".  The lenient parser accepts the result.
+    pub fn to_era_string(&self, style: EraStyle) -> String {
+        let y = self.year();
+        let (year, suffix) = match style {
+            Astronomical => (format!("{}", IsoYear(y)), ""),
+            BcAd if y < 1 => ((1 - y).to_string(), " BC"),
+            BceCe if y < 1 => ((1 - y).to_string(), " BCE"),
+            BuddhistEra => ((y + BUDDHIST_ERA_OFFSET).to_string(), " BE"),
+            _ => (y.to_string(), "")
+        };
+        let rest = match *self {
+            Year(_) => String::new(),
+            YearMonth(_, m) => format!("-{:02d}", m),
+            YearMonthDay(_, m, d) => format!("-{:02d}-{:02d}", m, d),
+            YearDay(_, d) => format!("-{:03d}", d),
+            YearWeek(_, w) => format!("-W{:02d}", w),
+            YearWeekDay(_, w, d) => format!("-W{:02d}-{}", w, d)
+        };
+        format!("{}{}{}", year, rest, suffix)
+    }
+
+    /// Format a complete date as an ISO 8601 ordinal date, such as
+    /// "2024-135".  Returns None for reduced precision dates and for dates
+    /// which do not exist.
+    pub fn to_ordinal_string(&self) -> Option<String> {
+        date_to_cjd(self).map(|cjd| {
+            let (y, d) = calendar_iso8601::to_ordinal(cjd);
+            format!("{}-{:03d}", IsoYear(y), d)
+        })
+    }
+
+    /// Format a complete date as an ISO 8601 week date, such as
+    /// "2024-W20-3".  Returns None for reduced precision dates and for
+    /// dates which do not exist.
+    pub fn to_iso_week_string(&self) -> Option<String> {
+        date_to_cjd(self).map(|cjd| {
+            let (y, w, d) = calendar_iso8601::to_week_date(cjd);
+            format!("{}-W{:02d}-{}", IsoYear(y), w, d)
+        })
+    }
+}
+
+impl Show for Date {
+    /// Uses the ISO 8601 extended format of the date's representation,
+    /// such as "2024-05-14", "2024-135", or "2024-W20-2".
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        let s = self.to_era_string(Astronomical);
+        fmtutil::write_field(f, s.as_bytes())
+    }
+}
+
+impl FromStr for Date {
+    fn from_str(s: &str) -> Option<Date> {
+        parse_date(s)
+    }
+}
+
+/// The fields found by parsing a date and time, before they are resolved
+/// into a particular type.  This allows a string to be parsed once and
+/// then interpreted according to which fields it contains.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct Parsed {
+    /// The date, if present.
+    pub date: Option<Date>,
+    /// The time of day, in ticks since midnight, if present.  This may be
+    /// equal to one day if the time was written as 24:00.
+    pub time: Option<i64>,
+    /// The offset from UTC in ticks, if present.  Offsets east of
+    /// Greenwich are positive, and "Z" is zero.
+    pub offset: Option<i64>
+}
+
+impl Parsed {
+    /// Resolve the fields into a date.  Returns None if there is no date.
+    pub fn to_date(&self) -> Option<Date> {
+        self.date
+    }
+
+    /// Resolve the fields into an instant.  Returns None unless there is a
+    /// complete date, a time, and an offset, or if the result is out of
+    /// range.
+    pub fn to_instant(&self) -> Option<Instant> {
+        match self.offset {
+            Some(offset) => self.to_instant_with_offset(offset),
+            None => None
+        }
+    }
+
+    /// Resolve the fields into an instant, using the given offset in ticks
+    /// if the input did not contain one.
+    pub fn to_instant_with_offset(&self, default_offset: i64)
+                                  -> Option<Instant> {
+        let cjd = match self.date.as_ref().and_then(date_to_cjd) {
+            Some(x) => x, None => return None
+        };
+        let time = match self.time {
+            Some(x) => x, None => return None
+        };
+        let offset = self.offset.unwrap_or(default_offset);
+        (cjd as i64).checked_mul(&tick::DAY)
+            .and_then(|n| n.checked_add(&time))
+            .and_then(|n| n.checked_sub(&offset))
+            .map(|n| Instant { ticks: n })
+    }
+}
+
 /// Read an integer from a bytestring.  Returns the integer, its length, and
 /// the remainter of the string.
 fn read_int<'a>(s: &'a [u8]) -> (int, uint, &'a [u8]) {
@@ -32,75 +526,433 @@ fn read_int<'a>(s: &'a [u8]) -> (int, uint, &'a [u8]) {
     (value, s.len(), &[])
 }
 
-/// Parse a date in ISO 8601 format.
-pub fn parse_date(s: &str) -> Option<Date> {
-    let rem = s.as_bytes();
-    if rem.is_empty() {
+/// Test whether a field read by read_int has an acceptable number of
+/// digits.  Lenient parsing also accepts fields with fewer digits.
+fn valid_width(n: uint, width: uint, opts: &ParseOptions) -> bool {
+    if opts.lenient { n >= 1 && n <= width } else { n == width }
+}
+
+/// Test whether a string starts with the given byte.
+fn starts_with(s: &[u8], c: char) -> bool {
+    !s.is_empty() && s[0] == c as u8
+}
+
+/// Read a date in ISO 8601 extended format from the beginning of a
+/// bytestring.  Returns the date and the remainder of the string.
+fn read_date<'a>(s: &'a [u8], opts: &ParseOptions)
+                 -> Option<(Date, &'a [u8])> {
+    read_date_year_digits(s, opts, 4)
+}
+
+/// Read a date whose year has at least the given number of digits.
+fn read_date_year_digits<'a>(s: &'a [u8], opts: &ParseOptions,
+                             year_digits: uint)
+                             -> Option<(Date, &'a [u8])> {
+    if s.is_empty() {
         return None;
     }
-    let (negative, rem) = match rem[0] as char {
-        '-' => (true, rem.slice_from(1)),
-        '+' => (false, rem.slice_from(1)),
-        _ => (false, rem)
+    let (negative, rem) = match s[0] as char {
+        '-' => (true, s.slice_from(1)),
+        '+' => (false, s.slice_from(1)),
+        _ => (false, s)
     };
     let (year, n, rem) = read_int(rem);
-    if n < 4 {
+    if n < year_digits {
         return None;
     }
-    let year = if negative { -year } else { year };
-    Some(if rem.is_empty() {
-        Year(year)
-    } else {
-        if rem[0] != '-' as u8 || rem.len() < 2 {
-            return None;
-        }
-        if rem[1] == 'W' as u8 {
-            let (week, n, rem) = read_int(rem.slice_from(2));
-            if n != 2 || week < 1 || week > 53 {
+    let year = match opts.year_numbering {
+        NoYearZero => {
+            if year == 0 {
                 return None;
             }
-            if rem.is_empty() {
-                YearWeek(year, week)
-            } else {
-                if rem[0] != '-' as u8 {
-                    return None;
-                }
-                let (day, n, rem) = read_int(rem.slice_from(1));
-                if !rem.is_empty() || n != 1 || day < 1 || day > 7 {
-                    return None;
-                }
-                YearWeekDay(year, week, day)
-            }
-        } else {
-            let (value, n, rem) = read_int(rem.slice_from(1));
-            if n == 2 {
-                let month = value;
-                if month < 1 || month > 12 {
-                    return None;
-                }
-                if rem.is_empty() {
-                    YearMonth(year, month)
-                } else {
-                    if rem[0] != '-' as u8 {
-                        return None;
-                    }
-                    let (day, n, rem) = read_int(rem.slice_from(1));
-                    if !rem.is_empty() || n != 2 || day < 1 || day > 31 {
-                        return None;
-                    }
-                    YearMonthDay(year, month, day)
-                }
-            } else if n == 3 {
-                let day = value;
-                if !rem.is_empty() || day < 1 || day > 366 {
+            if negative { 1 - year } else { year }
+        }
+        BuddhistEraYear =>
+            (if negative { -year } else { year }) - BUDDHIST_ERA_OFFSET,
+        AstronomicalYear => if negative { -year } else { year }
+    };
+    if rem.len() < 2 || rem[0] != '-' as u8 {
+        return Some((Year(year), rem));
+    }
+    if rem[1] == 'W' as u8 {
+        let (week, n, rem) = read_int(rem.slice_from(2));
+        if n != 2 || week < 1 || week > 53 {
+            return None;
+        }
+        if rem.len() < 2 || rem[0] != '-' as u8 {
+            return Some((YearWeek(year, week), rem));
+        }
+        let (day, n, rem) = read_int(rem.slice_from(1));
+        if n != 1 || day < 1 || day > 7 {
+            return None;
+        }
+        return Some((YearWeekDay(year, week, day), rem));
+    }
+    let (value, n, rem) = read_int(rem.slice_from(1));
+    if n == 3 {
+        let day = value;
+        if day < 1 || day > 366 {
+            return None;
+        }
+        return Some((YearDay(year, day), rem));
+    }
+    let month = value;
+    if !valid_width(n, 2, opts) || month < 1 || month > 12 {
+        return None;
+    }
+    if rem.len() < 2 || rem[0] != '-' as u8 {
+        return Some((YearMonth(year, month), rem));
+    }
+    let (day, n, rem) = read_int(rem.slice_from(1));
+    if !valid_width(n, 2, opts) || day < 1 || day > 31 {
+        return None;
+    }
+    Some((YearMonthDay(year, month, day), rem))
+}
+
+/// Read the fields of a time of day, allowing the hour to be 24.
+fn read_time_fields<'a>(s: &'a [u8], opts: &ParseOptions)
+                        -> Option<(i64, &'a [u8])> {
+    let mark = opts.decimal_mark;
+    let (hour, n, rem) = read_int(s);
+    if !valid_width(n, 2, opts) || hour > 24 {
+        return None;
+    }
+    let ticks = hour as i64 * tick::HOUR;
+    if parseutil::starts_with_fraction(rem, mark) {
+        return parseutil::read_fraction(rem, tick::HOUR, mark)
+            .map(|(frac, rem)| (ticks + frac, rem));
+    }
+    if !starts_with(rem, ':') {
+        return None;
+    }
+    let (minute, n, rem) = read_int(rem.slice_from(1));
+    if !valid_width(n, 2, opts) || minute > 59 {
+        return None;
+    }
+    let ticks = ticks + minute as i64 * tick::MINUTE;
+    if parseutil::starts_with_fraction(rem, mark) {
+        return parseutil::read_fraction(rem, tick::MINUTE, mark)
+            .map(|(frac, rem)| (ticks + frac, rem));
+    }
+    if !starts_with(rem, ':') {
+        return if opts.lenient { Some((ticks, rem)) } else { None };
+    }
+    let (second, n, rem) = read_int(rem.slice_from(1));
+    if !valid_width(n, 2, opts) || second > 59 {
+        return None;
+    }
+    let ticks = ticks + second as i64 * tick::SECOND;
+    if parseutil::starts_with_fraction(rem, mark) {
+        return parseutil::read_fraction(rem, tick::SECOND, mark)
+            .map(|(frac, rem)| (ticks + frac, rem));
+    }
+    Some((ticks, rem))
+}
+
+/// Read a time of day in ISO 8601 extended format from the beginning of a
+/// bytestring.  Returns the number of ticks since midnight and the
+/// remainder of the string.  The smallest component present may have a
+/// decimal fraction, as in "14.5" or "14:30,25".  Otherwise, lenient
+/// parsing also accepts times without seconds.  The end of the day may be
+/// written as "24:00:00", which is the same as midnight of the next day.
+fn read_time<'a>(s: &'a [u8], opts: &ParseOptions)
+                 -> Option<(i64, &'a [u8])> {
+    let (hour, _, _) = read_int(s);
+    match read_time_fields(s, opts) {
+        Some((ticks, rem)) if hour < 24 || ticks == tick::DAY =>
+            Some((ticks, rem)),
+        _ => None
+    }
+}
+
+/// Read a zone designator, either "Z" or a UTC offset of the form
+/// "+hh:mm" or "+hh:mm:ss".  When parsing leniently, a time zone
+/// abbreviation from the options is also accepted.  Returns the offset in
+/// ticks and the remainder of the string.
+fn read_offset<'a>(s: &'a [u8], opts: &ParseOptions)
+                   -> Option<(i64, &'a [u8])> {
+    if s.is_empty() {
+        return None;
+    }
+    if opts.lenient {
+        // The abbreviation may follow a space, as in "11:12:13 EST".
+        let rest = if starts_with(s, ' ') { s.slice_from(1) } else { s };
+        match parseutil::read_zone_abbreviation(rest, opts) {
+            Some(x) => return Some(x),
+            None => ()
+        }
+    }
+    let negative = match s[0] as char {
+        'Z' => return Some((0, s.slice_from(1))),
+        'z' if opts.lenient => return Some((0, s.slice_from(1))),
+        '+' => false,
+        '-' => true,
+        _ => return None
+    };
+    let (hour, n, rem) = read_int(s.slice_from(1));
+    if n != 2 || hour > 23 || !starts_with(rem, ':') {
+        return None;
+    }
+    let (minute, n, rem) = read_int(rem.slice_from(1));
+    if n != 2 || minute > 59 {
+        return None;
+    }
+    let (second, rem) = if starts_with(rem, ':') {
+        match read_int(rem.slice_from(1)) {
+            (second, 2, rem) if second <= 59 => (second, rem),
+            _ => return None
+        }
+    } else {
+        (0, rem)
+    };
+    let offset = hour as i64 * tick::HOUR + minute as i64 * tick::MINUTE +
+        second as i64 * tick::SECOND;
+    Some((if negative { -offset } else { offset }, rem))
+}
+
+/// Get the week of the year containing a chronological Julian day, with
+/// weeks starting on the given day and week 1 containing January 1.
+fn week_number_at(cjd: int, first_day: Weekday) -> int {
+    let (y, yday) = calendar_iso8601::to_ordinal(cjd);
+    let jan1 = calendar_iso8601::to_cjd(y, 1, 1);
+    let offset = (calendar_iso8601::weekday(jan1) - first_day as int + 7) % 7;
+    (yday - 1 + offset) / 7 + 1
+}
+
+/// Create a calendar date.  Returns None if it does not exist.
+fn checked_calendar_date(y: int, m: int, d: int) -> Option<Date> {
+    if d < 1 || d > calendar_iso8601::days_in_month(y, m) {
+        None
+    } else {
+        Some(YearMonthDay(y, m, d))
+    }
+}
+
+/// Test whether each field of a date is in range: the month from 1 to
+/// 12, the day within the month or year, the week within the year, and the
+/// weekday from 1 to 7.
+fn fields_in_range(date: &Date) -> bool {
+    let month_ok = |y: int, m: int, d: int| m >= 1 && m <= 12 &&
+        d >= 1 && d <= calendar_iso8601::days_in_month(y, m);
+    let week_ok = |y: int, w: int| w >= 1 &&
+        w <= calendar_iso8601::weeks_in_year(y);
+    match *date {
+        Year(_) => true,
+        YearMonth(y, m) => month_ok(y, m, 1),
+        YearMonthDay(y, m, d) => month_ok(y, m, d),
+        YearDay(y, d) => d >= 1 && d <= calendar_iso8601::days_in_year(y),
+        YearWeek(y, w) => week_ok(y, w),
+        YearWeekDay(y, w, d) => week_ok(y, w) && d >= 1 && d <= 7
+    }
+}
+
+/// Convert a complete date to a chronological Julian day.  Returns None
+/// for reduced precision dates and for dates which do not exist, such as
+/// February 30 or month 13.
+pub fn date_to_cjd(date: &Date) -> Option<int> {
+    if !fields_in_range(date) {
+        return None;
+    }
+    match *date {
+        YearMonthDay(y, m, d) => Some(calendar_iso8601::to_cjd(y, m, d)),
+        YearDay(y, d) => Some(calendar_iso8601::to_cjd(y, 1, 1) + d - 1),
+        YearWeekDay(y, w, d) => Some(calendar_iso8601::week_to_cjd(y, w, d)),
+        _ => None
+    }
+}
+
+/// An era designator accepted by the lenient parser.
+enum EraDesignator {
+    /// "BC" or "BCE", for years before 1 AD.
+    BeforeChrist,
+    /// "AD" or "CE".
+    AnnoDomini,
+    /// "BE", for years of the Thai Buddhist era.
+    BuddhistEraDesignator
+}
+
+/// Split an era designator, such as " BC" or " AD", from the end of a
+/// string.  Returns the rest of the string and the era.  The designator is
+/// case-insensitive.
+fn split_era<'a>(s: &'a [u8]) -> Option<(&'a [u8], EraDesignator)> {
+    static ERAS: [(&'static str, EraDesignator), ..5] = [
+        (" BC", BeforeChrist), (" BCE", BeforeChrist), (" AD", AnnoDomini),
+        (" CE", AnnoDomini), (" BE", BuddhistEraDesignator)
+    ];
+    for &(era, designator) in ERAS.iter() {
+        let era = era.as_bytes();
+        if s.len() <= era.len() {
+            continue;
+        }
+        let (rest, suffix) = (s.slice_to(s.len() - era.len()),
+                              s.slice_from(s.len() - era.len()));
+        let matches = suffix.iter().zip(era.iter()).all(|(&c, &e)| {
+            c == e || (e >= 'A' as u8 && e <= 'Z' as u8 && c == e + 32)
+        });
+        if matches {
+            return Some((rest, designator));
+        }
+    }
+    None
+}
+
+fn parse_date_bytes(s: &[u8], opts: &ParseOptions) -> Option<Date> {
+    if opts.lenient {
+        match split_era(s) {
+            Some((rest, era)) => {
+                // Years with an era need not be padded, and are positive
+                // except in the Buddhist era, which counts through zero.
+                let (digits, numbering) = match era {
+                    BuddhistEraDesignator if starts_with(rest, '-') =>
+                        (rest.slice_from(1), BuddhistEraYear),
+                    BuddhistEraDesignator => (rest, BuddhistEraYear),
+                    _ => (rest, AstronomicalYear)
+                };
+                if digits.is_empty() || digits[0] < '0' as u8 ||
+                    digits[0] > '9' as u8 {
                     return None;
                 }
-                YearDay(year, day)
-            } else {
-                return None;
+                let opts = ParseOptions { year_numbering: numbering, ..*opts };
+                return match read_date_year_digits(rest, &opts, 1) {
+                    Some((date, rem)) if rem.is_empty() => match era {
+                        BeforeChrist if date.year() >= 1 =>
+                            Some(date.replace_year(1 - date.year())),
+                        AnnoDomini if date.year() >= 1 => Some(date),
+                        BuddhistEraDesignator => Some(date),
+                        _ => None
+                    },
+                    _ => None
+                };
             }
+            None => ()
         }
-    })
+    }
+    match read_date(s, opts) {
+        Some((date, rem)) if rem.is_empty() => Some(date),
+        _ => None
+    }
+}
+
+/// Parse a date in ISO 8601 format.
+pub fn parse_date(s: &str) -> Option<Date> {
+    parse_date_bytes(s.as_bytes(), &ParseOptions::strict())
+}
+
+/// Parse a date in ISO 8601 format, also accepting months and days
+/// written with a single digit, and years followed by an era designator,
+/// as in "44 BC", "1066 AD", or "2557 BE".
+pub fn parse_date_lenient(s: &str) -> Option<Date> {
+    parse_date_bytes(s.as_bytes(), &ParseOptions::lenient())
+}
+
+/// Parse a date in ISO 8601 format using the given options.
+pub fn parse_date_with(s: &str, opts: &ParseOptions) -> Option<Date> {
+    parse_date_bytes(s.as_bytes(), opts)
+}
+
+/// Parse an ISO 8601 date at the beginning of a string using the given
+/// options, and return it with the number of bytes it occupies, so that it
+/// can be part of a larger grammar.  Era designators are not accepted.
+pub fn parse_date_prefix(s: &str, opts: &ParseOptions)
+                         -> Option<(Date, uint)> {
+    read_date(s.as_bytes(), opts)
+        .map(|(date, rem)| (date, s.len() - rem.len()))
+}
+
+/// Test whether a string starts with the separator between a date and a
+/// time.
+fn starts_with_time_separator(s: &[u8], opts: &ParseOptions) -> bool {
+    starts_with(s, 'T') ||
+        (opts.lenient && (starts_with(s, 't') || starts_with(s, ' ')))
+}
+
+/// Read a date, a time, or both, followed by an optional zone designator.
+fn read_fields<'a>(s: &'a [u8], opts: &ParseOptions)
+                   -> Option<(Parsed, &'a [u8])> {
+    let (date, rem) = if starts_with_time_separator(s, opts) {
+        (None, s)
+    } else {
+        match read_date(s, opts) {
+            Some((date, rem)) => (Some(date), rem),
+            None => return None
+        }
+    };
+    // Without a time, the fields end after the date, so that a date can be
+    // followed by other text.
+    let date_only = Some((Parsed { date: date, time: None, offset: None },
+                          rem));
+    if !starts_with_time_separator(rem, opts) {
+        return date_only;
+    }
+    let (time, rem) = match read_time(rem.slice_from(1), opts) {
+        Some(x) => x,
+        None => return if date.is_some() { date_only } else { None }
+    };
+    let (offset, rem) = match read_offset(rem, opts) {
+        Some((offset, rem)) => (Some(offset), rem),
+        None => (None, rem)
+    };
+    Some((Parsed { date: date, time: Some(time), offset: offset }, rem))
+}
+
+/// Parse an ISO 8601 date, time of day, or both, and record the fields
+/// which are present without resolving them to a particular type.  This
+/// does not allocate.
+pub fn parse_fields(s: &str, opts: &ParseOptions) -> Option<Parsed> {
+    match read_fields(s.as_bytes(), opts) {
+        Some((parsed, rem)) if rem.is_empty() => Some(parsed),
+        _ => None
+    }
+}
+
+/// Parse the fields of an ISO 8601 date, time of day, or both at the
+/// beginning of a string, and return them with the number of bytes they
+/// occupy.
+pub fn parse_fields_prefix(s: &str, opts: &ParseOptions)
+                           -> Option<(Parsed, uint)> {
+    read_fields(s.as_bytes(), opts)
+        .map(|(parsed, rem)| (parsed, s.len() - rem.len()))
+}
+
+fn parse_instant_bytes(s: &[u8], opts: &ParseOptions) -> Option<Instant> {
+    match read_fields(s, opts) {
+        Some((parsed, rem)) if rem.is_empty() => parsed.to_instant(),
+        _ => None
+    }
+}
+
+/// Parse an instant in ISO 8601 format, such as "2014-06-10T11:12:13.456Z".
+/// The date must be complete, and the time must be followed by either "Z"
+/// or a UTC offset.  The time must include seconds unless its smallest
+/// component has a decimal fraction, as in "T14.5Z" or "T14:30,5Z".
+pub fn parse_instant(s: &str) -> Option<Instant> {
+    parse_instant_bytes(s.as_bytes(), &ParseOptions::strict())
+}
+
+/// Parse an instant in ISO 8601 format, also accepting common deviations
+/// found in real-world timestamps: a lowercase "t" or "z", a space
+/// separating the date and time, missing seconds, and components written
+/// with a single digit.
+pub fn parse_instant_lenient(s: &str) -> Option<Instant> {
+    parse_instant_bytes(s.as_bytes(), &ParseOptions::lenient())
+}
+
+/// Parse an instant in ISO 8601 format using the given options.
+pub fn parse_instant_with(s: &str, opts: &ParseOptions) -> Option<Instant> {
+    parse_instant_bytes(s.as_bytes(), opts)
+}
+
+/// Parse an ISO 8601 instant at the beginning of a string using the given
+/// options, and return it with the number of bytes it occupies, so that it
+/// can be part of a larger grammar, such as a log line.
+pub fn parse_instant_prefix(s: &str, opts: &ParseOptions)
+                            -> Option<(Instant, uint)> {
+    match read_fields(s.as_bytes(), opts) {
+        Some((parsed, rem)) =>
+            parsed.to_instant().map(|t| (t, s.len() - rem.len())),
+        None => None
+    }
 }
 
 #[test]
@@ -126,3 +978,675 @@ fn test_read_date() {
     test("1995-W01", YearWeek(1995, 1));
     test("2007-W44-7", YearWeekDay(2007, 44, 7));
 }
+
+#[test]
+fn test_read_date_lenient() {
+    fn test(s: &str, d: Date) {
+        if parse_date(s).is_some() {
+            fail!("Strict parser accepted: {}", s);
+        }
+        match parse_date_lenient(s) {
+            None => fail!("Could not parse: {}", s),
+            Some(x) => if x != d {
+                fail!("Incorrect parse: {}", s);
+            }
+        }
+    }
+
+    test("2000-5", YearMonth(2000, 5));
+    test("2000-5-3", YearMonthDay(2000, 5, 3));
+    test("2000-05-3", YearMonthDay(2000, 5, 3));
+    test("2000-5-03", YearMonthDay(2000, 5, 3));
+
+    assert!(parse_date_lenient("2000-5-").is_none());
+    assert!(parse_date_lenient("2000-005-03").is_none());
+    assert!(parse_date_lenient("200-05-03").is_none());
+}
+
+#[test]
+fn test_parse_instant() {
+    fn test(s: &str, ticks: i64) {
+        match parse_instant(s) {
+            None => fail!("Could not parse: {}", s),
+            Some(x) => if x.ticks != ticks {
+                fail!("input: '{}', expected: {}, output: {}",
+                      s, ticks, x.ticks);
+            }
+        }
+        match parse_instant_lenient(s) {
+            None => fail!("Lenient parser rejected: {}", s),
+            Some(x) => assert_eq!(x.ticks, ticks)
+        }
+    }
+
+    test("2000-01-01T00:00:00Z", 0);
+    test("2000-01-01T00:00:01Z", tick::SECOND);
+    test("1999-12-31T23:59:59Z", -tick::SECOND);
+    test("2000-01-02T00:00:00Z", tick::DAY);
+    test("2000-01-01T00:00:00.5Z", tick::SECOND / 2);
+    test("2000-01-01T00:00:00,5Z", tick::SECOND / 2);
+    test("2000-01-01T00:00:00.00000005Z", 0);
+    test("2000-01-01T00:00:00.00000015Z", 2);
+    test("2000-01-01T00:00:00.000000051Z", 1);
+    test("2000-01-01T01:00:00+01:00", 0);
+    test("1999-12-31T23:00:00-01:00", 0);
+    test("2000-001T00:00:00Z", 0);
+    test("1999-W52-6T00:00:00Z", 0);
+
+    // Fractions on the smallest component present
+    test("2000-01-01T14.5Z", 14 * tick::HOUR + 30 * tick::MINUTE);
+    test("2000-01-01T14,5Z", 14 * tick::HOUR + 30 * tick::MINUTE);
+    test("2000-01-01T00.25Z", 15 * tick::MINUTE);
+    test("2000-01-01T14:30,25Z",
+         14 * tick::HOUR + 30 * tick::MINUTE + 15 * tick::SECOND);
+    test("2000-01-01T00:00.1Z", 6 * tick::SECOND);
+    test("2000-01-01T00.00000000001Z", 0);
+    test("2000-01-01T00.0000000000138888888888888Z", 0);
+    test("2000-01-01T00.0000000000138888888888889Z", 1);
+    test("2000-01-01T00.000000000125Z", 4);
+    test("2000-01-01T00.000000000375Z", 14);
+
+    // End of day
+    test("1999-12-31T24:00:00Z", 0);
+    test("1999-12-31T24:00:00.000Z", 0);
+    test("1999-12-31T24:00,0Z", 0);
+    test("1999-12-31T24.0Z", 0);
+    test("2000-01-01T24:00:00+01:00", tick::DAY - tick::HOUR);
+}
+
+#[test]
+fn test_parse_instant_lenient() {
+    fn test(s: &str, ticks: i64) {
+        if parse_instant(s).is_some() {
+            fail!("Strict parser accepted: {}", s);
+        }
+        match parse_instant_lenient(s) {
+            None => fail!("Could not parse: {}", s),
+            Some(x) => if x.ticks != ticks {
+                fail!("input: '{}', expected: {}, output: {}",
+                      s, ticks, x.ticks);
+            }
+        }
+    }
+
+    test("2000-01-01t00:00:00Z", 0);
+    test("2000-01-01T00:00:00z", 0);
+    test("2000-01-01 00:00:00Z", 0);
+    test("2000-01-01T00:00Z", 0);
+    test("2000-1-2T3:04:05Z",
+         tick::DAY + 3 * tick::HOUR + 4 * tick::MINUTE + 5 * tick::SECOND);
+    test("2000-01-01 1:02Z", tick::HOUR + 2 * tick::MINUTE);
+}
+
+#[test]
+fn test_parse_instant_fail() {
+    fn test(s: &str) {
+        if parse_instant_lenient(s).is_some() {
+            fail!("input: '{}'", s);
+        }
+    }
+
+    test("");
+    test("2000-01-01");
+    test("2000-01-01T");
+    test("2000-01-01T00:00:00");
+    test("2000-01-01T00:00:00Y");
+    test("2000-01-01T25:00:00Z");
+    test("2000-01-01T24:00:01Z");
+    test("2000-01-01T24:00:00.0000001Z");
+    test("2000-01-01T24:01Z");
+    test("2000-01-01T24.5Z");
+    test("2000-01-01T00:60:00Z");
+    test("2000-01-01T00:00:60Z");
+    test("2000-01-01T00:00:00.Z");
+    test("2000-01-01T00:00:00+0100");
+    test("2000-02-30T00:00:00Z");
+    test("2001-366T00:00:00Z");
+    test("2010-W53-1T00:00:00Z");
+    test("2000-01T00:00:00Z");
+    test("2000-01-01T00:00:00Zx");
+    test("2000-01-01x00:00:00Z");
+    test("2000-01-01T14.Z");
+    test("2000-01-01T14.5:00Z");
+    test("2000-01-01T14:30.5:00Z");
+    test("2000-01-01T00:00:00.5.5Z");
+}
+
+#[test]
+fn test_parse_instant_decimal_mark() {
+    use parse::{DecimalPoint, DecimalComma};
+
+    let point = ParseOptions { decimal_mark: DecimalPoint,
+                               ..ParseOptions::strict() };
+    let comma = ParseOptions { decimal_mark: DecimalComma,
+                               ..ParseOptions::strict() };
+    let half = Some(Instant { ticks: tick::SECOND / 2 });
+
+    assert_eq!(parse_instant_with("2000-01-01T00:00:00.5Z", &point), half);
+    assert_eq!(parse_instant_with("2000-01-01T00:00:00,5Z", &point), None);
+    assert_eq!(parse_instant_with("2000-01-01T00:00:00.5Z", &comma), None);
+    assert_eq!(parse_instant_with("2000-01-01T00:00:00,5Z", &comma), half);
+    assert_eq!(parse_instant_with("2000-01-01T00:00,5Z", &point), None);
+    assert_eq!(parse_instant_with("2000-01-01T00,5Z", &point), None);
+}
+
+#[test]
+fn test_date_to_cjd_range() {
+    assert_eq!(date_to_cjd(&YearMonthDay(2000, 1, 1)),
+               Some(calendar_iso8601::to_cjd(2000, 1, 1)));
+    assert_eq!(date_to_cjd(&YearMonthDay(2000, 0, 1)), None);
+    assert_eq!(date_to_cjd(&YearMonthDay(2000, 13, 1)), None);
+    assert_eq!(date_to_cjd(&YearMonthDay(2000, 1, 0)), None);
+    assert_eq!(date_to_cjd(&YearMonthDay(2000, 1, -5)), None);
+    assert_eq!(date_to_cjd(&YearDay(2000, 0)), None);
+    assert_eq!(date_to_cjd(&YearDay(2000, 367)), None);
+    assert_eq!(date_to_cjd(&YearWeekDay(2000, 0, 1)), None);
+    assert_eq!(date_to_cjd(&YearWeekDay(2000, 53, 1)), None);
+    assert_eq!(date_to_cjd(&YearWeekDay(2000, 1, 0)), None);
+    assert_eq!(date_to_cjd(&YearWeekDay(2000, 1, 8)), None);
+}
+
+#[test]
+fn test_date_alternate_formats() {
+    fn test(d: Date, ordinal: &str, week: &str) {
+        assert_eq!(d.to_ordinal_string(), Some(ordinal.to_string()));
+        assert_eq!(d.to_iso_week_string(), Some(week.to_string()));
+        assert_eq!(parse_date(ordinal).as_ref().and_then(date_to_cjd),
+                   date_to_cjd(&d));
+        assert_eq!(parse_date(week).as_ref().and_then(date_to_cjd),
+                   date_to_cjd(&d));
+    }
+
+    test(YearMonthDay(2024, 5, 15), "2024-136", "2024-W20-3");
+    test(YearMonthDay(2000, 1, 1), "2000-001", "1999-W52-6");
+    test(YearMonthDay(2008, 12, 29), "2008-364", "2009-W01-1");
+    test(YearDay(2024, 135), "2024-135", "2024-W20-2");
+    test(YearWeekDay(2009, 53, 7), "2010-003", "2009-W53-7");
+
+    assert_eq!(YearMonthDay(-1, 1, 1).to_ordinal_string(),
+               Some("-0001-001".to_string()));
+    assert_eq!(YearMonthDay(10000, 1, 1).to_iso_week_string(),
+               Some("9999-W52-6".to_string()));
+
+    assert_eq!(Year(2000).to_ordinal_string(), None);
+    assert_eq!(YearMonth(2000, 1).to_iso_week_string(), None);
+    assert_eq!(YearMonthDay(2001, 2, 29).to_ordinal_string(), None);
+}
+
+#[test]
+fn test_era() {
+    fn test(d: Date, bcad: &str, bcece: &str) {
+        let out = d.to_era_string(BcAd);
+        if out.as_slice() != bcad {
+            fail!("expected: '{}', output: '{}'", bcad, out);
+        }
+        let out = d.to_era_string(BceCe);
+        if out.as_slice() != bcece {
+            fail!("expected: '{}', output: '{}'", bcece, out);
+        }
+        assert!(parse_date_lenient(bcad) == Some(d));
+        assert!(parse_date_lenient(bcece) == Some(d));
+    }
+
+    test(Year(-43), "44 BC", "44 BCE");
+    test(Year(0), "1 BC", "1 BCE");
+    test(Year(1), "1", "1");
+    test(YearMonthDay(-43, 3, 15), "44-03-15 BC", "44-03-15 BCE");
+    test(YearMonthDay(800, 12, 25), "800-12-25", "800-12-25");
+    test(YearWeekDay(-99, 1, 1), "100-W01-1 BC", "100-W01-1 BCE");
+    test(YearDay(2024, 135), "2024-135", "2024-135");
+
+    assert_eq!(Year(-43).to_era_string(Astronomical), "-0043".to_string());
+    assert_eq!(Year(12345).to_era_string(Astronomical),
+               "+12345".to_string());
+    assert_eq!(Year(44).to_era_string(Astronomical), "0044".to_string());
+
+    assert_eq!(YearMonthDay(2014, 6, 10).to_era_string(BuddhistEra),
+               "2557-06-10 BE".to_string());
+    assert_eq!(Year(-43).to_era_string(BuddhistEra), "500 BE".to_string());
+    assert!(parse_date_lenient("2557-06-10 BE") ==
+            Some(YearMonthDay(2014, 6, 10)));
+    assert!(parse_date_lenient("500 be") == Some(Year(-43)));
+    assert_eq!(Year(-600).to_era_string(BuddhistEra), "-57 BE".to_string());
+    assert!(parse_date_lenient("-57 BE") == Some(Year(-600)));
+    assert!(parse_date_lenient("0 BE") == Some(Year(-543)));
+    assert!(parse_date_lenient("-57-01-01 BE") ==
+            Some(YearMonthDay(-600, 1, 1)));
+    assert!(parse_date_lenient("+57 BE").is_none());
+    assert!(parse_date_lenient("-57 BC").is_none());
+    assert!(parse_date("2557 BE").is_none());
+
+    assert!(parse_date_lenient("1066 AD") == Some(Year(1066)));
+    assert!(parse_date_lenient("1066 ce") == Some(Year(1066)));
+    assert!(parse_date_lenient("0044-03-15 bc") ==
+            Some(YearMonthDay(-43, 3, 15)));
+    assert!(parse_date("44 BC").is_none());
+    assert!(parse_date("0044 BC").is_none());
+    assert!(parse_date_lenient("44").is_none());
+    assert!(parse_date_lenient("0 BC").is_none());
+    assert!(parse_date_lenient("-44 BC").is_none());
+    assert!(parse_date_lenient("+44 BC").is_none());
+    assert!(parse_date_lenient(" BC").is_none());
+    assert!(parse_date_lenient("44BC").is_none());
+}
+
+#[test]
+fn test_year_numbering() {
+    let historical = ParseOptions { year_numbering: NoYearZero,
+                                    ..ParseOptions::strict() };
+    let astronomical = ParseOptions { year_numbering: AstronomicalYear,
+                                      ..ParseOptions::strict() };
+    fn test(opts: &ParseOptions, s: &str, d: Option<Date>) {
+        if parse_date_with(s, opts) != d {
+            fail!("Incorrect parse: {}", s);
+        }
+    }
+
+    test(&astronomical, "-0001", Some(Year(-1)));
+    test(&astronomical, "0000", Some(Year(0)));
+    test(&historical, "-0001", Some(Year(0)));
+    test(&historical, "-0002", Some(Year(-1)));
+    test(&historical, "0000", None);
+    test(&historical, "-0000", None);
+    test(&historical, "2000", Some(Year(2000)));
+    test(&historical, "-0044-03-15", Some(YearMonthDay(-43, 3, 15)));
+
+    assert_eq!(parse_instant_with("-0001-01-01T00:00:00Z", &historical),
+               parse_instant("0000-01-01T00:00:00Z"));
+
+    let buddhist = ParseOptions { year_numbering: BuddhistEraYear,
+                                  ..ParseOptions::strict() };
+    test(&buddhist, "2557-06-10", Some(YearMonthDay(2014, 6, 10)));
+    test(&buddhist, "0543", Some(Year(0)));
+    test(&buddhist, "0001", Some(Year(-542)));
+    test(&buddhist, "-0001", Some(Year(-544)));
+    assert_eq!(parse_instant_with("2557-06-10T11:12:13Z", &buddhist),
+               parse_instant("2014-06-10T11:12:13Z"));
+    let lenient = ParseOptions { lenient: true, ..buddhist };
+    test(&lenient, "2014-06-10 AD", Some(YearMonthDay(2014, 6, 10)));
+    test(&lenient, "2557-06-10 BE", Some(YearMonthDay(2014, 6, 10)));
+}
+
+#[test]
+fn test_parse_fields() {
+    let opts = ParseOptions::strict();
+    fn test(opts: &ParseOptions, s: &str, date: Option<Date>,
+            time: Option<i64>, offset: Option<i64>) {
+        match parse_fields(s, opts) {
+            None => fail!("Could not parse: {}", s),
+            Some(p) => if p != (Parsed { date: date, time: time,
+                                         offset: offset }) {
+                fail!("Incorrect parse: {}", s);
+            }
+        }
+    }
+
+    test(&opts, "2000-01-02", Some(YearMonthDay(2000, 1, 2)), None, None);
+    test(&opts, "2000-W01", Some(YearWeek(2000, 1)), None, None);
+    test(&opts, "2000-01-02T03:00:00", Some(YearMonthDay(2000, 1, 2)),
+         Some(3 * tick::HOUR), None);
+    test(&opts, "2000-01-02T03:00:00Z", Some(YearMonthDay(2000, 1, 2)),
+         Some(3 * tick::HOUR), Some(0));
+    test(&opts, "2000-01-02T03:00:00-01:30", Some(YearMonthDay(2000, 1, 2)),
+         Some(3 * tick::HOUR), Some(-90 * tick::MINUTE));
+    test(&opts, "T03:00:00", None, Some(3 * tick::HOUR), None);
+    test(&opts, "T14.5+01:00", None,
+         Some(14 * tick::HOUR + 30 * tick::MINUTE), Some(tick::HOUR));
+    test(&ParseOptions::lenient(), "2000-1-2 03:00",
+         Some(YearMonthDay(2000, 1, 2)), Some(3 * tick::HOUR), None);
+
+    assert!(parse_fields("", &opts).is_none());
+    assert!(parse_fields("T", &opts).is_none());
+    assert!(parse_fields("2000-01-02T", &opts).is_none());
+    assert!(parse_fields("2000-01-02Z", &opts).is_none());
+    assert!(parse_fields("2000-01-02T03:00:00X", &opts).is_none());
+}
+
+#[test]
+fn test_zone_abbreviations() {
+    use parse::ZoneAbbreviations;
+    static TABLE: ZoneAbbreviations = &[("CET", tick::HOUR),
+                                        ("JST", 9 * tick::HOUR)];
+    let lenient = ParseOptions::lenient();
+    let custom = ParseOptions { zone_abbreviations: TABLE,
+                                ..ParseOptions::lenient() };
+    fn test(opts: &ParseOptions, s: &str, expected: Option<&str>) {
+        let out = parse_instant_with(s, opts);
+        if out != expected.and_then(|e| parse_instant(e)) {
+            fail!("input: '{}', expected: {}, output: {}",
+                  s, expected, out);
+        }
+    }
+    test(&lenient, "2014-06-10 11:12:13 EST",
+         Some("2014-06-10T11:12:13-05:00"));
+    test(&lenient, "2014-06-10T11:12:13UTC", Some("2014-06-10T11:12:13Z"));
+    test(&lenient, "2014-06-10 11:12:13 gmt", Some("2014-06-10T11:12:13Z"));
+    test(&lenient, "2014-06-10 11:12:13 CET", None);
+    test(&custom, "2014-06-10 11:12:13 CET",
+         Some("2014-06-10T11:12:13+01:00"));
+    test(&custom, "2014-06-10 11:12:13 JST",
+         Some("2014-06-10T11:12:13+09:00"));
+    test(&custom, "2014-06-10 11:12:13 EST", None);
+    test(&custom, "2014-06-10 11:12:13 CETX", None);
+    test(&ParseOptions::strict(), "2014-06-10T11:12:13EST", None);
+}
+
+#[test]
+fn test_parsed_resolve() {
+    let opts = ParseOptions::strict();
+    let p = parse_fields("2000-01-02T03:00:00", &opts).unwrap();
+    assert!(p.to_date() == Some(YearMonthDay(2000, 1, 2)));
+    assert_eq!(p.to_instant(), None);
+    assert_eq!(p.to_instant_with_offset(tick::HOUR),
+               Some(Instant { ticks: tick::DAY + 2 * tick::HOUR }));
+
+    let p = parse_fields("2000-01-02T03:00:00Z", &opts).unwrap();
+    assert_eq!(p.to_instant(),
+               Some(Instant { ticks: tick::DAY + 3 * tick::HOUR }));
+    assert_eq!(p.to_instant_with_offset(tick::HOUR), p.to_instant());
+
+    let p = parse_fields("2000-01", &opts).unwrap();
+    assert!(p.to_date() == Some(YearMonth(2000, 1)));
+    assert_eq!(p.to_instant_with_offset(0), None);
+
+    let p = parse_fields("T03:00:00Z", &opts).unwrap();
+    assert!(p.to_date().is_none());
+    assert_eq!(p.to_instant(), None);
+}
+
+#[test]
+fn test_date_compare() {
+    let a = YearMonthDay(1999, 12, 31);
+    let b = YearMonthDay(2000, 1, 1);
+    let c = YearMonthDay(2000, 1, 2);
+    assert!(a.is_before(b) && !b.is_before(a) && !b.is_before(b));
+    assert!(c.is_after(b) && !b.is_after(c) && !b.is_after(b));
+    assert!(b.is_between(a, c) && a.is_between(a, c) && !c.is_between(a, c));
+
+    // Different representations of the same day are equal on the calendar.
+    assert!(!YearDay(2000, 1).is_before(b) && !YearDay(2000, 1).is_after(b));
+    assert!(!YearWeekDay(1999, 52, 6).is_after(b));
+    assert!(YearWeekDay(2000, 1, 1).is_after(b));
+    assert!(Year(2000).is_between(b, c));
+    assert!(YearMonth(2000, 1).is_before(c));
+    assert!(YearWeek(2000, 1).is_after(c));
+    assert!(YearWeekDay(2009, 53, 7).is_before(YearDay(2010, 4)));
+    assert!(YearMonth(2000, 12).is_between(b, Year(2001)));
+}
+
+#[test]
+#[should_fail]
+fn test_date_compare_bad_month() {
+    YearMonth(2014, 13).is_before(YearMonthDay(2014, 6, 10));
+}
+
+#[test]
+#[should_fail]
+fn test_date_compare_bad_day() {
+    YearMonthDay(2014, 6, 10).is_after(YearMonthDay(2014, 0, 1));
+}
+
+#[test]
+#[should_fail]
+fn test_date_compare_bad_week() {
+    YearMonthDay(2014, 6, 10).is_between(YearWeek(2014, 0), Year(2015));
+}
+
+#[test]
+fn test_date_periods() {
+    let v: Vec<Date> = YearMonthDay(2023, 12, 15).months_iter().take(2)
+        .collect();
+    assert!(v == vec![YearMonthDay(2023, 12, 1), YearMonthDay(2024, 1, 1)]);
+    let v: Vec<Date> = YearDay(2024, 31).months_iter_clamped().take(2)
+        .collect();
+    assert!(v == vec![YearMonthDay(2024, 1, 31), YearMonthDay(2024, 2, 29)]);
+    let v: Vec<Date> = YearWeek(2021, 1).years_iter().take(2).collect();
+    assert!(v == vec![YearMonthDay(2021, 1, 1), YearMonthDay(2022, 1, 1)]);
+    let v: Vec<Date> = YearMonthDay(2020, 2, 29).years_iter_clamped()
+        .take(2).collect();
+    assert!(v == vec![YearMonthDay(2020, 2, 29), YearMonthDay(2021, 2, 28)]);
+}
+
+#[test]
+fn test_date_range() {
+    let v: Vec<Date> = Date::range(YearMonthDay(2024, 2, 28),
+                                   YearMonthDay(2024, 3, 2)).collect();
+    assert!(v == vec![YearMonthDay(2024, 2, 28), YearMonthDay(2024, 2, 29),
+                      YearMonthDay(2024, 3, 1)]);
+    // 2024-05-13 is a Monday.
+    let v: Vec<Date> = Date::range(YearMonth(2024, 5), YearMonth(2024, 6))
+        .only_weekdays([Monday, Wednesday, Friday]).take(4).collect();
+    assert!(v == vec![YearMonthDay(2024, 5, 1), YearMonthDay(2024, 5, 3),
+                      YearMonthDay(2024, 5, 6), YearMonthDay(2024, 5, 8)]);
+    assert_eq!(Date::range(YearWeek(2024, 20), YearWeek(2024, 22))
+               .exclude_weekends().count(), 10);
+}
+
+#[test]
+fn test_date_show() {
+    fn test(date: Date, expected: &str) {
+        let s = format!("{}", date);
+        assert_eq!(s.as_slice(), expected);
+        let parsed: Option<Date> = from_str(s.as_slice());
+        assert_eq!(parsed, Some(date));
+    }
+    test(Year(2024), "2024");
+    test(YearMonth(2024, 5), "2024-05");
+    test(YearMonthDay(2024, 5, 14), "2024-05-14");
+    test(YearDay(2024, 135), "2024-135");
+    test(YearWeek(2024, 20), "2024-W20");
+    test(YearWeekDay(2024, 20, 2), "2024-W20-2");
+    test(YearMonthDay(-43, 3, 15), "-0043-03-15");
+    test(YearMonthDay(10000, 1, 1), "+10000-01-01");
+    assert_eq!(format!("{:>12}", YearMonth(2024, 5)).as_slice(),
+               "     2024-05");
+    let parsed: Option<Date> = from_str("2024-5-14");
+    assert_eq!(parsed, None);
+}
+
+#[test]
+fn test_week_of_year() {
+    fn test(date: Date, iso: (int, int), us: int, sunday: int, monday: int) {
+        assert_eq!(date.week_of_year(IsoWeeks), Some(iso));
+        let y = date.year();
+        assert_eq!(date.week_of_year(UsWeeks), Some((y, us)));
+        assert_eq!(date.week_of_year(SundayWeeks), Some((y, sunday)));
+        assert_eq!(date.week_of_year(MondayWeeks), Some((y, monday)));
+    }
+    // 2022-01-01 is a Saturday.
+    test(YearMonthDay(2022, 1, 1), (2021, 52), 1, 0, 0);
+    test(YearMonthDay(2022, 1, 2), (2021, 52), 2, 1, 0);
+    test(YearMonthDay(2022, 1, 3), (2022, 1), 2, 1, 1);
+    test(YearMonthDay(2022, 12, 31), (2022, 52), 53, 52, 52);
+    // 2024-01-01 is a Monday.
+    test(YearMonthDay(2024, 1, 1), (2024, 1), 1, 0, 1);
+    test(YearMonthDay(2024, 12, 30), (2025, 1), 53, 52, 53);
+    test(YearDay(2024, 7), (2024, 1), 2, 1, 1);
+    assert_eq!(YearMonth(2024, 1).week_of_year(IsoWeeks), None);
+    assert_eq!(YearMonthDay(2023, 2, 29).week_of_year(UsWeeks), None);
+}
+
+#[test]
+fn test_first_day_of_week() {
+    // 2024-05-15 is a Wednesday.
+    let date = YearMonthDay(2024, 5, 15);
+    assert_eq!(date.start_of_week(Monday), Some(YearMonthDay(2024, 5, 13)));
+    assert_eq!(date.start_of_week(Sunday), Some(YearMonthDay(2024, 5, 12)));
+    assert_eq!(date.start_of_week(Wednesday), Some(date));
+    assert_eq!(date.start_of_week(Thursday),
+               Some(YearMonthDay(2024, 5, 9)));
+    assert_eq!(YearMonthDay(2024, 1, 1).start_of_week(Sunday),
+               Some(YearMonthDay(2023, 12, 31)));
+    assert_eq!(YearMonth(2024, 5).start_of_week(Monday), None);
+    // 2022-01-01 is a Saturday.
+    assert_eq!(YearMonthDay(2022, 1, 1).week_number(Saturday), Some(1));
+    assert_eq!(YearMonthDay(2022, 1, 7).week_number(Saturday), Some(1));
+    assert_eq!(YearMonthDay(2022, 1, 8).week_number(Saturday), Some(2));
+    assert_eq!(YearMonthDay(2022, 1, 3).week_number(Monday), Some(2));
+    assert_eq!(YearMonthDay(2022, 1, 2).week_number(Sunday), Some(2));
+}
+
+#[test]
+fn test_month_grid() {
+    fn test(year: int, month: int, first_day: Weekday, rows: uint,
+            first: Date, last: Date) {
+        let grid = Date::month_grid(year, month, first_day).unwrap();
+        if grid.len() != rows || grid[0][0] != first ||
+            grid[rows - 1][6] != last {
+            fail!("input: {}-{} {}, rows: {}, first: {}, last: {}",
+                  year, month, first_day, grid.len(), grid[0][0],
+                  grid[grid.len() - 1][6]);
+        }
+    }
+    // February 2015 starts on a Sunday and has exactly four weeks.
+    test(2015, 2, Sunday, 4, YearMonthDay(2015, 2, 1),
+         YearMonthDay(2015, 2, 28));
+    test(2015, 2, Monday, 5, YearMonthDay(2015, 1, 26),
+         YearMonthDay(2015, 3, 1));
+    // August 2015 starts on a Saturday and needs six weeks.
+    test(2015, 8, Sunday, 6, YearMonthDay(2015, 7, 26),
+         YearMonthDay(2015, 9, 5));
+    test(2015, 8, Monday, 6, YearMonthDay(2015, 7, 27),
+         YearMonthDay(2015, 9, 6));
+    test(2014, 12, Monday, 5, YearMonthDay(2014, 12, 1),
+         YearMonthDay(2015, 1, 4));
+    let grid = Date::month_grid(2015, 2, Monday).unwrap();
+    assert_eq!(grid[1][0], YearMonthDay(2015, 2, 2));
+    assert!(Date::month_grid(2015, 13, Monday).is_none());
+}
+
+#[test]
+fn test_nth_weekday() {
+    fn test(year: int, month: int, weekday: Weekday, n: int,
+            expected: Option<Date>) {
+        let out = Date::nth_weekday(year, month, weekday, n);
+        if out != expected {
+            fail!("input: {}-{} {} {}, expected: {}, output: {}",
+                  year, month, weekday, n, expected, out);
+        }
+    }
+    // June 2014 starts on a Sunday and ends on a Monday.
+    test(2014, 6, Sunday, 1, Some(YearMonthDay(2014, 6, 1)));
+    test(2014, 6, Wednesday, 3, Some(YearMonthDay(2014, 6, 18)));
+    test(2014, 6, Monday, 5, Some(YearMonthDay(2014, 6, 30)));
+    test(2014, 6, Tuesday, 5, None);
+    test(2014, 6, Monday, -1, Some(YearMonthDay(2014, 6, 30)));
+    test(2014, 6, Sunday, -1, Some(YearMonthDay(2014, 6, 29)));
+    test(2014, 6, Sunday, -5, Some(YearMonthDay(2014, 6, 1)));
+    test(2014, 6, Saturday, -5, None);
+    test(2016, 2, Monday, -1, Some(YearMonthDay(2016, 2, 29)));
+    test(2014, 6, Monday, 0, None);
+    test(2014, 13, Monday, 1, None);
+}
+
+#[test]
+fn test_with_fields() {
+    assert_eq!(YearMonthDay(2014, 6, 10).with_year(2015),
+               Some(YearMonthDay(2015, 6, 10)));
+    assert_eq!(YearMonthDay(2012, 2, 29).with_year(2013), None);
+    assert_eq!(YearWeekDay(2015, 53, 1).with_year(2014), None);
+    assert_eq!(YearWeek(2015, 53).with_year(2020), Some(YearWeek(2020, 53)));
+    assert_eq!(YearDay(2012, 366).with_year(2016), Some(YearDay(2016, 366)));
+    assert_eq!(YearMonth(2014, 6).with_year(-44), Some(YearMonth(-44, 6)));
+    assert_eq!(YearMonthDay(2014, 1, 31).with_month(2), None);
+    assert_eq!(YearMonthDay(2014, 1, 31).with_month(3),
+               Some(YearMonthDay(2014, 3, 31)));
+    assert_eq!(YearDay(2014, 161).with_month(7),
+               Some(YearMonthDay(2014, 7, 10)));
+    assert_eq!(Year(2014).with_month(7), Some(YearMonth(2014, 7)));
+    assert_eq!(YearMonthDay(2014, 1, 1).with_month(13), None);
+    assert_eq!(YearWeek(2014, 5).with_month(1), None);
+    assert_eq!(YearMonthDay(2014, 6, 10).with_day(1),
+               Some(YearMonthDay(2014, 6, 1)));
+    assert_eq!(YearWeekDay(2014, 24, 2).with_day(30),
+               Some(YearMonthDay(2014, 6, 30)));
+    assert_eq!(YearMonth(2014, 2).with_day(28),
+               Some(YearMonthDay(2014, 2, 28)));
+    assert_eq!(YearMonth(2014, 2).with_day(29), None);
+    assert_eq!(YearMonthDay(2014, 6, 10).with_day(0), None);
+    assert_eq!(Year(2014).with_day(1), None);
+}
+
+#[test]
+fn test_parse_prefix() {
+    let strict = ParseOptions::strict();
+    let lenient = ParseOptions::lenient();
+    assert_eq!(parse_date_prefix("2014-06-10 GET /", &strict),
+               Some((YearMonthDay(2014, 6, 10), 10)));
+    assert_eq!(parse_date_prefix("2014-06-10T11:12:13Z", &strict),
+               Some((YearMonthDay(2014, 6, 10), 10)));
+    assert_eq!(parse_date_prefix("2014-W24-2;", &strict),
+               Some((YearWeekDay(2014, 24, 2), 10)));
+    assert_eq!(parse_date_prefix("June 10", &strict), None);
+    let t: Instant = from_str("2014-06-10T11:12:13Z").unwrap();
+    assert_eq!(parse_instant_prefix("2014-06-10T11:12:13Z GET /", &strict),
+               Some((t, 20)));
+    assert_eq!(parse_instant_prefix("2014-06-10T13:12:13+02:00]", &strict),
+               Some((t, 25)));
+    assert_eq!(parse_instant_prefix("2014-06-10T11:12:13 GET /", &strict),
+               None);
+    assert_eq!(parse_instant_prefix("2014-06-10 11:12:13Z up", &lenient),
+               Some((t, 20)));
+    assert_eq!(parse_instant_prefix("2014-06-10 up", &lenient), None);
+    // The whole-string parsers still reject trailing text.
+    assert_eq!(parse_instant("2014-06-10T11:12:13Z GET"), None);
+    assert_eq!(parse_date("2014-06-10 "), None);
+    assert_eq!(parse_instant_lenient("2014-06-10 up"), None);
+}
+
+#[test]
+fn test_add_period() {
+    fn test(date: Date, years: int, months: int, days: int,
+            expected: Option<Date>) {
+        let out = date.add_period(&Period::new(years, months, days));
+        if out != expected {
+            fail!("input: {}, period: {} {} {}, expected: {}, output: {}",
+                  date, years, months, days, expected, out);
+        }
+    }
+    test(YearMonthDay(2014, 6, 10), 0, 0, 0, Some(YearMonthDay(2014, 6, 10)));
+    test(YearMonthDay(2014, 6, 10), 1, 2, 3, Some(YearMonthDay(2015, 8, 13)));
+    test(YearMonthDay(2014, 1, 31), 0, 1, 0, Some(YearMonthDay(2014, 2, 28)));
+    test(YearMonthDay(2012, 1, 31), 0, 1, 0, Some(YearMonthDay(2012, 2, 29)));
+    test(YearMonthDay(2012, 2, 29), 1, 0, 0, Some(YearMonthDay(2013, 2, 28)));
+    test(YearMonthDay(2014, 1, 31), 0, 1, 1, Some(YearMonthDay(2014, 3, 1)));
+    test(YearMonthDay(2014, 3, 31), 0, -1, 0, Some(YearMonthDay(2014, 2, 28)));
+    test(YearMonthDay(2014, 1, 15), 0, -13, 0,
+         Some(YearMonthDay(2012, 12, 15)));
+    test(YearMonthDay(2014, 1, 1), 0, 0, -1, Some(YearMonthDay(2013, 12, 31)));
+    test(YearDay(2014, 161), 0, 0, 7, Some(YearMonthDay(2014, 6, 17)));
+    test(YearWeekDay(2014, 24, 2), 0, 1, 0, Some(YearMonthDay(2014, 7, 10)));
+    test(YearMonth(2014, 6), 0, 1, 0, None);
+    test(YearMonthDay(2014, 2, 30), 0, 1, 0, None);
+    test(YearMonthDay(2014, 1, 1), MAX_YEAR, 0, 0, None);
+    let max: int = Bounded::max_value();
+    test(YearMonthDay(2014, 1, 1), max, 0, 0, None);
+    test(YearMonthDay(2014, 1, 1), 0, 0, max, None);
+}
+
+#[test]
+fn test_add_years() {
+    use period::{Feb28, Mar1, Skip};
+    fn test(date: Date, years: int, policy: AnniversaryPolicy,
+            expected: Option<Date>) {
+        let out = date.add_years(years, policy);
+        if out != expected {
+            fail!("input: {} + {}, policy: {}, expected: {}, output: {}",
+                  date, years, policy, expected, out);
+        }
+    }
+    test(YearMonthDay(2014, 6, 10), 1, Skip, Some(YearMonthDay(2015, 6, 10)));
+    test(YearMonthDay(2014, 6, 10), -14, Skip,
+         Some(YearMonthDay(2000, 6, 10)));
+    test(YearMonthDay(2012, 2, 29), 1, Feb28, Some(YearMonthDay(2013, 2, 28)));
+    test(YearMonthDay(2012, 2, 29), 1, Mar1, Some(YearMonthDay(2013, 3, 1)));
+    test(YearMonthDay(2012, 2, 29), 1, Skip, None);
+    test(YearMonthDay(2012, 2, 29), 4, Skip, Some(YearMonthDay(2016, 2, 29)));
+    test(YearMonthDay(2012, 2, 29), 88, Mar1, Some(YearMonthDay(2100, 3, 1)));
+    test(YearDay(2012, 60), 1, Mar1, Some(YearMonthDay(2013, 3, 1)));
+    test(YearMonth(2012, 2), 1, Feb28, None);
+    test(YearMonthDay(2013, 2, 29), 1, Feb28, None);
+    test(YearMonthDay(2014, 1, 1), MAX_YEAR, Feb28, None);
+    let max: int = Bounded::max_value();
+    test(YearMonthDay(2014, 1, 1), max, Feb28, None);
+}
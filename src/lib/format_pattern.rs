@@ -0,0 +1,1007 @@
+//! Formatting instants with strftime-style patterns.
+//!
+//! A pattern such as "%Y-%m-%d %H:%M:%S" is compiled once into a FormatSpec,
+//! which can then format any number of instants in UTC or in a zone.  The
+//! supported conversions are:
+//!
+//! * `%Y`: the year, with at least four digits, as in ISO 8601, counted
+//!   in the locale's era, such as the Thai Buddhist era
+//! * `%m`, `%d`: the month and day of the month, with two digits
+//! * `%H`, `%M`, `%S`: the hour, minute, and second, with two digits
+//! * `%f`: the fraction of the second, with six digits, or with N digits
+//!   from 1 to 7 if written `%Nf`, truncated
+//! * `%j`: the day of the year, with three digits
+//! * `%u`: the ISO 8601 day of the week, from 1 for Monday to 7 for Sunday
+//! * `%I`: the hour on a 12-hour clock, from 01 to 12
+//! * `%p`: the designator for before or after noon, such as "AM"
+//! * `%b`, `%a`: the abbreviated names of the month and weekday
+//! * `%B`, `%A`: the full names of the month and weekday
+//! * `%o`: the day of the month with an English ordinal suffix, as in "3rd"
+//! * `%Z`: the abbreviation of the zone, such as "CEST", or "UTC"
+//! * `%F`, `%T`: shorthand for `%Y-%m-%d` and `%H:%M:%S`
+//! * `%x`, `%X`: the locale's preferred date and time patterns
+//! * `%%`: a literal percent sign
+//!
+//! Names are taken from a Locale, which is English unless another locale
+//! is given when the pattern is compiled.  The compiled pattern keeps a
+//! copy of the locale's names, so `%x` and `%X` always expand to patterns
+//! of the same locale whose names are written.
+//!
+//! A FormatSpec can also parse the strings it writes.  Numbers must have
+//! the same width as when formatted, names are case-insensitive, and `%Z`
+//! accepts the abbreviations in `parse::DEFAULT_ZONE_ABBREVIATIONS`.  The
+//! pattern must contain `%Y`.  Other fields which are not in the pattern
+//! default to the start of the year or day, and fields given more than
+//! once must agree, so a weekday name must match the date.  parse_many()
+//! parses a batch of strings, such as a column of timestamps, optionally
+//! dividing the work among tasks.
+
+use std::str;
+use std::num::Saturating;
+use std::io::{Writer, MemWriter, BufWriter, IoResult};
+use div_mod::div_mod;
+use tick;
+use calendar_iso8601;
+use fmtutil;
+use instant::Instant;
+use locale::{Locale, EnglishLocale};
+use format_iso8601::Weekday;
+use parse::ParseOptions;
+use parseutil;
+use parseutil::read_fixed_digits;
+use zone::Zone;
+
+/// One piece of a compiled pattern.
+#[deriving(PartialEq, Eq, Clone, Show)]
+enum Item {
+    Literal(Vec<u8>),
+    YearItem,
+    MonthItem,
+    DayItem,
+    HourItem,
+    MinuteItem,
+    SecondItem,
+    FractionItem(uint),
+    DayOfYearItem,
+    WeekdayItem,
+    MonthNameItem,
+    WeekdayNameItem,
+    FullMonthNameItem,
+    FullWeekdayNameItem,
+    OrdinalDayItem,
+    Hour12Item,
+    AmPmItem,
+    ZoneItem
+}
+
+/// A copy of the names and patterns of a locale, taken when a pattern is
+/// compiled.
+#[deriving(PartialEq, Eq, Clone, Show)]
+struct LocaleNames {
+    month_names: Vec<String>,
+    month_abbreviations: Vec<String>,
+    weekday_names: Vec<String>,
+    weekday_abbreviations: Vec<String>,
+    am: String,
+    pm: String,
+    date_pattern: String,
+    time_pattern: String,
+    first_day_of_week: Weekday,
+    year_offset: int
+}
+
+impl LocaleNames {
+    fn new(locale: &Locale) -> LocaleNames {
+        LocaleNames {
+            month_names: range(1, 13)
+                .map(|m| locale.month_name(m).to_string()).collect(),
+            month_abbreviations: range(1, 13)
+                .map(|m| locale.month_abbreviation(m).to_string()).collect(),
+            weekday_names: range(1, 8)
+                .map(|d| locale.weekday_name(d).to_string()).collect(),
+            weekday_abbreviations: range(1, 8)
+                .map(|d| locale.weekday_abbreviation(d).to_string()).collect(),
+            am: locale.am_pm(false).to_string(),
+            pm: locale.am_pm(true).to_string(),
+            date_pattern: locale.date_pattern().to_string(),
+            time_pattern: locale.time_pattern().to_string(),
+            first_day_of_week: locale.first_day_of_week(),
+            year_offset: locale.year_offset()
+        }
+    }
+}
+
+impl Locale for LocaleNames {
+    fn month_name<'a>(&'a self, month: int) -> &'a str {
+        self.month_names.get((month - 1) as uint).as_slice()
+    }
+
+    fn month_abbreviation<'a>(&'a self, month: int) -> &'a str {
+        self.month_abbreviations.get((month - 1) as uint).as_slice()
+    }
+
+    fn weekday_name<'a>(&'a self, weekday: int) -> &'a str {
+        self.weekday_names.get((weekday - 1) as uint).as_slice()
+    }
+
+    fn weekday_abbreviation<'a>(&'a self, weekday: int) -> &'a str {
+        self.weekday_abbreviations.get((weekday - 1) as uint).as_slice()
+    }
+
+    fn am_pm<'a>(&'a self, pm: bool) -> &'a str {
+        if pm { self.pm.as_slice() } else { self.am.as_slice() }
+    }
+
+    fn date_pattern<'a>(&'a self) -> &'a str {
+        self.date_pattern.as_slice()
+    }
+
+    fn time_pattern<'a>(&'a self) -> &'a str {
+        self.time_pattern.as_slice()
+    }
+
+    fn first_day_of_week(&self) -> Weekday {
+        self.first_day_of_week
+    }
+
+    fn year_offset(&self) -> int {
+        self.year_offset
+    }
+}
+
+/// A compiled format pattern, with the locale it was compiled for.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct FormatSpec {
+    items: Vec<Item>,
+    locale: LocaleNames
+}
+
+/// The calendar fields of a day, which are reused when consecutive
+/// instants fall on the same day.
+struct DayFields {
+    cjd: i64,
+    year: int,
+    month: int,
+    day: int,
+    day_of_year: int,
+    weekday: int
+}
+
+impl DayFields {
+    fn new(cjd: i64) -> DayFields {
+        let (year, month, day) = calendar_iso8601::from_cjd(cjd as int);
+        let (_, day_of_year) = calendar_iso8601::to_ordinal(cjd as int);
+        DayFields {
+            cjd: cjd,
+            year: year,
+            month: month,
+            day: day,
+            day_of_year: day_of_year,
+            weekday: calendar_iso8601::weekday(cjd as int)
+        }
+    }
+}
+
+/// Get the English ordinal suffix for a number, such as "st" for 1 and 21,
+/// "nd" for 2, "rd" for 3, and "th" for 4 and for 11 to 13.
+pub fn ordinal_suffix(n: i64) -> &'static str {
+    let n = if n < 0 { -(n % 100) } else { n % 100 };
+    match (n % 10, n / 10) {
+        (_, 1) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th"
+    }
+}
+
+/// Get the number of ticks in a unit of the last digit of a fraction of a
+/// second with the given number of digits.
+fn fraction_scale(digits: uint) -> i64 {
+    range(digits, 7).fold(1, |n, _| n * 10)
+}
+
+/// Get the fraction of a second to the given number of digits, truncated.
+fn fraction_digits(time: i64, digits: uint) -> i64 {
+    time % tick::SECOND / fraction_scale(digits)
+}
+
+/// A field read when parsing, used as an index into an array of values.
+enum Field {
+    YearField,
+    MonthField,
+    DayField,
+    DayOfYearField,
+    WeekdayField,
+    HourField,
+    Hour12Field,
+    /// 1 for times from noon on, and 0 before.
+    PmField,
+    MinuteField,
+    SecondField,
+    /// The fraction of the second, in ticks.
+    FractionField,
+    /// The UTC offset, in seconds.
+    OffsetField
+}
+
+static FIELD_COUNT: uint = 12;
+
+/// Store the value of a field.  Returns false if the field already has a
+/// different value.
+fn set_field(fields: &mut [Option<int>], field: Field, value: int) -> bool {
+    let slot = &mut fields[field as uint];
+    match *slot {
+        Some(v) if v != value => false,
+        _ => {
+            *slot = Some(value);
+            true
+        }
+    }
+}
+
+/// Read a year written by `%Y`: four digits, or a sign followed by at
+/// least four digits.
+fn read_year<'a>(s: &'a [u8]) -> Option<(int, &'a [u8])> {
+    let negative = if parseutil::starts_with(s, '-') {
+        true
+    } else if parseutil::starts_with(s, '+') {
+        false
+    } else {
+        return read_fixed_digits(s, 4);
+    };
+    let rest = s.slice_from(1);
+    let digits = rest.iter()
+        .take_while(|&&c| c >= '0' as u8 && c <= '9' as u8)
+        .count();
+    if digits < 4 || digits > 9 {
+        return None;
+    }
+    read_fixed_digits(rest, digits)
+        .map(|(n, rest)| (if negative { -n } else { n }, rest))
+}
+
+/// Read a day of the month with its English ordinal suffix, as written by
+/// `%o`.
+fn read_ordinal<'a>(s: &'a [u8]) -> Option<(int, &'a [u8])> {
+    let digits = s.iter().take(2)
+        .take_while(|&&c| c >= '0' as u8 && c <= '9' as u8)
+        .count();
+    let (n, rest) = match read_fixed_digits(s, digits) {
+        Some(x) if digits > 0 => x,
+        _ => return None
+    };
+    let suffix = ordinal_suffix(n as i64).as_bytes();
+    if rest.len() < 2 || !parseutil::eq_ignore_case(rest.slice_to(2), suffix) {
+        return None;
+    }
+    Some((n, rest.slice_from(2)))
+}
+
+/// Read one of a list of names, ignoring case, and preferring the longest
+/// match.  Returns the number of the name, counting from 1.
+fn read_name<'a>(s: &'a [u8], names: &[&str]) -> Option<(int, &'a [u8])> {
+    let mut best: Option<(uint, uint)> = None;
+    for (i, name) in names.iter().enumerate() {
+        let n = name.len();
+        if n == 0 || s.len() < n ||
+            !parseutil::eq_ignore_case(s.slice_to(n), name.as_bytes()) {
+            continue;
+        }
+        match best {
+            Some((_, len)) if len >= n => (),
+            _ => best = Some((i, n))
+        }
+    }
+    best.map(|(i, n)| (i as int + 1, s.slice_from(n)))
+}
+
+/// The names and era of a locale used when parsing, looked up once so that
+/// they can be reused for many strings.
+struct ParseNames<'a> {
+    month_abbreviations: [&'a str, ..12],
+    month_names: [&'a str, ..12],
+    weekday_abbreviations: [&'a str, ..7],
+    weekday_names: [&'a str, ..7],
+    am_pm: [&'a str, ..2],
+    year_offset: int
+}
+
+impl<'a> ParseNames<'a> {
+    fn new(locale: &'a Locale) -> ParseNames<'a> {
+        let mut names = ParseNames {
+            month_abbreviations: ["", ..12],
+            month_names: ["", ..12],
+            weekday_abbreviations: ["", ..7],
+            weekday_names: ["", ..7],
+            am_pm: [locale.am_pm(false), locale.am_pm(true)],
+            year_offset: locale.year_offset()
+        };
+        for i in range(0u, 12) {
+            names.month_abbreviations[i] =
+                locale.month_abbreviation(i as int + 1);
+            names.month_names[i] = locale.month_name(i as int + 1);
+        }
+        for i in range(0u, 7) {
+            names.weekday_abbreviations[i] =
+                locale.weekday_abbreviation(i as int + 1);
+            names.weekday_names[i] = locale.weekday_name(i as int + 1);
+        }
+        names
+    }
+}
+
+/// Read a single item, storing the value of its field.  Returns the
+/// remainder of the string.
+fn read_item<'a>(s: &'a [u8], item: &Item, fields: &mut [Option<int>],
+                 names: &ParseNames) -> Option<&'a [u8]> {
+    fn tag<'a>(field: Field, r: Option<(int, &'a [u8])>)
+               -> Option<(Field, int, &'a [u8])> {
+        r.map(|(value, rest)| (field, value, rest))
+    }
+    let read = match *item {
+        Literal(ref l) => {
+            return if s.starts_with(l.as_slice()) {
+                Some(s.slice_from(l.len()))
+            } else {
+                None
+            };
+        }
+        YearItem => tag(YearField, read_year(s)),
+        MonthItem => tag(MonthField, read_fixed_digits(s, 2)),
+        DayItem => tag(DayField, read_fixed_digits(s, 2)),
+        HourItem => tag(HourField, read_fixed_digits(s, 2)),
+        MinuteItem => tag(MinuteField, read_fixed_digits(s, 2)),
+        SecondItem => tag(SecondField, read_fixed_digits(s, 2)),
+        FractionItem(digits) => {
+            let scale = fraction_scale(digits) as int;
+            tag(FractionField, read_fixed_digits(s, digits)
+                .map(|(n, rest)| (n * scale, rest)))
+        }
+        DayOfYearItem => tag(DayOfYearField, read_fixed_digits(s, 3)),
+        WeekdayItem => tag(WeekdayField, read_fixed_digits(s, 1)),
+        MonthNameItem => tag(MonthField, read_name(
+            s, names.month_abbreviations.as_slice())),
+        WeekdayNameItem => tag(WeekdayField, read_name(
+            s, names.weekday_abbreviations.as_slice())),
+        FullMonthNameItem => tag(MonthField, read_name(
+            s, names.month_names.as_slice())),
+        FullWeekdayNameItem => tag(WeekdayField, read_name(
+            s, names.weekday_names.as_slice())),
+        OrdinalDayItem => tag(DayField, read_ordinal(s)),
+        Hour12Item => tag(Hour12Field, read_fixed_digits(s, 2)),
+        AmPmItem => tag(PmField, read_name(s, names.am_pm.as_slice())
+                        .map(|(i, rest)| (i - 1, rest))),
+        ZoneItem => tag(OffsetField, parseutil::read_zone_abbreviation(
+            s, &ParseOptions::strict())
+            .map(|(offset, rest)| ((offset / tick::SECOND) as int, rest)))
+    };
+    match read {
+        Some((field, value, rest)) if set_field(fields, field, value) =>
+            Some(rest),
+        _ => None
+    }
+}
+
+impl FormatSpec {
+    /// Compile a pattern for the English locale.  Returns None if the
+    /// pattern contains an unknown conversion.
+    pub fn compile(pattern: &str) -> Option<FormatSpec> {
+        FormatSpec::compile_with_locale(pattern, &EnglishLocale)
+    }
+
+    /// Compile a pattern for the given locale, which supplies the patterns
+    /// for `%x` and `%X` and the names used when formatting and parsing.
+    /// Returns None if the pattern contains an unknown conversion.
+    pub fn compile_with_locale(pattern: &str, locale: &Locale)
+                               -> Option<FormatSpec> {
+        FormatSpec::compile_items(pattern, Some(locale)).map(|items| {
+            FormatSpec { items: items, locale: LocaleNames::new(locale) }
+        })
+    }
+
+    /// Compile a pattern, expanding `%x` and `%X` only if a locale is
+    /// given, so that the locale's own patterns cannot refer to them.
+    fn compile_items(pattern: &str, locale: Option<&Locale>)
+                     -> Option<Vec<Item>> {
+        let mut items = Vec::new();
+        let mut literal = Vec::new();
+        let mut s = pattern.as_bytes();
+        while !s.is_empty() {
+            if s[0] != '%' as u8 {
+                literal.push(s[0]);
+                s = s.slice_from(1);
+                continue;
+            }
+            if s.len() < 2 {
+                return None;
+            }
+            let (digits, c, rest) = match s[1] as char {
+                d @ '1'..'7' if s.len() >= 3 && s[2] == 'f' as u8 =>
+                    ((d as u8 - '0' as u8) as uint, 'f', s.slice_from(3)),
+                c => (6, c, s.slice_from(2))
+            };
+            s = rest;
+            if c == '%' {
+                literal.push('%' as u8);
+                continue;
+            }
+            let new = match c {
+                'Y' => vec![YearItem],
+                'm' => vec![MonthItem],
+                'd' => vec![DayItem],
+                'H' => vec![HourItem],
+                'M' => vec![MinuteItem],
+                'S' => vec![SecondItem],
+                'f' => vec![FractionItem(digits)],
+                'j' => vec![DayOfYearItem],
+                'u' => vec![WeekdayItem],
+                'b' => vec![MonthNameItem],
+                'a' => vec![WeekdayNameItem],
+                'B' => vec![FullMonthNameItem],
+                'A' => vec![FullWeekdayNameItem],
+                'o' => vec![OrdinalDayItem],
+                'I' => vec![Hour12Item],
+                'p' => vec![AmPmItem],
+                'Z' => vec![ZoneItem],
+                'x' | 'X' => {
+                    let locale = match locale {
+                        Some(l) => l, None => return None
+                    };
+                    let pattern = if c == 'x' {
+                        locale.date_pattern()
+                    } else {
+                        locale.time_pattern()
+                    };
+                    match FormatSpec::compile_items(pattern, None) {
+                        Some(items) => items,
+                        None => return None
+                    }
+                }
+                'F' => vec![YearItem, Literal(vec!['-' as u8]), MonthItem,
+                            Literal(vec!['-' as u8]), DayItem],
+                'T' => vec![HourItem, Literal(vec![':' as u8]), MinuteItem,
+                            Literal(vec![':' as u8]), SecondItem],
+                _ => return None
+            };
+            for item in new.move_iter() {
+                match item {
+                    Literal(l) => literal.push_all(l.as_slice()),
+                    item => {
+                        if !literal.is_empty() {
+                            items.push(Literal(literal));
+                            literal = Vec::new();
+                        }
+                        items.push(item);
+                    }
+                }
+            }
+        }
+        if !literal.is_empty() {
+            items.push(Literal(literal));
+        }
+        Some(items)
+    }
+
+    /// Write a single item using the calendar fields of its day and the
+    /// abbreviation of its zone.
+    fn write_item(w: &mut Writer, item: &Item, day: &DayFields, time: i64,
+                  zone: &str, locale: &Locale) -> IoResult<()> {
+        let sec = time / tick::SECOND;
+        match *item {
+            Literal(ref s) => w.write(s.as_slice()),
+            YearItem =>
+                fmtutil::write_year(w, day.year + locale.year_offset()),
+            MonthItem => fmtutil::write_2digits(w, day.month as uint),
+            DayItem => fmtutil::write_2digits(w, day.day as uint),
+            HourItem => fmtutil::write_2digits(w, (sec / 3600) as uint),
+            MinuteItem =>
+                fmtutil::write_2digits(w, (sec / 60 % 60) as uint),
+            SecondItem => fmtutil::write_2digits(w, (sec % 60) as uint),
+            FractionItem(digits) => fmtutil::write_number(
+                w, fraction_digits(time, digits) as u64, digits, '0'),
+            DayOfYearItem =>
+                fmtutil::write_3digits(w, day.day_of_year as uint),
+            WeekdayItem =>
+                fmtutil::write_number(w, day.weekday as u64, 1, '0'),
+            MonthNameItem =>
+                w.write_str(locale.month_abbreviation(day.month)),
+            WeekdayNameItem =>
+                w.write_str(locale.weekday_abbreviation(day.weekday)),
+            FullMonthNameItem => w.write_str(locale.month_name(day.month)),
+            FullWeekdayNameItem =>
+                w.write_str(locale.weekday_name(day.weekday)),
+            OrdinalDayItem =>
+                write!(w, "{}{}", day.day, ordinal_suffix(day.day as i64)),
+            Hour12Item => {
+                let hour = (sec / 3600 % 12) as uint;
+                fmtutil::write_2digits(w, if hour == 0 { 12 } else { hour })
+            }
+            AmPmItem => w.write_str(locale.am_pm(sec >= 12 * 3600)),
+            ZoneItem => w.write_str(zone)
+        }
+    }
+
+    /// Write an instant using the calendar fields of its day and the
+    /// abbreviation of its zone.
+    fn write_fields(&self, w: &mut Writer, day: &DayFields, time: i64,
+                    zone: &str) -> IoResult<()> {
+        for item in self.items.iter() {
+            try!(FormatSpec::write_item(w, item, day, time, zone,
+                                        &self.locale));
+        }
+        Ok(())
+    }
+
+    /// Write an instant, in UTC, to a writer.
+    pub fn write(&self, t: Instant, w: &mut Writer) -> IoResult<()> {
+        let (cjd, time) = div_mod(t.ticks(), tick::DAY);
+        self.write_fields(w, &DayFields::new(cjd), time, "UTC")
+    }
+
+    /// Write an instant, in local time in a zone, to a writer.  Local
+    /// times beyond the range of an instant are clamped.
+    pub fn write_in_zone(&self, t: Instant, zone: &Zone, w: &mut Writer)
+                         -> IoResult<()> {
+        let local = t.ticks().saturating_add(zone.offset_at(t));
+        let (cjd, time) = div_mod(local, tick::DAY);
+        self.write_fields(w, &DayFields::new(cjd), time,
+                          zone.abbreviation_at(t))
+    }
+
+    /// Format an instant, in UTC.
+    pub fn format(&self, t: Instant) -> String {
+        let mut w = MemWriter::with_capacity(32);
+        self.write(t, &mut w).unwrap();
+        String::from_utf8(w.unwrap()).unwrap()
+    }
+
+    /// Format an instant, in local time in a zone.
+    pub fn format_in_zone(&self, t: Instant, zone: &Zone) -> String {
+        let mut w = MemWriter::with_capacity(32);
+        self.write_in_zone(t, zone, &mut w).unwrap();
+        String::from_utf8(w.unwrap()).unwrap()
+    }
+
+    /// Parse a string written with the pattern, in UTC unless it contains
+    /// a zone abbreviation.  Returns None if the string does not match the
+    /// pattern or is not a valid time.
+    pub fn parse(&self, s: &str) -> Option<Instant> {
+        self.parse_with_names(s, &ParseNames::new(&self.locale))
+    }
+
+    /// Parse a string written with the pattern, using names which have
+    /// already been looked up.
+    fn parse_with_names(&self, s: &str, names: &ParseNames)
+                        -> Option<Instant> {
+        let mut fields = [None, ..FIELD_COUNT];
+        let mut rest = s.as_bytes();
+        for item in self.items.iter() {
+            rest = match read_item(rest, item, fields.as_mut_slice(),
+                                   names) {
+                Some(rest) => rest, None => return None
+            };
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+        let get = |field: Field| fields[field as uint];
+        let year = match get(YearField) {
+            Some(y) => y - names.year_offset, None => return None
+        };
+        let (month, day) = match get(DayOfYearField) {
+            Some(n) => {
+                if n < 1 || n > calendar_iso8601::days_in_year(year) {
+                    return None;
+                }
+                let cjd = calendar_iso8601::to_cjd(year, 1, 1) + n - 1;
+                let (_, m, d) = calendar_iso8601::from_cjd(cjd);
+                if get(MonthField).unwrap_or(m) != m ||
+                    get(DayField).unwrap_or(d) != d {
+                    return None;
+                }
+                (m, d)
+            }
+            None => (get(MonthField).unwrap_or(1), get(DayField).unwrap_or(1))
+        };
+        let hour12 = match (get(Hour12Field), get(PmField)) {
+            (Some(h), Some(pm)) if h >= 1 && h <= 12 => Some(h % 12 + pm * 12),
+            (Some(_), _) => return None,
+            (None, _) => None
+        };
+        let hour = match (hour12, get(HourField)) {
+            (Some(a), Some(b)) if a != b => return None,
+            (Some(h), _) | (None, Some(h)) => h,
+            (None, None) => 0
+        };
+        let t = match Instant::from_ymd_hms_ticks(
+            year, month, day, hour, get(MinuteField).unwrap_or(0),
+            get(SecondField).unwrap_or(0),
+            get(FractionField).unwrap_or(0) as i64) {
+            Some(t) => t, None => return None
+        };
+        match get(WeekdayField) {
+            Some(w) if w != calendar_iso8601::weekday(
+                calendar_iso8601::to_cjd(year, month, day)) => return None,
+            _ => ()
+        }
+        let offset = get(OffsetField).unwrap_or(0) as i64 * tick::SECOND;
+        t.ticks().checked_sub(&offset).map(|n| Instant::from_ticks(n))
+    }
+}
+
+/// Write many instants, in UTC, each followed by a newline.  This is
+/// faster than formatting each instant separately, since the calendar
+/// fields are only computed again when the day changes, so it is best
+/// suited to sorted or clustered timestamps.
+pub fn format_many(instants: &[Instant], spec: &FormatSpec, w: &mut Writer)
+                   -> IoResult<()> {
+    let mut day: Option<DayFields> = None;
+    for t in instants.iter() {
+        let (cjd, time) = div_mod(t.ticks(), tick::DAY);
+        let same = match day {
+            Some(ref d) => d.cjd == cjd,
+            None => false
+        };
+        if !same {
+            day = Some(DayFields::new(cjd));
+        }
+        try!(spec.write_fields(w, day.get_ref(), time, "UTC"));
+        try!(w.write(&['\n' as u8]));
+    }
+    Ok(())
+}
+
+/// Parse many strings with the same pattern, as by FormatSpec::parse().
+/// Returns the instants in order or, if any string is invalid, the indices
+/// of the invalid strings.
+pub fn parse_many(strings: &[&str], spec: &FormatSpec)
+                  -> Result<Vec<Instant>, Vec<uint>> {
+    let names = ParseNames::new(&spec.locale);
+    let mut instants = Vec::with_capacity(strings.len());
+    let mut errors = Vec::new();
+    for (i, s) in strings.iter().enumerate() {
+        match spec.parse_with_names(*s, &names) {
+            Some(t) => if errors.is_empty() { instants.push(t) },
+            None => errors.push(i)
+        }
+    }
+    if errors.is_empty() { Ok(instants) } else { Err(errors) }
+}
+
+/// Parse many strings like parse_many(), dividing them among up to the
+/// given number of tasks.  The strings are copied to send them to the
+/// tasks, so this only pays off for large batches.
+pub fn parse_many_in_tasks(strings: &[&str], spec: &FormatSpec, tasks: uint)
+                           -> Result<Vec<Instant>, Vec<uint>> {
+    if tasks <= 1 || strings.len() < 2 {
+        return parse_many(strings, spec);
+    }
+    let size = (strings.len() + tasks - 1) / tasks;
+    let (tx, rx) = channel();
+    let mut count = 0;
+    for (n, chunk) in strings.chunks(size).enumerate() {
+        let chunk: Vec<String> = chunk.iter().map(|s| s.to_string()).collect();
+        let spec = spec.clone();
+        let tx = tx.clone();
+        spawn(proc() {
+            let strings: Vec<&str> = chunk.iter().map(|s| s.as_slice())
+                .collect();
+            tx.send((n, parse_many(strings.as_slice(), &spec)));
+        });
+        count += 1;
+    }
+    let mut results = Vec::from_fn(count, |_| None);
+    for _ in range(0, count) {
+        let (n, result) = rx.recv();
+        *results.get_mut(n) = Some(result);
+    }
+    let mut instants = Vec::with_capacity(strings.len());
+    let mut errors = Vec::new();
+    for (n, result) in results.move_iter().enumerate() {
+        match result.unwrap() {
+            Ok(v) => instants.push_all_move(v),
+            Err(e) => for i in e.move_iter() {
+                errors.push(i + n * size)
+            }
+        }
+    }
+    if errors.is_empty() { Ok(instants) } else { Err(errors) }
+}
+
+/// A formatter which remembers its output for the last second formatted.
+/// When the next instant falls in the same second, only the fraction of
+/// the second is written again, which makes formatting the timestamps of
+/// a busy log much cheaper.
+pub struct CachedFormatter {
+    spec: FormatSpec,
+    second: Option<i64>,
+    buf: Vec<u8>,
+    /// The position and width of each fraction in the buffer.
+    fractions: Vec<(uint, uint)>
+}
+
+impl CachedFormatter {
+    /// Create a cached formatter for a compiled pattern.
+    pub fn new(spec: FormatSpec) -> CachedFormatter {
+        CachedFormatter {
+            spec: spec,
+            second: None,
+            buf: Vec::new(),
+            fractions: Vec::new()
+        }
+    }
+
+    /// Format an instant, in UTC.  The result is only valid until the next
+    /// call.
+    pub fn format<'a>(&'a mut self, t: Instant) -> &'a str {
+        let (second, _) = div_mod(t.ticks(), tick::SECOND);
+        let (cjd, time) = div_mod(t.ticks(), tick::DAY);
+        if self.second == Some(second) {
+            for &(pos, digits) in self.fractions.iter() {
+                let n = fraction_digits(time, digits) as u64;
+                let mut w = BufWriter::new(
+                    self.buf.mut_slice(pos, pos + digits));
+                fmtutil::write_number(&mut w, n, digits, '0').unwrap();
+            }
+        } else {
+            let day = DayFields::new(cjd);
+            let mut w = MemWriter::with_capacity(32);
+            self.fractions.clear();
+            for item in self.spec.items.iter() {
+                match *item {
+                    FractionItem(digits) =>
+                        self.fractions.push((w.get_ref().len(), digits)),
+                    _ => ()
+                }
+                FormatSpec::write_item(&mut w, item, &day, time, "UTC",
+                                       &self.spec.locale).unwrap();
+            }
+            self.buf = w.unwrap();
+            self.second = Some(second);
+        }
+        str::from_utf8(self.buf.as_slice()).unwrap()
+    }
+}
+
+#[test]
+fn test_format() {
+    fn test(pattern: &str, t: Instant, expected: &str) {
+        let spec = FormatSpec::compile(pattern).unwrap();
+        let out = spec.format(t);
+        if out.as_slice() != expected {
+            fail!("pattern: '{}', expected: '{}', output: '{}'",
+                  pattern, expected, out);
+        }
+    }
+    // 2014-06-10T11:12:13.4567891Z, a Tuesday.
+    let t = Instant::from_ticks(tick::DAY * 5274 + tick::HOUR * 11 +
+                                tick::MINUTE * 12 + tick::SECOND * 13 +
+                                4567891);
+    test("%Y-%m-%d %H:%M:%S", t, "2014-06-10 11:12:13");
+    test("%F %T.%f", t, "2014-06-10 11:12:13.456789");
+    test("%3f %7f %1f", t, "456 4567891 4");
+    test("%a, %d %b %Y", t, "Tue, 10 Jun 2014");
+    test("%j %u 100%%", t, "161 2 100%");
+    test("%B %o, %Y", t, "June 10th, 2014");
+    test("%B %o", Instant::from_ticks(tick::DAY * 5236), "May 3rd");
+    test("%A %I %p", t, "Tuesday 11 AM");
+    test("%I %p", Instant::from_ticks(0), "12 AM");
+    test("%I %p", Instant::from_ticks(tick::HOUR * 12), "12 PM");
+    test("%I %p", Instant::from_ticks(tick::HOUR * 23), "11 PM");
+    test("%x %X", t, "06/10/2014 11:12:13 AM");
+    test("[%T]", t, "[11:12:13]");
+    test("", t, "");
+    test("%Y", Instant::from_ticks(tick::DAY * -730486), "-0001");
+    assert_eq!(FormatSpec::compile("%"), None);
+    assert_eq!(FormatSpec::compile("%q"), None);
+    assert_eq!(FormatSpec::compile("%8f"), None);
+}
+
+#[test]
+fn test_locale() {
+    struct Shouting;
+    impl Locale for Shouting {
+        fn month_name<'a>(&'a self, _: int) -> &'a str { "MONTH" }
+        fn month_abbreviation<'a>(&'a self, _: int) -> &'a str { "MON" }
+        fn weekday_name<'a>(&'a self, _: int) -> &'a str { "DAY" }
+        fn weekday_abbreviation<'a>(&'a self, _: int) -> &'a str { "D" }
+        fn am_pm<'a>(&'a self, pm: bool) -> &'a str {
+            if pm { "P" } else { "A" }
+        }
+        fn date_pattern<'a>(&'a self) -> &'a str { "%d.%m.%Y" }
+        fn time_pattern<'a>(&'a self) -> &'a str { "%x" }
+    }
+    let t = Instant::from_ticks(tick::DAY * 5274 + tick::HOUR * 13);
+    let spec = FormatSpec::compile_with_locale("%A %a %B %b %p %x", &Shouting)
+        .unwrap();
+    assert_eq!(spec.format(t).as_slice(), "DAY D MONTH MON P 10.06.2014");
+    let mut w = MemWriter::new();
+    format_many(&[t], &spec, &mut w).unwrap();
+    assert_eq!(String::from_utf8(w.unwrap()).unwrap().as_slice(),
+               "DAY D MONTH MON P 10.06.2014\n");
+    assert_eq!(CachedFormatter::new(spec.clone()).format(t),
+               "DAY D MONTH MON P 10.06.2014");
+    let english = FormatSpec::compile("%A %a %B %b %p %x").unwrap();
+    assert!(spec != english);
+    assert_eq!(english.format(t).as_slice(),
+               "Tuesday Tue June Jun PM 06/10/2014");
+    assert_eq!(FormatSpec::compile_with_locale("%X", &Shouting), None);
+}
+
+#[test]
+fn test_buddhist_era() {
+    use locale::BuddhistEraLocale;
+    let t = Instant::from_ticks(tick::DAY * 5274 + tick::HOUR * 13);
+    let era = BuddhistEraLocale(EnglishLocale);
+    let spec = FormatSpec::compile_with_locale("%Y-%m-%d %x", &era).unwrap();
+    assert_eq!(spec.format(t).as_slice(), "2557-06-10 06/10/2557");
+    assert_eq!(FormatSpec::compile("%Y-%m-%d %x").unwrap().format(t)
+               .as_slice(), "2014-06-10 06/10/2014");
+    let t = Instant::from_ticks(tick::DAY * -913000);
+    assert_eq!(FormatSpec::compile_with_locale("%Y", &era).unwrap()
+               .format(t).as_slice(), "0043");
+}
+
+#[test]
+fn test_zone() {
+    use zone;
+    let z = zone::test_zone();
+    let spec = FormatSpec::compile("%F %T %Z").unwrap();
+    // 2014-03-30T00:30:00Z and 2014-03-30T01:30:00Z.
+    let before = Instant::from_ticks(tick::DAY * 5202 + tick::MINUTE * 30);
+    let after = Instant::from_ticks(tick::DAY * 5202 + tick::MINUTE * 90);
+    assert_eq!(spec.format(before).as_slice(), "2014-03-30 00:30:00 UTC");
+    assert_eq!(spec.format_in_zone(before, &z).as_slice(),
+               "2014-03-30 01:30:00 CET");
+    assert_eq!(spec.format_in_zone(after, &z).as_slice(),
+               "2014-03-30 03:30:00 CEST");
+}
+
+#[test]
+fn test_ordinal_suffix() {
+    let expected = [(0, "th"), (1, "st"), (2, "nd"), (3, "rd"), (4, "th"),
+                    (11, "th"), (12, "th"), (13, "th"), (21, "st"),
+                    (22, "nd"), (101, "st"), (111, "th"), (112, "th"),
+                    (-1, "st"), (-11, "th")];
+    for &(n, suffix) in expected.iter() {
+        assert_eq!(ordinal_suffix(n), suffix);
+    }
+}
+
+#[test]
+fn test_format_many() {
+    let spec = FormatSpec::compile("%F %T").unwrap();
+    let times = [Instant::from_ticks(0), Instant::from_ticks(tick::HOUR),
+                 Instant::from_ticks(tick::DAY), Instant::from_ticks(0)];
+    let mut w = MemWriter::new();
+    format_many(times.as_slice(), &spec, &mut w).unwrap();
+    assert_eq!(String::from_utf8(w.unwrap()).unwrap().as_slice(),
+               "2000-01-01 00:00:00\n2000-01-01 01:00:00\n\
+                2000-01-02 00:00:00\n2000-01-01 00:00:00\n");
+}
+
+#[test]
+fn test_parse() {
+    fn test(pattern: &str, s: &str, expected: Option<&str>) {
+        let spec = FormatSpec::compile(pattern).unwrap();
+        let out = spec.parse(s).map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != expected {
+            fail!("input: {} {}, expected: {}, output: {}",
+                  pattern, s, expected, out);
+        }
+    }
+    test("%F %T", "2014-06-10 11:12:13", Some("2014-06-10T11:12:13Z"));
+    test("%Y%m%d%H%M%S", "20140610111213", Some("2014-06-10T11:12:13Z"));
+    test("%F %T.%3f", "2014-06-10 11:12:13.250",
+         Some("2014-06-10T11:12:13.25Z"));
+    test("%d %b %Y", "10 jun 2014", Some("2014-06-10T00:00:00Z"));
+    test("%A, %B %o, %Y", "Tuesday, June 10th, 2014",
+         Some("2014-06-10T00:00:00Z"));
+    test("%a %B %o %Y", "Tue June 1st 2014", None);
+    test("%Y-%j", "2014-161", Some("2014-06-10T00:00:00Z"));
+    test("%Y-%m-%d %j", "2014-06-11 161", None);
+    test("%Y-%j", "2014-366", None);
+    test("%F %I:%M %p", "2014-06-10 12:30 AM", Some("2014-06-10T00:30:00Z"));
+    test("%F %I:%M %p", "2014-06-10 05:30 pm", Some("2014-06-10T17:30:00Z"));
+    test("%F %I:%M", "2014-06-10 05:30", None);
+    test("%F %H %I %p", "2014-06-10 17 05 PM", Some("2014-06-10T17:00:00Z"));
+    test("%F %H %I %p", "2014-06-10 05 05 PM", None);
+    test("%F %T %Z", "2014-06-10 11:12:13 UTC", Some("2014-06-10T11:12:13Z"));
+    test("%F %T %Z", "2014-06-10 07:12:13 EDT", Some("2014-06-10T11:12:13Z"));
+    test("%F %T %Z", "2014-06-10 11:12:13 CEST", None);
+    test("%Y", "-0044", Some("-0044-01-01T00:00:00Z"));
+    test("%F", "2014-02-30", None);
+    test("%F", "2014-06-10 ", None);
+    test("%F", "2014-6-10", None);
+    test("%m/%d", "06/10", None);
+    test("%F %T", "2014-06-10 24:00:00", None);
+}
+
+#[test]
+fn test_parse_round_trip() {
+    use locale::BuddhistEraLocale;
+    let pattern = "%A %o %B %Y %I:%M:%S.%6f %p %j %u";
+    let spec = FormatSpec::compile(pattern).unwrap();
+    let era = FormatSpec::compile_with_locale(
+        pattern, &BuddhistEraLocale(EnglishLocale)).unwrap();
+    for &ticks in [0, 1234567, tick::DAY * 5274 + tick::HOUR * 13,
+                   -tick::DAY * 400 - 10, tick::DAY * 366 - 10].iter() {
+        let t = Instant::from_ticks(ticks / 10 * 10);
+        assert_eq!(spec.parse(spec.format(t).as_slice()), Some(t));
+        assert_eq!(era.parse(era.format(t).as_slice()), Some(t));
+    }
+}
+
+#[test]
+fn test_parse_many() {
+    let spec = FormatSpec::compile("%F %T").unwrap();
+    let strings: Vec<String> = range(0, 1000i64).map(|n| {
+        spec.format(Instant::from_ticks(n * tick::HOUR))
+    }).collect();
+    let mut strings: Vec<&str> = strings.iter().map(|s| s.as_slice())
+        .collect();
+    let expected: Vec<Instant> = range(0, 1000i64)
+        .map(|n| Instant::from_ticks(n * tick::HOUR)).collect();
+    assert_eq!(parse_many(strings.as_slice(), &spec), Ok(expected.clone()));
+    for &tasks in [0, 1, 3, 4, 2000].iter() {
+        assert_eq!(parse_many_in_tasks(strings.as_slice(), &spec, tasks),
+                   Ok(expected.clone()));
+    }
+    *strings.get_mut(3) = "2014-06-10";
+    *strings.get_mut(500) = "";
+    *strings.get_mut(999) = "2014-02-30 00:00:00";
+    let errors = Err(vec![3u, 500, 999]);
+    assert_eq!(parse_many(strings.as_slice(), &spec), errors);
+    for &tasks in [1, 3, 4, 2000].iter() {
+        assert_eq!(parse_many_in_tasks(strings.as_slice(), &spec, tasks),
+                   errors);
+    }
+    let empty: &[&str] = &[];
+    assert_eq!(parse_many(empty, &spec), Ok(vec![]));
+    assert_eq!(parse_many_in_tasks(empty, &spec, 4), Ok(vec![]));
+}
+
+#[test]
+fn test_parse_many_locale() {
+    struct German;
+    static MONTHS: [&'static str, ..12] = [
+        "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli",
+        "August", "September", "Oktober", "November", "Dezember"
+    ];
+    impl Locale for German {
+        fn month_name<'a>(&'a self, m: int) -> &'a str {
+            MONTHS[(m - 1) as uint]
+        }
+        fn month_abbreviation<'a>(&'a self, m: int) -> &'a str {
+            self.month_name(m)
+        }
+        fn weekday_name<'a>(&'a self, _: int) -> &'a str { "Tag" }
+        fn weekday_abbreviation<'a>(&'a self, _: int) -> &'a str { "T" }
+        fn am_pm<'a>(&'a self, pm: bool) -> &'a str {
+            if pm { "PM" } else { "AM" }
+        }
+        fn date_pattern<'a>(&'a self) -> &'a str { "%d.%m.%Y" }
+        fn time_pattern<'a>(&'a self) -> &'a str { "%H:%M:%S" }
+    }
+    let spec = FormatSpec::compile_with_locale("%d. %B %Y", &German).unwrap();
+    let strings = ["10. Juni 2014", "01. März 2014"];
+    let expected = vec![Instant::from_ymd_hms(2014, 6, 10, 0, 0, 0).unwrap(),
+                        Instant::from_ymd_hms(2014, 3, 1, 0, 0, 0).unwrap()];
+    assert_eq!(parse_many(strings.as_slice(), &spec), Ok(expected.clone()));
+    assert_eq!(parse_many_in_tasks(strings.as_slice(), &spec, 2),
+               Ok(expected));
+    assert_eq!(parse_many(["10. June 2014"].as_slice(), &spec), Err(vec![0u]));
+}
+
+#[test]
+fn test_cached() {
+    let spec = FormatSpec::compile("%F %T.%3f (%6f)").unwrap();
+    let mut cached = CachedFormatter::new(spec.clone());
+    let base = tick::DAY * 5274 + tick::HOUR * 11;
+    for &ticks in [base, base + 1234567, base + 9999999, base + 10000000,
+                   base - 1, base - 1, base + tick::DAY].iter() {
+        let t = Instant::from_ticks(ticks);
+        assert_eq!(cached.format(t), spec.format(t).as_slice());
+    }
+}
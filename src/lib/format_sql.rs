@@ -0,0 +1,203 @@
+//! SQL literal formats for dates, times, and timestamps.
+//!
+//! Dates are written as "2014-06-10", times as "11:12:13.456", and
+//! timestamps as a date and time separated by a space, optionally followed
+//! by a UTC offset such as "+05:30".  These are the SQL-92 literal formats,
+//! and are accepted and produced by PostgreSQL and MySQL.  Fractions of a
+//! second are written with up to six digits, since that is the precision
+//! those databases store.
+
+use div_mod::div_mod;
+use tick;
+use calendar_iso8601;
+use instant::Instant;
+use format_iso8601::{Date, YearMonthDay, Parsed};
+use parse::DecimalPoint;
+use parseutil;
+use parseutil::starts_with;
+
+/// Read a time of the form "HH:MM:SS", with an optional fraction.
+/// Returns the number of ticks since midnight.
+fn read_time<'a>(s: &'a [u8]) -> Option<(i64, &'a [u8])> {
+    let (ticks, rem) = match parseutil::read_hms(s) {
+        Some(x) => x, None => return None
+    };
+    if parseutil::starts_with_fraction(rem, DecimalPoint) {
+        return parseutil::read_fraction(rem, tick::SECOND, DecimalPoint)
+            .and_then(|(frac, rem)| {
+                if ticks + frac < tick::DAY {
+                    Some((ticks + frac, rem))
+                } else {
+                    None
+                }
+            });
+    }
+    Some((ticks, rem))
+}
+
+/// Read a UTC offset of the form "+HH", "+HH:MM", or "+HHMM".  Returns the
+/// offset in ticks.
+fn read_offset<'a>(s: &'a [u8]) -> Option<(i64, &'a [u8])> {
+    let negative = match s.head() {
+        Some(&c) if c == '+' as u8 => false,
+        Some(&c) if c == '-' as u8 => true,
+        _ => return None
+    };
+    let (hour, rem) = match parseutil::read_digits(s.slice_from(1), 2) {
+        Some(x) => x, None => return None
+    };
+    let minute = if starts_with(rem, ':') {
+        parseutil::read_digits(rem.slice_from(1), 2)
+    } else if rem.is_empty() {
+        Some((0, rem))
+    } else {
+        parseutil::read_digits(rem, 2)
+    };
+    let (minute, rem) = match minute {
+        Some(x) => x, None => return None
+    };
+    if hour > 23 || minute > 59 {
+        return None;
+    }
+    let offset = hour as i64 * tick::HOUR + minute as i64 * tick::MINUTE;
+    Some((if negative { -offset } else { offset }, rem))
+}
+
+/// Parse a SQL date literal, such as "2014-06-10".
+pub fn parse_date(s: &str) -> Option<Date> {
+    match parseutil::read_calendar_date(s.as_bytes()) {
+        Some((date, rem)) if rem.is_empty() => Some(date),
+        _ => None
+    }
+}
+
+/// Parse a SQL time literal, such as "11:12:13.456".  Returns the number of
+/// ticks since midnight.
+pub fn parse_time(s: &str) -> Option<i64> {
+    match read_time(s.as_bytes()) {
+        Some((time, rem)) if rem.is_empty() => Some(time),
+        _ => None
+    }
+}
+
+/// Parse a SQL timestamp literal, such as "2014-06-10 11:12:13.456" or
+/// "2014-06-10 11:12:13+05:30".  A "T" is also accepted between the date
+/// and time.  The offset is missing from the result if the input has no
+/// offset, in which case the timestamp is in some unknown time zone.
+pub fn parse_timestamp(s: &str) -> Option<Parsed> {
+    let (date, rem) = match parseutil::read_calendar_date(s.as_bytes()) {
+        Some(x) => x, None => return None
+    };
+    if !starts_with(rem, ' ') && !starts_with(rem, 'T') {
+        return None;
+    }
+    let (time, rem) = match read_time(rem.slice_from(1)) {
+        Some(x) => x, None => return None
+    };
+    let offset = if rem.is_empty() {
+        None
+    } else {
+        match read_offset(rem) {
+            Some((offset, rem)) if rem.is_empty() => Some(offset),
+            _ => return None
+        }
+    };
+    Some(Parsed { date: Some(date), time: Some(time), offset: offset })
+}
+
+/// Format a complete calendar date as a SQL date literal.  Returns None for
+/// other dates, and for years which SQL cannot represent.
+pub fn format_date(date: &Date) -> Option<String> {
+    match *date {
+        YearMonthDay(y, m, d) if y >= 1 && y <= 9999 =>
+            Some(format!("{:04d}-{:02d}-{:02d}", y, m, d)),
+        _ => None
+    }
+}
+
+/// Format a number of ticks since midnight as a SQL time literal.  Trailing
+/// zeros are removed from the fraction, and ticks smaller than a
+/// microsecond are truncated.
+pub fn format_time(ticks: i64) -> String {
+    let (sec, subsec) = div_mod(ticks, tick::SECOND);
+    let mut s = format!("{:02d}:{:02d}:{:02d}",
+                        sec / 3600, sec / 60 % 60, sec % 60);
+    let usec = subsec / tick::MICROSECOND;
+    if usec != 0 {
+        let frac = format!(".{:06d}", usec);
+        s.push_str(frac.as_slice().trim_right_chars('0'));
+    }
+    s
+}
+
+/// Format an instant as a SQL timestamp literal in UTC, without an offset.
+/// Returns None if the year is outside the range 1 to 9999.
+pub fn format_timestamp(t: Instant) -> Option<String> {
+    let (cjd, time) = div_mod(t.ticks(), tick::DAY);
+    let (y, m, d) = calendar_iso8601::from_cjd(cjd as int);
+    format_date(&YearMonthDay(y, m, d))
+        .map(|date| format!("{} {}", date, format_time(time)))
+}
+
+/// Format an instant as a SQL timestamp literal with the given UTC offset
+/// in ticks, such as "2014-06-10 16:42:13+05:30".  The offset is rounded
+/// down to whole minutes.  Returns None if the local year is outside the
+/// range 1 to 9999.
+pub fn format_timestamp_with_offset(t: Instant, offset: i64)
+                                    -> Option<String> {
+    let (minutes, _) = div_mod(offset, tick::MINUTE);
+    let offset = minutes * tick::MINUTE;
+    let local = match t.ticks().checked_add(&offset) {
+        Some(n) => Instant::from_ticks(n), None => return None
+    };
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = if minutes < 0 { -minutes } else { minutes };
+    format_timestamp(local).map(|s| {
+        format!("{}{}{:02d}:{:02d}", s, sign, minutes / 60, minutes % 60)
+    })
+}
+
+#[test]
+fn test_parse() {
+    assert!(parse_date("2014-06-10") == Some(YearMonthDay(2014, 6, 10)));
+    assert!(parse_date("2014-02-30").is_none());
+    assert!(parse_date("2014-6-10").is_none());
+    assert_eq!(parse_time("11:12:13"),
+               Some(11 * tick::HOUR + 12 * tick::MINUTE + 13 * tick::SECOND));
+    assert_eq!(parse_time("00:00:00.000001"), Some(10));
+    assert_eq!(parse_time("23:59:59.99999999"), None);
+    assert_eq!(parse_time("24:00:00"), None);
+    assert_eq!(parse_time("00:00"), None);
+
+    let p = parse_timestamp("2014-06-10 11:12:13.5").unwrap();
+    assert!(p.date == Some(YearMonthDay(2014, 6, 10)));
+    assert_eq!(p.time, Some(tick::HOUR * 11 + tick::MINUTE * 12 +
+                            tick::SECOND * 27 / 2));
+    assert_eq!(p.offset, None);
+    let p = parse_timestamp("2014-06-10T11:12:13+05:30").unwrap();
+    assert_eq!(p.offset, Some(tick::HOUR * 11 / 2));
+    let p = parse_timestamp("2014-06-10 11:12:13-08").unwrap();
+    assert_eq!(p.offset, Some(-tick::HOUR * 8));
+    let p = parse_timestamp("2014-06-10 11:12:13+0100").unwrap();
+    assert_eq!(p.offset, Some(tick::HOUR));
+    assert!(parse_timestamp("2014-06-10 11:12:13Z").is_none());
+    assert!(parse_timestamp("2014-06-10 11:12:13+05:").is_none());
+    assert!(parse_timestamp("2014-06-10").is_none());
+}
+
+#[test]
+fn test_format() {
+    assert_eq!(format_date(&YearMonthDay(44, 3, 15)),
+               Some("0044-03-15".to_string()));
+    assert_eq!(format_date(&YearMonthDay(0, 3, 15)), None);
+    assert_eq!(format_time(tick::HOUR * 11 + 5000000).as_slice(),
+               "11:00:00.5");
+    assert_eq!(format_time(123456789).as_slice(), "00:00:12.345678");
+    let t = Instant::from_ticks(tick::DAY * 5274 + tick::HOUR * 11 + 1230);
+    assert_eq!(format_timestamp(t),
+               Some("2014-06-10 11:00:00.000123".to_string()));
+    assert_eq!(format_timestamp_with_offset(t, tick::HOUR * 11 / 2),
+               Some("2014-06-10 16:30:00.000123+05:30".to_string()));
+    assert_eq!(format_timestamp_with_offset(t, -tick::HOUR * 12),
+               Some("2014-06-09 23:00:00.000123-12:00".to_string()));
+}
@@ -0,0 +1,155 @@
+//! Syslog timestamps, as specified by RFC 5424.
+//!
+//! The TIMESTAMP field is a restricted form of RFC 3339, such as
+//! "2003-10-11T22:14:15.003Z" or "2003-08-24T05:14:15.000003-07:00".  The
+//! fraction has at most six digits, leap seconds are not allowed, and a
+//! missing timestamp is written as the nil value "-".
+
+use div_mod::div_mod;
+use tick;
+use calendar_iso8601;
+use instant::Instant;
+use format_iso8601::YearMonthDay;
+use format_sql;
+use parse::DecimalPoint;
+use parseutil;
+use parseutil::starts_with;
+
+/// The maximum number of digits in a fraction of a second.
+static MAX_FRACTION_DIGITS: uint = 6;
+
+/// Read a time offset, either "Z" or "+HH:MM".  Returns the offset in
+/// ticks.
+fn read_offset<'a>(s: &'a [u8]) -> Option<(i64, &'a [u8])> {
+    let negative = match s.head() {
+        Some(&c) if c == 'Z' as u8 => return Some((0, s.slice_from(1))),
+        Some(&c) if c == '+' as u8 => false,
+        Some(&c) if c == '-' as u8 => true,
+        _ => return None
+    };
+    let (hour, rem) = match parseutil::read_digits(s.slice_from(1), 2) {
+        Some(x) => x, None => return None
+    };
+    if hour > 23 || !starts_with(rem, ':') {
+        return None;
+    }
+    let (minute, rem) = match parseutil::read_digits(rem.slice_from(1), 2) {
+        Some(x) => x, None => return None
+    };
+    if minute > 59 {
+        return None;
+    }
+    let offset = hour as i64 * tick::HOUR + minute as i64 * tick::MINUTE;
+    Some((if negative { -offset } else { offset }, rem))
+}
+
+/// Parse an RFC 5424 timestamp.  Returns Some(None) for the nil value "-",
+/// and None if the timestamp is invalid or out of range.
+pub fn parse_timestamp(s: &str) -> Option<Option<Instant>> {
+    if s == "-" {
+        return Some(None);
+    }
+    let (date, rem) = match parseutil::read_calendar_date(s.as_bytes()) {
+        Some(x) => x, None => return None
+    };
+    if !starts_with(rem, 'T') {
+        return None;
+    }
+    let (time, rem) = match parseutil::read_hms(rem.slice_from(1)) {
+        Some(x) => x, None => return None
+    };
+    let (time, rem) = if starts_with(rem, '.') {
+        let digits = rem.slice_from(1).iter()
+            .take_while(|&&c| c >= '0' as u8 && c <= '9' as u8)
+            .count();
+        if digits > MAX_FRACTION_DIGITS {
+            return None;
+        }
+        match parseutil::read_fraction(rem, tick::SECOND, DecimalPoint) {
+            Some((frac, rem)) => (time + frac, rem),
+            None => return None
+        }
+    } else {
+        (time, rem)
+    };
+    let offset = match read_offset(rem) {
+        Some((offset, rem)) if rem.is_empty() => offset,
+        _ => return None
+    };
+    let cjd = match date {
+        YearMonthDay(y, m, d) => calendar_iso8601::to_cjd(y, m, d),
+        _ => return None
+    };
+    Some(Some(Instant::from_ticks(cjd as i64 * tick::DAY + time - offset)))
+}
+
+/// Format an instant as an RFC 5424 timestamp in UTC, or write the nil
+/// value if there is no instant.
+pub fn format_timestamp(t: Option<Instant>) -> String {
+    match t {
+        Some(t) => format_timestamp_with_offset(t, 0),
+        None => "-".to_string()
+    }
+}
+
+/// Format an instant as an RFC 5424 timestamp with the given UTC offset in
+/// ticks.  A zero offset is written as "Z", and other offsets are rounded
+/// down to whole minutes.  Ticks smaller than a microsecond are truncated.
+/// Instants whose local year is outside the range 1 to 9999 are written
+/// as the nil value.
+pub fn format_timestamp_with_offset(t: Instant, offset: i64) -> String {
+    let (minutes, _) = div_mod(offset, tick::MINUTE);
+    let local = match t.ticks().checked_add(&(minutes * tick::MINUTE)) {
+        Some(n) => n, None => return "-".to_string()
+    };
+    let (cjd, time) = div_mod(local, tick::DAY);
+    let (y, m, d) = calendar_iso8601::from_cjd(cjd as int);
+    let date = match format_sql::format_date(&YearMonthDay(y, m, d)) {
+        Some(s) => s, None => return "-".to_string()
+    };
+    let time = format_sql::format_time(time);
+    if minutes == 0 {
+        format!("{}T{}Z", date, time)
+    } else {
+        let sign = if minutes < 0 { '-' } else { '+' };
+        let minutes = if minutes < 0 { -minutes } else { minutes };
+        format!("{}T{}{}{:02d}:{:02d}", date, time, sign,
+                minutes / 60, minutes % 60)
+    }
+}
+
+#[test]
+fn test_parse() {
+    use duration::Duration;
+    // 2003-10-11T22:14:15Z
+    let t = Instant::from_ticks(tick::DAY * 1379 + tick::HOUR * 22 +
+                                tick::MINUTE * 14 + tick::SECOND * 15);
+    let ms = tick::MILLISECOND;
+    assert_eq!(parse_timestamp("-"), Some(None));
+    assert_eq!(parse_timestamp("2003-10-11T22:14:15.003Z"),
+               Some(Some(t + Duration { ticks: 3 * ms })));
+    assert_eq!(parse_timestamp("2003-10-11T15:14:15.000003-07:00"),
+               Some(Some(t + Duration { ticks: 30 })));
+    assert_eq!(parse_timestamp("2003-10-11T22:14:15.0000003Z"), None);
+    assert_eq!(parse_timestamp("2003-10-11T22:14:15z"), None);
+    assert_eq!(parse_timestamp("2003-10-11t22:14:15Z"), None);
+    assert_eq!(parse_timestamp("2003-10-11T22:14:60Z"), None);
+    assert_eq!(parse_timestamp("2003-10-11T22:14:15"), None);
+    assert_eq!(parse_timestamp("2003-10-11T22:14:15.Z"), None);
+}
+
+#[test]
+fn test_format() {
+    use std::num::Bounded;
+    let t = Instant::from_ticks(tick::DAY * 1379 + tick::HOUR * 22 +
+                                tick::MINUTE * 14 + tick::SECOND * 15 + 30);
+    assert_eq!(format_timestamp(None).as_slice(), "-");
+    assert_eq!(format_timestamp(Some(t)).as_slice(),
+               "2003-10-11T22:14:15.000003Z");
+    assert_eq!(format_timestamp_with_offset(t, -tick::HOUR * 7).as_slice(),
+               "2003-10-11T15:14:15.000003-07:00");
+    assert_eq!(format_timestamp(Some(Instant::from_ticks(0))).as_slice(),
+               "2000-01-01T00:00:00Z");
+    let min: Instant = Bounded::min_value();
+    assert_eq!(format_timestamp(Some(min)).as_slice(), "-");
+}
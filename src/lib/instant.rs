@@ -1,19 +1,39 @@
 use libc::types::os::common::posix01::{timespec, timeval};
-use std::num::Bounded;
-use std::io::{MemWriter, IoResult};
+use std::num::{Bounded, Saturating, FromPrimitive};
+use std::io::{Writer, MemWriter, BufWriter, IoResult};
 use std::fmt::{Show, Formatter, FormatError, WriteError};
+use std::from_str::FromStr;
 use tick;
 use fmtutil;
+use fmtutil::{Number, Text};
 use calendar_iso8601;
-use duration::Duration;
+use format_iso8601;
+use format_iso8601::Weekday;
+use iter;
+use datetime::{DateTime, FieldPolicy, Strict, civil_to_instant_utc};
+use duration::{Duration, PosixTimeError, InvalidNanoseconds,
+               InvalidMicroseconds, PosixTimeOverflow};
+use duration::{RoundingMode, Nearest, Floor, Ceiling, TowardZero};
+use wide_duration::WideDuration;
 use div_mod::div_mod;
 use std::num::div_rem;
+use std::cmp::min;
+
+/// The length of the longest instant written by Show and format_into().
+pub static FORMATTED_LEN: uint = 22;
 
 /// An absolute moment in time, independent of time zones and calendars.
 /// This uses the default time scale, which does not account for leap seconds.
+///
+/// The representable range is from -27228-04-18T21:11:54.5224192Z to
+/// +31227-09-14T02:48:05.4775807Z.  Years outside 0000 to 9999 are
+/// formatted using the ISO 8601 expanded representation, with a sign.
 #[deriving(PartialEq, PartialOrd, Ord, Eq,
            Clone, Hash, Rand)]
 pub struct Instant {
+    /// The number of ticks since the epoch.  Direct access to this field
+    /// is deprecated, use Instant::from_ticks() and ticks() instead.
+    #[deprecated = "use Instant::from_ticks() and Instant::ticks()"]
     pub ticks : i64,
 }
 
@@ -21,13 +41,38 @@ pub struct Instant {
 static UNIX_EPOCH_DAY: i64 = -10957;
 
 /// This library's epoch, relative to the Unix epoch.
-static EPOCH_UNIX_SECOND: i64 = 10957 * 86400;
+static EPOCH_UNIX_SECOND: i64 = -UNIX_EPOCH_DAY * 86400;
 
 /// The Unix epoch: January 1, 1970.
 pub static UNIX_EPOCH: Instant = Instant {
     ticks: tick::DAY * UNIX_EPOCH_DAY
 };
 
+/// The J2000.0 astronomical epoch: noon on January 1, 2000.
+pub static J2000_EPOCH: Instant = Instant {
+    ticks: tick::HOUR * 12
+};
+
+/// The GPS epoch: January 6, 1980.
+pub static GPS_EPOCH: Instant = Instant {
+    ticks: tick::DAY * -7300
+};
+
+/// The NTP epoch: January 1, 1900.
+pub static NTP_EPOCH: Instant = Instant {
+    ticks: tick::DAY * -36524
+};
+
+/// The Windows FILETIME epoch: January 1, 1601.
+pub static WINDOWS_EPOCH: Instant = Instant {
+    ticks: tick::DAY * -145731
+};
+
+/// The .NET DateTime epoch: January 1, 0001.
+pub static DOTNET_EPOCH: Instant = Instant {
+    ticks: tick::DAY * -730119
+};
+
 impl Add<Duration, Instant> for Instant {
     fn add(&self, rhs: &Duration) -> Instant {
         Instant { ticks: self.ticks + rhs.ticks }
@@ -50,9 +95,39 @@ impl Bounded for Instant {
     }
 }
 
+impl FromStr for Instant {
+    fn from_str(s: &str) -> Option<Instant> {
+        format_iso8601::parse_instant(s)
+    }
+}
+
+/// The ISO 8601 representations of a date.
+enum DateStyle {
+    CalendarDate,
+    OrdinalDate,
+    WeekDate
+}
+
+/// A wrapper which formats an instant at midnight as 24:00:00 on the
+/// preceding day, rather than 00:00:00 on the following day.  ISO 8601
+/// permits this notation for the end point of an interval, for example,
+/// "2014-06-10T00:00:00Z/2014-06-10T24:00:00Z".
+pub struct EndOfDay(pub Instant);
+
 impl Show for Instant {
     fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
-        let datavec = match self.to_utf8_io() {
+        let datavec = match self.to_utf8_io(CalendarDate, false) {
+            Ok(x) => x,
+            Err(_) => return Err(WriteError)
+        };
+        fmtutil::write_field(f, datavec.as_slice())
+    }
+}
+
+impl Show for EndOfDay {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        let EndOfDay(ref instant) = *self;
+        let datavec = match instant.to_utf8_io(CalendarDate, true) {
             Ok(x) => x,
             Err(_) => return Err(WriteError)
         };
@@ -61,36 +136,462 @@ impl Show for Instant {
 }
 
 impl Instant {
-    fn to_utf8_io(&self) -> IoResult<Vec<u8>> {
+    /// Convert an instant to a UTF-8 vector, used to implement Show.  If
+    /// end_of_day is set, midnight is written as 24:00:00.
+    fn to_utf8_io(&self, style: DateStyle, end_of_day: bool)
+                  -> IoResult<Vec<u8>> {
         let mut w = MemWriter::with_capacity(32);
+        try!(self.write_utf8(&mut w, style, end_of_day));
+        Ok(w.unwrap())
+    }
+
+    /// Write an instant as UTF-8, as for to_utf8_io().
+    fn write_utf8(&self, w: &mut Writer, style: DateStyle, end_of_day: bool)
+                  -> IoResult<()> {
         let (cjd, tickrem) = div_mod(self.ticks, tick::DAY);
-        let (y, m, d) = calendar_iso8601::from_cjd(cjd as int);
+        let (cjd, tickrem) = if end_of_day && tickrem == 0 {
+            (cjd - 1, tick::DAY)
+        } else {
+            (cjd, tickrem)
+        };
+        match style {
+            CalendarDate => {
+                let (y, m, d) = calendar_iso8601::from_cjd(cjd as int);
+                try!(fmtutil::write_year(w, y));
+                try!(w.write_char('-'));
+                try!(fmtutil::write_2digits(w, m as uint));
+                try!(w.write_char('-'));
+                try!(fmtutil::write_2digits(w, d as uint));
+            }
+            OrdinalDate => {
+                let (y, d) = calendar_iso8601::to_ordinal(cjd as int);
+                try!(fmtutil::write_year(w, y));
+                try!(w.write_char('-'));
+                try!(fmtutil::write_3digits(w, d as uint));
+            }
+            WeekDate => {
+                let (y, wk, d) = calendar_iso8601::to_week_date(cjd as int);
+                try!(fmtutil::write_year(w, y));
+                try!(w.write_str("-W"));
+                try!(fmtutil::write_2digits(w, wk as uint));
+                try!(w.write_char('-'));
+                try!(w.write_u8('0' as u8 + d as u8));
+            }
+        }
         let (ss, _) = div_rem(tickrem, tick::SECOND);
-        let (mm, ss) = div_rem(ss as int, 60);
+        let (mm, ss) = div_rem(ss as u64, 60);
         let (hh, mm) = div_rem(mm, 60);
-        try!(write!(w, "{:04d}-{:02d}-{:02d}T{:02d}:{:02d}:{:02d}Z",
-                    y, m, d, hh, mm, ss));
-        Ok(w.unwrap())
+        fmtutil::write_segments(w, &[
+            Text("T"), Number(hh, 2, '0'), Text(":"), Number(mm, 2, '0'),
+            Text(":"), Number(ss, 2, '0'), Text("Z")])
     }
 
-    /// Convert from a POSIX timeval structure.  The input must measure
-    /// time since the POSIX epoch, using the POSIX time scale.
-    pub fn from_timespec(value: &timespec) -> Option<Duration> {
+    /// Write the instant in the format used by Show, such as
+    /// "2014-06-10T11:12:13Z", into a buffer without allocating.  Returns
+    /// the number of bytes written, or None if the buffer is too small,
+    /// in which case its contents are unspecified.  A buffer of
+    /// FORMATTED_LEN bytes is large enough for any instant.
+    pub fn format_into(&self, buf: &mut [u8]) -> Option<uint> {
+        let mut w = BufWriter::new(buf);
+        match self.write_utf8(&mut w, CalendarDate, false) {
+            Ok(()) => w.tell().ok().map(|n| n as uint),
+            Err(_) => None
+        }
+    }
+
+    /// Format the instant using an ISO 8601 ordinal date, such as
+    /// "2024-135T09:30:00Z".
+    pub fn to_ordinal_string(&self) -> String {
+        let v = self.to_utf8_io(OrdinalDate, false).unwrap();
+        String::from_utf8(v).unwrap()
+    }
+
+    /// Format the instant using an ISO 8601 week date, such as
+    /// "2024-W20-2T09:30:00Z".
+    pub fn to_iso_week_string(&self) -> String {
+        let v = self.to_utf8_io(WeekDate, false).unwrap();
+        String::from_utf8(v).unwrap()
+    }
+
+    /// Create an instant from a number of ticks since this library's epoch.
+    #[inline]
+    pub fn from_ticks(ticks: i64) -> Instant {
+        Instant { ticks: ticks }
+    }
+
+    /// Create an instant from a date and time in UTC.  Returns None if any
+    /// field is out of its usual range, such as February 30 or hour 24, or
+    /// if the result is out of range.  Leap seconds are not accepted.
+    pub fn from_ymd_hms(year: int, month: int, day: int, hour: int,
+                        minute: int, second: int) -> Option<Instant> {
+        Instant::from_ymd_hms_ticks(year, month, day, hour, minute, second, 0)
+    }
+
+    /// Create an instant from a date and time in UTC, with a number of
+    /// ticks after the second, which must be less than a second.  Returns
+    /// None under the same conditions as from_ymd_hms().
+    pub fn from_ymd_hms_ticks(year: int, month: int, day: int, hour: int,
+                              minute: int, second: int, subsec_ticks: i64)
+                              -> Option<Instant> {
+        let dt = DateTime::new(year, month, day, hour, minute, second,
+                               subsec_ticks);
+        Instant::from_components(&dt, Strict)
+    }
+
+    /// Get the number of ticks since this library's epoch.
+    #[inline]
+    pub fn ticks(&self) -> i64 {
+        self.ticks
+    }
+
+    /// Get the date and time in UTC.  This is the cheapest way to read
+    /// several calendar fields, since the date is only computed once.
+    pub fn to_datetime(&self) -> DateTime {
+        let (y, m, d) = self.ymd();
+        let (hh, mm, ss, frac) = self.hms();
+        DateTime::new(y, m, d, hh, mm, ss, frac)
+    }
+
+    /// Get the year, month, and day in UTC.
+    fn ymd(&self) -> (int, int, int) {
+        let (cjd, _) = div_mod(self.ticks, tick::DAY);
+        calendar_iso8601::from_cjd(cjd as int)
+    }
+
+    /// Get the hour, minute, second, and ticks after the second in UTC.
+    fn hms(&self) -> (int, int, int, i64) {
+        let (_, time) = div_mod(self.ticks, tick::DAY);
+        let sec = (time / tick::SECOND) as int;
+        (sec / 3600, sec / 60 % 60, sec % 60, time % tick::SECOND)
+    }
+
+    /// Get the day of the week in UTC.
+    pub fn weekday(&self) -> Weekday {
+        let (cjd, _) = div_mod(self.ticks, tick::DAY);
+        FromPrimitive::from_int(calendar_iso8601::weekday(cjd as int))
+            .unwrap()
+    }
+
+    /// Get the day of the year in UTC, starting from 1.
+    pub fn day_of_year(&self) -> int {
+        let (cjd, _) = div_mod(self.ticks, tick::DAY);
+        let (_, yday) = calendar_iso8601::to_ordinal(cjd as int);
+        yday
+    }
+
+    /// Get the ISO 8601 week in UTC, as the year the week belongs to and
+    /// the week number.  The year differs from the calendar year for the
+    /// first and last days of some years.
+    pub fn iso_week(&self) -> (int, int) {
+        let (cjd, _) = div_mod(self.ticks, tick::DAY);
+        let (y, w, _) = calendar_iso8601::to_week_date(cjd as int);
+        (y, w)
+    }
+
+    /// Get the year in UTC, using astronomical year numbering.
+    pub fn year(&self) -> int {
+        let (y, _, _) = self.ymd();
+        y
+    }
+
+    /// Get the month in UTC, from 1 to 12.
+    pub fn month(&self) -> int {
+        let (_, m, _) = self.ymd();
+        m
+    }
+
+    /// Get the day of the month in UTC, starting from 1.
+    pub fn day(&self) -> int {
+        let (_, _, d) = self.ymd();
+        d
+    }
+
+    /// Get the hour in UTC, from 0 to 23.
+    pub fn hour(&self) -> int {
+        let (hh, _, _, _) = self.hms();
+        hh
+    }
+
+    /// Get the minute, from 0 to 59.
+    pub fn minute(&self) -> int {
+        let (_, mm, _, _) = self.hms();
+        mm
+    }
+
+    /// Get the second, from 0 to 59.
+    pub fn second(&self) -> int {
+        let (_, _, ss, _) = self.hms();
+        ss
+    }
+
+    /// Get the number of ticks after the second.
+    pub fn subsec_ticks(&self) -> i64 {
+        let (_, _, _, frac) = self.hms();
+        frac
+    }
+
+    /// Create an instant from a date and time in UTC, with fields outside
+    /// their usual ranges treated according to the given policy.  Returns
+    /// None if the policy rejects the fields or the result is out of range.
+    pub fn from_components(dt: &DateTime, policy: FieldPolicy)
+                           -> Option<Instant> {
+        dt.resolve(policy).and_then(|dt| civil_to_instant_utc(&dt))
+    }
+
+    /// Convert a date and time in UTC from one of the with_* methods.
+    fn from_changed_fields(dt: Option<DateTime>) -> Option<Instant> {
+        dt.and_then(|dt| Instant::from_ymd_hms_ticks(
+            dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second,
+            dt.subsec_ticks))
+    }
+
+    /// Get the same date and time in UTC in a different year.  Returns
+    /// None if the date does not exist in that year, such as February 29,
+    /// or if the result is out of range.
+    pub fn with_year(&self, year: int) -> Option<Instant> {
+        Instant::from_changed_fields(self.to_datetime().with_year(year))
+    }
+
+    /// Get the same date and time in UTC in a different month, such as
+    /// the same time next month.  Returns None if the month is not from 1
+    /// to 12, if the day does not exist in it, or if the result is out of
+    /// range.
+    pub fn with_month(&self, month: int) -> Option<Instant> {
+        Instant::from_changed_fields(self.to_datetime().with_month(month))
+    }
+
+    /// Get the same time in UTC on a different day of the month, such as
+    /// the first of this month.  Returns None if the day does not exist in
+    /// the month or if the result is out of range.
+    pub fn with_day(&self, day: int) -> Option<Instant> {
+        Instant::from_changed_fields(self.to_datetime().with_day(day))
+    }
+
+    /// Get the same instant with a different hour in UTC.  Returns None if
+    /// the hour is not from 0 to 23 or if the result is out of range.
+    pub fn with_hour(&self, hour: int) -> Option<Instant> {
+        Instant::from_changed_fields(self.to_datetime().with_hour(hour))
+    }
+
+    /// Get the same instant with a different minute.  Returns None if the
+    /// minute is not from 0 to 59 or if the result is out of range.
+    pub fn with_minute(&self, minute: int) -> Option<Instant> {
+        Instant::from_changed_fields(self.to_datetime().with_minute(minute))
+    }
+
+    /// Get the same instant with a different second.  Returns None if the
+    /// second is not from 0 to 59 or if the result is out of range.
+    pub fn with_second(&self, second: int) -> Option<Instant> {
+        Instant::from_changed_fields(self.to_datetime().with_second(second))
+    }
+
+    /// Get the Julian date, the number of days since noon on November 24,
+    /// 4714 BC in the proleptic Gregorian calendar.  For contemporary
+    /// dates, the result is precise to tens of microseconds.
+    pub fn to_julian_date(&self) -> f64 {
+        let (day, rem) = div_mod(self.ticks, tick::DAY);
+        2451544.5 + day as f64 + rem as f64 / tick::DAY as f64
+    }
+
+    /// Test whether this instant is earlier than another instant.
+    pub fn is_before(&self, other: Instant) -> bool {
+        self.ticks < other.ticks
+    }
+
+    /// Test whether this instant is later than another instant.
+    pub fn is_after(&self, other: Instant) -> bool {
+        self.ticks > other.ticks
+    }
+
+    /// Test whether this instant is in the half-open interval from start
+    /// to end, which includes start but not end.
+    pub fn is_between(&self, start: Instant, end: Instant) -> bool {
+        start.ticks <= self.ticks && self.ticks < end.ticks
+    }
+
+    /// Add a duration, clamping the result to the range of instants
+    /// instead of overflowing.
+    pub fn saturating_add(&self, d: Duration) -> Instant {
+        Instant { ticks: self.ticks.saturating_add(d.ticks) }
+    }
+
+    /// Subtract a duration, clamping the result to the range of instants
+    /// instead of overflowing.
+    pub fn saturating_sub(&self, d: Duration) -> Instant {
+        Instant { ticks: self.ticks.saturating_sub(d.ticks) }
+    }
+
+    /// Add a duration multiplied by an integer, such as the time of the
+    /// nth occurrence of a repeating event.  The product is computed with
+    /// 128 bits, so it may be out of range as long as the result is not.
+    /// Returns None if the result is out of range.
+    pub fn add_multiple(&self, d: Duration, n: i64) -> Option<Instant> {
+        let offset = match WideDuration::from_duration(d).checked_mul(n) {
+            Some(x) => x, None => return None
+        };
+        (WideDuration::from_ticks(self.ticks) + offset).to_duration()
+            .map(|x| Instant { ticks: x.ticks })
+    }
+
+    /// Round to a point on a grid of instants spaced by an interval from
+    /// an origin, such as every 15 minutes since midnight.  Floor gives
+    /// the previous grid point, Ceiling gives the next, Nearest breaks ties
+    /// by choosing the point an even number of intervals from the origin,
+    /// and TowardZero rounds towards the origin.  Instants on the grid are
+    /// unchanged.  Returns None if the interval is not positive or if the
+    /// result is out of range.
+    pub fn snap_to_grid(&self, origin: Instant, interval: Duration,
+                        mode: RoundingMode) -> Option<Instant> {
+        let step = interval.ticks;
+        if step <= 0 {
+            return None;
+        }
+        // Splitting both instants avoids overflow in self - origin, which
+        // is then qt - qo intervals and r ticks.
+        let (qt, rt) = div_mod(self.ticks, step);
+        let (qo, ro) = div_mod(origin.ticks, step);
+        let (qt, r) = if rt < ro {
+            (qt - 1, rt - ro + step)
+        } else {
+            (qt, rt - ro)
+        };
+        if r == 0 {
+            return Some(*self);
+        }
+        let up = match mode {
+            Floor => false,
+            Ceiling => true,
+            TowardZero => *self < origin,
+            Nearest => r > step - r ||
+                (r == step - r && ((qt ^ qo) & 1) != 0)
+        };
+        let ticks = if up {
+            self.ticks.checked_add(&(step - r))
+        } else {
+            self.ticks.checked_sub(&r)
+        };
+        ticks.map(|n| Instant { ticks: n })
+    }
+
+    /// Get the instant halfway between two instants, rounding down to the
+    /// nearest tick.  This cannot overflow.
+    pub fn midpoint(a: Instant, b: Instant) -> Instant {
+        Instant {
+            ticks: (a.ticks >> 1) + (b.ticks >> 1) + (a.ticks & b.ticks & 1)
+        }
+    }
+
+    /// Interpolate linearly between two instants, returning a when t is 0
+    /// and b when t is 1.  Values of t outside that range are clamped, and
+    /// NaN is treated as 0.  This cannot overflow, but the result is only
+    /// exact to about 53 bits of the distance between the instants.
+    pub fn lerp(a: Instant, b: Instant, t: f64) -> Instant {
+        if t >= 1.0 {
+            return b;
+        }
+        let t = if t > 0.0 { t } else { 0.0 };
+        // Scale the distance, clamping while it is still a float, since
+        // casting a float which is out of range to an integer is undefined.
+        let scale = |dist: u64| {
+            let off = (dist as f64 * t).round();
+            if off < dist as f64 { min(off as u64, dist) } else { dist }
+        };
+        // The distance always fits in a u64, using wrapping arithmetic.
+        if a.ticks <= b.ticks {
+            let off = scale((b.ticks as u64) - (a.ticks as u64));
+            Instant { ticks: ((a.ticks as u64) + off) as i64 }
+        } else {
+            let off = scale((a.ticks as u64) - (b.ticks as u64));
+            Instant { ticks: ((a.ticks as u64) - off) as i64 }
+        }
+    }
+
+    /// Iterate over instants from start up to but not including end,
+    /// separated by step.  See iter::range_step.
+    pub fn range_step(start: Instant, end: Instant, step: Duration)
+                      -> iter::RangeStep {
+        iter::range_step(start, end, step)
+    }
+
+    /// Parse an instant in ISO 8601 format, tolerating deviations common in
+    /// log files: a lowercase "t" or "z", a space instead of "T", missing
+    /// seconds, and single-digit components.
+    pub fn parse_lenient(s: &str) -> Option<Instant> {
+        format_iso8601::parse_instant_lenient(s)
+    }
+
+    /// Convert from a POSIX timespec structure.  The input must measure
+    /// time since the POSIX epoch, using the POSIX time scale.  Returns
+    /// None if the result is out of range.
+    pub fn from_unix_timespec(value: &timespec) -> Option<Instant> {
         let sec = match value.tv_sec.checked_add(&-EPOCH_UNIX_SECOND) {
             Some(n) => n, None => return None
         };
         tick::from_sec_nsec(sec, value.tv_nsec)
-            .map(|n| Duration { ticks: n })
+            .map(|n| Instant { ticks: n })
     }
 
-    /// Convert from a POSIX timespec structure.  The input must measure
-    /// time since the POSIX epoch, using the POSIX time scale.
-    pub fn from_timesval(value: &timeval) -> Option<Duration> {
+    /// Convert from a POSIX timeval structure.  The input must measure
+    /// time since the POSIX epoch, using the POSIX time scale.  Returns
+    /// None if the result is out of range.
+    pub fn from_unix_timeval(value: &timeval) -> Option<Instant> {
         let sec = match value.tv_sec.checked_add(&-EPOCH_UNIX_SECOND) {
             Some(n) => n, None => return None
         };
         tick::from_sec_usec(sec, value.tv_usec as i64)
-            .map(|n| Duration { ticks: n })
+            .map(|n| Instant { ticks: n })
+    }
+
+    /// Convert from nanoseconds since the POSIX epoch, using the POSIX
+    /// time scale, as returned by many kernel interfaces.  The result is
+    /// rounded to the nearest tick, with ties going to even.  This cannot
+    /// overflow, since an i64 holds about ±292 years of nanoseconds.
+    pub fn from_unix_nanoseconds(n: i64) -> Instant {
+        Instant { ticks: tick::from_nsec(n) + UNIX_EPOCH.ticks }
+    }
+
+    /// Convert to nanoseconds since the POSIX epoch, using the POSIX time
+    /// scale.  Returns None if the result is out of range, which is the
+    /// case for instants before 1677 or after 2262.
+    pub fn to_unix_nanoseconds(&self) -> Option<i64> {
+        self.ticks.checked_sub(&UNIX_EPOCH.ticks)
+            .and_then(|n| tick::to_nsec(n))
+    }
+
+    /// Convert from a POSIX timespec structure measuring time since the
+    /// POSIX epoch, checking that the nanoseconds are in range.
+    pub fn try_from_unix_timespec(value: &timespec)
+                                  -> Result<Instant, PosixTimeError> {
+        if value.tv_nsec < 0 || value.tv_nsec >= 1000000000 {
+            return Err(InvalidNanoseconds);
+        }
+        Instant::from_unix_timespec(value).ok_or(PosixTimeOverflow)
+    }
+
+    /// Convert from a POSIX timeval structure measuring time since the
+    /// POSIX epoch, checking that the microseconds are in range.
+    pub fn try_from_unix_timeval(value: &timeval)
+                                 -> Result<Instant, PosixTimeError> {
+        if value.tv_usec < 0 || value.tv_usec >= 1000000 {
+            return Err(InvalidMicroseconds);
+        }
+        Instant::from_unix_timeval(value).ok_or(PosixTimeOverflow)
+    }
+
+    /// Convert from a POSIX timespec structure, giving the time since this
+    /// library's epoch.  Deprecated, since the result should be an
+    /// instant.
+    #[deprecated = "use Instant::from_unix_timespec()"]
+    pub fn from_timespec(value: &timespec) -> Option<Duration> {
+        Instant::from_unix_timespec(value).map(|t| Duration { ticks: t.ticks })
+    }
+
+    /// Convert from a POSIX timeval structure, giving the time since this
+    /// library's epoch.  Deprecated, since the result should be an
+    /// instant.
+    #[deprecated = "use Instant::from_unix_timeval()"]
+    pub fn from_timesval(value: &timeval) -> Option<Duration> {
+        Instant::from_unix_timeval(value).map(|t| Duration { ticks: t.ticks })
     }
 
     /// Convert to a POSIX timeval structure.  The resulting structure
@@ -127,4 +628,424 @@ fn format() {
     test("1999-12-31T23:00:00Z", -tick::HOUR);
     test("2000-01-02T00:00:00Z", tick::DAY);
     test("1999-12-31T00:00:00Z", -tick::DAY);
+
+    // Expanded years
+    test("-27228-04-18T21:11:54Z", Bounded::min_value());
+    test("+31227-09-14T02:48:05Z", Bounded::max_value());
+    test("-0001-12-31T23:59:59Z",
+         tick::DAY * calendar_iso8601::to_cjd(0, 1, 1) as i64 - tick::SECOND);
+    test("+10000-01-01T00:00:00Z",
+         tick::DAY * calendar_iso8601::to_cjd(10000, 1, 1) as i64);
+}
+
+#[test]
+fn format_into() {
+    fn test(ticks: i64) {
+        let t = Instant { ticks: ticks };
+        let expected = format!("{}", t);
+        let mut buf = [0u8, ..FORMATTED_LEN];
+        let n = t.format_into(&mut buf).unwrap();
+        assert_eq!(buf.slice_to(n), expected.as_bytes());
+        let mut short = [0u8, ..40];
+        assert_eq!(t.format_into(short.mut_slice_to(n)), Some(n));
+        assert_eq!(t.format_into(short.mut_slice_to(n - 1)), None);
+    }
+    test(0);
+    test(4567891234567890);
+    test(Bounded::min_value());
+    test(Bounded::max_value());
+}
+
+#[test]
+fn format_end_of_day() {
+    fn test(expected: &str, ticks: i64) {
+        let output = format!("{}", EndOfDay(Instant { ticks: ticks }));
+        if output.as_slice() != expected {
+            fail!("ticks: {}, expected: '{}', output: '{}'",
+                  ticks, expected, output);
+        }
+        assert_eq!(from_str::<Instant>(output.as_slice()),
+                   Some(Instant { ticks: ticks }));
+    }
+
+    test("1999-12-31T24:00:00Z", 0);
+    test("2000-01-01T00:00:01Z", tick::SECOND);
+    test("1999-12-31T23:59:59Z", -tick::SECOND);
+    test("2000-01-01T24:00:00Z", tick::DAY);
+}
+
+#[test]
+fn from_fields() {
+    fn test(fields: (int, int, int, int, int, int), expected: Option<&str>) {
+        let (y, mo, d, h, mi, s) = fields;
+        let out = Instant::from_ymd_hms(y, mo, d, h, mi, s)
+            .map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != expected {
+            fail!("fields: {}, expected: {}, output: {}",
+                  fields, expected, out);
+        }
+    }
+    test((2014, 6, 10, 11, 12, 13), Some("2014-06-10T11:12:13Z"));
+    test((2000, 1, 1, 0, 0, 0), Some("2000-01-01T00:00:00Z"));
+    test((1999, 12, 31, 23, 59, 59), Some("1999-12-31T23:59:59Z"));
+    test((2012, 2, 29, 0, 0, 0), Some("2012-02-29T00:00:00Z"));
+    test((-44, 3, 15, 12, 0, 0), Some("-0044-03-15T12:00:00Z"));
+    test((2014, 2, 29, 0, 0, 0), None);
+    test((2014, 13, 1, 0, 0, 0), None);
+    test((2014, 0, 1, 0, 0, 0), None);
+    test((2014, 6, 0, 0, 0, 0), None);
+    test((2014, 6, 31, 0, 0, 0), None);
+    test((2014, 6, 10, 24, 0, 0), None);
+    test((2014, 6, 10, 0, 60, 0), None);
+    test((2014, 6, 10, 0, 0, 60), None);
+    test((2014, 6, 10, -1, 0, 0), None);
+    test((40000, 1, 1, 0, 0, 0), None);
+    test((-30000, 1, 1, 0, 0, 0), None);
+    test((::std::int::MAX, 1, 1, 0, 0, 0), None);
+    assert_eq!(Instant::from_ymd_hms_ticks(2014, 6, 10, 0, 0, 0, 1234567),
+               Some(Instant { ticks: tick::DAY * 5274 + 1234567 }));
+    assert_eq!(Instant::from_ymd_hms_ticks(2014, 6, 10, 0, 0, 0,
+                                           tick::SECOND), None);
+    assert_eq!(Instant::from_ymd_hms_ticks(2014, 6, 10, 0, 0, 0, -1), None);
+    // The last second in range.
+    assert_eq!(Instant::from_ymd_hms_ticks(31227, 9, 14, 2, 48, 5, 4775807),
+               Some(Bounded::max_value()));
+    assert_eq!(Instant::from_ymd_hms(31227, 9, 14, 2, 48, 6), None);
+}
+
+#[test]
+fn fields() {
+    let t = Instant::from_ymd_hms_ticks(2014, 6, 10, 11, 12, 13, 4567891)
+        .unwrap();
+    assert_eq!((t.year(), t.month(), t.day()), (2014, 6, 10));
+    assert_eq!((t.hour(), t.minute(), t.second(), t.subsec_ticks()),
+               (11, 12, 13, 4567891));
+    assert_eq!(t.to_datetime(),
+               DateTime::new(2014, 6, 10, 11, 12, 13, 4567891));
+    let t = Instant { ticks: -1 };
+    assert_eq!(t.to_datetime(),
+               DateTime::new(1999, 12, 31, 23, 59, 59, tick::SECOND - 1));
+    let min: Instant = Bounded::min_value();
+    assert_eq!(min.to_datetime(),
+               DateTime::new(-27228, 4, 18, 21, 11, 54, 5224192));
+}
+
+#[test]
+fn week_fields() {
+    use format_iso8601::{Tuesday, Sunday, Wednesday};
+    let t: Instant = from_str("2014-06-10T23:59:59Z").unwrap();
+    assert_eq!(t.weekday(), Tuesday);
+    assert_eq!(t.day_of_year(), 161);
+    assert_eq!(t.iso_week(), (2014, 24));
+    let t: Instant = from_str("2012-01-01T00:00:00Z").unwrap();
+    assert_eq!(t.weekday(), Sunday);
+    assert_eq!(t.iso_week(), (2011, 52));
+    let t: Instant = from_str("2014-12-31T12:00:00Z").unwrap();
+    assert_eq!(t.weekday(), Wednesday);
+    assert_eq!(t.day_of_year(), 365);
+    assert_eq!(t.iso_week(), (2015, 1));
+    let t = Instant { ticks: -1 };
+    assert_eq!(t.day_of_year(), 365);
+}
+
+#[test]
+fn from_components() {
+    use datetime::{Lenient, Overflowing};
+    let dt = DateTime::new(2014, 1, 31, 23, 59, 90, 0);
+    assert_eq!(Instant::from_components(&dt, Strict), None);
+    assert_eq!(Instant::from_components(&dt, Lenient),
+               from_str("2014-01-31T23:59:59Z"));
+    assert_eq!(Instant::from_components(&dt, Overflowing),
+               from_str("2014-02-01T00:00:30Z"));
+    let dt = DateTime::new(40000, 1, 1, 0, 0, 0, 0);
+    assert_eq!(Instant::from_components(&dt, Lenient), None);
+}
+
+#[test]
+fn with_fields() {
+    let t: Instant = from_str("2014-01-31T11:12:13.5Z").unwrap();
+    fn test(t: Option<Instant>, expected: &str) {
+        assert_eq!(t, from_str(expected));
+    }
+    test(t.with_day(1), "2014-01-01T11:12:13.5Z");
+    test(t.with_month(3), "2014-03-31T11:12:13.5Z");
+    test(t.with_year(2000), "2000-01-31T11:12:13.5Z");
+    test(t.with_hour(0), "2014-01-31T00:12:13.5Z");
+    test(t.with_minute(0), "2014-01-31T11:00:13.5Z");
+    test(t.with_second(59), "2014-01-31T11:12:59.5Z");
+    test(t.with_day(1).and_then(|t| t.with_hour(0))
+         .and_then(|t| t.with_minute(0)).and_then(|t| t.with_second(0)),
+         "2014-01-01T00:00:00.5Z");
+    assert_eq!(t.with_month(2), None);
+    assert_eq!(t.with_day(32), None);
+    assert_eq!(t.with_hour(24), None);
+    assert_eq!(t.with_year(40000), None);
+    let max: Instant = Bounded::max_value();
+    assert_eq!(max.with_second(6), None);
+    assert_eq!(max.with_second(4),
+               Some(max - Duration { ticks: tick::SECOND }));
+}
+
+#[test]
+fn format_precision() {
+    let t = Instant { ticks: tick::DAY * 5274 + 1234567 };
+    assert_eq!(format!("{:.10}", t).as_slice(), "2014-06-10");
+    assert_eq!(format!("{:.13}", t).as_slice(), "2014-06-10T00");
+    assert_eq!(format!("{:.30}", t).as_slice(), "2014-06-10T00:00:00Z");
+    assert_eq!(format!("{:>12.10}", t).as_slice(), "  2014-06-10");
+    assert_eq!(format!("{:.10}", EndOfDay(Instant { ticks: 0 })).as_slice(),
+               "1999-12-31");
+}
+
+#[test]
+fn parse() {
+    fn test(s: &str, ticks: i64) {
+        match from_str::<Instant>(s) {
+            None => fail!("input: '{}' failed to parse", s),
+            Some(x) => assert_eq!(x.ticks, ticks)
+        }
+        let r = format!("{}", Instant { ticks: ticks });
+        assert_eq!(from_str::<Instant>(r.as_slice()),
+                   Some(Instant { ticks: ticks }));
+    }
+
+    test("2000-01-01T00:00:00Z", 0);
+    test("1999-12-31T23:59:59Z", -tick::SECOND);
+    test("2000-01-02T00:00:00Z", tick::DAY);
+
+    assert!(from_str::<Instant>("2000-01-01 00:00Z").is_none());
+    assert_eq!(Instant::parse_lenient("2000-01-01 00:00Z"),
+               Some(Instant { ticks: 0 }));
+}
+
+#[test]
+fn format_alternate_dates() {
+    fn test(ordinal: &str, week: &str, ticks: i64) {
+        let instant = Instant { ticks: ticks };
+        assert_eq!(instant.to_ordinal_string().as_slice(), ordinal);
+        assert_eq!(instant.to_iso_week_string().as_slice(), week);
+        assert_eq!(from_str::<Instant>(ordinal), Some(instant));
+        assert_eq!(from_str::<Instant>(week), Some(instant));
+    }
+
+    test("2000-001T00:00:00Z", "1999-W52-6T00:00:00Z", 0);
+    test("1999-365T23:59:59Z", "1999-W52-5T23:59:59Z", -tick::SECOND);
+    test("2000-003T12:00:00Z", "2000-W01-1T12:00:00Z",
+         2 * tick::DAY + 12 * tick::HOUR);
+}
+
+#[test]
+fn ticks() {
+    assert_eq!(Instant::from_ticks(0), Instant { ticks: 0 });
+    assert_eq!(Instant::from_ticks(-5).ticks(), -5);
+    assert_eq!(UNIX_EPOCH.ticks(), tick::DAY * UNIX_EPOCH_DAY);
+}
+
+#[test]
+fn compare() {
+    let a = Instant { ticks: -1 };
+    let b = Instant { ticks: 0 };
+    let c = Instant { ticks: 1 };
+    assert!(a.is_before(b) && !b.is_before(a) && !b.is_before(b));
+    assert!(c.is_after(b) && !b.is_after(c) && !b.is_after(b));
+    assert!(a.is_between(a, c));
+    assert!(b.is_between(a, c));
+    assert!(!c.is_between(a, c));
+    assert!(!a.is_between(b, c));
+    assert!(!b.is_between(b, b));
+    assert!(!b.is_between(c, a));
+}
+
+#[test]
+fn midpoint_lerp() {
+    fn i(ticks: i64) -> Instant {
+        Instant { ticks: ticks }
+    }
+    let max: Instant = Bounded::max_value();
+    let min: Instant = Bounded::min_value();
+
+    assert_eq!(Instant::midpoint(i(0), i(10)), i(5));
+    assert_eq!(Instant::midpoint(i(10), i(0)), i(5));
+    assert_eq!(Instant::midpoint(i(0), i(1)), i(0));
+    assert_eq!(Instant::midpoint(i(-1), i(0)), i(-1));
+    assert_eq!(Instant::midpoint(i(-3), i(-3)), i(-3));
+    assert_eq!(Instant::midpoint(max, max), max);
+    assert_eq!(Instant::midpoint(min, min), min);
+    assert_eq!(Instant::midpoint(min, max), i(-1));
+    assert_eq!(Instant::midpoint(max, i(max.ticks - 2)), i(max.ticks - 1));
+
+    assert_eq!(Instant::lerp(i(0), i(100), 0.0), i(0));
+    assert_eq!(Instant::lerp(i(0), i(100), 0.25), i(25));
+    assert_eq!(Instant::lerp(i(0), i(100), 1.0), i(100));
+    assert_eq!(Instant::lerp(i(100), i(0), 0.25), i(75));
+    assert_eq!(Instant::lerp(i(0), i(100), -1.0), i(0));
+    assert_eq!(Instant::lerp(i(0), i(100), 2.0), i(100));
+    assert_eq!(Instant::lerp(i(0), i(100), 0.0 / 0.0), i(0));
+    assert_eq!(Instant::lerp(min, max, 0.0), min);
+    assert_eq!(Instant::lerp(min, max, 1.0), max);
+    assert_eq!(Instant::lerp(max, min, 1.0), min);
+    let near = Instant::lerp(min, max, 0.9999999999999999);
+    assert!(near < max && near.ticks > max.ticks - (1 << 12));
+    let near = Instant::lerp(max, min, 0.9999999999999999);
+    assert!(near > min && near.ticks < min.ticks + (1 << 12));
+    assert_eq!(Instant::lerp(min, max, 0.5).ticks >> 20, 0);
+}
+
+#[test]
+fn julian_date() {
+    assert_eq!(Instant { ticks: 0 }.to_julian_date(), 2451544.5);
+    assert_eq!(Instant { ticks: tick::HOUR * 12 }.to_julian_date(),
+               2451545.0);
+    assert_eq!(UNIX_EPOCH.to_julian_date(), 2440587.5);
+    assert_eq!(Instant { ticks: -tick::HOUR * 6 }.to_julian_date(),
+               2451544.25);
+}
+
+#[test]
+fn epochs() {
+    fn day(year: int, month: int, day: int) -> i64 {
+        tick::DAY * calendar_iso8601::to_cjd(year, month, day) as i64
+    }
+    assert_eq!(UNIX_EPOCH.ticks(), day(1970, 1, 1));
+    assert_eq!(J2000_EPOCH.ticks(), day(2000, 1, 1) + tick::HOUR * 12);
+    assert_eq!(GPS_EPOCH.ticks(), day(1980, 1, 6));
+    assert_eq!(NTP_EPOCH.ticks(), day(1900, 1, 1));
+    assert_eq!(WINDOWS_EPOCH.ticks(), day(1601, 1, 1));
+    assert_eq!(DOTNET_EPOCH.ticks(), day(1, 1, 1));
+    assert_eq!(EPOCH_UNIX_SECOND * tick::SECOND, -UNIX_EPOCH.ticks());
+    assert_eq!(format!("{}", DOTNET_EPOCH).as_slice(),
+               "0001-01-01T00:00:00Z");
+    assert_eq!(format!("{}", NTP_EPOCH).as_slice(), "1900-01-01T00:00:00Z");
+}
+
+#[test]
+fn saturating() {
+    let max: Instant = Bounded::max_value();
+    let min: Instant = Bounded::min_value();
+    let d = Duration { ticks: 10 };
+    let big: Duration = Bounded::max_value();
+    assert_eq!(Instant { ticks: 5 }.saturating_add(d), Instant { ticks: 15 });
+    assert_eq!(Instant { ticks: 5 }.saturating_sub(d), Instant { ticks: -5 });
+    assert_eq!(max.saturating_add(d), max);
+    assert_eq!(min.saturating_sub(d), min);
+    assert_eq!(min.saturating_add(-d), min);
+    assert_eq!(max.saturating_sub(-d), max);
+    assert_eq!(Instant { ticks: 0 }.saturating_add(big).saturating_add(big),
+               max);
+}
+
+#[bench]
+fn bench_format(b: &mut ::test::Bencher) {
+    let t = Instant { ticks: 4567891234567890 };
+    b.iter(|| format!("{}", t));
+}
+
+#[bench]
+fn bench_format_week(b: &mut ::test::Bencher) {
+    let t = Instant { ticks: 4567891234567890 };
+    b.iter(|| t.to_iso_week_string());
+}
+
+#[test]
+fn add_multiple() {
+    let min: Instant = Bounded::min_value();
+    let max: Instant = Bounded::max_value();
+    let t = Instant::from_ticks(tick::DAY);
+    let hour = Duration::from_hours(1);
+    assert_eq!(t.add_multiple(hour, 3),
+               Some(Instant::from_ticks(tick::DAY + tick::HOUR * 3)));
+    assert_eq!(t.add_multiple(hour, -24), Some(Instant::from_ticks(0)));
+    assert_eq!(t.add_multiple(hour, 0), Some(t));
+    // The product overflows, but the sum does not.
+    let big = Duration::from_seconds(1 << 30);
+    let n = max.ticks() / (1 << 30) / tick::SECOND + 1;
+    assert!(big.checked_mul_i64(n).is_none());
+    assert!(min.add_multiple(big, n).is_some());
+    assert_eq!(max.add_multiple(big, n), None);
+    assert_eq!(min.add_multiple(hour, -1), None);
+    assert_eq!(max.add_multiple(Duration { ticks: 1 }, 1), None);
+}
+
+#[test]
+fn snap_to_grid() {
+    fn test(t: i64, origin: i64, interval: i64, mode: RoundingMode,
+            expected: Option<i64>) {
+        let out = Instant::from_ticks(t).snap_to_grid(
+            Instant::from_ticks(origin), Duration { ticks: interval }, mode);
+        if out != expected.map(|n| Instant::from_ticks(n)) {
+            fail!("input: {} {} {} {}, expected: {}, output: {}",
+                  t, origin, interval, mode, expected, out);
+        }
+    }
+    let q = tick::MINUTE * 15;
+    let t = tick::HOUR * 10 + tick::MINUTE * 20;
+    test(t, 0, q, Floor, Some(tick::HOUR * 10 + q));
+    test(t, 0, q, Ceiling, Some(tick::HOUR * 10 + q * 2));
+    test(t, 0, q, Nearest, Some(tick::HOUR * 10 + q));
+    test(t, 0, q, TowardZero, Some(tick::HOUR * 10 + q));
+    test(-t, 0, q, Floor, Some(-tick::HOUR * 10 - q * 2));
+    test(-t, 0, q, Ceiling, Some(-tick::HOUR * 10 - q));
+    test(-t, 0, q, Nearest, Some(-tick::HOUR * 10 - q));
+    test(-t, 0, q, TowardZero, Some(-tick::HOUR * 10 - q));
+    test(t, q * 2, q, Floor, Some(tick::HOUR * 10 + q));
+    test(t, tick::MINUTE * 7, q, Floor,
+         Some(tick::HOUR * 10 + tick::MINUTE * 7));
+    test(t, tick::MINUTE * -7, q, Ceiling,
+         Some(tick::HOUR * 10 + tick::MINUTE * 23));
+    // Ties go to an even number of intervals from the origin.
+    test(15, 0, 10, Nearest, Some(20));
+    test(25, 0, 10, Nearest, Some(20));
+    test(-15, 0, 10, Nearest, Some(-20));
+    test(-25, 0, 10, Nearest, Some(-20));
+    test(25, 10, 10, Nearest, Some(30));
+    test(20, 0, 10, Ceiling, Some(20));
+    test(20, 0, 0, Floor, None);
+    test(20, 0, -10, Floor, None);
+    let min: Instant = Bounded::min_value();
+    let max: Instant = Bounded::max_value();
+    // The distance from the origin does not fit in 64 bits.
+    let origin = max.ticks() / tick::DAY * tick::DAY;
+    let day = min.ticks() / tick::DAY * tick::DAY;
+    test(day + 5, origin, tick::DAY, Floor, Some(day));
+    test(min.ticks(), 0, 3, Floor, None);
+    test(max.ticks(), 0, 3, Ceiling, None);
+}
+
+#[test]
+fn unix_timespec() {
+    let t = Instant { ticks: tick::DAY * 5274 + 1234567 };
+    let ts = t.to_timespec();
+    assert_eq!(ts.tv_sec, 1402358400);
+    assert_eq!(ts.tv_nsec, 123456700);
+    assert_eq!(Instant::from_unix_timespec(&ts), Some(t));
+    let tv = t.to_timeval();
+    assert_eq!(Instant::from_unix_timeval(&tv),
+               Some(Instant { ticks: t.ticks + 3 }));
+    let max: i64 = Bounded::max_value();
+    assert_eq!(Instant::from_unix_timespec(&timespec {
+        tv_sec: max, tv_nsec: 0 }), None);
+    assert_eq!(Instant::try_from_unix_timespec(&ts), Ok(t));
+    assert_eq!(Instant::try_from_unix_timespec(&timespec {
+        tv_sec: 0, tv_nsec: -5 }), Err(InvalidNanoseconds));
+    assert_eq!(Instant::try_from_unix_timespec(&timespec {
+        tv_sec: max, tv_nsec: 0 }), Err(PosixTimeOverflow));
+    assert_eq!(Instant::try_from_unix_timeval(&timeval {
+        tv_sec: 0, tv_usec: 1000000 }), Err(InvalidMicroseconds));
+}
+
+#[test]
+fn unix_nanoseconds() {
+    let t = Instant { ticks: tick::DAY * 5274 + 1234567 };
+    assert_eq!(t.to_unix_nanoseconds(), Some(1402358400123456700));
+    assert_eq!(Instant::from_unix_nanoseconds(1402358400123456700), t);
+    assert_eq!(Instant::from_unix_nanoseconds(1402358400123456740), t);
+    assert_eq!(Instant::from_unix_nanoseconds(-50), UNIX_EPOCH);
+    assert_eq!(Instant::from_unix_nanoseconds(-51),
+               Instant { ticks: UNIX_EPOCH.ticks - 1 });
+    let max: Instant = Bounded::max_value();
+    assert_eq!(max.to_unix_nanoseconds(), None);
+    let last = Instant::from_unix_nanoseconds(Bounded::max_value());
+    assert_eq!(last.to_unix_nanoseconds(), Some(9223372036854775800));
+    assert_eq!(format!("{}", last).as_slice(),
+               "2262-04-11T23:47:16Z");
 }
@@ -0,0 +1,336 @@
+//! Iterator adaptors for sequences of instants, dates, and durations.
+
+use std::cmp::min;
+use std::num::Bounded;
+use instant::Instant;
+use duration::Duration;
+use format_iso8601::{Date, YearMonthDay, Weekday, Saturday, Sunday};
+use calendar_iso8601;
+use period::AnniversaryPolicy;
+
+/// An iterator over the durations between consecutive instants.
+#[deriving(Clone)]
+pub struct Deltas<I> {
+    iter: I,
+    last: Option<Instant>
+}
+
+impl<I: Iterator<Instant>> Iterator<Duration> for Deltas<I> {
+    fn next(&mut self) -> Option<Duration> {
+        let last = match self.last {
+            Some(t) => t,
+            None => match self.iter.next() {
+                Some(t) => t,
+                None => return None
+            }
+        };
+        match self.iter.next() {
+            Some(t) => {
+                self.last = Some(t);
+                Some(Duration { ticks: t.ticks - last.ticks })
+            }
+            None => {
+                self.last = None;
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let (lo, hi) = self.iter.size_hint();
+        if self.last.is_some() {
+            (lo, hi)
+        } else {
+            (if lo > 0 { lo - 1 } else { 0 },
+             hi.map(|n| if n > 0 { n - 1 } else { 0 }))
+        }
+    }
+}
+
+/// Iterate over the durations between consecutive instants, such as the
+/// inter-arrival times of a stream of events.  A sequence of N instants
+/// produces N-1 durations.
+pub fn deltas<I: Iterator<Instant>>(iter: I) -> Deltas<I> {
+    Deltas { iter: iter, last: None }
+}
+
+/// An iterator over the running sums of durations added to an instant.
+#[deriving(Clone)]
+pub struct Accumulate<I> {
+    iter: I,
+    next: Option<Instant>
+}
+
+impl<I: Iterator<Duration>> Iterator<Instant> for Accumulate<I> {
+    fn next(&mut self) -> Option<Instant> {
+        let t = match self.next {
+            Some(t) => t,
+            None => return None
+        };
+        self.next = self.iter.next().map(|d| t + d);
+        Some(t)
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        if self.next.is_none() {
+            return (0, Some(0));
+        }
+        let (lo, hi) = self.iter.size_hint();
+        (lo + 1, hi.map(|n| n + 1))
+    }
+}
+
+/// Iterate over an instant followed by the running sums of durations
+/// added to it.  This is the inverse of deltas: accumulating the deltas of
+/// a sequence of instants, starting with the first instant, produces the
+/// original sequence.
+pub fn accumulate<I: Iterator<Duration>>(start: Instant, iter: I)
+                                         -> Accumulate<I> {
+    Accumulate { iter: iter, next: Some(start) }
+}
+
+/// An iterator over instants separated by a fixed step.
+#[deriving(Clone)]
+pub struct RangeStep {
+    next: Option<Instant>,
+    end: Instant,
+    step: Duration
+}
+
+impl Iterator<Instant> for RangeStep {
+    fn next(&mut self) -> Option<Instant> {
+        let t = match self.next {
+            Some(t) => t,
+            None => return None
+        };
+        let more = if self.step.ticks > 0 { t < self.end }
+                   else { t > self.end };
+        if !more {
+            self.next = None;
+            return None;
+        }
+        self.next = t.ticks.checked_add(&self.step.ticks)
+            .map(|ticks| Instant { ticks: ticks });
+        Some(t)
+    }
+}
+
+/// Iterate over instants from start up to but not including end,
+/// separated by step.  If step is negative, the instants count down from
+/// start and stop before reaching end.  Iteration stops cleanly rather
+/// than wrapping around if the next instant would be out of range.  Fails
+/// if step is zero.
+pub fn range_step(start: Instant, end: Instant, step: Duration)
+                  -> RangeStep {
+    if step.ticks == 0 {
+        fail!("range_step: step is zero");
+    }
+    RangeStep { next: Some(start), end: end, step: step }
+}
+
+/// An unbounded iterator over dates separated by a fixed number of
+/// calendar months.
+#[deriving(Clone)]
+pub struct Periods {
+    year: int,
+    month: int,
+    day: int,
+    months: int
+}
+
+impl Iterator<Date> for Periods {
+    fn next(&mut self) -> Option<Date> {
+        let d = min(self.day,
+                    calendar_iso8601::days_in_month(self.year, self.month));
+        let date = YearMonthDay(self.year, self.month, d);
+        let m = self.month - 1 + self.months;
+        self.year += m / 12;
+        self.month = m % 12 + 1;
+        Some(date)
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (Bounded::max_value(), None)
+    }
+}
+
+/// Iterate over dates starting with the given year, month, and day, and
+/// separated by the given positive number of months.  If the day does not
+/// exist in a month, the last day of the month is used instead, but later
+/// months still use the original day.
+pub fn periods(year: int, month: int, day: int, months: int) -> Periods {
+    if months <= 0 {
+        fail!("periods: months is not positive");
+    }
+    Periods { year: year, month: month, day: day, months: months }
+}
+
+/// An unbounded iterator over the anniversaries of a day of the year.
+#[deriving(Clone)]
+pub struct Anniversaries {
+    year: int,
+    month: int,
+    day: int,
+    policy: AnniversaryPolicy
+}
+
+impl Iterator<Date> for Anniversaries {
+    fn next(&mut self) -> Option<Date> {
+        loop {
+            let year = self.year;
+            self.year += 1;
+            match self.policy.resolve(year, self.month, self.day) {
+                Some(date) => return Some(date),
+                None => ()
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (Bounded::max_value(), None)
+    }
+}
+
+/// Iterate over the anniversaries of a month and day, starting with the
+/// given year.  In common years, the anniversary of February 29 is placed
+/// according to the policy, and with the `Skip` policy, those years are
+/// left out.
+pub fn anniversaries(year: int, month: int, day: int,
+                     policy: AnniversaryPolicy) -> Anniversaries {
+    Anniversaries { year: year, month: month, day: day, policy: policy }
+}
+
+/// An iterator over consecutive days, optionally restricted to certain
+/// days of the week.
+#[deriving(Clone)]
+pub struct DateRange {
+    next: int,
+    end: int,
+    weekdays: u8
+}
+
+impl DateRange {
+    /// Restrict the iterator to dates which fall on one of the given days
+    /// of the week.
+    pub fn only_weekdays(self, weekdays: &[Weekday]) -> DateRange {
+        let mask = weekdays.iter().fold(0u8, |m, &w| m | 1 << (w as uint));
+        DateRange { weekdays: self.weekdays & mask, ..self }
+    }
+
+    /// Restrict the iterator to dates from Monday to Friday.
+    pub fn exclude_weekends(self) -> DateRange {
+        let mask = !(1u8 << (Saturday as uint) | 1u8 << (Sunday as uint));
+        DateRange { weekdays: self.weekdays & mask, ..self }
+    }
+}
+
+impl Iterator<Date> for DateRange {
+    fn next(&mut self) -> Option<Date> {
+        while self.next < self.end {
+            let cjd = self.next;
+            self.next += 1;
+            let w = calendar_iso8601::weekday(cjd);
+            if self.weekdays & 1 << (w as uint) != 0 {
+                let (y, m, d) = calendar_iso8601::from_cjd(cjd);
+                return Some(YearMonthDay(y, m, d));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let n = if self.next < self.end { self.end - self.next } else { 0 };
+        (0, Some(n as uint))
+    }
+}
+
+/// Iterate over the days from one chronological Julian day up to but not
+/// including another.
+pub fn date_range(start: int, end: int) -> DateRange {
+    DateRange { next: start, end: end, weekdays: 0xfe }
+}
+
+#[test]
+fn deltas_accumulate() {
+    let times = [Instant { ticks: 10 }, Instant { ticks: 15 },
+                 Instant { ticks: 15 }, Instant { ticks: 12 }];
+    let d: Vec<Duration> = deltas(times.iter().map(|&t| t)).collect();
+    assert_eq!(d, vec![Duration { ticks: 5 }, Duration { ticks: 0 },
+                       Duration { ticks: -3 }]);
+    let t: Vec<Instant> = accumulate(times[0], d.iter().map(|&x| x))
+        .collect();
+    assert_eq!(t.as_slice(), times.as_slice());
+
+    assert_eq!(deltas(times.iter().map(|&t| t)).size_hint(), (3, Some(3)));
+    assert_eq!(accumulate(times[0], d.iter().map(|&x| x)).size_hint(),
+               (4, Some(4)));
+}
+
+#[test]
+fn deltas_short() {
+    let empty: [Instant, ..0] = [];
+    assert_eq!(deltas(empty.iter().map(|&t| t)).next(), None);
+    let one = [Instant { ticks: 0 }];
+    assert_eq!(deltas(one.iter().map(|&t| t)).next(), None);
+    let mut it = accumulate(Instant { ticks: 7 }, empty.iter().map(|_| {
+        Duration { ticks: 0 }
+    }));
+    assert_eq!(it.next(), Some(Instant { ticks: 7 }));
+    assert_eq!(it.next(), None);
+}
+
+#[test]
+fn range_step_test() {
+    fn test(start: i64, end: i64, step: i64, expected: &[i64]) {
+        let v: Vec<i64> = range_step(Instant { ticks: start },
+                                     Instant { ticks: end },
+                                     Duration { ticks: step })
+            .map(|t| t.ticks).collect();
+        assert_eq!(v.as_slice(), expected);
+    }
+    test(0, 10, 3, [0, 3, 6, 9]);
+    test(0, 9, 3, [0, 3, 6]);
+    test(10, 0, -4, [10, 6, 2]);
+    test(0, 0, 1, []);
+    test(0, 5, -1, []);
+    test(5, 0, 1, []);
+    let max: i64 = Bounded::max_value();
+    let min: i64 = Bounded::min_value();
+    test(max - 5, max, 2, [max - 5, max - 3, max - 1]);
+    test(min + 5, min, -2, [min + 5, min + 3, min + 1]);
+    test(max - 1, max, max, [max - 1]);
+}
+
+#[test]
+fn periods_test() {
+    fn test(mut it: Periods, expected: &[(int, int, int)]) {
+        for &(y, m, d) in expected.iter() {
+            assert!(it.next() == Some(YearMonthDay(y, m, d)));
+        }
+    }
+    test(periods(2023, 11, 1, 1),
+         [(2023, 11, 1), (2023, 12, 1), (2024, 1, 1), (2024, 2, 1)]);
+    test(periods(2024, 1, 31, 1),
+         [(2024, 1, 31), (2024, 2, 29), (2024, 3, 31), (2024, 4, 30)]);
+    test(periods(2024, 2, 29, 12),
+         [(2024, 2, 29), (2025, 2, 28), (2026, 2, 28), (2028, 2, 29)]);
+    test(periods(-1, 12, 31, 3),
+         [(-1, 12, 31), (0, 3, 31), (0, 6, 30), (0, 9, 30), (0, 12, 31)]);
+}
+
+#[test]
+fn anniversaries_test() {
+    use period::{Feb28, Mar1, Skip};
+    fn test(policy: AnniversaryPolicy, expected: &[(int, int, int)]) {
+        let mut it = anniversaries(2011, 2, 29, policy);
+        for &(y, m, d) in expected.iter() {
+            assert!(it.next() == Some(YearMonthDay(y, m, d)));
+        }
+    }
+    test(Feb28, [(2011, 2, 28), (2012, 2, 29), (2013, 2, 28)]);
+    test(Mar1, [(2011, 3, 1), (2012, 2, 29), (2013, 3, 1)]);
+    test(Skip, [(2012, 2, 29), (2016, 2, 29), (2020, 2, 29)]);
+    let mut it = YearMonthDay(2096, 2, 29).anniversaries(Skip);
+    assert!(it.next() == Some(YearMonthDay(2096, 2, 29)));
+    assert!(it.next() == Some(YearMonthDay(2104, 2, 29)));
+}
@@ -5,11 +5,54 @@
 #![license = "MIT/ASL2"]
 #![crate_type = "rlib"]
 #![crate_type = "dylib"]
+#![feature(unsafe_destructor)]
 
 extern crate libc;
+#[cfg(test)]
+extern crate test;
+#[cfg(feature = "time")]
+extern crate time;
+
+pub mod astro;
+pub mod builder;
+pub mod business;
+pub mod calendar_chinese;
+#[cfg(target_os = "linux")]
+pub mod clock;
+pub mod datetime;
+pub mod div_mod;
 pub mod duration;
+pub mod epoch;
+pub mod file_time;
+pub mod format_clf;
+pub mod format_cookie;
+pub mod format_iso8601;
+pub mod format_pattern;
+pub mod format_sql;
+pub mod format_syslog;
 pub mod instant;
+pub mod iter;
+pub mod locale;
+#[cfg(feature = "locales")]
+pub mod locale_data;
+#[cfg(feature = "natural")]
+pub mod natural;
+pub mod offset_datetime;
+pub mod parse;
+pub mod period;
+pub mod scan;
+pub mod schedule;
+pub mod smear;
+pub mod stream;
+#[cfg(feature = "time")]
+pub mod time_compat;
+pub mod time_of_day;
+pub mod wide_duration;
+pub mod windows_zones;
+pub mod zone;
+pub mod zoned_datetime;
 mod calendar_iso8601;
-mod div_mod;
 mod fmtutil;
+mod parseutil;
 mod tick;
+mod wide;
@@ -0,0 +1,145 @@
+//! Names and conventions for formatting dates in different languages.
+//!
+//! The pattern formatter looks up month names, weekday names, and the
+//! preferred date and time patterns through the Locale trait, so
+//! applications can supply their own translations.  EnglishLocale is used
+//! when no locale is given.
+
+use parseutil;
+use parse::BUDDHIST_ERA_OFFSET;
+use format_iso8601::{Weekday, Sunday};
+
+/// A source of names and patterns for formatting dates and times.  Months
+/// are numbered from 1 for January, and weekdays from 1 for Monday.
+pub trait Locale {
+    /// Get the full name of a month, such as "January".
+    fn month_name<'a>(&'a self, month: int) -> &'a str;
+
+    /// Get the abbreviated name of a month, such as "Jan".
+    fn month_abbreviation<'a>(&'a self, month: int) -> &'a str;
+
+    /// Get the full name of a day of the week, such as "Monday".
+    fn weekday_name<'a>(&'a self, weekday: int) -> &'a str;
+
+    /// Get the abbreviated name of a day of the week, such as "Mon".
+    fn weekday_abbreviation<'a>(&'a self, weekday: int) -> &'a str;
+
+    /// Get the designator for times before noon, or from noon on if pm is
+    /// set, such as "AM" or "PM".
+    fn am_pm<'a>(&'a self, pm: bool) -> &'a str;
+
+    /// Get the preferred pattern for a date, used by the `%x` conversion.
+    /// The pattern may not itself contain `%x` or `%X`.
+    fn date_pattern<'a>(&'a self) -> &'a str;
+
+    /// Get the preferred pattern for a time of day, used by the `%X`
+    /// conversion.  The pattern may not itself contain `%x` or `%X`.
+    fn time_pattern<'a>(&'a self) -> &'a str;
+
+    /// Get the day on which weeks start, for calendars and week numbers.
+    /// The default is Sunday.
+    fn first_day_of_week(&self) -> Weekday {
+        Sunday
+    }
+
+    /// Get the number added to the year when it is displayed, for locales
+    /// counting years from a different epoch than the Gregorian calendar,
+    /// such as 543 for the Thai Buddhist era.  The default is 0.
+    fn year_offset(&self) -> int {
+        0
+    }
+}
+
+/// The English names of the days of the week, starting with Monday.
+static WEEKDAY_NAMES: [&'static str, ..7] = [
+    "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
+    "Sunday"
+];
+
+/// The English locale, with United States conventions for dates.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct EnglishLocale;
+
+impl Locale for EnglishLocale {
+    fn month_name<'a>(&'a self, month: int) -> &'a str {
+        parseutil::MONTH_NAMES[(month - 1) as uint]
+    }
+
+    fn month_abbreviation<'a>(&'a self, month: int) -> &'a str {
+        parseutil::MONTH_ABBREVIATIONS[(month - 1) as uint]
+    }
+
+    fn weekday_name<'a>(&'a self, weekday: int) -> &'a str {
+        WEEKDAY_NAMES[(weekday - 1) as uint]
+    }
+
+    fn weekday_abbreviation<'a>(&'a self, weekday: int) -> &'a str {
+        parseutil::WEEKDAY_ABBREVIATIONS[(weekday - 1) as uint]
+    }
+
+    fn am_pm<'a>(&'a self, pm: bool) -> &'a str {
+        if pm { "PM" } else { "AM" }
+    }
+
+    fn date_pattern<'a>(&'a self) -> &'a str {
+        "%m/%d/%Y"
+    }
+
+    fn time_pattern<'a>(&'a self) -> &'a str {
+        "%I:%M:%S %p"
+    }
+}
+
+/// A locale which displays years of the Thai Buddhist era, 543 years more
+/// than the year AD, and otherwise behaves like the wrapped locale.  This
+/// selects the era for a pattern, as in
+/// `FormatSpec::compile_with_locale(p, &BuddhistEraLocale(EnglishLocale))`.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct BuddhistEraLocale<L>(pub L);
+
+impl<L: Locale> Locale for BuddhistEraLocale<L> {
+    fn month_name<'a>(&'a self, month: int) -> &'a str {
+        let BuddhistEraLocale(ref l) = *self;
+        l.month_name(month)
+    }
+
+    fn month_abbreviation<'a>(&'a self, month: int) -> &'a str {
+        let BuddhistEraLocale(ref l) = *self;
+        l.month_abbreviation(month)
+    }
+
+    fn weekday_name<'a>(&'a self, weekday: int) -> &'a str {
+        let BuddhistEraLocale(ref l) = *self;
+        l.weekday_name(weekday)
+    }
+
+    fn weekday_abbreviation<'a>(&'a self, weekday: int) -> &'a str {
+        let BuddhistEraLocale(ref l) = *self;
+        l.weekday_abbreviation(weekday)
+    }
+
+    fn am_pm<'a>(&'a self, pm: bool) -> &'a str {
+        let BuddhistEraLocale(ref l) = *self;
+        l.am_pm(pm)
+    }
+
+    fn date_pattern<'a>(&'a self) -> &'a str {
+        let BuddhistEraLocale(ref l) = *self;
+        l.date_pattern()
+    }
+
+    fn time_pattern<'a>(&'a self) -> &'a str {
+        let BuddhistEraLocale(ref l) = *self;
+        l.time_pattern()
+    }
+
+    fn first_day_of_week(&self) -> Weekday {
+        let BuddhistEraLocale(ref l) = *self;
+        l.first_day_of_week()
+    }
+
+    fn year_offset(&self) -> int {
+        let BuddhistEraLocale(ref l) = *self;
+        l.year_offset() + BUDDHIST_ERA_OFFSET
+    }
+}
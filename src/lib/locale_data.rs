@@ -0,0 +1,217 @@
+//! Locales for several major languages, built from the CLDR data.
+//!
+//! This module is only compiled with the "locales" feature.  Month names
+//! are in the form used within a date, which for some languages, such as
+//! Russian, differs from the form used on its own.
+
+use locale::Locale;
+use format_iso8601::{Weekday, Monday, Sunday};
+use parseutil;
+
+/// A locale defined by tables of names and patterns.
+pub struct TableLocale {
+    /// The BCP 47 language tag, such as "de".
+    pub tag: &'static str,
+    pub months: [&'static str, ..12],
+    pub month_abbreviations: [&'static str, ..12],
+    /// The weekday names, starting with Monday.
+    pub weekdays: [&'static str, ..7],
+    pub weekday_abbreviations: [&'static str, ..7],
+    pub am: &'static str,
+    pub pm: &'static str,
+    pub date_pattern: &'static str,
+    pub time_pattern: &'static str,
+    pub first_day_of_week: Weekday
+}
+
+impl Locale for TableLocale {
+    fn month_name<'a>(&'a self, month: int) -> &'a str {
+        self.months[(month - 1) as uint]
+    }
+
+    fn month_abbreviation<'a>(&'a self, month: int) -> &'a str {
+        self.month_abbreviations[(month - 1) as uint]
+    }
+
+    fn weekday_name<'a>(&'a self, weekday: int) -> &'a str {
+        self.weekdays[(weekday - 1) as uint]
+    }
+
+    fn weekday_abbreviation<'a>(&'a self, weekday: int) -> &'a str {
+        self.weekday_abbreviations[(weekday - 1) as uint]
+    }
+
+    fn am_pm<'a>(&'a self, pm: bool) -> &'a str {
+        if pm { self.pm } else { self.am }
+    }
+
+    fn date_pattern<'a>(&'a self) -> &'a str {
+        self.date_pattern
+    }
+
+    fn time_pattern<'a>(&'a self) -> &'a str {
+        self.time_pattern
+    }
+
+    fn first_day_of_week(&self) -> Weekday {
+        self.first_day_of_week
+    }
+}
+
+/// German.
+pub static GERMAN: TableLocale = TableLocale {
+    tag: "de",
+    months: ["Januar", "Februar", "März", "April", "Mai", "Juni", "Juli",
+             "August", "September", "Oktober", "November", "Dezember"],
+    month_abbreviations: ["Jan.", "Feb.", "März", "Apr.", "Mai", "Juni",
+                          "Juli", "Aug.", "Sept.", "Okt.", "Nov.", "Dez."],
+    weekdays: ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag",
+               "Samstag", "Sonntag"],
+    weekday_abbreviations: ["Mo.", "Di.", "Mi.", "Do.", "Fr.", "Sa.", "So."],
+    am: "AM",
+    pm: "PM",
+    date_pattern: "%d.%m.%Y",
+    time_pattern: "%H:%M:%S",
+    first_day_of_week: Monday
+};
+
+/// French.
+pub static FRENCH: TableLocale = TableLocale {
+    tag: "fr",
+    months: ["janvier", "février", "mars", "avril", "mai", "juin",
+             "juillet", "août", "septembre", "octobre", "novembre",
+             "décembre"],
+    month_abbreviations: ["janv.", "févr.", "mars", "avr.", "mai", "juin",
+                          "juil.", "août", "sept.", "oct.", "nov.", "déc."],
+    weekdays: ["lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi",
+               "dimanche"],
+    weekday_abbreviations: ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.",
+                            "dim."],
+    am: "AM",
+    pm: "PM",
+    date_pattern: "%d/%m/%Y",
+    time_pattern: "%H:%M:%S",
+    first_day_of_week: Monday
+};
+
+/// Spanish.
+pub static SPANISH: TableLocale = TableLocale {
+    tag: "es",
+    months: ["enero", "febrero", "marzo", "abril", "mayo", "junio", "julio",
+             "agosto", "septiembre", "octubre", "noviembre", "diciembre"],
+    month_abbreviations: ["ene", "feb", "mar", "abr", "may", "jun", "jul",
+                          "ago", "sept", "oct", "nov", "dic"],
+    weekdays: ["lunes", "martes", "miércoles", "jueves", "viernes",
+               "sábado", "domingo"],
+    weekday_abbreviations: ["lun", "mar", "mié", "jue", "vie", "sáb",
+                            "dom"],
+    am: "a. m.",
+    pm: "p. m.",
+    date_pattern: "%d/%m/%Y",
+    time_pattern: "%H:%M:%S",
+    first_day_of_week: Monday
+};
+
+/// Japanese.
+pub static JAPANESE: TableLocale = TableLocale {
+    tag: "ja",
+    months: ["1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月",
+             "10月", "11月", "12月"],
+    month_abbreviations: ["1月", "2月", "3月", "4月", "5月", "6月", "7月",
+                          "8月", "9月", "10月", "11月", "12月"],
+    weekdays: ["月曜日", "火曜日", "水曜日", "木曜日", "金曜日", "土曜日",
+               "日曜日"],
+    weekday_abbreviations: ["月", "火", "水", "木", "金", "土", "日"],
+    am: "午前",
+    pm: "午後",
+    date_pattern: "%Y/%m/%d",
+    time_pattern: "%H:%M:%S",
+    first_day_of_week: Sunday
+};
+
+/// Chinese, in simplified characters.
+pub static CHINESE: TableLocale = TableLocale {
+    tag: "zh",
+    months: ["一月", "二月", "三月", "四月", "五月", "六月", "七月", "八月",
+             "九月", "十月", "十一月", "十二月"],
+    month_abbreviations: ["1月", "2月", "3月", "4月", "5月", "6月", "7月",
+                          "8月", "9月", "10月", "11月", "12月"],
+    weekdays: ["星期一", "星期二", "星期三", "星期四", "星期五", "星期六",
+               "星期日"],
+    weekday_abbreviations: ["周一", "周二", "周三", "周四", "周五", "周六",
+                            "周日"],
+    am: "上午",
+    pm: "下午",
+    date_pattern: "%Y/%m/%d",
+    time_pattern: "%H:%M:%S",
+    first_day_of_week: Monday
+};
+
+/// Russian.
+pub static RUSSIAN: TableLocale = TableLocale {
+    tag: "ru",
+    months: ["января", "февраля", "марта", "апреля", "мая", "июня", "июля",
+             "августа", "сентября", "октября", "ноября", "декабря"],
+    month_abbreviations: ["янв.", "февр.", "мар.", "апр.", "мая", "июн.",
+                          "июл.", "авг.", "сент.", "окт.", "нояб.", "дек."],
+    weekdays: ["понедельник", "вторник", "среда", "четверг", "пятница",
+               "суббота", "воскресенье"],
+    weekday_abbreviations: ["пн", "вт", "ср", "чт", "пт", "сб", "вс"],
+    am: "AM",
+    pm: "PM",
+    date_pattern: "%d.%m.%Y",
+    time_pattern: "%H:%M:%S",
+    first_day_of_week: Monday
+};
+
+/// All of the bundled locales.
+pub static ALL: [&'static TableLocale, ..6] = [
+    &GERMAN, &FRENCH, &SPANISH, &JAPANESE, &CHINESE, &RUSSIAN
+];
+
+/// Find a bundled locale by its language tag, such as "de".  Region
+/// subtags are ignored, so "de-AT" finds German.  Returns None if there is
+/// no locale for the language.
+pub fn find(tag: &str) -> Option<&'static TableLocale> {
+    let lang = match tag.find(|c: char| c == '-' || c == '_') {
+        Some(i) => tag.slice_to(i),
+        None => tag
+    };
+    ALL.iter().find(|l| {
+        parseutil::eq_ignore_case(l.tag.as_bytes(), lang.as_bytes())
+    }).map(|&l| l)
+}
+
+#[test]
+fn format() {
+    use tick;
+    use instant::Instant;
+    use format_pattern::FormatSpec;
+    // 2014-03-10T15:00:00Z, a Monday.
+    let t = Instant::from_ticks(tick::DAY * 5182 + tick::HOUR * 15);
+    fn test(l: &TableLocale, t: Instant, expected: &str) {
+        let spec = FormatSpec::compile_with_locale("%A %d %B %Y, %x %X", l)
+            .unwrap();
+        assert_eq!(spec.format(t).as_slice(), expected);
+    }
+    test(&GERMAN, t, "Montag 10 März 2014, 10.03.2014 15:00:00");
+    test(&FRENCH, t, "lundi 10 mars 2014, 10/03/2014 15:00:00");
+    test(&SPANISH, t, "lunes 10 marzo 2014, 10/03/2014 15:00:00");
+    test(&JAPANESE, t, "月曜日 10 3月 2014, 2014/03/10 15:00:00");
+    test(&CHINESE, t, "星期一 10 三月 2014, 2014/03/10 15:00:00");
+    test(&RUSSIAN, t, "понедельник 10 марта 2014, 10.03.2014 15:00:00");
+}
+
+#[test]
+fn first_day() {
+    assert_eq!(GERMAN.first_day_of_week(), Monday);
+    assert_eq!(JAPANESE.first_day_of_week(), Sunday);
+}
+
+#[test]
+fn find_tag() {
+    assert_eq!(find("de").map(|l| l.tag), Some("de"));
+    assert_eq!(find("de-AT").map(|l| l.tag), Some("de"));
+    assert_eq!(find("ZH_cn").map(|l| l.tag), Some("zh"));
+    assert_eq!(find("en").map(|l| l.tag), None);
+}
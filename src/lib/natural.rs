@@ -0,0 +1,255 @@
+//! Informal English date expressions, such as "tomorrow", "next Tuesday",
+//! "in 3 hours", and "last friday 5pm".
+//!
+//! This module is only available with the "natural" feature.
+//!
+//! Expressions are interpreted relative to a reference instant in a time
+//! zone, and words are case-insensitive.  The accepted forms are:
+//!
+//! * "now".
+//! * "in N UNIT" and "N UNIT ago", where N is a number, "a", or "an", and
+//!   UNIT is seconds, minutes, hours, days, or weeks, in singular, plural,
+//!   or abbreviated form.  Days and weeks are calendar days, so the local
+//!   time of day is kept across changes to daylight saving time.
+//! * A day, a time, or a day followed by a time.  The day is "today",
+//!   "tomorrow", "yesterday", or the name of a weekday, which may follow
+//!   "next" or "last".  A weekday alone is the next day with that name,
+//!   which may be today, while "next" and "last" skip today.  The time is
+//!   "noon", "midnight", a 24-hour time such as "17:30", or a 12-hour time
+//!   such as "5pm" or "5:30 pm", optionally after "at".  A bare hour, such
+//!   as "5", is only accepted after "at".  A day without a time means the
+//!   start of the day, and a time without a day means today.
+
+use std::ascii::StrAsciiExt;
+use std::num;
+use datetime::{DateTime, civil_to_instant_local};
+use instant::Instant;
+use parseutil;
+use tick;
+use zone::Zone;
+
+/// The English names of the days of the week, starting with Monday.
+static WEEKDAY_NAMES: [&'static str, ..7] = [
+    "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
+    "Sunday"
+];
+
+/// A unit in a relative expression.
+enum Unit {
+    /// A fixed number of ticks.
+    Ticks(i64),
+    /// A number of calendar days.
+    Days(i64)
+}
+
+fn read_unit(w: &str) -> Option<Unit> {
+    Some(match w {
+        "s" | "sec" | "secs" | "second" | "seconds" => Ticks(tick::SECOND),
+        "min" | "mins" | "minute" | "minutes" => Ticks(tick::MINUTE),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Ticks(tick::HOUR),
+        "d" | "day" | "days" => Days(1),
+        "w" | "wk" | "wks" | "week" | "weeks" => Days(7),
+        _ => return None
+    })
+}
+
+/// Read the amount in "in 3 hours" or "a week ago".
+fn read_amount(n: &str, unit: &str) -> Option<(i64, Unit)> {
+    let n = match n {
+        "a" | "an" => 1,
+        _ => match from_str::<u32>(n) {
+            Some(n) => n as i64,
+            None => return None
+        }
+    };
+    read_unit(unit).map(|unit| (n, unit))
+}
+
+/// Move an instant by a number of units.  The local date and time are
+/// given for moving by calendar days.
+fn shift(now: Instant, local: &DateTime, zone: &Zone, n: i64, unit: Unit)
+         -> Option<Instant> {
+    match unit {
+        Ticks(size) => n.checked_mul(&size)
+            .and_then(|d| now.ticks().checked_add(&d))
+            .map(|t| Instant::from_ticks(t)),
+        Days(size) => {
+            let day = match num::cast(local.day as i64 + n * size) {
+                Some(day) => day, None => return None
+            };
+            civil_to_instant_local(&DateTime { day: day, ..*local }, zone)
+        }
+    }
+}
+
+/// Read the name of a weekday or its abbreviation.  Returns the ISO
+/// number of the weekday, starting with 1 for Monday.
+fn read_weekday(w: &str) -> Option<int> {
+    let w = w.as_bytes();
+    WEEKDAY_NAMES.iter()
+        .zip(parseutil::WEEKDAY_ABBREVIATIONS.iter())
+        .position(|(name, abbr)| {
+            parseutil::eq_ignore_case(w, name.as_bytes()) ||
+                parseutil::eq_ignore_case(w, abbr.as_bytes())
+        })
+        .map(|i| i as int + 1)
+}
+
+/// Read a day, given the number of today's weekday.  Returns the number of
+/// days from today and the remaining words.
+fn read_day<'a, 'b>(words: &'a [&'b str], weekday: int)
+                    -> Option<(int, &'a [&'b str])> {
+    if words.is_empty() {
+        return None;
+    }
+    let rest = words.slice_from(1);
+    match words[0] {
+        "today" => Some((0, rest)),
+        "tomorrow" => Some((1, rest)),
+        "yesterday" => Some((-1, rest)),
+        "next" if words.len() > 1 => read_weekday(words[1]).map(|d| {
+            ((d - weekday + 6) % 7 + 1, words.slice_from(2))
+        }),
+        "last" if words.len() > 1 => read_weekday(words[1]).map(|d| {
+            (-((weekday - d + 6) % 7 + 1), words.slice_from(2))
+        }),
+        w => read_weekday(w).map(|d| ((d - weekday + 7) % 7, rest))
+    }
+}
+
+/// Read a time of day.  Returns the hour, the minute, and the remaining
+/// words.
+fn read_time<'a, 'b>(words: &'a [&'b str])
+                     -> Option<(int, int, &'a [&'b str])> {
+    let (at, words) = if !words.is_empty() && words[0] == "at" {
+        (true, words.slice_from(1))
+    } else {
+        (false, words)
+    };
+    if words.is_empty() {
+        return None;
+    }
+    match words[0] {
+        "noon" => return Some((12, 0, words.slice_from(1))),
+        "midnight" => return Some((0, 0, words.slice_from(1))),
+        _ => ()
+    }
+    let w = words[0];
+    let digits = w.bytes().take_while(|&c| c >= '0' as u8 && c <= '9' as u8)
+        .count();
+    if digits == 0 || digits > 2 {
+        return None;
+    }
+    let hour: int = from_str(w.slice_to(digits)).unwrap();
+    let rem = w.slice_from(digits);
+    let (minute, rem) = if rem.starts_with(":") {
+        match parseutil::read_digits(rem.slice_from(1).as_bytes(), 2) {
+            Some((minute, _)) if minute <= 59 =>
+                (Some(minute), rem.slice_from(3)),
+            _ => return None
+        }
+    } else {
+        (None, rem)
+    };
+    let (suffix, rest) = if !rem.is_empty() {
+        (rem, words.slice_from(1))
+    } else if words.len() > 1 && (words[1] == "am" || words[1] == "pm") {
+        (words[1], words.slice_from(2))
+    } else {
+        ("", words.slice_from(1))
+    };
+    let hour = match suffix {
+        "am" if hour >= 1 && hour <= 12 => hour % 12,
+        "pm" if hour >= 1 && hour <= 12 => hour % 12 + 12,
+        "" if (at || minute.is_some()) && hour <= 23 => hour,
+        _ => return None
+    };
+    Some((hour, minute.unwrap_or(0), rest))
+}
+
+/// Parse an informal date expression relative to the instant `now` in the
+/// given zone.  Returns None if the expression is not recognized or the
+/// result is out of range.
+pub fn parse_natural(s: &str, now: Instant, zone: &Zone) -> Option<Instant> {
+    let lower: Vec<String> = s.words().map(|w| w.to_ascii_lower()).collect();
+    let words: Vec<&str> = lower.iter().map(|w| w.as_slice()).collect();
+    let words = words.as_slice();
+    let local = match now.ticks().checked_add(&zone.offset_at(now)) {
+        Some(t) => Instant::from_ticks(t), None => return None
+    };
+    let today = local.to_datetime();
+    if words.len() == 1 && words[0] == "now" {
+        return Some(now);
+    }
+    if words.len() == 3 && words[0] == "in" {
+        return read_amount(words[1], words[2])
+            .and_then(|(n, unit)| shift(now, &today, zone, n, unit));
+    }
+    if words.len() == 3 && words[2] == "ago" {
+        return read_amount(words[0], words[1])
+            .and_then(|(n, unit)| shift(now, &today, zone, -n, unit));
+    }
+    let (days, rem) = match read_day(words, local.weekday() as int) {
+        Some(x) => x, None => (0, words)
+    };
+    let (hour, minute, rem) = match read_time(rem) {
+        Some(x) => x,
+        None if rem.len() < words.len() => (0, 0, rem),
+        None => return None
+    };
+    if !rem.is_empty() {
+        return None;
+    }
+    let day = DateTime::new(today.year, today.month, today.day + days,
+                            hour, minute, 0, 0);
+    civil_to_instant_local(&day, zone)
+}
+
+#[test]
+fn test_parse_natural() {
+    use format_iso8601::parse_instant;
+    use zone::test_zone;
+    let zone = test_zone();
+    fn test(zone: &Zone, now: &str, s: &str, expected: Option<&str>) {
+        let now = parse_instant(now).unwrap();
+        let out = parse_natural(s, now, zone).map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != expected {
+            fail!("input: {}, expected: {}, output: {}", s, expected, out);
+        }
+    }
+    // Tuesday, 13:12:13 in the zone.
+    let now = "2014-06-10T11:12:13Z";
+    test(&zone, now, "now", Some("2014-06-10T11:12:13Z"));
+    test(&zone, now, "Now", Some("2014-06-10T11:12:13Z"));
+    test(&zone, now, "today", Some("2014-06-09T22:00:00Z"));
+    test(&zone, now, "tomorrow", Some("2014-06-10T22:00:00Z"));
+    test(&zone, now, "yesterday noon", Some("2014-06-09T10:00:00Z"));
+    test(&zone, now, "tuesday", Some("2014-06-09T22:00:00Z"));
+    test(&zone, now, "next Tuesday", Some("2014-06-16T22:00:00Z"));
+    test(&zone, now, "next wed", Some("2014-06-10T22:00:00Z"));
+    test(&zone, now, "last Tuesday", Some("2014-06-02T22:00:00Z"));
+    test(&zone, now, "last friday 5pm", Some("2014-06-06T15:00:00Z"));
+    test(&zone, now, "Friday at 5:30 PM", Some("2014-06-13T15:30:00Z"));
+    test(&zone, now, "tomorrow at midnight", Some("2014-06-10T22:00:00Z"));
+    test(&zone, now, "17:45", Some("2014-06-10T15:45:00Z"));
+    test(&zone, now, "at 9", Some("2014-06-10T07:00:00Z"));
+    test(&zone, now, "12am", Some("2014-06-09T22:00:00Z"));
+    test(&zone, now, "in 3 hours", Some("2014-06-10T14:12:13Z"));
+    test(&zone, now, "in 90 min", Some("2014-06-10T12:42:13Z"));
+    test(&zone, now, "2 days ago", Some("2014-06-08T11:12:13Z"));
+    test(&zone, now, "in a week", Some("2014-06-17T11:12:13Z"));
+    test(&zone, now, "", None);
+    test(&zone, now, "5", None);
+    test(&zone, now, "next", None);
+    test(&zone, now, "next tomorrow", None);
+    test(&zone, now, "tomorrow tomorrow", None);
+    test(&zone, now, "in 3 fortnights", None);
+    test(&zone, now, "in -3 hours", None);
+    test(&zone, now, "13pm", None);
+    test(&zone, now, "25:00", None);
+    test(&zone, now, "10:60", None);
+    // Calendar days keep the local time across the change to summer time.
+    let now = "2014-03-29T12:00:00Z";
+    test(&zone, now, "in 1 day", Some("2014-03-30T11:00:00Z"));
+    test(&zone, now, "in 24 hours", Some("2014-03-30T12:00:00Z"));
+}
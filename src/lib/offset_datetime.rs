@@ -0,0 +1,174 @@
+//! Instants paired with a fixed UTC offset.
+//!
+//! An OffsetDateTime remembers the offset it was parsed with, so it can be
+//! formatted again in the same local time, such as the local time of the
+//! author of a message.  It knows nothing about the rules of any time zone,
+//! so adding a duration keeps the same offset even across a change to or
+//! from daylight saving time.
+
+use std::io::{MemWriter, IoResult};
+use std::fmt::{Show, Formatter, FormatError, WriteError};
+use std::from_str::FromStr;
+use tick;
+use fmtutil;
+use instant::Instant;
+use datetime::DateTime;
+use format_iso8601;
+use parse::ParseOptions;
+
+/// An instant with the UTC offset used to display it.  Two values are only
+/// equal if both the instant and the offset are equal.
+#[deriving(PartialEq, Eq, Clone, Hash)]
+pub struct OffsetDateTime {
+    instant: Instant,
+    offset: i64
+}
+
+impl OffsetDateTime {
+    /// Create a value from an instant and an offset from UTC in ticks,
+    /// positive east of Greenwich.  Returns None unless the offset is a
+    /// whole number of seconds less than a day, or if the local time is out
+    /// of range.  Offsets with seconds, such as the local mean time of the
+    /// tz database, are written as "+00:19:32".
+    pub fn new(instant: Instant, offset: i64) -> Option<OffsetDateTime> {
+        if offset <= -tick::DAY || offset >= tick::DAY ||
+            offset % tick::SECOND != 0 ||
+            instant.ticks().checked_add(&offset).is_none() {
+            return None;
+        }
+        Some(OffsetDateTime { instant: instant, offset: offset })
+    }
+
+    /// Get the instant.
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+
+    /// Get the offset from UTC in ticks.
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Get the same instant with a different offset.  Returns None under
+    /// the same conditions as new().
+    pub fn with_offset(&self, offset: i64) -> Option<OffsetDateTime> {
+        OffsetDateTime::new(self.instant, offset)
+    }
+
+    /// Get the number of ticks since the epoch on the local time scale.
+    fn local_ticks(&self) -> i64 {
+        self.instant.ticks() + self.offset
+    }
+
+    /// Get the local date and time.
+    pub fn to_datetime(&self) -> DateTime {
+        Instant::from_ticks(self.local_ticks()).to_datetime()
+    }
+
+    /// Parse an ISO 8601 date and time with a UTC offset using the given
+    /// options.  Returns None if the date is incomplete, if there is no
+    /// offset, or if the result is out of range.
+    pub fn parse_with(s: &str, opts: &ParseOptions)
+                      -> Option<OffsetDateTime> {
+        let parsed = match format_iso8601::parse_fields(s, opts) {
+            Some(p) => p, None => return None
+        };
+        match (parsed.to_instant(), parsed.offset) {
+            (Some(t), Some(offset)) => OffsetDateTime::new(t, offset),
+            _ => None
+        }
+    }
+
+    /// Convert to a UTF-8 vector, used to implement Show.
+    fn to_utf8_io(&self) -> IoResult<Vec<u8>> {
+        let mut w = MemWriter::with_capacity(32);
+        let local = Instant::from_ticks(self.local_ticks());
+        try!(write!(&mut w, "{}", local));
+        let mut out = w.unwrap();
+        // Replace the "Z" written for the instant with the offset.
+        out.pop();
+        let mut w = MemWriter::from_vec(out);
+        if self.offset == 0 {
+            try!(w.write_char('Z'));
+        } else {
+            let seconds = self.offset / tick::SECOND;
+            let (sign, seconds) = if seconds < 0 {
+                ('-', -seconds)
+            } else {
+                ('+', seconds)
+            };
+            try!(w.write_char(sign));
+            try!(fmtutil::write_2digits(&mut w, (seconds / 3600) as uint));
+            try!(w.write_char(':'));
+            try!(fmtutil::write_2digits(&mut w, (seconds / 60 % 60) as uint));
+            if seconds % 60 != 0 {
+                try!(w.write_char(':'));
+                try!(fmtutil::write_2digits(&mut w, (seconds % 60) as uint));
+            }
+        }
+        Ok(w.unwrap())
+    }
+}
+
+impl Show for OffsetDateTime {
+    /// Uses the ISO 8601 extended format with the local time and the
+    /// offset, such as "2014-06-10T13:12:13+02:00".
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        let datavec = match self.to_utf8_io() {
+            Ok(x) => x,
+            Err(_) => return Err(WriteError)
+        };
+        fmtutil::write_field(f, datavec.as_slice())
+    }
+}
+
+impl FromStr for OffsetDateTime {
+    /// Parses an ISO 8601 date and time, which must have "Z" or a UTC
+    /// offset.
+    fn from_str(s: &str) -> Option<OffsetDateTime> {
+        OffsetDateTime::parse_with(s, &ParseOptions::strict())
+    }
+}
+
+#[test]
+fn format() {
+    fn test(s: &str, expected: &str) {
+        let t: OffsetDateTime = match from_str(s) {
+            Some(t) => t, None => fail!("could not parse: '{}'", s)
+        };
+        let out = format!("{}", t);
+        if out.as_slice() != expected {
+            fail!("input: '{}', expected: '{}', output: '{}'",
+                  s, expected, out);
+        }
+    }
+    test("2014-06-10T13:12:13+02:00", "2014-06-10T13:12:13+02:00");
+    test("2014-06-10T01:12:13-05:30", "2014-06-10T01:12:13-05:30");
+    test("2014-06-10T00:00:00Z", "2014-06-10T00:00:00Z");
+    test("2014-06-10T00:00:00+00:00", "2014-06-10T00:00:00Z");
+    test("2014-06-10T23:30:00-01:00", "2014-06-10T23:30:00-01:00");
+    test("1900-01-01T00:19:32+00:19:32", "1900-01-01T00:19:32+00:19:32");
+    test("1900-01-01T00:00:00-00:00:45", "1900-01-01T00:00:00-00:00:45");
+    test("2014-06-10T00:00:00+02:00:00", "2014-06-10T00:00:00+02:00");
+}
+
+#[test]
+fn fields() {
+    let t: OffsetDateTime = from_str("2014-06-10T01:12:13.5-05:30").unwrap();
+    assert_eq!(t.offset(), -tick::HOUR * 11 / 2);
+    assert_eq!(t.instant(), from_str("2014-06-10T06:42:13.5Z").unwrap());
+    assert_eq!(t.to_datetime(),
+               DateTime::new(2014, 6, 10, 1, 12, 13, tick::SECOND / 2));
+    let u = t.with_offset(tick::HOUR * 2).unwrap();
+    assert_eq!(format!("{}", u).as_slice(), "2014-06-10T08:42:13+02:00");
+    assert!(t != u);
+    assert_eq!(t.instant(), u.instant());
+    assert_eq!(format!("{}", t.with_offset(-tick::SECOND * 45).unwrap())
+               .as_slice(), "2014-06-10T06:42:12-00:00:45");
+    assert!(t.with_offset(tick::MILLISECOND).is_none());
+    assert!(t.with_offset(tick::DAY).is_none());
+    let none: Option<OffsetDateTime> = from_str("2014-06-10T01:12:13");
+    assert!(none.is_none());
+    let none: Option<OffsetDateTime> = from_str("2014-06-10");
+    assert!(none.is_none());
+}
@@ -0,0 +1,164 @@
+//! Options for parsing dates, times, and durations.
+
+use tick;
+use parseutil;
+
+/// The characters accepted as a decimal mark in fractional values.  ISO
+/// 8601 permits either a comma or a full stop.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum DecimalMark {
+    /// Accept either a full stop or a comma.
+    AnyDecimalMark,
+    /// Only accept a full stop.
+    DecimalPoint,
+    /// Only accept a comma.
+    DecimalComma
+}
+
+/// Conventions for numbering years before 1 AD.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum YearNumbering {
+    /// Astronomical year numbering, used by ISO 8601, where the year
+    /// before 1 is 0, and year -1 is 2 BC.
+    AstronomicalYear,
+    /// Historical year numbering, where the year before 1 is -1, which is
+    /// 1 BC, and there is no year 0.
+    NoYearZero,
+    /// Years of the Thai Buddhist era, which are BUDDHIST_ERA_OFFSET more
+    /// than astronomical years, so 2557 is 2014 AD.
+    BuddhistEraYear
+}
+
+/// The difference between a year of the Buddhist era, as used in Thailand,
+/// and the same year AD.
+pub static BUDDHIST_ERA_OFFSET: int = 543;
+
+/// Convert a year from astronomical numbering to historical numbering,
+/// where there is no year 0.
+pub fn astronomical_to_historical(year: int) -> int {
+    if year < 1 { year - 1 } else { year }
+}
+
+/// Convert a year from historical numbering, where there is no year 0, to
+/// astronomical numbering.  Returns None for year 0.
+pub fn historical_to_astronomical(year: int) -> Option<int> {
+    if year == 0 {
+        None
+    } else if year < 0 {
+        Some(year + 1)
+    } else {
+        Some(year)
+    }
+}
+
+/// A table of time zone abbreviations and the UTC offsets, in ticks, which
+/// they stand for.
+pub type ZoneAbbreviations = &'static [(&'static str, i64)];
+
+/// The abbreviations accepted by default: UTC and the North American zones
+/// defined by RFC 5322.  Many abbreviations are ambiguous, such as "IST",
+/// which are left out, except that the RFC 5322 meanings are deliberately
+/// preferred, so "CST" is US Central Standard Time and not China Standard
+/// Time.  Use a custom table to choose other meanings.
+pub static DEFAULT_ZONE_ABBREVIATIONS: ZoneAbbreviations = &[
+    ("UTC", 0),
+    ("UT", 0),
+    ("GMT", 0),
+    ("EST", -5 * tick::HOUR),
+    ("EDT", -4 * tick::HOUR),
+    ("CST", -6 * tick::HOUR),
+    ("CDT", -5 * tick::HOUR),
+    ("MST", -7 * tick::HOUR),
+    ("MDT", -6 * tick::HOUR),
+    ("PST", -8 * tick::HOUR),
+    ("PDT", -7 * tick::HOUR)
+];
+
+/// Options controlling how text is parsed.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct ParseOptions {
+    /// Accept common deviations from ISO 8601 found in real-world
+    /// timestamps, such as a space separating the date and time, and in
+    /// durations, such as components out of order.
+    pub lenient: bool,
+    /// The characters accepted as a decimal mark.
+    pub decimal_mark: DecimalMark,
+    /// The interpretation of negative years.
+    pub year_numbering: YearNumbering,
+    /// The time zone abbreviations accepted in place of a UTC offset when
+    /// parsing leniently, such as "EST" in "2014-06-10 11:12:13 EST".
+    pub zone_abbreviations: ZoneAbbreviations,
+    /// Accept a minus sign on any component of a duration, as ISO 8601-2
+    /// allows, so "PT1M-30S" is 30 seconds.  Otherwise only the first
+    /// component may have a sign, which applies to the whole duration.
+    pub signed_components: bool
+}
+
+impl ParseOptions {
+    /// Options which only accept ISO 8601 representations.
+    pub fn strict() -> ParseOptions {
+        ParseOptions {
+            lenient: false,
+            decimal_mark: AnyDecimalMark,
+            year_numbering: AstronomicalYear,
+            zone_abbreviations: DEFAULT_ZONE_ABBREVIATIONS,
+            signed_components: false
+        }
+    }
+
+    /// Options which accept common deviations from ISO 8601.
+    pub fn lenient() -> ParseOptions {
+        ParseOptions {
+            lenient: true,
+            decimal_mark: AnyDecimalMark,
+            year_numbering: AstronomicalYear,
+            zone_abbreviations: DEFAULT_ZONE_ABBREVIATIONS,
+            signed_components: false
+        }
+    }
+
+    /// Look up a time zone abbreviation, ignoring case.  Returns the UTC
+    /// offset in ticks, or None if the abbreviation is not in the table.
+    pub fn zone_abbreviation_offset(&self, name: &[u8]) -> Option<i64> {
+        self.zone_abbreviations.iter()
+            .find(|&&(abbr, _)| parseutil::eq_ignore_case(abbr.as_bytes(),
+                                                          name))
+            .map(|&(_, offset)| offset)
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions::strict()
+    }
+}
+
+#[test]
+fn year_numbering() {
+    fn test(astronomical: int, historical: int) {
+        assert_eq!(astronomical_to_historical(astronomical), historical);
+        assert_eq!(historical_to_astronomical(historical), Some(astronomical));
+    }
+
+    test(2000, 2000);
+    test(1, 1);
+    test(0, -1);
+    test(-1, -2);
+    test(-43, -44);
+    assert_eq!(historical_to_astronomical(0), None);
+}
+
+#[test]
+fn zone_abbreviations() {
+    static TABLE: ZoneAbbreviations = &[("CET", tick::HOUR)];
+    let opts = ParseOptions::lenient();
+    assert_eq!(opts.zone_abbreviation_offset("EST".as_bytes()),
+               Some(-5 * tick::HOUR));
+    assert_eq!(opts.zone_abbreviation_offset("gmt".as_bytes()), Some(0));
+    assert_eq!(opts.zone_abbreviation_offset("CET".as_bytes()), None);
+    let custom = ParseOptions { zone_abbreviations: TABLE,
+                                ..ParseOptions::lenient() };
+    assert_eq!(custom.zone_abbreviation_offset("CET".as_bytes()),
+               Some(tick::HOUR));
+    assert_eq!(custom.zone_abbreviation_offset("EST".as_bytes()), None);
+}
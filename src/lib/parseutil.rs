@@ -0,0 +1,284 @@
+use tick;
+use calendar_iso8601;
+use format_iso8601::{Date, YearMonthDay};
+use parse::{ParseOptions, DecimalMark, AnyDecimalMark, DecimalPoint,
+            DecimalComma};
+
+/// Read an unsigned integer with exactly the given number of digits from
+/// the beginning of a string.  Returns the integer and the remainder of
+/// the string.
+pub fn read_digits<'a>(s: &'a [u8], width: uint) -> Option<(int, &'a [u8])> {
+    match read_fixed_digits(s, width) {
+        Some((_, rem)) if starts_with_digit(rem) => None,
+        r => r
+    }
+}
+
+/// Read exactly the given number of digits, like read_digits(), but allow
+/// another digit to follow, as in "20140610".
+pub fn read_fixed_digits<'a>(s: &'a [u8], width: uint)
+                             -> Option<(int, &'a [u8])> {
+    if s.len() < width {
+        return None;
+    }
+    let mut value: int = 0;
+    for &c in s.slice_to(width).iter() {
+        if c < '0' as u8 || c > '9' as u8 {
+            return None;
+        }
+        value = value * 10 + (c - '0' as u8) as int;
+    }
+    Some((value, s.slice_from(width)))
+}
+
+/// Test whether a string starts with a digit.
+fn starts_with_digit(s: &[u8]) -> bool {
+    !s.is_empty() && s[0] >= '0' as u8 && s[0] <= '9' as u8
+}
+
+/// Test whether a string starts with the given byte.
+pub fn starts_with(s: &[u8], c: char) -> bool {
+    !s.is_empty() && s[0] == c as u8
+}
+
+/// Compare two ASCII strings, ignoring case.
+pub fn eq_ignore_case(a: &[u8], b: &[u8]) -> bool {
+    fn lower(c: u8) -> u8 {
+        if c >= 'A' as u8 && c <= 'Z' as u8 { c + 32 } else { c }
+    }
+    a.len() == b.len() &&
+        a.iter().zip(b.iter()).all(|(&x, &y)| lower(x) == lower(y))
+}
+
+/// Read a time zone abbreviation, such as "EST", made of ASCII letters.
+/// Returns its UTC offset in ticks from the options and the remainder of
+/// the string.
+pub fn read_zone_abbreviation<'a>(s: &'a [u8], opts: &ParseOptions)
+                                  -> Option<(i64, &'a [u8])> {
+    let len = s.iter()
+        .take_while(|&&c| (c >= 'A' as u8 && c <= 'Z' as u8) ||
+                    (c >= 'a' as u8 && c <= 'z' as u8))
+        .count();
+    if len == 0 {
+        return None;
+    }
+    opts.zone_abbreviation_offset(s.slice_to(len))
+        .map(|offset| (offset, s.slice_from(len)))
+}
+
+/// English abbreviations for the names of the months.
+pub static MONTH_ABBREVIATIONS: [&'static str, ..12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun",
+    "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"
+];
+
+/// The English names of the months.
+pub static MONTH_NAMES: [&'static str, ..12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December"
+];
+
+/// English abbreviations for the names of the days of the week, starting
+/// with Monday.
+pub static WEEKDAY_ABBREVIATIONS: [&'static str, ..7] = [
+    "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"
+];
+
+/// Read an English month abbreviation, such as "Oct", from the beginning of
+/// a string.  The comparison is case-insensitive.  Returns the month, from
+/// 1 to 12, and the remainder of the string.
+pub fn read_month_abbreviation<'a>(s: &'a [u8]) -> Option<(int, &'a [u8])> {
+    if s.len() < 3 {
+        return None;
+    }
+    let prefix = s.slice_to(3);
+    for (i, name) in MONTH_ABBREVIATIONS.iter().enumerate() {
+        if eq_ignore_case(prefix, name.as_bytes()) {
+            return Some((i as int + 1, s.slice_from(3)));
+        }
+    }
+    None
+}
+
+/// Read a calendar date of the form "YYYY-MM-DD", which must exist.
+/// Returns the date and the remainder of the string.
+pub fn read_calendar_date<'a>(s: &'a [u8]) -> Option<(Date, &'a [u8])> {
+    let (year, rem) = match read_digits(s, 4) {
+        Some(x) => x, None => return None
+    };
+    if !starts_with(rem, '-') {
+        return None;
+    }
+    let (month, rem) = match read_digits(rem.slice_from(1), 2) {
+        Some(x) => x, None => return None
+    };
+    if month < 1 || month > 12 || !starts_with(rem, '-') {
+        return None;
+    }
+    let (day, rem) = match read_digits(rem.slice_from(1), 2) {
+        Some(x) => x, None => return None
+    };
+    if day < 1 || day > calendar_iso8601::days_in_month(year, month) {
+        return None;
+    }
+    Some((YearMonthDay(year, month, day), rem))
+}
+
+/// Read a time of day of the form "HH:MM:SS", without leap seconds.
+/// Returns the number of ticks since midnight and the remainder of the
+/// string.
+pub fn read_hms<'a>(s: &'a [u8]) -> Option<(i64, &'a [u8])> {
+    read_time_of_day(s, true)
+}
+
+/// Read a time of day of the form "HH:MM" or "HH:MM:SS", like read_hms().
+pub fn read_hm_or_hms<'a>(s: &'a [u8]) -> Option<(i64, &'a [u8])> {
+    read_time_of_day(s, false)
+}
+
+/// Read a time of day, with the seconds only if they are required or are
+/// present.
+fn read_time_of_day<'a>(s: &'a [u8], need_seconds: bool)
+                        -> Option<(i64, &'a [u8])> {
+    let (hour, rem) = match read_digits(s, 2) {
+        Some(x) => x, None => return None
+    };
+    if hour > 23 || !starts_with(rem, ':') {
+        return None;
+    }
+    let (minute, rem) = match read_digits(rem.slice_from(1), 2) {
+        Some(x) => x, None => return None
+    };
+    if minute > 59 {
+        return None;
+    }
+    let (second, rem) = if starts_with(rem, ':') {
+        match read_digits(rem.slice_from(1), 2) {
+            Some((second, rem)) if second <= 59 => (second, rem),
+            _ => return None
+        }
+    } else if need_seconds {
+        return None;
+    } else {
+        (0, rem)
+    };
+    Some((hour as i64 * tick::HOUR + minute as i64 * tick::MINUTE +
+          second as i64 * tick::SECOND, rem))
+}
+
+/// Test whether a string starts with an acceptable decimal mark.
+pub fn starts_with_fraction(s: &[u8], mark: DecimalMark) -> bool {
+    if s.is_empty() {
+        return false;
+    }
+    match mark {
+        AnyDecimalMark => s[0] == '.' as u8 || s[0] == ',' as u8,
+        DecimalPoint => s[0] == '.' as u8,
+        DecimalComma => s[0] == ',' as u8
+    }
+}
+
+/// Read a decimal mark followed by the fractional part of a component
+/// whose length is the given number of ticks, and convert it to ticks.
+/// The result is rounded to the nearest tick, with ties going to even.
+/// Returns the ticks and the remainder of the string.
+pub fn read_fraction<'a>(s: &'a [u8], unit: i64, mark: DecimalMark)
+                         -> Option<(i64, &'a [u8])> {
+    if !starts_with_fraction(s, mark) {
+        return None;
+    }
+    let digits = s.slice_from(1);
+    let len = digits.iter()
+        .take_while(|&&c| c >= '0' as u8 && c <= '9' as u8)
+        .count();
+    if len == 0 {
+        return None;
+    }
+    // Evaluate 0.ddd * unit from the least significant digit up, keeping
+    // track of the last remainder and whether any earlier remainder was
+    // nonzero, so the rounding is exact no matter how many digits appear.
+    let mut ticks: i64 = 0;
+    let mut last: i64 = 0;
+    let mut sticky = false;
+    for &c in digits.slice_to(len).iter().rev() {
+        sticky = sticky || last != 0;
+        let n = (c - '0' as u8) as i64 * unit + ticks;
+        ticks = n / 10;
+        last = n % 10;
+    }
+    if last > 5 || (last == 5 && (sticky || (ticks & 1) != 0)) {
+        ticks += 1;
+    }
+    Some((ticks, digits.slice_from(len)))
+}
+
+#[test]
+fn fraction() {
+    fn test(s: &str, unit: i64, mark: DecimalMark, expected: Option<i64>) {
+        let out = read_fraction(s.as_bytes(), unit, mark).map(|(n, _)| n);
+        if out != expected {
+            fail!("input: '{}', expected: {}, output: {}",
+                  s, expected, out);
+        }
+    }
+
+    test(".5", 10, AnyDecimalMark, Some(5));
+    test(",5", 10, AnyDecimalMark, Some(5));
+    test(".5", 10, DecimalPoint, Some(5));
+    test(",5", 10, DecimalPoint, None);
+    test(".5", 10, DecimalComma, None);
+    test(",5", 10, DecimalComma, Some(5));
+    test(".", 10, AnyDecimalMark, None);
+    test("5", 10, AnyDecimalMark, None);
+    test(".05", 10, AnyDecimalMark, Some(0));
+    test(".15", 10, AnyDecimalMark, Some(2));
+    test(".051", 10, AnyDecimalMark, Some(1));
+    test(".25", 60, AnyDecimalMark, Some(15));
+}
+
+#[test]
+fn digits() {
+    fn test(s: &str, width: uint, expected: Option<(int, &str)>) {
+        let out = read_digits(s.as_bytes(), width);
+        assert_eq!(out, expected.map(|(n, rem)| (n, rem.as_bytes())));
+    }
+    test("2014-", 4, Some((2014, "-")));
+    test("07", 2, Some((7, "")));
+    test("201", 4, None);
+    test("20145", 4, None);
+    test("2a", 2, None);
+    let fixed = |s: &str, width: uint| read_fixed_digits(s.as_bytes(), width)
+        .map(|(n, rem)| (n, rem.len()));
+    assert_eq!(fixed("20140610", 4), Some((2014, 4)));
+    assert_eq!(fixed("201", 4), None);
+    assert_eq!(fixed("2a", 2), None);
+}
+
+#[test]
+fn zone_abbreviation() {
+    let opts = ParseOptions::strict();
+    let read = |s: &str| read_zone_abbreviation(s.as_bytes(), &opts)
+        .map(|(n, rem)| (n, rem.len()));
+    assert_eq!(read("UTC"), Some((0, 0)));
+    assert_eq!(read("est)"), Some((-5 * tick::HOUR, 1)));
+    assert_eq!(read("ESTX"), None);
+    assert_eq!(read("E1"), None);
+    assert_eq!(read(""), None);
+}
+
+#[test]
+fn time_of_day() {
+    fn ticks(h: i64, m: i64, s: i64) -> i64 {
+        h * tick::HOUR + m * tick::MINUTE + s * tick::SECOND
+    }
+    let hms = |s: &str| read_hms(s.as_bytes()).map(|(n, rem)| (n, rem.len()));
+    let hm = |s: &str| read_hm_or_hms(s.as_bytes())
+        .map(|(n, rem)| (n, rem.len()));
+    assert_eq!(hms("11:12:13Z"), Some((ticks(11, 12, 13), 1)));
+    assert_eq!(hms("11:12"), None);
+    assert_eq!(hms("24:00:00"), None);
+    assert_eq!(hm("11:12:13"), Some((ticks(11, 12, 13), 0)));
+    assert_eq!(hm("11:12 "), Some((ticks(11, 12, 0), 1)));
+    assert_eq!(hm("11:12:60"), None);
+    assert_eq!(hm("11:60"), None);
+}
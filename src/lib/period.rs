@@ -0,0 +1,264 @@
+//! Calendar periods, and relative adjustments such as "+3d" or "-1mo2d"
+//! which combine a period with a duration.
+
+use std::from_str::FromStr;
+use std::num;
+use std::str;
+use calendar_iso8601;
+use datetime::{DateTime, civil_to_instant_utc};
+use div_mod::div_mod;
+use duration::Duration;
+use format_iso8601::{Date, YearMonthDay};
+use instant::Instant;
+use parseutil::starts_with;
+use tick;
+
+/// An amount of calendar time, in years, months, and days.  Unlike a
+/// duration, its length depends on the date it is added to.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct Period {
+    /// The number of years.
+    pub years: int,
+    /// The number of months.
+    pub months: int,
+    /// The number of days.
+    pub days: int
+}
+
+impl Period {
+    /// Create a period from its fields.
+    pub fn new(years: int, months: int, days: int) -> Period {
+        Period { years: years, months: months, days: days }
+    }
+
+    /// Test whether the period is empty.
+    pub fn is_zero(&self) -> bool {
+        self.years == 0 && self.months == 0 && self.days == 0
+    }
+}
+
+/// Where the anniversary of February 29 falls in a common year, for
+/// birthdays, renewals, and other yearly dates.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum AnniversaryPolicy {
+    /// Use February 28, so the anniversary stays in February.
+    Feb28,
+    /// Use March 1, the day after February 28.
+    Mar1,
+    /// Skip the year, so the anniversary only occurs in leap years.
+    Skip
+}
+
+impl AnniversaryPolicy {
+    /// Get the anniversary of a month and day in a year.  Returns None if
+    /// the day is February 29, the year is a common year, and the policy
+    /// is `Skip`.  Other days are not checked.
+    pub fn resolve(&self, year: int, month: int, day: int) -> Option<Date> {
+        if month != 2 || day != 29 || calendar_iso8601::is_leap_year(year) {
+            return Some(YearMonthDay(year, month, day));
+        }
+        match *self {
+            Feb28 => Some(YearMonthDay(year, 2, 28)),
+            Mar1 => Some(YearMonthDay(year, 3, 1)),
+            Skip => None
+        }
+    }
+}
+
+/// A change to a date or an instant, made by adding a period and then a
+/// duration.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct Adjustment {
+    /// The calendar part, in years, months, and days.
+    pub period: Period,
+    /// The fixed part, in hours, minutes, and seconds.
+    pub duration: Duration
+}
+
+/// Add a number of units to a total.  Returns None on overflow.
+fn add_units(total: i64, n: i64, unit: i64) -> Option<i64> {
+    n.checked_mul(&unit).and_then(|x| total.checked_add(&x))
+}
+
+impl Adjustment {
+    /// Parse a compact relative expression, such as "+3d", "-2w", or
+    /// "+1mo2d".  An optional sign applies to the whole expression, and is
+    /// followed by one or more numbers, each with a unit: "y" for years,
+    /// "mo" for months, "w" for weeks, "d" for days, "h" for hours, "m" or
+    /// "min" for minutes, and "s" for seconds.  Weeks are counted as seven
+    /// calendar days.  Units are lowercase and may appear in any order.
+    pub fn parse(s: &str) -> Option<Adjustment> {
+        let s = s.as_bytes();
+        let (sign, mut rem) = if starts_with(s, '-') {
+            (-1, s.slice_from(1))
+        } else if starts_with(s, '+') {
+            (1, s.slice_from(1))
+        } else {
+            (1, s)
+        };
+        if rem.is_empty() {
+            return None;
+        }
+        let (mut years, mut months, mut days, mut ticks) =
+            (0i64, 0i64, 0i64, 0i64);
+        while !rem.is_empty() {
+            let digits = rem.iter()
+                .take_while(|&&c| c >= '0' as u8 && c <= '9' as u8)
+                .count();
+            if digits == 0 {
+                return None;
+            }
+            let mut n: i64 = 0;
+            for &c in rem.slice_to(digits).iter() {
+                n = match add_units((c - '0' as u8) as i64, n, 10) {
+                    Some(n) => n, None => return None
+                };
+            }
+            let n = n * sign;
+            rem = rem.slice_from(digits);
+            let letters = rem.iter()
+                .take_while(|&&c| c >= 'a' as u8 && c <= 'z' as u8)
+                .count();
+            let unit = str::from_utf8(rem.slice_to(letters)).unwrap();
+            rem = rem.slice_from(letters);
+            let (total, scale) = match unit {
+                "y" => (&mut years, 1),
+                "mo" => (&mut months, 1),
+                "w" => (&mut days, 7),
+                "d" => (&mut days, 1),
+                "h" => (&mut ticks, tick::HOUR),
+                "m" | "min" => (&mut ticks, tick::MINUTE),
+                "s" => (&mut ticks, tick::SECOND),
+                _ => return None
+            };
+            *total = match add_units(*total, n, scale) {
+                Some(n) => n, None => return None
+            };
+        }
+        match (num::cast(years), num::cast(months), num::cast(days)) {
+            (Some(y), Some(m), Some(d)) => Some(Adjustment {
+                period: Period::new(y, m, d),
+                duration: Duration { ticks: ticks }
+            }),
+            _ => None
+        }
+    }
+
+    /// Apply the adjustment to a complete date, as by `Date::add_period`.
+    /// The duration must be a whole number of days, which are added after
+    /// the period.  Returns None if the duration has a fraction of a day,
+    /// for reduced precision dates, and if the result is out of range.
+    pub fn apply_to_date(&self, date: &Date) -> Option<Date> {
+        let (n, rem) = div_mod(self.duration.ticks, tick::DAY);
+        if rem != 0 {
+            return None;
+        }
+        let days = match num::cast::<i64, int>(n)
+            .and_then(|n| n.checked_add(&self.period.days)) {
+            Some(n) => n, None => return None
+        };
+        date.add_period(&Period { days: days, ..self.period })
+    }
+
+    /// Apply the adjustment to an instant.  The period is added to the
+    /// date in UTC, keeping the time of day, and then the duration is
+    /// added.  Returns None if the result is out of range.
+    pub fn apply_to_instant(&self, t: Instant) -> Option<Instant> {
+        let dt = t.to_datetime();
+        let date = YearMonthDay(dt.year, dt.month, dt.day);
+        let (y, m, d) = match date.add_period(&self.period) {
+            Some(YearMonthDay(y, m, d)) => (y, m, d),
+            _ => return None
+        };
+        civil_to_instant_utc(&DateTime { year: y, month: m, day: d, ..dt })
+            .and_then(|t| t.ticks().checked_add(&self.duration.ticks))
+            .map(|n| Instant::from_ticks(n))
+    }
+}
+
+impl FromStr for Adjustment {
+    fn from_str(s: &str) -> Option<Adjustment> {
+        Adjustment::parse(s)
+    }
+}
+
+#[test]
+fn test_parse() {
+    fn test(s: &str, expected: Option<(int, int, int, i64)>) {
+        let out = Adjustment::parse(s);
+        let expected = expected.map(|(y, m, d, ticks)| Adjustment {
+            period: Period::new(y, m, d),
+            duration: Duration { ticks: ticks }
+        });
+        if out != expected {
+            fail!("input: {}, expected: {}, output: {}", s, expected, out);
+        }
+    }
+    test("+3d", Some((0, 0, 3, 0)));
+    test("-2w", Some((0, 0, -14, 0)));
+    test("+1mo2d", Some((0, 1, 2, 0)));
+    test("1y", Some((1, 0, 0, 0)));
+    test("-1y6mo", Some((-1, -6, 0, 0)));
+    test("+1w2d", Some((0, 0, 9, 0)));
+    test("+1h30m", Some((0, 0, 0, 90 * tick::MINUTE)));
+    test("-90min15s",
+         Some((0, 0, 0, -90 * tick::MINUTE - 15 * tick::SECOND)));
+    test("+1d12h", Some((0, 0, 1, 12 * tick::HOUR)));
+    test("", None);
+    test("+", None);
+    test("3", None);
+    test("d", None);
+    test("+3x", None);
+    test("+3D", None);
+    test("+3d-1h", None);
+    test("+ 3d", None);
+    test("+99999999999999999999d", None);
+    test("+9999999999999999h", None);
+}
+
+#[test]
+fn test_anniversary() {
+    fn test(policy: AnniversaryPolicy, year: int, month: int, day: int,
+            expected: Option<Date>) {
+        assert_eq!(policy.resolve(year, month, day), expected);
+    }
+    test(Feb28, 2013, 2, 29, Some(YearMonthDay(2013, 2, 28)));
+    test(Mar1, 2013, 2, 29, Some(YearMonthDay(2013, 3, 1)));
+    test(Skip, 2013, 2, 29, None);
+    test(Skip, 2016, 2, 29, Some(YearMonthDay(2016, 2, 29)));
+    test(Mar1, 2000, 2, 29, Some(YearMonthDay(2000, 2, 29)));
+    test(Mar1, 1900, 2, 29, Some(YearMonthDay(1900, 3, 1)));
+    test(Skip, 2013, 2, 28, Some(YearMonthDay(2013, 2, 28)));
+    test(Skip, 2013, 3, 1, Some(YearMonthDay(2013, 3, 1)));
+}
+
+#[test]
+fn test_apply() {
+    use format_iso8601::parse_instant;
+    fn date(s: &str, date: Date, expected: Option<Date>) {
+        let out = Adjustment::parse(s).unwrap().apply_to_date(&date);
+        if out != expected {
+            fail!("input: {} {}, expected: {}, output: {}",
+                  s, date, expected, out);
+        }
+    }
+    fn instant(s: &str, t: &str, expected: Option<&str>) {
+        let t = parse_instant(t).unwrap();
+        let out = Adjustment::parse(s).unwrap().apply_to_instant(t)
+            .map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != expected {
+            fail!("input: {} {}, expected: {}, output: {}",
+                  s, t, expected, out);
+        }
+    }
+    date("+3d", YearMonthDay(2014, 6, 10), Some(YearMonthDay(2014, 6, 13)));
+    date("-2w", YearMonthDay(2014, 6, 10), Some(YearMonthDay(2014, 5, 27)));
+    date("+1mo2d", YearMonthDay(2014, 1, 31), Some(YearMonthDay(2014, 3, 2)));
+    date("+24h", YearMonthDay(2014, 6, 10), Some(YearMonthDay(2014, 6, 11)));
+    date("+1h", YearMonthDay(2014, 6, 10), None);
+    date("+1d", YearMonthDay(2014, 2, 30), None);
+    instant("+1mo", "2014-01-31T11:12:13Z", Some("2014-02-28T11:12:13Z"));
+    instant("-1y", "2012-02-29T00:00:00Z", Some("2011-02-28T00:00:00Z"));
+    instant("+1d12h", "2014-06-10T18:00:00Z", Some("2014-06-12T06:00:00Z"));
+    instant("+30000y", "2014-06-10T18:00:00Z", None);
+}
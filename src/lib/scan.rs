@@ -0,0 +1,212 @@
+//! Finding timestamps in free text, such as log lines and messages.
+//!
+//! The scanner recognizes ISO 8601 and RFC 3339 timestamps, such as
+//! "2014-06-10T11:12:13Z" or "2014-06-10 11:12:13 EST", complete calendar
+//! dates, such as "2014-06-10", and RFC 2822 dates as used in email and
+//! HTTP headers, such as "Tue, 10 Jun 2014 11:12:13 GMT".  A timestamp is
+//! only recognized at the start of a word and must not be followed by a
+//! letter or digit, so identifiers and long numbers are skipped.
+
+use calendar_iso8601;
+use format_iso8601;
+use format_iso8601::{Parsed, YearMonthDay};
+use parse::ParseOptions;
+use parseutil;
+use parseutil::starts_with;
+use tick;
+
+/// An iterator over the timestamps in a string.  Each item contains the
+/// start and end byte offsets of a timestamp and its fields, which may be
+/// missing a time or a UTC offset.
+#[deriving(Clone)]
+pub struct Scanner<'a> {
+    text: &'a str,
+    pos: uint,
+    opts: ParseOptions
+}
+
+/// Find the timestamps in a string, accepting the deviations from ISO
+/// 8601 which lenient parsing accepts.
+pub fn scan<'a>(s: &'a str) -> Scanner<'a> {
+    scan_with(s, ParseOptions::lenient())
+}
+
+/// Find the timestamps in a string using the given options.
+pub fn scan_with<'a>(s: &'a str, opts: ParseOptions) -> Scanner<'a> {
+    Scanner { text: s, pos: 0, opts: opts }
+}
+
+/// Test whether a byte may be part of a word.  Bytes outside ASCII are
+/// treated as letters.
+fn is_word_byte(c: u8) -> bool {
+    c >= 0x80 || (c as char).is_alphanumeric()
+}
+
+/// Test whether a match of the given length ends at a word boundary.
+fn at_boundary(s: &[u8], len: uint) -> bool {
+    len == s.len() || !is_word_byte(s[len])
+}
+
+fn count_spaces(s: &[u8]) -> uint {
+    s.iter().take_while(|&&c| c == ' ' as u8).count()
+}
+
+/// Skip one or more spaces.
+fn read_spaces<'a>(s: &'a [u8]) -> Option<&'a [u8]> {
+    match count_spaces(s) {
+        0 => None,
+        n => Some(s.slice_from(n))
+    }
+}
+
+/// Read a weekday abbreviation followed by a comma.
+fn read_weekday<'a>(s: &'a [u8]) -> Option<&'a [u8]> {
+    if s.len() < 4 || s[3] != ',' as u8 {
+        return None;
+    }
+    let prefix = s.slice_to(3);
+    if parseutil::WEEKDAY_ABBREVIATIONS.iter()
+        .any(|name| parseutil::eq_ignore_case(prefix, name.as_bytes())) {
+        let rem = s.slice_from(4);
+        Some(rem.slice_from(count_spaces(rem)))
+    } else {
+        None
+    }
+}
+
+/// Read a numeric offset, such as "+0200", or a zone abbreviation.
+fn read_zone<'a>(s: &'a [u8], opts: &ParseOptions)
+                 -> Option<(i64, &'a [u8])> {
+    if starts_with(s, '+') || starts_with(s, '-') {
+        let (n, rem) = match parseutil::read_digits(s.slice_from(1), 4) {
+            Some(x) => x, None => return None
+        };
+        if n % 100 > 59 {
+            return None;
+        }
+        let offset = (n / 100) as i64 * tick::HOUR +
+            (n % 100) as i64 * tick::MINUTE;
+        Some((if starts_with(s, '-') { -offset } else { offset }, rem))
+    } else {
+        parseutil::read_zone_abbreviation(s, opts)
+    }
+}
+
+/// Read an RFC 2822 date, such as "Tue, 10 Jun 2014 11:12:13 +0000".  The
+/// weekday, seconds, and zone are optional.
+fn read_rfc2822<'a>(s: &'a [u8], opts: &ParseOptions)
+                    -> Option<(Parsed, &'a [u8])> {
+    let s = read_weekday(s).unwrap_or(s);
+    let (day, rem) = match parseutil::read_digits(s, 2)
+        .or_else(|| parseutil::read_digits(s, 1)) {
+        Some(x) => x, None => return None
+    };
+    let (month, rem) = match read_spaces(rem)
+        .and_then(parseutil::read_month_abbreviation) {
+        Some(x) => x, None => return None
+    };
+    let (year, rem) = match read_spaces(rem)
+        .and_then(|r| parseutil::read_digits(r, 4)) {
+        Some(x) => x, None => return None
+    };
+    if day < 1 || day > calendar_iso8601::days_in_month(year, month) {
+        return None;
+    }
+    let (time, rem) = match read_spaces(rem)
+        .and_then(parseutil::read_hm_or_hms) {
+        Some(x) => x, None => return None
+    };
+    let (offset, rem) = match read_spaces(rem)
+        .and_then(|r| read_zone(r, opts)) {
+        Some((offset, rem)) => (Some(offset), rem),
+        None => (None, rem)
+    };
+    Some((Parsed {
+        date: Some(YearMonthDay(year, month, day)),
+        time: Some(time),
+        offset: offset
+    }, rem))
+}
+
+/// Test whether ISO 8601 fields are specific enough to be reported.  A
+/// date alone must be a complete calendar date with separators, since
+/// basic dates such as "20140610" cannot be told apart from numbers.
+fn is_timestamp(parsed: &Parsed, text: &str) -> bool {
+    match parsed.date {
+        Some(YearMonthDay(..)) =>
+            parsed.time.is_some() || text.contains_char('-'),
+        _ => false
+    }
+}
+
+/// Read a timestamp at the beginning of a string.  Returns its fields and
+/// length.
+fn read_timestamp(s: &str, opts: &ParseOptions) -> Option<(Parsed, uint)> {
+    let bytes = s.as_bytes();
+    match format_iso8601::parse_fields_prefix(s, opts) {
+        Some((parsed, len)) if is_timestamp(&parsed, s.slice_to(len)) &&
+            at_boundary(bytes, len) => return Some((parsed, len)),
+        _ => ()
+    }
+    match read_rfc2822(bytes, opts) {
+        Some((parsed, rem)) if at_boundary(bytes, s.len() - rem.len()) =>
+            Some((parsed, s.len() - rem.len())),
+        _ => None
+    }
+}
+
+impl<'a> Iterator<((uint, uint), Parsed)> for Scanner<'a> {
+    fn next(&mut self) -> Option<((uint, uint), Parsed)> {
+        let bytes = self.text.as_bytes();
+        while self.pos < bytes.len() {
+            let start = self.pos;
+            self.pos += 1;
+            let c = bytes[start];
+            if c >= 0x80 || !is_word_byte(c) ||
+                (start > 0 && is_word_byte(bytes[start - 1])) {
+                continue;
+            }
+            match read_timestamp(self.text.slice_from(start), &self.opts) {
+                Some((parsed, len)) => {
+                    self.pos = start + len;
+                    return Some(((start, start + len), parsed));
+                }
+                None => ()
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn test_scan() {
+    fn fields(y: int, m: int, d: int, time: Option<i64>,
+              offset: Option<i64>) -> Parsed {
+        Parsed {
+            date: Some(YearMonthDay(y, m, d)),
+            time: time,
+            offset: offset
+        }
+    }
+    fn test(s: &str, expected: &[((uint, uint), Parsed)]) {
+        let v: Vec<((uint, uint), Parsed)> = scan(s).collect();
+        assert!(v.as_slice() == expected);
+    }
+    let t = 11 * tick::HOUR + 12 * tick::MINUTE + 13 * tick::SECOND;
+    test("", []);
+    test("nothing to see here", []);
+    test("order 12345 shipped 2014", []);
+    test("id=20140610 x2014-06-10 2014-06-10x", []);
+    test("Error at 2014-06-10T11:12:13Z: disk full",
+         [((9, 29), fields(2014, 6, 10, Some(t), Some(0)))]);
+    test("Date: Tue, 10 Jun 2014 11:12:13 GMT\r\n",
+         [((6, 35), fields(2014, 6, 10, Some(t), Some(0)))]);
+    test("sent 10 Jun 2014 11:12 -0130 by mail",
+         [((5, 28), fields(2014, 6, 10,
+                           Some(11 * tick::HOUR + 12 * tick::MINUTE),
+                           Some(-90 * tick::MINUTE)))]);
+    test("backup 2014-06-10 and 2014-06-11 08:00:00",
+         [((7, 17), fields(2014, 6, 10, None, None)),
+          ((22, 41), fields(2014, 6, 11, Some(8 * tick::HOUR), None))]);
+    test("31 Feb 2014 11:12:13", []);
+}
@@ -0,0 +1,152 @@
+//! Finding the instants at which a local time of day occurs in a zone.
+//!
+//! These follow the usual conventions of cron: a local time which is
+//! skipped when the clocks are turned forward occurs at the same distance
+//! after the change, so 02:30 on a day when clocks move from 02:00 to 03:00
+//! occurs at 03:30, and a local time which occurs twice when the clocks are
+//! turned back only occurs the first time.
+
+use div_mod::div_mod;
+use tick;
+use calendar_iso8601;
+use instant::Instant;
+use format_iso8601::Weekday;
+use datetime::{DateTime, civil_to_instant_local};
+use time_of_day::TimeOfDay;
+use zone::Zone;
+
+/// Get the instant at which a local time of day occurs on a day, given as
+/// the number of days since the epoch on the local time scale, if it is
+/// after the given instant.  Returns None if it is not after the instant
+/// or is out of range.
+fn occurrence_on(day: i64, tod: TimeOfDay, zone: &Zone, after: Instant)
+                 -> Option<Instant> {
+    let (year, month, day) = calendar_iso8601::from_cjd(day as int);
+    let dt = DateTime::new(year, month, day, 0, 0, 0, tod.ticks());
+    match civil_to_instant_local(&dt, zone) {
+        Some(t) if t > after => Some(t),
+        _ => None
+    }
+}
+
+/// Get the local day containing an instant, as the number of days since
+/// the epoch.
+fn local_day(t: Instant, zone: &Zone) -> i64 {
+    let (day, rem) = div_mod(t.ticks(), tick::DAY);
+    let (offset_days, _) = div_mod(rem + zone.offset_at(t), tick::DAY);
+    day + offset_days
+}
+
+/// Get the first instant after the given instant at which a local time of
+/// day occurs in a zone, optionally only on one day of the week.
+fn next_on(after: Instant, weekday: Option<Weekday>, tod: TimeOfDay,
+           zone: &Zone) -> Option<Instant> {
+    // The time may have passed on the current local day, and the next day
+    // may not reach it if the clocks are turned back by nearly a day.
+    let day = local_day(after, zone);
+    let days = if weekday.is_some() { 9 } else { 3 };
+    range(day, day + days)
+        .filter(|&d| match weekday {
+            Some(w) => calendar_iso8601::weekday(d as int) == w as int,
+            None => true
+        })
+        .filter_map(|d| occurrence_on(d, tod, zone, after))
+        .next()
+}
+
+/// Get the first instant after the given instant at which a local time of
+/// day occurs in a zone.  Returns None if the result is out of range.
+pub fn next_occurrence(after: Instant, tod: TimeOfDay, zone: &Zone)
+                       -> Option<Instant> {
+    next_on(after, None, tod, zone)
+}
+
+/// Get the first instant after the given instant at which a local time of
+/// day occurs on a day of the week in a zone, such as Tuesday at 09:00.
+/// Returns None if the result is out of range.
+pub fn next_weekly(after: Instant, weekday: Weekday, tod: TimeOfDay,
+                   zone: &Zone) -> Option<Instant> {
+    next_on(after, Some(weekday), tod, zone)
+}
+
+/// An iterator over the instants at which a local time of day occurs on a
+/// day of the week in a zone.
+pub struct Weekly<'a> {
+    after: Instant,
+    weekday: Weekday,
+    tod: TimeOfDay,
+    zone: &'a Zone
+}
+
+impl<'a> Weekly<'a> {
+    /// Create an iterator over the occurrences after the given instant.
+    pub fn new(after: Instant, weekday: Weekday, tod: TimeOfDay,
+               zone: &'a Zone) -> Weekly<'a> {
+        Weekly { after: after, weekday: weekday, tod: tod, zone: zone }
+    }
+}
+
+impl<'a> Iterator<Instant> for Weekly<'a> {
+    fn next(&mut self) -> Option<Instant> {
+        let t = next_weekly(self.after, self.weekday, self.tod, self.zone);
+        match t {
+            Some(t) => self.after = t,
+            None => ()
+        }
+        t
+    }
+}
+
+#[test]
+fn occurrence() {
+    use std::num::Bounded;
+    use zone;
+    let z = zone::test_zone();
+    fn test(z: &Zone, after: &str, tod: &str, expected: &str) {
+        let after: Instant = from_str(after).unwrap();
+        let tod: TimeOfDay = from_str(tod).unwrap();
+        let out = next_occurrence(after, tod, z).map(|t| format!("{}", t));
+        if out.as_ref().map(|s| s.as_slice()) != Some(expected) {
+            fail!("after: {}, time: {}, expected: {}, output: {}",
+                  after, tod, expected, out);
+        }
+    }
+    // 12:00 CET.
+    test(&z, "2014-01-10T10:00:00Z", "12:00", "2014-01-10T11:00:00Z");
+    test(&z, "2014-01-10T11:00:00Z", "12:00", "2014-01-11T11:00:00Z");
+    // Late in the UTC day, but already the next local day.
+    test(&z, "2014-01-10T23:30:00Z", "00:15", "2014-01-11T23:15:00Z");
+    test(&z, "2014-01-10T23:30:00Z", "00:45", "2014-01-10T23:45:00Z");
+    test(&z, "2014-01-10T23:30:00Z", "01:00", "2014-01-11T00:00:00Z");
+    // Skipped, so 02:30 occurs at 03:30 CEST.
+    test(&z, "2014-03-29T12:00:00Z", "02:30", "2014-03-30T01:30:00Z");
+    test(&z, "2014-03-30T01:30:00Z", "02:30", "2014-03-31T00:30:00Z");
+    // Repeated, so 02:30 only occurs at 02:30 CEST.
+    test(&z, "2014-10-25T12:00:00Z", "02:30", "2014-10-26T00:30:00Z");
+    test(&z, "2014-10-26T00:30:00Z", "02:30", "2014-10-27T01:30:00Z");
+    test(&z, "2014-10-26T01:00:00Z", "02:30", "2014-10-27T01:30:00Z");
+    let max: Instant = Bounded::max_value();
+    assert!(next_occurrence(max, from_str("12:00").unwrap(), &z).is_none());
+}
+
+#[test]
+fn weekly() {
+    use zone;
+    use format_iso8601::{Tuesday, Sunday};
+    let z = zone::test_zone();
+    let tod: TimeOfDay = from_str("09:00").unwrap();
+    // 2014-03-25 is a Tuesday.
+    let after: Instant = from_str("2014-03-21T12:00:00Z").unwrap();
+    let out: Vec<String> = Weekly::new(after, Tuesday, tod, &z).take(3)
+        .map(|t| format!("{}", t)).collect();
+    assert_eq!(out, vec!["2014-03-25T08:00:00Z".to_string(),
+                         "2014-04-01T07:00:00Z".to_string(),
+                         "2014-04-08T07:00:00Z".to_string()]);
+    // At the time itself, the next week is chosen.
+    let at: Instant = from_str("2014-03-25T08:00:00Z").unwrap();
+    assert_eq!(next_weekly(at, Tuesday, tod, &z),
+               from_str("2014-04-01T07:00:00Z"));
+    // Sunday 02:30 on the day summer time starts.
+    assert_eq!(next_weekly(after, Sunday, from_str("02:30").unwrap(), &z),
+               from_str("2014-03-30T01:30:00Z"));
+}
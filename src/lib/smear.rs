@@ -0,0 +1,154 @@
+//! Conversion between UTC and a leap-smeared time scale.
+//!
+//! Some time servers hide leap seconds from their clients by running the
+//! clock slightly slow or fast for a period around each leap second, so the
+//! clock never reads 23:59:60.  This module implements a linear smear over
+//! the 24 hours from noon to noon UTC centered on the leap second, which is
+//! the scheme used by major public NTP services.
+//!
+//! UTC times are written as an instant and a flag which is set during a
+//! positive leap second.  The leap second 23:59:60.25 is written as the
+//! instant 23:59:59.25 with the flag set.
+
+use div_mod::div_mod;
+use tick;
+use instant::Instant;
+
+/// Half the length of the smear window.
+static HALF_WINDOW: i64 = 12 * tick::HOUR;
+
+/// Multiply ticks by the ratio of two whole numbers of seconds.  The
+/// result is rounded down if the ratio is greater than one and up
+/// otherwise, so scaling by a ratio and then by its inverse gives back
+/// the original value.
+fn scale(t: i64, num: i64, den: i64) -> i64 {
+    let n = t * (num / tick::SECOND);
+    let d = den / tick::SECOND;
+    if num > den {
+        let (q, _) = div_mod(n, d);
+        q
+    } else {
+        let (q, _) = div_mod(-n, d);
+        -q
+    }
+}
+
+/// A leap second and the smear which hides it.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct LeapSmear {
+    /// The midnight at the end of the day containing the leap second.
+    pub midnight: Instant,
+    /// True if a second is inserted, false if a second is removed.
+    pub positive: bool
+}
+
+impl LeapSmear {
+    /// Create a smear for a leap second at the end of the day which ends
+    /// at the given midnight.
+    pub fn new(midnight: Instant, positive: bool) -> LeapSmear {
+        LeapSmear { midnight: midnight, positive: positive }
+    }
+
+    /// The leap second, in ticks.
+    fn delta(&self) -> i64 {
+        if self.positive { tick::SECOND } else { -tick::SECOND }
+    }
+
+    /// Convert a smeared time to the number of ticks actually elapsed,
+    /// counted so that it agrees with the smeared time before the window.
+    fn smeared_to_elapsed(&self, t: i64) -> i64 {
+        let start = self.midnight.ticks - HALF_WINDOW;
+        let len = 2 * HALF_WINDOW;
+        if t < start {
+            t
+        } else if t < start + len {
+            start + scale(t - start, len + self.delta(), len)
+        } else {
+            t + self.delta()
+        }
+    }
+
+    /// Convert elapsed ticks back to a smeared time.
+    fn elapsed_to_smeared(&self, t: i64) -> i64 {
+        let start = self.midnight.ticks - HALF_WINDOW;
+        let len = 2 * HALF_WINDOW + self.delta();
+        if t < start {
+            t
+        } else if t < start + len {
+            start + scale(t - start, 2 * HALF_WINDOW, len)
+        } else {
+            t - self.delta()
+        }
+    }
+
+    /// Convert a time read from a smeared clock to UTC.  Returns the
+    /// instant and whether it falls within a positive leap second.
+    pub fn to_utc(&self, smeared: Instant) -> (Instant, bool) {
+        let t = self.smeared_to_elapsed(smeared.ticks);
+        let m = self.midnight.ticks;
+        if self.positive {
+            if t < m {
+                (Instant { ticks: t }, false)
+            } else if t < m + tick::SECOND {
+                (Instant { ticks: t - tick::SECOND }, true)
+            } else {
+                (Instant { ticks: t - tick::SECOND }, false)
+            }
+        } else if t < m - tick::SECOND {
+            (Instant { ticks: t }, false)
+        } else {
+            (Instant { ticks: t + tick::SECOND }, false)
+        }
+    }
+
+    /// Convert UTC to the time a smeared clock would read.  The leap flag
+    /// is ignored unless the leap second is positive and the instant falls
+    /// within the last second of the day.  Times skipped by a negative leap
+    /// second are treated as if the leap second had not happened yet.
+    pub fn from_utc(&self, utc: Instant, leap: bool) -> Instant {
+        let m = self.midnight.ticks;
+        let t = utc.ticks;
+        let elapsed = if self.positive {
+            if t >= m || (leap && t >= m - tick::SECOND) {
+                t + tick::SECOND
+            } else {
+                t
+            }
+        } else if t >= m {
+            t - tick::SECOND
+        } else {
+            t
+        };
+        Instant { ticks: self.elapsed_to_smeared(elapsed) }
+    }
+}
+
+#[test]
+fn smear() {
+    // The leap second at the end of 2016-12-31.
+    let m = tick::DAY * 6210;
+    let i = |ticks: i64| Instant { ticks: m + ticks };
+    let pos = LeapSmear::new(i(0), true);
+    assert_eq!(pos.to_utc(i(-HALF_WINDOW - 1)),
+               (i(-HALF_WINDOW - 1), false));
+    assert_eq!(pos.to_utc(i(-HALF_WINDOW)), (i(-HALF_WINDOW), false));
+    assert_eq!(pos.to_utc(i(0)), (i(-tick::SECOND / 2), true));
+    assert_eq!(pos.to_utc(i(HALF_WINDOW)), (i(HALF_WINDOW), false));
+    assert_eq!(pos.from_utc(i(-tick::SECOND / 2), true), i(0));
+    assert_eq!(pos.from_utc(i(-tick::SECOND / 2), false), i(-9999884));
+    assert_eq!(pos.from_utc(i(HALF_WINDOW), false), i(HALF_WINDOW));
+
+    let neg = LeapSmear::new(i(0), false);
+    assert_eq!(neg.to_utc(i(0)), (i(tick::SECOND / 2), false));
+    assert_eq!(neg.from_utc(i(tick::SECOND / 2), false), i(0));
+    assert_eq!(neg.to_utc(i(HALF_WINDOW)), (i(HALF_WINDOW), false));
+
+    // Round trips are exact in the direction which does not lose ticks.
+    for &t in [-HALF_WINDOW, -tick::HOUR, -tick::SECOND - 1, 0, 1,
+               tick::HOUR].iter() {
+        let (utc, leap) = pos.to_utc(i(t));
+        assert_eq!(pos.from_utc(utc, leap), i(t));
+        let utc = neg.to_utc(neg.from_utc(i(t), false));
+        assert_eq!(utc, (i(t), false));
+    }
+}
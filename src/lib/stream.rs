@@ -0,0 +1,297 @@
+//! Incremental parsing of timestamps and durations from a byte stream.
+
+use std::io::{Reader, IoResult, IoError, EndOfFile, InvalidInput,
+              NoProgress};
+use std::io::standard_error;
+use std::str;
+use duration::Duration;
+use format_iso8601;
+use instant::Instant;
+use parse::ParseOptions;
+
+/// The maximum number of bytes buffered while looking for the end of a
+/// value.  Longer values are parsed from their first `LOOKAHEAD` bytes.
+pub static LOOKAHEAD: uint = 64;
+
+/// The number of reads in a row which may return no bytes before giving
+/// up with a `NoProgress` error, as `Reader::read_at_least` does.
+static ZERO_READ_LIMIT: uint = 1000;
+
+/// A parser which pulls ISO 8601 instants and durations from a `Reader`.
+///
+/// Values are separated by whitespace.  Bytes are requested from the
+/// reader only until the end of the next value is seen, so values can be
+/// decoded as they arrive, for example from a socket.  At most
+/// `LOOKAHEAD` bytes are buffered at a time.
+pub struct StreamParser<R> {
+    reader: R,
+    opts: ParseOptions,
+    buf: Vec<u8>,
+    eof: bool
+}
+
+fn is_space(c: u8) -> bool {
+    (c as char).is_whitespace()
+}
+
+fn invalid_input() -> IoError {
+    IoError {
+        kind: InvalidInput,
+        desc: "invalid timestamp or duration",
+        detail: None
+    }
+}
+
+impl<R: Reader> StreamParser<R> {
+    /// Create a parser which only accepts ISO 8601 representations.
+    pub fn new(reader: R) -> StreamParser<R> {
+        StreamParser::with_options(reader, ParseOptions::strict())
+    }
+
+    /// Create a parser using the given options.  Values are still
+    /// delimited by whitespace, so lenient forms containing spaces, such
+    /// as "2014-06-10 11:12:13", are not accepted.
+    pub fn with_options(reader: R, opts: ParseOptions) -> StreamParser<R> {
+        StreamParser {
+            reader: reader,
+            opts: opts,
+            buf: Vec::with_capacity(LOOKAHEAD),
+            eof: false
+        }
+    }
+
+    /// Get the underlying reader.  Any bytes which have been read but not
+    /// yet parsed are lost.
+    pub fn unwrap(self) -> R {
+        self.reader
+    }
+
+    /// Read the next instant.  Returns an `EndOfFile` error if there is
+    /// no more input, and an `InvalidInput` error if the next value is
+    /// not an instant, in which case that value is skipped.
+    pub fn read_instant(&mut self) -> IoResult<Instant> {
+        self.read_value(|s, opts| {
+            format_iso8601::parse_instant_prefix(s, opts)
+        })
+    }
+
+    /// Read the next duration.  Errors are reported as for
+    /// `read_instant`.
+    pub fn read_duration(&mut self) -> IoResult<Duration> {
+        self.read_value(|s, opts| Duration::parse_prefix(s, opts))
+    }
+
+    /// Get an iterator over the remaining instants.  The iterator stops
+    /// at the end of the input.
+    pub fn instants<'a>(&'a mut self) -> Instants<'a, R> {
+        Instants { parser: self }
+    }
+
+    /// Get an iterator over the remaining durations.  The iterator stops
+    /// at the end of the input.
+    pub fn durations<'a>(&'a mut self) -> Durations<'a, R> {
+        Durations { parser: self }
+    }
+
+    fn read_value<T>(&mut self,
+                     parse: |&str, &ParseOptions| -> Option<(T, uint)>)
+                     -> IoResult<T> {
+        let len = try!(self.next_token());
+        let result = match str::from_utf8(self.buf.slice_to(len)) {
+            Some(s) => parse(s, &self.opts),
+            None => None
+        };
+        match result {
+            Some((value, n)) if n > 0 => {
+                self.consume(n);
+                Ok(value)
+            }
+            _ => {
+                self.consume(len);
+                Err(invalid_input())
+            }
+        }
+    }
+
+    /// Skip whitespace and buffer the next value.  Returns its length,
+    /// which is limited to `LOOKAHEAD`.
+    fn next_token(&mut self) -> IoResult<uint> {
+        loop {
+            let skip = self.buf.iter().take_while(|&&c| is_space(c)).count();
+            self.consume(skip);
+            if self.buf.is_empty() {
+                if self.eof {
+                    return Err(standard_error(EndOfFile));
+                }
+            } else {
+                match self.buf.iter().position(|&c| is_space(c)) {
+                    Some(n) => return Ok(n),
+                    None => ()
+                }
+                if self.eof || self.buf.len() >= LOOKAHEAD {
+                    return Ok(self.buf.len());
+                }
+            }
+            try!(self.fill());
+        }
+    }
+
+    /// Read at least one byte into the buffer, or reach the end of the
+    /// input.
+    fn fill(&mut self) -> IoResult<()> {
+        let mut chunk = [0u8, ..LOOKAHEAD];
+        let want = LOOKAHEAD - self.buf.len();
+        for _ in range(0, ZERO_READ_LIMIT) {
+            match self.reader.read(chunk.mut_slice_to(want)) {
+                Ok(0) => continue,
+                Ok(n) => self.buf.push_all(chunk.slice_to(n)),
+                Err(ref e) if e.kind == EndOfFile => self.eof = true,
+                Err(e) => return Err(e)
+            }
+            return Ok(());
+        }
+        Err(standard_error(NoProgress))
+    }
+
+    fn consume(&mut self, n: uint) {
+        if n > 0 {
+            self.buf = self.buf.slice_from(n).to_vec();
+        }
+    }
+}
+
+/// An iterator over the instants read by a `StreamParser`.
+pub struct Instants<'a, R> {
+    parser: &'a mut StreamParser<R>
+}
+
+impl<'a, R: Reader> Iterator<IoResult<Instant>> for Instants<'a, R> {
+    fn next(&mut self) -> Option<IoResult<Instant>> {
+        match self.parser.read_instant() {
+            Err(ref e) if e.kind == EndOfFile => None,
+            r => Some(r)
+        }
+    }
+}
+
+/// An iterator over the durations read by a `StreamParser`.
+pub struct Durations<'a, R> {
+    parser: &'a mut StreamParser<R>
+}
+
+impl<'a, R: Reader> Iterator<IoResult<Duration>> for Durations<'a, R> {
+    fn next(&mut self) -> Option<IoResult<Duration>> {
+        match self.parser.read_duration() {
+            Err(ref e) if e.kind == EndOfFile => None,
+            r => Some(r)
+        }
+    }
+}
+
+#[cfg(test)]
+struct Trickle {
+    data: Vec<u8>,
+    pos: uint
+}
+
+#[cfg(test)]
+impl Reader for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        if self.pos >= self.data.len() {
+            return Err(standard_error(EndOfFile));
+        }
+        buf[0] = *self.data.get(self.pos);
+        self.pos += 1;
+        Ok(1)
+    }
+}
+
+#[test]
+fn test_instants() {
+    use std::io::MemReader;
+    fn test(s: &str, expected: &[Option<&str>]) {
+        fn check<R: Reader>(mut p: StreamParser<R>,
+                            expected: &[Option<&str>]) {
+            let v: Vec<Option<Instant>> =
+                p.instants().map(|r| r.ok()).collect();
+            let e: Vec<Option<Instant>> = expected.iter().map(|x| {
+                x.map(|s| format_iso8601::parse_instant(s).unwrap())
+            }).collect();
+            assert_eq!(v, e);
+        }
+        let bytes = Vec::from_slice(s.as_bytes());
+        check(StreamParser::new(MemReader::new(bytes.clone())), expected);
+        check(StreamParser::new(Trickle { data: bytes, pos: 0 }), expected);
+    }
+    test("", []);
+    test("  \n", []);
+    test("2014-06-10T11:12:13Z",
+         [Some("2014-06-10T11:12:13Z")]);
+    test("2014-06-10T11:12:13Z\n2000-01-01T00:00:00+01:00\n",
+         [Some("2014-06-10T11:12:13Z"), Some("1999-12-31T23:00:00Z")]);
+    test("\t2014-06-10T11:12:13Z  junk 2014-06-10T11:12:14Z",
+         [Some("2014-06-10T11:12:13Z"), None,
+          Some("2014-06-10T11:12:14Z")]);
+}
+
+#[test]
+fn test_no_progress() {
+    // A reader which never returns any bytes, but is not at the end.
+    struct Stalled;
+    impl Reader for Stalled {
+        fn read(&mut self, _: &mut [u8]) -> IoResult<uint> {
+            Ok(0)
+        }
+    }
+    let mut p = StreamParser::new(Stalled);
+    assert_eq!(p.read_instant().err().map(|e| e.kind), Some(NoProgress));
+    // Some empty reads are fine.
+    struct Hesitant {
+        data: Vec<u8>,
+        calls: uint
+    }
+    impl Reader for Hesitant {
+        fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+            self.calls += 1;
+            if self.calls % 10 != 0 {
+                return Ok(0);
+            }
+            match self.data.remove(0) {
+                Some(c) => {
+                    buf[0] = c;
+                    Ok(1)
+                }
+                None => Err(standard_error(EndOfFile))
+            }
+        }
+    }
+    let data = Vec::from_slice("PT1H PT2M".as_bytes());
+    let mut p = StreamParser::new(Hesitant { data: data, calls: 0 });
+    assert_eq!(p.read_duration().ok(), Some(Duration::from_hours(1)));
+    assert_eq!(p.read_duration().ok(), Some(Duration::from_minutes(2)));
+    assert_eq!(p.read_duration().err().map(|e| e.kind), Some(EndOfFile));
+}
+
+#[test]
+fn test_durations() {
+    use std::io::MemReader;
+    let s = "PT1H P1D\nPT\n-PT1S";
+    let r = MemReader::new(Vec::from_slice(s.as_bytes()));
+    let mut p = StreamParser::new(r);
+    assert_eq!(p.read_duration().ok(), Some(Duration::from_hours(1)));
+    assert_eq!(p.read_duration().ok(), Some(Duration::from_standard_days(1)));
+    assert!(p.read_duration().is_err());
+    assert_eq!(p.read_duration().ok(), Some(Duration::from_seconds(-1)));
+    assert_eq!(p.read_duration().err().map(|e| e.kind), Some(EndOfFile));
+}
+
+#[test]
+fn test_adjacent_values() {
+    use std::io::MemReader;
+    let s = "2014-06-10T11:12:13ZPT5M";
+    let r = MemReader::new(Vec::from_slice(s.as_bytes()));
+    let mut p = StreamParser::new(r);
+    assert_eq!(p.read_instant().ok(),
+               format_iso8601::parse_instant("2014-06-10T11:12:13Z"));
+    assert_eq!(p.read_duration().ok(), Some(Duration::from_minutes(5)));
+}
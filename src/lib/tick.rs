@@ -4,6 +4,9 @@
  * that minute.
  */
 
+use div_mod::div_mod;
+use wide;
+
 /// The number of nanoseconds in a tick.
 static PER_NANOSECOND: i64 = 100;
 /// The number of ticks in a microsecond.
@@ -21,7 +24,7 @@ pub static DAY: i64 = 24 * HOUR;
 
 /// Convert ticks to a larger unit, rounding to the nearest even integer.
 #[inline]
-fn to_larger_unit(ticks: i64, unit: i64) -> i64 {
+pub fn to_larger_unit(ticks: i64, unit: i64) -> i64 {
     let mut total = ticks / unit;
     let mut rem = ticks % unit;
     if rem < 0 {
@@ -35,6 +38,20 @@ fn to_larger_unit(ticks: i64, unit: i64) -> i64 {
     total
 }
 
+/// Convert ticks to a larger unit, rounding down.
+#[inline]
+pub fn to_larger_unit_floor(ticks: i64, unit: i64) -> i64 {
+    let (total, _) = div_mod(ticks, unit);
+    total
+}
+
+/// Convert ticks to a larger unit, rounding up.
+#[inline]
+pub fn to_larger_unit_ceil(ticks: i64, unit: i64) -> i64 {
+    let (total, rem) = div_mod(ticks, unit);
+    if rem > 0 { total + 1 } else { total }
+}
+
 /// Convert ticks to whole seconds, with rounding.
 pub fn to_sec(ticks: i64) -> i64 {
     to_larger_unit(ticks, SECOND)
@@ -50,6 +67,47 @@ pub fn to_usec(ticks: i64) -> i64 {
     to_larger_unit(ticks, MICROSECOND)
 }
 
+/// Convert ticks to whole seconds, rounding down.
+pub fn to_sec_floor(ticks: i64) -> i64 {
+    to_larger_unit_floor(ticks, SECOND)
+}
+
+/// Convert ticks to whole seconds, rounding up.
+pub fn to_sec_ceil(ticks: i64) -> i64 {
+    to_larger_unit_ceil(ticks, SECOND)
+}
+
+/// Convert ticks to whole milliseconds, rounding down.
+pub fn to_msec_floor(ticks: i64) -> i64 {
+    to_larger_unit_floor(ticks, MILLISECOND)
+}
+
+/// Convert ticks to whole milliseconds, rounding up.
+pub fn to_msec_ceil(ticks: i64) -> i64 {
+    to_larger_unit_ceil(ticks, MILLISECOND)
+}
+
+/// Convert ticks to whole microseconds, rounding down.
+pub fn to_usec_floor(ticks: i64) -> i64 {
+    to_larger_unit_floor(ticks, MICROSECOND)
+}
+
+/// Convert ticks to whole microseconds, rounding up.
+pub fn to_usec_ceil(ticks: i64) -> i64 {
+    to_larger_unit_ceil(ticks, MICROSECOND)
+}
+
+/// Convert nanoseconds to ticks, rounding to the nearest tick, with ties
+/// going to even.  This cannot overflow.
+pub fn from_nsec(nsec: i64) -> i64 {
+    to_larger_unit(nsec, PER_NANOSECOND)
+}
+
+/// Convert ticks to nanoseconds.  Returns None on overflow.
+pub fn to_nsec(ticks: i64) -> Option<i64> {
+    ticks.checked_mul(&PER_NANOSECOND)
+}
+
 /// Convert ticks to seconds and fractional microseconds, with rounding.
 pub fn to_sec_usec(ticks: i64) -> (i64, i32) {
     let total_usec = to_larger_unit(ticks, MICROSECOND);
@@ -73,19 +131,14 @@ pub fn to_sec_nsec(ticks: i64) -> (i64, i32) {
     (sec, rem * 100)
 }
 
-/// Convert from larger units to ticks.  The units can't be larger than
-/// about 2^30.
+/// Convert from a sum of two quantities in larger units to ticks.  Returns
+/// None on overflow.
 fn from_larger_units(v1: i64, v2: i64, u1: i64, u2: i64) -> Option<i64> {
-    // Why is this complicated?  Because intermediate results may overflow
-    // even if the final result does not.
-    let hi: i64 = (v1 >> 32) * u1 + (v2 >> 32) * u2;
-    let lo: i64 = (v1 & 0xffffffff) * u1 + (v2 & 0xffffffff) * u2;
-    let hi: i64 = hi + (lo >> 32);
-    let lo: i64 = lo & 0xffffffff;
-    if hi != (hi as i32) as i64 {
-        return None;
-    }
-    Some(hi | (lo << 32))
+    // The products and their sum may overflow 64 bits even if the result
+    // does not, so they are computed with 128 bits.
+    wide::checked_add_signed(wide::mul_signed(v1, u1),
+                             wide::mul_signed(v2, u2))
+        .and_then(|(hi, lo)| wide::narrow_signed(hi, lo))
 }
 
 /// Convert from seconds and microseconds to ticks.  Returns None on
@@ -100,3 +153,66 @@ pub fn from_sec_nsec(sec: i64, nsec: i64) -> Option<i64> {
     from_larger_units(sec, to_larger_unit(nsec, PER_NANOSECOND),
                       SECOND, 1)
 }
+
+#[test]
+fn floor_ceil() {
+    fn test(ticks: i64, unit: i64, floor: i64, ceil: i64) {
+        let out = (to_larger_unit_floor(ticks, unit),
+                   to_larger_unit_ceil(ticks, unit));
+        if out != (floor, ceil) {
+            fail!("ticks: {}, unit: {}, expected: {}, output: {}",
+                  ticks, unit, (floor, ceil), out);
+        }
+    }
+
+    test(0, SECOND, 0, 0);
+    test(1, SECOND, 0, 1);
+    test(-1, SECOND, -1, 0);
+    test(SECOND, SECOND, 1, 1);
+    test(-SECOND, SECOND, -1, -1);
+    test(SECOND + 1, SECOND, 1, 2);
+    test(-SECOND - 1, SECOND, -2, -1);
+    test(::std::i64::MAX, SECOND, 922337203685, 922337203686);
+    test(::std::i64::MIN, SECOND, -922337203686, -922337203685);
+
+    assert_eq!(to_sec_floor(SECOND * 3 / 2), 1);
+    assert_eq!(to_sec_ceil(SECOND * 3 / 2), 2);
+    assert_eq!(to_msec_floor(-1), -1);
+    assert_eq!(to_msec_ceil(-1), 0);
+    assert_eq!(to_usec_floor(15), 1);
+    assert_eq!(to_usec_ceil(15), 2);
+}
+
+#[test]
+fn from_sec() {
+    assert_eq!(from_sec_nsec(1, 0), Some(SECOND));
+    assert_eq!(from_sec_nsec(-1, 250), Some(-SECOND + 2));
+    assert_eq!(from_sec_nsec(0, 50), Some(0));
+    assert_eq!(from_sec_nsec(0, 150), Some(2));
+    assert_eq!(from_nsec(250), 2);
+    assert_eq!(from_nsec(-251), -3);
+    assert_eq!(from_nsec(::std::i64::MIN), -92233720368547758);
+    assert_eq!(to_nsec(-3), Some(-300));
+    assert_eq!(to_nsec(92233720368547758), Some(9223372036854775800));
+    assert_eq!(to_nsec(92233720368547759), None);
+    assert_eq!(from_sec_usec(1402358400, 123457),
+               Some(14023584001234570));
+    assert_eq!(from_sec_usec(-1, 999999), Some(-MICROSECOND));
+    assert_eq!(from_sec_nsec(922337203685, 477580700),
+               Some(::std::i64::MAX));
+    assert_eq!(from_sec_nsec(922337203685, 477580800), None);
+    assert_eq!(from_sec_nsec(-922337203686, 0), None);
+    assert_eq!(from_sec_usec(::std::i64::MAX, 0), None);
+    // Huge values which cancel out.
+    assert_eq!(from_larger_units(::std::i64::MAX, -::std::i64::MAX,
+                                 DAY, DAY), Some(0));
+    assert_eq!(from_larger_units(1 << 40, -(1 << 40), 1 << 40, 1 << 40),
+               Some(0));
+    assert_eq!(from_larger_units(1 << 40, 1, 1 << 22, -1),
+               Some((1 << 62) - 1));
+    assert_eq!(from_sec_nsec(-922337203685, -477580800),
+               Some(::std::i64::MIN));
+    assert_eq!(from_sec_nsec(0, ::std::i64::MAX), Some(92233720368547758));
+    assert_eq!(from_sec_nsec(-1, ::std::i64::MAX),
+               Some(92233720368547758 - SECOND));
+}
@@ -0,0 +1,111 @@
+//! Conversions to and from the time crate's Timespec and Tm types.
+//!
+//! This module is only available with the "time" feature.
+
+use time::{Timespec, Tm};
+use tick;
+use calendar_iso8601;
+use div_mod::div_mod;
+use instant::{Instant, UNIX_EPOCH};
+
+/// Convert a Timespec, which measures time since the Unix epoch, to an
+/// instant.  Returns None if the result is out of range.
+pub fn from_timespec(value: Timespec) -> Option<Instant> {
+    let epoch = UNIX_EPOCH.ticks() / tick::SECOND;
+    value.sec.checked_add(&epoch)
+        .and_then(|sec| tick::from_sec_nsec(sec, value.nsec as i64))
+        .map(|n| Instant::from_ticks(n))
+}
+
+/// Convert an instant to a Timespec, which measures time since the Unix
+/// epoch.  Fractions of a nanosecond are rounded down.
+pub fn to_timespec(value: Instant) -> Timespec {
+    // Convert to seconds first, since the difference in ticks between the
+    // epochs can overflow.
+    let (sec, subsec) = div_mod(value.ticks(), tick::SECOND);
+    let epoch = UNIX_EPOCH.ticks() / tick::SECOND;
+    Timespec::new(sec - epoch, (subsec * 100) as i32)
+}
+
+/// Convert a broken-down time to an instant, using the UTC offset in the
+/// tm_gmtoff field.  The tm_wday, tm_yday, and tm_isdst fields are
+/// ignored.  Returns None if any field is out of range.  A leap second is
+/// treated as the first second of the next minute.
+pub fn from_tm(value: &Tm) -> Option<Instant> {
+    let year = value.tm_year as int + 1900;
+    let month = value.tm_mon as int + 1;
+    let day = value.tm_mday as int;
+    if month < 1 || month > 12 || day < 1 ||
+        day > calendar_iso8601::days_in_month(year, month) ||
+        value.tm_hour < 0 || value.tm_hour > 23 ||
+        value.tm_min < 0 || value.tm_min > 59 ||
+        value.tm_sec < 0 || value.tm_sec > 60 ||
+        value.tm_nsec < 0 || value.tm_nsec > 999999999 {
+        return None;
+    }
+    let cjd = calendar_iso8601::to_cjd(year, month, day) as i64;
+    let sec = value.tm_hour as i64 * 3600 + value.tm_min as i64 * 60 +
+        value.tm_sec as i64 - value.tm_gmtoff as i64;
+    let time = sec * tick::SECOND + value.tm_nsec as i64 / 100;
+    cjd.checked_mul(&tick::DAY)
+        .and_then(|n| n.checked_add(&time))
+        .map(|n| Instant::from_ticks(n))
+}
+
+/// Convert an instant to a broken-down time in UTC.  Fractions of a
+/// nanosecond are rounded down.
+pub fn to_tm_utc(value: Instant) -> Tm {
+    let (cjd, tickrem) = div_mod(value.ticks(), tick::DAY);
+    let cjd = cjd as int;
+    let (y, m, d) = calendar_iso8601::from_cjd(cjd);
+    let (_, yday) = calendar_iso8601::to_ordinal(cjd);
+    let (sec, subsec) = div_mod(tickrem, tick::SECOND);
+    Tm {
+        tm_sec: (sec % 60) as i32,
+        tm_min: (sec / 60 % 60) as i32,
+        tm_hour: (sec / 3600) as i32,
+        tm_mday: d as i32,
+        tm_mon: (m - 1) as i32,
+        tm_year: (y - 1900) as i32,
+        tm_wday: (calendar_iso8601::weekday(cjd) % 7) as i32,
+        tm_yday: (yday - 1) as i32,
+        tm_isdst: 0,
+        tm_gmtoff: 0,
+        tm_nsec: (subsec * 100) as i32
+    }
+}
+
+#[test]
+fn timespec() {
+    use std::num::Bounded;
+    let t = Instant::from_ticks(12345678);
+    assert_eq!(to_timespec(UNIX_EPOCH), Timespec::new(0, 0));
+    assert_eq!(to_timespec(t), Timespec::new(946684801, 234567800));
+    assert_eq!(from_timespec(Timespec::new(946684801, 234567800)), Some(t));
+    assert_eq!(to_timespec(Instant::from_ticks(-1)),
+               Timespec::new(946684799, 999999900));
+    assert_eq!(from_timespec(Timespec::new(0x7fffffffffffffff, 0)), None);
+    let min: Instant = Bounded::min_value();
+    let max: Instant = Bounded::max_value();
+    assert_eq!(to_timespec(min), Timespec::new(-921390518886, 522419200));
+    assert_eq!(to_timespec(max), Timespec::new(923283888485, 477580700));
+    assert_eq!(from_timespec(to_timespec(min)), Some(min));
+    assert_eq!(from_timespec(to_timespec(max)), Some(max));
+}
+
+#[test]
+fn tm() {
+    // 2024-05-15T12:34:56.5Z, a Wednesday.
+    let t = Instant::from_ticks(8901 * tick::DAY + 45296 * tick::SECOND +
+                                5000000);
+    let tm = to_tm_utc(t);
+    assert_eq!((tm.tm_year, tm.tm_mon, tm.tm_mday), (124, 4, 15));
+    assert_eq!((tm.tm_hour, tm.tm_min, tm.tm_sec), (12, 34, 56));
+    assert_eq!((tm.tm_wday, tm.tm_yday, tm.tm_nsec), (3, 135, 500000000));
+    assert_eq!(from_tm(&tm), Some(t));
+
+    let east = Tm { tm_hour: 14, tm_gmtoff: 7200, ..tm };
+    assert_eq!(from_tm(&east), Some(t));
+    let bad = Tm { tm_mday: 31, tm_mon: 3, ..tm };
+    assert_eq!(from_tm(&bad), None);
+}
@@ -0,0 +1,218 @@
+//! Times of day, independent of any date or time zone.
+
+use std::io::{MemWriter, IoResult};
+use std::fmt::{Show, Formatter, FormatError, WriteError};
+use std::from_str::FromStr;
+use tick;
+use fmtutil;
+use parseutil;
+use parse::AnyDecimalMark;
+
+/// A time of day, stored as the number of ticks since midnight.  Leap
+/// seconds cannot be represented.
+#[deriving(PartialEq, PartialOrd, Ord, Eq, Clone, Hash)]
+pub struct TimeOfDay {
+    ticks: i64
+}
+
+/// Midnight, at the start of the day.
+pub static MIDNIGHT: TimeOfDay = TimeOfDay { ticks: 0 };
+
+impl TimeOfDay {
+    /// Create a time of day from the hour, minute, second, and ticks
+    /// after the second.  Returns None if any field is out of range.
+    pub fn new(hour: int, minute: int, second: int, subsec_ticks: i64)
+               -> Option<TimeOfDay> {
+        if hour < 0 || hour > 23 || minute < 0 || minute > 59 ||
+            second < 0 || second > 59 ||
+            subsec_ticks < 0 || subsec_ticks >= tick::SECOND {
+            return None;
+        }
+        Some(TimeOfDay {
+            ticks: hour as i64 * tick::HOUR + minute as i64 * tick::MINUTE +
+                second as i64 * tick::SECOND + subsec_ticks
+        })
+    }
+
+    /// Create a time of day from the number of ticks since midnight.
+    /// Returns None unless the ticks are within a single day.
+    pub fn from_ticks(ticks: i64) -> Option<TimeOfDay> {
+        if ticks >= 0 && ticks < tick::DAY {
+            Some(TimeOfDay { ticks: ticks })
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of ticks since midnight.
+    #[inline]
+    pub fn ticks(&self) -> i64 {
+        self.ticks
+    }
+
+    /// Get the hour, from 0 to 23.
+    pub fn hour(&self) -> int {
+        (self.ticks / tick::HOUR) as int
+    }
+
+    /// Get the minute, from 0 to 59.
+    pub fn minute(&self) -> int {
+        (self.ticks / tick::MINUTE % 60) as int
+    }
+
+    /// Get the second, from 0 to 59.
+    pub fn second(&self) -> int {
+        (self.ticks / tick::SECOND % 60) as int
+    }
+
+    /// Get the number of ticks after the second.
+    pub fn subsec_ticks(&self) -> i64 {
+        self.ticks % tick::SECOND
+    }
+
+    /// Convert to a UTF-8 vector, used to implement Show.  Without a
+    /// precision, trailing zeros are removed from the fraction.  With a
+    /// precision, the fraction has exactly that many digits, and is
+    /// truncated rather than rounded so the time stays within the day.
+    fn to_utf8_io(&self, precision: Option<uint>) -> IoResult<Vec<u8>> {
+        let mut w = MemWriter::with_capacity(16);
+        try!(fmtutil::write_2digits(&mut w, self.hour() as uint));
+        try!(w.write_char(':'));
+        try!(fmtutil::write_2digits(&mut w, self.minute() as uint));
+        try!(w.write_char(':'));
+        try!(fmtutil::write_2digits(&mut w, self.second() as uint));
+        let mut digits = [0u8, ..7];
+        let mut frac = self.subsec_ticks();
+        for i in range(0u, 7).rev() {
+            digits[i] = '0' as u8 + (frac % 10) as u8;
+            frac /= 10;
+        }
+        let (len, zeroes) = match precision {
+            Some(p) if p > 7 => (7, p - 7),
+            Some(p) => (p, 0),
+            None => {
+                let mut len = 7;
+                while len > 0 && digits[len - 1] == '0' as u8 {
+                    len -= 1;
+                }
+                (len, 0)
+            }
+        };
+        if len > 0 {
+            try!(w.write_char('.'));
+            try!(w.write(digits.slice_to(len)));
+            for _ in range(0, zeroes) {
+                try!(w.write_char('0'));
+            }
+        }
+        Ok(w.unwrap())
+    }
+}
+
+impl Show for TimeOfDay {
+    /// Uses the ISO 8601 extended format, such as "14:30:15.25".
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        let datavec = match self.to_utf8_io(f.precision) {
+            Ok(x) => x,
+            Err(_) => return Err(WriteError)
+        };
+        fmtutil::write_padded(f, datavec.as_slice())
+    }
+}
+
+impl FromStr for TimeOfDay {
+    /// Parses the ISO 8601 extended format with reduced precision, so
+    /// "14", "14:30", and "14:30:15" are all accepted.  The smallest
+    /// component may have a decimal fraction, as in "14:30.5".
+    fn from_str(s: &str) -> Option<TimeOfDay> {
+        static UNITS: [(i64, int), ..3] = [
+            (tick::HOUR, 23), (tick::MINUTE, 59), (tick::SECOND, 59)
+        ];
+        let mut rem = s.as_bytes();
+        let mut ticks = 0;
+        for (i, &(unit, max)) in UNITS.iter().enumerate() {
+            if i > 0 {
+                if !parseutil::starts_with(rem, ':') {
+                    break;
+                }
+                rem = rem.slice_from(1);
+            }
+            let (n, r) = match parseutil::read_digits(rem, 2) {
+                Some(x) => x, None => return None
+            };
+            if n > max {
+                return None;
+            }
+            ticks += n as i64 * unit;
+            rem = r;
+            if parseutil::starts_with_fraction(rem, AnyDecimalMark) {
+                let (frac, r) = match parseutil::read_fraction(
+                        rem, unit, AnyDecimalMark) {
+                    Some(x) => x, None => return None
+                };
+                ticks += frac;
+                rem = r;
+                break;
+            }
+        }
+        if !rem.is_empty() {
+            return None;
+        }
+        TimeOfDay::from_ticks(ticks)
+    }
+}
+
+#[test]
+fn format() {
+    fn test(t: TimeOfDay, precision: Option<uint>, expected: &str) {
+        let out = match precision {
+            Some(p) => format!("{0:.1$}", t, p),
+            None => format!("{}", t)
+        };
+        if out.as_slice() != expected {
+            fail!("ticks: {}, expected: '{}', output: '{}'",
+                  t.ticks, expected, out);
+        }
+    }
+    let t = TimeOfDay::new(14, 30, 15, 2500000).unwrap();
+    test(t, None, "14:30:15.25");
+    test(t, Some(0), "14:30:15");
+    test(t, Some(1), "14:30:15.2");
+    test(t, Some(3), "14:30:15.250");
+    test(t, Some(9), "14:30:15.250000000");
+    test(MIDNIGHT, None, "00:00:00");
+    test(TimeOfDay::from_ticks(tick::DAY - 1).unwrap(), Some(3),
+         "23:59:59.999");
+    assert_eq!(format!("{:>10}", TimeOfDay::new(9, 5, 0, 0).unwrap())
+               .as_slice(), "  09:05:00");
+    // The precision gives the digits of the fraction, and does not
+    // truncate the field.
+    assert_eq!(format!("{:.3}", t).as_slice(), "14:30:15.250");
+    assert_eq!(format!("{:.0}", t).as_slice(), "14:30:15");
+    assert_eq!(format!("[{:<14.1}]", t).as_slice(), "[14:30:15.2    ]");
+}
+
+#[test]
+fn parse() {
+    fn test(s: &str, expected: Option<TimeOfDay>) {
+        let out: Option<TimeOfDay> = from_str(s);
+        if out != expected {
+            fail!("input: '{}', expected: {}, output: {}",
+                  s, expected, out);
+        }
+    }
+    test("14", TimeOfDay::new(14, 0, 0, 0));
+    test("14:30", TimeOfDay::new(14, 30, 0, 0));
+    test("14:30:15", TimeOfDay::new(14, 30, 15, 0));
+    test("14:30:15.25", TimeOfDay::new(14, 30, 15, 2500000));
+    test("14:30:15,25", TimeOfDay::new(14, 30, 15, 2500000));
+    test("14.5", TimeOfDay::new(14, 30, 0, 0));
+    test("14:30.5", TimeOfDay::new(14, 30, 30, 0));
+    test("23:59:59.99999999", None);
+    test("24", None);
+    test("14:60", None);
+    test("14:30:", None);
+    test("4:30", None);
+    test("14.5:30", None);
+    test("", None);
+}
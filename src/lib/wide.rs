@@ -0,0 +1,132 @@
+//! Arithmetic on 128-bit integers, stored as pairs of 64-bit halves.  The
+//! high half of a signed integer is signed, and the low half is unsigned.
+
+static LOW_MASK: u64 = 0xffffffff;
+
+/// Multiply two 64-bit integers, giving the high and low halves of the
+/// 128-bit product.
+pub fn mul_wide(a: u64, b: u64) -> (u64, u64) {
+    let (a0, a1) = (a & LOW_MASK, a >> 32);
+    let (b0, b1) = (b & LOW_MASK, b >> 32);
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+    let mid = (p00 >> 32) + (p01 & LOW_MASK) + (p10 & LOW_MASK);
+    (p11 + (p01 >> 32) + (p10 >> 32) + (mid >> 32),
+     (p00 & LOW_MASK) | (mid << 32))
+}
+
+/// Get the magnitude of an integer, which does not overflow for i64::MIN.
+pub fn magnitude(x: i64) -> u64 {
+    if x < 0 { !(x as u64) + 1 } else { x as u64 }
+}
+
+/// Negate a signed 128-bit integer.  Only the most negative value
+/// overflows, and it is unchanged.
+pub fn neg_signed(hi: i64, lo: u64) -> (i64, u64) {
+    let lo = !lo + 1;
+    (!hi + if lo == 0 { 1 } else { 0 }, lo)
+}
+
+/// Multiply two signed 64-bit integers, giving the 128-bit product.  The
+/// magnitude is at most 2^126, so this cannot overflow.
+pub fn mul_signed(a: i64, b: i64) -> (i64, u64) {
+    let (hi, lo) = mul_wide(magnitude(a), magnitude(b));
+    if (a < 0) != (b < 0) {
+        neg_signed(hi as i64, lo)
+    } else {
+        (hi as i64, lo)
+    }
+}
+
+/// Add two signed 128-bit integers.  Returns None on overflow.
+pub fn checked_add_signed(a: (i64, u64), b: (i64, u64))
+                          -> Option<(i64, u64)> {
+    let ((a_hi, a_lo), (b_hi, b_lo)) = (a, b);
+    let lo = a_lo + b_lo;
+    let carry = if lo < a_lo { 1 } else { 0 };
+    let hi = a_hi + b_hi + carry;
+    // Overflow gives a result with the other sign from both operands.
+    if (a_hi < 0) == (b_hi < 0) && (hi < 0) != (a_hi < 0) {
+        None
+    } else {
+        Some((hi, lo))
+    }
+}
+
+/// Narrow a signed 128-bit integer to 64 bits.  Returns None if it is out
+/// of range.
+pub fn narrow_signed(hi: i64, lo: u64) -> Option<i64> {
+    let x = lo as i64;
+    if hi == (if x < 0 { -1 } else { 0 }) { Some(x) } else { None }
+}
+
+/// Divide a 128-bit unsigned integer by a 32-bit divisor.  Returns the
+/// high and low halves of the quotient, and the remainder.
+pub fn div_small(hi: u64, lo: u64, d: u64) -> (u64, u64, u64) {
+    let mut rem = 0;
+    let mut q = [0u64, ..4];
+    let limbs = [hi >> 32, hi & LOW_MASK, lo >> 32, lo & LOW_MASK];
+    for (i, &limb) in limbs.iter().enumerate() {
+        let cur = (rem << 32) | limb;
+        q[i] = cur / d;
+        rem = cur % d;
+    }
+    ((q[0] << 32) | q[1], (q[2] << 32) | q[3], rem)
+}
+
+/// Divide a 128-bit unsigned integer by a 64-bit divisor.  Returns the
+/// quotient and the remainder, or None if the quotient does not fit in 64
+/// bits.
+pub fn div_wide(hi: u64, lo: u64, d: u64) -> Option<(u64, u64)> {
+    if hi >= d {
+        return None;
+    }
+    // Long division, one bit at a time.  The remainder is less than the
+    // divisor, so shifting it only overflows into the carry.
+    let mut rem = hi;
+    let mut q = 0;
+    for i in range(0u, 64).rev() {
+        let carry = rem >> 63;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        q <<= 1;
+        if carry != 0 || rem >= d {
+            rem -= d;
+            q |= 1;
+        }
+    }
+    Some((q, rem))
+}
+
+#[test]
+fn divide() {
+    let max = !0u64;
+    assert_eq!(div_wide(0, 100, 7), Some((14, 2)));
+    assert_eq!(div_wide(1, 0, 2), Some((1 << 63, 0)));
+    assert_eq!(div_wide(1, 0, 1), None);
+    // (2^64 - 1)^2 = (2^64 - 2) * 2^64 + 1.
+    assert_eq!(div_wide(max - 1, 1, max), Some((max, 0)));
+    assert_eq!(div_wide(max - 1, 2, max), Some((max, 1)));
+    let (hi, lo) = mul_wide(max, 12345);
+    assert_eq!(div_wide(hi, lo + 10, 12345), Some((max, 10)));
+    assert_eq!(div_small(hi, lo, 12345), (0, max, 0));
+}
+
+#[test]
+fn signed() {
+    use std::i64;
+    assert_eq!(mul_signed(-3, 5), (-1, -15i64 as u64));
+    assert_eq!(mul_signed(i64::MIN, i64::MIN), (1 << 62, 0));
+    assert_eq!(mul_signed(i64::MIN, i64::MAX),
+               (-(1 << 62), 1 << 63));
+    assert_eq!(narrow_signed(-1, -15i64 as u64), Some(-15));
+    assert_eq!(narrow_signed(0, 1 << 63), None);
+    assert_eq!(narrow_signed(-1, 1 << 63), Some(i64::MIN));
+    let big = mul_signed(i64::MIN, i64::MIN);
+    assert_eq!(checked_add_signed(big, big), None);
+    assert_eq!(checked_add_signed(big, mul_signed(i64::MIN, 1)),
+               Some(((1 << 62) - 1, 1 << 63)));
+    assert_eq!(checked_add_signed((0, !0), (0, 1)), Some((1, 0)));
+    assert_eq!(checked_add_signed((-1, 0), (0, 5)), Some((-1, 5)));
+}
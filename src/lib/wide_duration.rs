@@ -0,0 +1,179 @@
+//! Durations with a 128-bit range.
+//!
+//! A Duration holds about ±29,000 years, and multiplying durations easily
+//! overflows that.  A WideDuration stores ticks as a 128-bit integer, made
+//! from a pair of 64-bit halves, which is enough for any span of time that
+//! has physical meaning.  Any Duration converts to a WideDuration without
+//! loss, and converting back is checked.
+
+use std::fmt::{Show, Formatter, FormatError};
+use tick;
+use fmtutil;
+use duration::Duration;
+use wide::{mul_wide, div_small};
+
+/// A duration stored as a 128-bit two's complement count of ticks.
+#[deriving(PartialEq, PartialOrd, Ord, Eq, Clone, Hash)]
+pub struct WideDuration {
+    hi: i64,
+    lo: u64
+}
+
+impl WideDuration {
+    /// Create a wide duration from a number of ticks.
+    pub fn from_ticks(ticks: i64) -> WideDuration {
+        WideDuration { hi: if ticks < 0 { -1 } else { 0 }, lo: ticks as u64 }
+    }
+
+    /// Convert a duration to a wide duration.
+    pub fn from_duration(d: Duration) -> WideDuration {
+        WideDuration::from_ticks(d.ticks)
+    }
+
+    /// Narrow a wide duration to a duration.  Returns None if it is out of
+    /// range.
+    pub fn to_duration(&self) -> Option<Duration> {
+        let ticks = self.lo as i64;
+        if self.hi == (if ticks < 0 { -1 } else { 0 }) {
+            Some(Duration { ticks: ticks })
+        } else {
+            None
+        }
+    }
+
+    /// Test whether the duration is negative.
+    pub fn is_negative(&self) -> bool {
+        self.hi < 0
+    }
+
+    /// Get the magnitude as the high and low halves of an unsigned
+    /// 128-bit integer.
+    fn magnitude(&self) -> (u64, u64) {
+        if self.is_negative() {
+            let WideDuration { hi, lo } = -*self;
+            (hi as u64, lo)
+        } else {
+            (self.hi as u64, self.lo)
+        }
+    }
+
+    /// Multiply by an integer.  Returns None if the result is out of
+    /// range.
+    pub fn checked_mul(&self, n: i64) -> Option<WideDuration> {
+        let (hi, lo) = self.magnitude();
+        let m = if n < 0 { -n as u64 } else { n as u64 };
+        let (p_hi, p_lo) = mul_wide(lo, m);
+        let (q_hi, q_lo) = mul_wide(hi, m);
+        let r_hi = p_hi + q_lo;
+        let negative = self.is_negative() != (n < 0);
+        // The magnitude may be 2^127 only if the result is negative.
+        let limit = 1 << 63;
+        if q_hi != 0 || r_hi < p_hi || r_hi > limit ||
+            (r_hi == limit && (p_lo != 0 || !negative)) {
+            return None;
+        }
+        let r = WideDuration { hi: r_hi as i64, lo: p_lo };
+        Some(if negative { -r } else { r })
+    }
+}
+
+impl Add<WideDuration, WideDuration> for WideDuration {
+    fn add(&self, rhs: &WideDuration) -> WideDuration {
+        let lo = self.lo + rhs.lo;
+        let carry = if lo < self.lo { 1 } else { 0 };
+        WideDuration { hi: self.hi + rhs.hi + carry, lo: lo }
+    }
+}
+
+impl Sub<WideDuration, WideDuration> for WideDuration {
+    fn sub(&self, rhs: &WideDuration) -> WideDuration {
+        *self + -*rhs
+    }
+}
+
+impl Neg<WideDuration> for WideDuration {
+    fn neg(&self) -> WideDuration {
+        let lo = !self.lo + 1;
+        WideDuration { hi: !self.hi + if lo == 0 { 1 } else { 0 }, lo: lo }
+    }
+}
+
+impl Show for WideDuration {
+    /// Uses the same format as Duration, such as "PT-1.5S".
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        let (hi, lo) = self.magnitude();
+        let (mut hi, mut lo, frac) = div_small(hi, lo, tick::SECOND as u64);
+        let mut digits = Vec::new();
+        loop {
+            let (q_hi, q_lo, d) = div_small(hi, lo, 10);
+            digits.push('0' as u8 + d as u8);
+            hi = q_hi;
+            lo = q_lo;
+            if hi == 0 && lo == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+        let mut s = String::from_str("PT");
+        if self.is_negative() {
+            s.push_char('-');
+        }
+        s.push_str(String::from_utf8(digits).unwrap().as_slice());
+        if frac != 0 {
+            let frac = format!(".{:07u}", frac);
+            s.push_str(frac.as_slice().trim_right_chars('0'));
+        }
+        s.push_char('S');
+        fmtutil::write_field(f, s.as_bytes())
+    }
+}
+
+#[test]
+fn convert() {
+    use std::num::Bounded;
+    let max: Duration = Bounded::max_value();
+    let min: Duration = Bounded::min_value();
+    for &d in [max, min, Duration { ticks: 0 }, Duration { ticks: -1 }]
+        .iter() {
+        assert_eq!(WideDuration::from_duration(d).to_duration(), Some(d));
+    }
+    let one = WideDuration::from_ticks(1);
+    assert_eq!((WideDuration::from_duration(max) + one).to_duration(), None);
+    assert_eq!((WideDuration::from_duration(min) - one).to_duration(), None);
+    assert!(WideDuration::from_duration(min) - one <
+            WideDuration::from_duration(min));
+    assert!(WideDuration::from_ticks(-1) < WideDuration::from_ticks(0));
+}
+
+#[test]
+fn multiply() {
+    use std::num::Bounded;
+    let max: Duration = Bounded::max_value();
+    let w = WideDuration::from_duration(max);
+    let sq = w.checked_mul(max.ticks).unwrap();
+    assert_eq!(sq, WideDuration { hi: 0x3fffffffffffffff, lo: 1 });
+    assert_eq!(w.checked_mul(-3).unwrap(), -(w + w + w));
+    assert_eq!(sq.checked_mul(2),
+               Some(WideDuration { hi: 0x7ffffffffffffffe, lo: 2 }));
+    assert_eq!(sq.checked_mul(3), None);
+    assert_eq!(sq.checked_mul(-3), None);
+    let min = WideDuration { hi: Bounded::min_value(), lo: 0 };
+    assert_eq!(WideDuration { hi: -0x4000000000000000, lo: 0 }
+               .checked_mul(2), Some(min));
+    assert_eq!(WideDuration { hi: 0x4000000000000000, lo: 0 }
+               .checked_mul(-2), Some(min));
+    assert_eq!(WideDuration { hi: 0x4000000000000000, lo: 0 }
+               .checked_mul(2), None);
+}
+
+#[test]
+fn format() {
+    fn test(w: WideDuration, expected: &str) {
+        assert_eq!(format!("{}", w).as_slice(), expected);
+    }
+    test(WideDuration::from_ticks(0), "PT0S");
+    test(WideDuration::from_ticks(-15000000), "PT-1.5S");
+    test(WideDuration::from_ticks(1), "PT0.0000001S");
+    test(WideDuration { hi: 1, lo: 0 }, "PT1844674407370.9551616S");
+    test(WideDuration { hi: -1, lo: 0 }, "PT-1844674407370.9551616S");
+}
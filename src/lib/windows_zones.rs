@@ -0,0 +1,235 @@
+//! Names of time zones on Windows.
+//!
+//! Windows has its own names for time zones, such as "Pacific Standard
+//! Time", which are converted to and from names in the tz database, such as
+//! "America/Los_Angeles", using the windowsZones table from the Unicode
+//! CLDR.  Each Windows zone corresponds to many zones in the tz database;
+//! converting a Windows name gives the zone CLDR lists for the territory
+//! "001", which is the zone's principal location.
+
+use parseutil;
+use zone::Zone;
+
+/// Windows zone names and the tz database zone for the territory "001".
+static WINDOWS_ZONES: [(&'static str, &'static str), ..139] = [
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("UTC-11", "Etc/GMT+11"),
+    ("Aleutian Standard Time", "America/Adak"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Marquesas Standard Time", "Pacific/Marquesas"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("UTC-09", "Etc/GMT+9"),
+    ("Pacific Standard Time (Mexico)", "America/Tijuana"),
+    ("UTC-08", "Etc/GMT+8"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time (Mexico)", "America/Mazatlan"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Yukon Standard Time", "America/Whitehorse"),
+    ("Central America Standard Time", "America/Guatemala"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Easter Island Standard Time", "Pacific/Easter"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Eastern Standard Time (Mexico)", "America/Cancun"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Haiti Standard Time", "America/Port-au-Prince"),
+    ("Cuba Standard Time", "America/Havana"),
+    ("US Eastern Standard Time", "America/Indiana/Indianapolis"),
+    ("Turks And Caicos Standard Time", "America/Grand_Turk"),
+    ("Paraguay Standard Time", "America/Asuncion"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("Venezuela Standard Time", "America/Caracas"),
+    ("Central Brazilian Standard Time", "America/Cuiaba"),
+    ("SA Western Standard Time", "America/La_Paz"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("Tocantins Standard Time", "America/Araguaina"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("SA Eastern Standard Time", "America/Cayenne"),
+    ("Argentina Standard Time", "America/Argentina/Buenos_Aires"),
+    ("Greenland Standard Time", "America/Nuuk"),
+    ("Montevideo Standard Time", "America/Montevideo"),
+    ("Magallanes Standard Time", "America/Punta_Arenas"),
+    ("Saint Pierre Standard Time", "America/Miquelon"),
+    ("Bahia Standard Time", "America/Bahia"),
+    ("UTC-02", "Etc/GMT+2"),
+    ("Azores Standard Time", "Atlantic/Azores"),
+    ("Cape Verde Standard Time", "Atlantic/Cape_Verde"),
+    ("UTC", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("Sao Tome Standard Time", "Africa/Sao_Tome"),
+    ("Morocco Standard Time", "Africa/Casablanca"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("Jordan Standard Time", "Asia/Amman"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("Middle East Standard Time", "Asia/Beirut"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("Syria Standard Time", "Asia/Damascus"),
+    ("West Bank Standard Time", "Asia/Hebron"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("FLE Standard Time", "Europe/Kyiv"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("South Sudan Standard Time", "Africa/Juba"),
+    ("Kaliningrad Standard Time", "Europe/Kaliningrad"),
+    ("Sudan Standard Time", "Africa/Khartoum"),
+    ("Libya Standard Time", "Africa/Tripoli"),
+    ("Namibia Standard Time", "Africa/Windhoek"),
+    ("Arabic Standard Time", "Asia/Baghdad"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("Belarus Standard Time", "Europe/Minsk"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("E. Africa Standard Time", "Africa/Nairobi"),
+    ("Volgograd Standard Time", "Europe/Volgograd"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Astrakhan Standard Time", "Europe/Astrakhan"),
+    ("Azerbaijan Standard Time", "Asia/Baku"),
+    ("Russia Time Zone 3", "Europe/Samara"),
+    ("Mauritius Standard Time", "Indian/Mauritius"),
+    ("Saratov Standard Time", "Europe/Saratov"),
+    ("Georgian Standard Time", "Asia/Tbilisi"),
+    ("Caucasus Standard Time", "Asia/Yerevan"),
+    ("Afghanistan Standard Time", "Asia/Kabul"),
+    ("West Asia Standard Time", "Asia/Tashkent"),
+    ("Ekaterinburg Standard Time", "Asia/Yekaterinburg"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("Qyzylorda Standard Time", "Asia/Qyzylorda"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("Sri Lanka Standard Time", "Asia/Colombo"),
+    ("Nepal Standard Time", "Asia/Kathmandu"),
+    ("Central Asia Standard Time", "Asia/Almaty"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("Omsk Standard Time", "Asia/Omsk"),
+    ("Myanmar Standard Time", "Asia/Yangon"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("Altai Standard Time", "Asia/Barnaul"),
+    ("W. Mongolia Standard Time", "Asia/Hovd"),
+    ("North Asia Standard Time", "Asia/Krasnoyarsk"),
+    ("N. Central Asia Standard Time", "Asia/Novosibirsk"),
+    ("Tomsk Standard Time", "Asia/Tomsk"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("North Asia East Standard Time", "Asia/Irkutsk"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("Ulaanbaatar Standard Time", "Asia/Ulaanbaatar"),
+    ("Aus Central W. Standard Time", "Australia/Eucla"),
+    ("Transbaikal Standard Time", "Asia/Chita"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("North Korea Standard Time", "Asia/Pyongyang"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Yakutsk Standard Time", "Asia/Yakutsk"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("AUS Central Standard Time", "Australia/Darwin"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("West Pacific Standard Time", "Pacific/Port_Moresby"),
+    ("Tasmania Standard Time", "Australia/Hobart"),
+    ("Vladivostok Standard Time", "Asia/Vladivostok"),
+    ("Lord Howe Standard Time", "Australia/Lord_Howe"),
+    ("Bougainville Standard Time", "Pacific/Bougainville"),
+    ("Russia Time Zone 10", "Asia/Srednekolymsk"),
+    ("Magadan Standard Time", "Asia/Magadan"),
+    ("Norfolk Standard Time", "Pacific/Norfolk"),
+    ("Sakhalin Standard Time", "Asia/Sakhalin"),
+    ("Central Pacific Standard Time", "Pacific/Guadalcanal"),
+    ("Russia Time Zone 11", "Asia/Kamchatka"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+    ("UTC+12", "Etc/GMT-12"),
+    ("Fiji Standard Time", "Pacific/Fiji"),
+    ("Chatham Islands Standard Time", "Pacific/Chatham"),
+    ("UTC+13", "Etc/GMT-13"),
+    ("Tonga Standard Time", "Pacific/Tongatapu"),
+    ("Samoa Standard Time", "Pacific/Apia"),
+    ("Line Islands Standard Time", "Pacific/Kiritimati")
+];
+
+/// Other common zones in the tz database and their Windows names, sorted
+/// by the tz database name.
+static OTHER_ZONES: [(&'static str, &'static str), ..24] = [
+    ("America/Detroit", "Eastern Standard Time"),
+    ("America/Edmonton", "Mountain Standard Time"),
+    ("America/Toronto", "Eastern Standard Time"),
+    ("America/Vancouver", "Pacific Standard Time"),
+    ("America/Winnipeg", "Central Standard Time"),
+    ("Asia/Hong_Kong", "China Standard Time"),
+    ("Asia/Jakarta", "SE Asia Standard Time"),
+    ("Asia/Kuala_Lumpur", "Singapore Standard Time"),
+    ("Asia/Manila", "Singapore Standard Time"),
+    ("Australia/Melbourne", "AUS Eastern Standard Time"),
+    ("Europe/Amsterdam", "W. Europe Standard Time"),
+    ("Europe/Athens", "GTB Standard Time"),
+    ("Europe/Brussels", "Romance Standard Time"),
+    ("Europe/Copenhagen", "Romance Standard Time"),
+    ("Europe/Dublin", "GMT Standard Time"),
+    ("Europe/Helsinki", "FLE Standard Time"),
+    ("Europe/Lisbon", "GMT Standard Time"),
+    ("Europe/Madrid", "Romance Standard Time"),
+    ("Europe/Oslo", "W. Europe Standard Time"),
+    ("Europe/Prague", "Central Europe Standard Time"),
+    ("Europe/Rome", "W. Europe Standard Time"),
+    ("Europe/Stockholm", "W. Europe Standard Time"),
+    ("Europe/Vienna", "W. Europe Standard Time"),
+    ("Europe/Zurich", "W. Europe Standard Time")
+];
+
+/// Convert the name of a Windows zone, such as "Pacific Standard Time", to
+/// the name of a zone in the tz database, such as "America/Los_Angeles".
+/// Case is ignored.  Returns None if the name is unknown.
+pub fn windows_to_iana(name: &str) -> Option<&'static str> {
+    WINDOWS_ZONES.iter()
+        .find(|&&(w, _)| parseutil::eq_ignore_case(w.as_bytes(),
+                                                   name.as_bytes()))
+        .map(|&(_, iana)| iana)
+}
+
+/// Convert the name of a zone in the tz database, such as
+/// "America/Los_Angeles", to the name of a Windows zone, such as "Pacific
+/// Standard Time".  Old names are resolved first, as by
+/// Zone::canonical_name().  Returns None if the zone has no Windows name in
+/// the table.
+pub fn iana_to_windows(name: &str) -> Option<&'static str> {
+    let name = Zone::canonical_name(name);
+    match WINDOWS_ZONES.iter().find(|&&(_, iana)| iana == name) {
+        Some(&(w, _)) => return Some(w),
+        None => ()
+    }
+    OTHER_ZONES.iter()
+        .find(|&&(iana, _)| iana == name)
+        .map(|&(_, w)| w)
+}
+
+#[test]
+fn windows_names() {
+    assert_eq!(windows_to_iana("Pacific Standard Time"),
+               Some("America/Los_Angeles"));
+    assert_eq!(windows_to_iana("pacific standard time"),
+               Some("America/Los_Angeles"));
+    assert_eq!(windows_to_iana("Pacific Standard Time (Mexico)"),
+               Some("America/Tijuana"));
+    assert_eq!(windows_to_iana("W. Europe Standard Time"),
+               Some("Europe/Berlin"));
+    assert_eq!(windows_to_iana("UTC"), Some("Etc/UTC"));
+    assert_eq!(windows_to_iana("Pacific"), None);
+    assert_eq!(iana_to_windows("America/Los_Angeles"),
+               Some("Pacific Standard Time"));
+    assert_eq!(iana_to_windows("US/Pacific"), Some("Pacific Standard Time"));
+    assert_eq!(iana_to_windows("Asia/Calcutta"),
+               Some("India Standard Time"));
+    assert_eq!(iana_to_windows("Europe/Rome"),
+               Some("W. Europe Standard Time"));
+    assert_eq!(iana_to_windows("Test/Berlin"), None);
+    // Every zone in the main table converts back to its Windows name.
+    for &(w, iana) in WINDOWS_ZONES.iter() {
+        assert_eq!(iana_to_windows(iana), Some(w));
+    }
+}
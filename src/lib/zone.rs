@@ -0,0 +1,1043 @@
+//! Time zones.
+//!
+//! A zone is a list of transitions, each of which changes the local time
+//! type in effect: the UTC offset, whether it is daylight saving time, and
+//! the abbreviation.  This is the same model used by the tz database, and
+//! zones can be loaded from the TZif files it is compiled into.  Version 2
+//! and later TZif files end with a POSIX TZ string, such as
+//! "CET-1CEST,M3.5.0,M10.5.0/3", which gives the rule for instants after
+//! the last transition in the file.
+
+use std::i64;
+use std::os;
+use std::io::File;
+use std::io::fs;
+use std::num::Saturating;
+use std::str;
+use calendar_iso8601;
+use div_mod::div_mod;
+use tick;
+use instant::{Instant, UNIX_EPOCH};
+use parseutil::starts_with;
+
+/// A local time type, in effect between two transitions of a zone.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct LocalTimeType {
+    /// The offset from UTC in ticks, positive east of Greenwich.
+    pub offset: i64,
+    /// Whether this is daylight saving time.
+    pub is_dst: bool,
+    /// The abbreviation, such as "CEST".
+    pub abbreviation: String
+}
+
+/// A change from one local time type to another.
+#[deriving(PartialEq, Eq, Clone, Show)]
+struct Transition {
+    at: i64,
+    index: uint
+}
+
+/// The day of a change between standard and daylight saving time in a
+/// POSIX TZ string.
+#[deriving(PartialEq, Eq, Clone, Show)]
+enum RuleDay {
+    /// "Jn": the day of the year from 1 to 365, never counting February 29.
+    JulianDay(int),
+    /// "n": the day of the year from 0 to 365, counting February 29.
+    YearDay(int),
+    /// "Mm.w.d": the month, the week from 1 to 5, where 5 is the last, and
+    /// the day of the week, from 0 for Sunday to 6.
+    MonthWeekDay(int, int, int)
+}
+
+/// A change between standard and daylight saving time in a POSIX TZ
+/// string.
+#[deriving(PartialEq, Eq, Clone, Show)]
+struct RuleChange {
+    day: RuleDay,
+    /// The local time of the change on its day, in ticks.  This may be
+    /// negative or more than a day.
+    time: i64
+}
+
+/// The rule in a POSIX TZ string, with the change to daylight saving
+/// time and the change back, if there is daylight saving time.
+#[deriving(PartialEq, Eq, Clone, Show)]
+struct PosixRule {
+    standard: LocalTimeType,
+    daylight: Option<(LocalTimeType, RuleChange, RuleChange)>
+}
+
+/// A time zone.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub struct Zone {
+    name: String,
+    types: Vec<LocalTimeType>,
+    transitions: Vec<Transition>,
+    /// The rule after the last transition, from a TZif footer.
+    rule: Option<PosixRule>,
+    source: ZoneSource
+}
+
+/// Where the rules of a zone came from.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum ZoneSource {
+    /// The rules were supplied by the program, including TZif data passed
+    /// to Zone::from_tzif().
+    Supplied,
+    /// The rules were read from the TZif file at the given path in the
+    /// system's tz database.
+    System(String)
+}
+
+/// The result of converting a local time to an instant.
+#[deriving(PartialEq, Eq, Clone, Show)]
+pub enum LocalResult {
+    /// The local time occurs once.
+    Single(Instant),
+    /// The local time occurs twice, when the clocks are turned back.  The
+    /// earlier instant is first.
+    Ambiguous(Instant, Instant),
+    /// The local time is skipped, when the clocks are turned forward.
+    /// Contains the instant given by the offset in effect before the
+    /// skipped interval, which is the local time moved forward by the
+    /// length of the interval, as mktime does.
+    Skipped(Instant)
+}
+
+/// The directory containing TZif files, unless the ZONEINFO environment
+/// variable gives another.
+static ZONEINFO_DIR: &'static str = "/usr/share/zoneinfo";
+
+/// Get the directory containing TZif files.
+fn zoneinfo_dir() -> Path {
+    match os::getenv("ZONEINFO") {
+        Some(d) => Path::new(d), None => Path::new(ZONEINFO_DIR)
+    }
+}
+
+/// Test whether a file starts with the TZif magic number.
+fn is_tzif_file(path: &Path) -> bool {
+    match File::open(path).read_exact(4) {
+        Ok(magic) => magic.as_slice() == "TZif".as_bytes(),
+        Err(_) => false
+    }
+}
+
+/// Get the version of the system's tz database, such as "2014e", which is
+/// read from the "+VERSION" file or the first line of the "tzdata.zi" file
+/// in the directory used by Zone::from_name().  Returns None if neither
+/// file gives a version, which is common, since many systems install
+/// neither file.
+pub fn tzdb_version() -> Option<String> {
+    let dir = zoneinfo_dir();
+    match File::open(&dir.join("+VERSION")).read_to_string() {
+        Ok(s) if !s.as_slice().trim().is_empty() =>
+            return Some(String::from_str(s.as_slice().trim())),
+        _ => ()
+    }
+    let data = match File::open(&dir.join("tzdata.zi")).read_to_string() {
+        Ok(s) => s, Err(_) => return None
+    };
+    let line = data.as_slice().lines().next().unwrap_or("");
+    if line.starts_with("# version ") {
+        let version = line.slice_from(10).trim();
+        if !version.is_empty() {
+            return Some(String::from_str(version));
+        }
+    }
+    None
+}
+
+/// Common links in the tz database from an old name to the current name
+/// of a zone, sorted by the old name.
+static LINKS: [(&'static str, &'static str), ..26] = [
+    ("America/Buenos_Aires", "America/Argentina/Buenos_Aires"),
+    ("America/Indianapolis", "America/Indiana/Indianapolis"),
+    ("America/Louisville", "America/Kentucky/Louisville"),
+    ("Asia/Calcutta", "Asia/Kolkata"),
+    ("Asia/Katmandu", "Asia/Kathmandu"),
+    ("Asia/Rangoon", "Asia/Yangon"),
+    ("Asia/Saigon", "Asia/Ho_Chi_Minh"),
+    ("Atlantic/Faeroe", "Atlantic/Faroe"),
+    ("Etc/Zulu", "Etc/UTC"),
+    ("Europe/Kiev", "Europe/Kyiv"),
+    ("GB", "Europe/London"),
+    ("GMT", "Etc/GMT"),
+    ("Hongkong", "Asia/Hong_Kong"),
+    ("Japan", "Asia/Tokyo"),
+    ("PRC", "Asia/Shanghai"),
+    ("Pacific/Truk", "Pacific/Chuuk"),
+    ("ROK", "Asia/Seoul"),
+    ("Singapore", "Asia/Singapore"),
+    ("US/Central", "America/Chicago"),
+    ("US/Eastern", "America/New_York"),
+    ("US/Hawaii", "Pacific/Honolulu"),
+    ("US/Mountain", "America/Denver"),
+    ("US/Pacific", "America/Los_Angeles"),
+    ("UTC", "Etc/UTC"),
+    ("Universal", "Etc/UTC"),
+    ("Zulu", "Etc/UTC")
+];
+
+/// Read a big-endian signed integer of the given number of bytes.
+fn read_be(s: &[u8], size: uint) -> i64 {
+    let mut n: i64 = if (s[0] & 0x80) != 0 { -1 } else { 0 };
+    for &b in s.slice_to(size).iter() {
+        n = (n << 8) | b as i64;
+    }
+    n
+}
+
+/// The local time types and transitions of a zone read from TZif data.
+/// Transitions are in seconds since the Unix epoch.
+struct TzifBlock {
+    types: Vec<LocalTimeType>,
+    transitions: Vec<(i64, uint)>,
+    len: uint
+}
+
+/// Read the header and data block of TZif data, whose transition times
+/// have the given number of bytes.  Returns None if the data is invalid.
+fn read_tzif_block(s: &[u8], time_size: uint) -> Option<TzifBlock> {
+    if s.len() < 44 || s.slice_to(4) != "TZif".as_bytes() {
+        return None;
+    }
+    let count = |i: uint| read_be(s.slice_from(20 + i * 4), 4) as u32 as uint;
+    let (isutcnt, isstdcnt, leapcnt) = (count(0), count(1), count(2));
+    let (timecnt, typecnt, charcnt) = (count(3), count(4), count(5));
+    let times = 44;
+    let indices = times + timecnt * time_size;
+    let infos = indices + timecnt;
+    let chars = infos + typecnt * 6;
+    let len = chars + charcnt + leapcnt * (time_size + 4) + isstdcnt +
+        isutcnt;
+    if s.len() < len || typecnt == 0 {
+        return None;
+    }
+    let mut types = Vec::with_capacity(typecnt);
+    for i in range(0, typecnt) {
+        let info = s.slice(infos + i * 6, infos + i * 6 + 6);
+        if info[5] as uint >= charcnt {
+            return None;
+        }
+        let abbr = s.slice(chars + info[5] as uint, chars + charcnt);
+        let abbr = abbr.slice_to(abbr.iter().position(|&c| c == 0)
+                                 .unwrap_or(abbr.len()));
+        types.push(LocalTimeType {
+            offset: read_be(info, 4) * tick::SECOND,
+            is_dst: info[4] != 0,
+            abbreviation: match str::from_utf8(abbr) {
+                Some(a) => String::from_str(a), None => return None
+            }
+        });
+    }
+    let mut transitions = Vec::with_capacity(timecnt);
+    for i in range(0, timecnt) {
+        let at = read_be(s.slice_from(times + i * time_size), time_size);
+        transitions.push((at, s[indices + i] as uint));
+    }
+    Some(TzifBlock { types: types, transitions: transitions, len: len })
+}
+
+/// Read a number of up to three digits which is at most the given maximum.
+fn read_rule_number<'a>(s: &'a [u8], max: int) -> Option<(int, &'a [u8])> {
+    let digits = s.iter().take(3)
+        .take_while(|&&c| c >= '0' as u8 && c <= '9' as u8)
+        .count();
+    if digits == 0 {
+        return None;
+    }
+    let n = s.slice_to(digits).iter()
+        .fold(0, |n, &c| n * 10 + (c - '0' as u8) as int);
+    if n > max {
+        return None;
+    }
+    Some((n, s.slice_from(digits)))
+}
+
+/// Read an abbreviation in a POSIX TZ string, which is either three or
+/// more letters, or three or more characters in angle brackets.
+fn read_rule_name<'a>(s: &'a [u8]) -> Option<(String, &'a [u8])> {
+    let (name, rest) = if starts_with(s, '<') {
+        match s.iter().position(|&c| c == '>' as u8) {
+            Some(end) => (s.slice(1, end), s.slice_from(end + 1)),
+            None => return None
+        }
+    } else {
+        let len = s.iter()
+            .take_while(|&&c| c < 128 && (c as char).is_alphabetic())
+            .count();
+        (s.slice_to(len), s.slice_from(len))
+    };
+    if name.len() < 3 {
+        return None;
+    }
+    str::from_utf8(name).map(|n| (String::from_str(n), rest))
+}
+
+/// Read a time in a POSIX TZ string, "hh[:mm[:ss]]" with an optional
+/// sign and at most the given number of hours.  Returns the time in ticks.
+fn read_rule_time<'a>(s: &'a [u8], max_hours: int)
+                      -> Option<(i64, &'a [u8])> {
+    let (sign, mut s) = if starts_with(s, '-') {
+        (-1, s.slice_from(1))
+    } else if starts_with(s, '+') {
+        (1, s.slice_from(1))
+    } else {
+        (1, s)
+    };
+    let mut total = 0;
+    for (i, &unit) in [tick::HOUR, tick::MINUTE, tick::SECOND].iter()
+        .enumerate() {
+        if i > 0 {
+            if !starts_with(s, ':') {
+                break;
+            }
+            s = s.slice_from(1);
+        }
+        let max = if i == 0 { max_hours } else { 59 };
+        let (n, rest) = match read_rule_number(s, max) {
+            Some(x) => x, None => return None
+        };
+        total += n as i64 * unit;
+        s = rest;
+    }
+    Some((sign * total, s))
+}
+
+/// Read the day and optional time of a change in a POSIX TZ string, such
+/// as "M3.5.0" or "J60/3".  The time is 02:00 if it is not given.
+fn read_rule_change<'a>(s: &'a [u8]) -> Option<(RuleChange, &'a [u8])> {
+    let (day, s) = if starts_with(s, 'J') {
+        match read_rule_number(s.slice_from(1), 365) {
+            Some((n, rest)) if n >= 1 => (JulianDay(n), rest),
+            _ => return None
+        }
+    } else if starts_with(s, 'M') {
+        let mut fields = [0, ..3];
+        let mut s = s.slice_from(1);
+        for (i, &(min, max)) in [(1, 12), (1, 5), (0, 6)].iter().enumerate() {
+            if i > 0 {
+                if !starts_with(s, '.') {
+                    return None;
+                }
+                s = s.slice_from(1);
+            }
+            match read_rule_number(s, max) {
+                Some((n, rest)) if n >= min => {
+                    fields[i] = n;
+                    s = rest;
+                }
+                _ => return None
+            }
+        }
+        (MonthWeekDay(fields[0], fields[1], fields[2]), s)
+    } else {
+        match read_rule_number(s, 365) {
+            Some((n, rest)) => (YearDay(n), rest),
+            None => return None
+        }
+    };
+    let (time, s) = if starts_with(s, '/') {
+        match read_rule_time(s.slice_from(1), 167) {
+            Some(x) => x, None => return None
+        }
+    } else {
+        (2 * tick::HOUR, s)
+    };
+    Some((RuleChange { day: day, time: time }, s))
+}
+
+/// Parse a POSIX TZ string, such as "CET-1CEST,M3.5.0,M10.5.0/3".
+/// Returns None if the string is invalid, or if it has daylight saving
+/// time without a rule for when it starts and ends, since the default
+/// rule depends on the system.
+fn parse_posix_rule(s: &[u8]) -> Option<PosixRule> {
+    fn local_type(abbreviation: String, offset: i64, is_dst: bool)
+                  -> Option<LocalTimeType> {
+        if offset <= -tick::DAY || offset >= tick::DAY {
+            return None;
+        }
+        Some(LocalTimeType {
+            offset: offset,
+            is_dst: is_dst,
+            abbreviation: abbreviation
+        })
+    }
+    let (name, s) = match read_rule_name(s) {
+        Some(x) => x, None => return None
+    };
+    // Offsets in POSIX TZ strings are positive west of Greenwich.
+    let (offset, s) = match read_rule_time(s, 24) {
+        Some((offset, rest)) => (-offset, rest),
+        None => return None
+    };
+    let standard = match local_type(name, offset, false) {
+        Some(t) => t, None => return None
+    };
+    if s.is_empty() {
+        return Some(PosixRule { standard: standard, daylight: None });
+    }
+    let (name, s) = match read_rule_name(s) {
+        Some(x) => x, None => return None
+    };
+    let (offset, s) = if starts_with(s, ',') {
+        (standard.offset + tick::HOUR, s)
+    } else {
+        match read_rule_time(s, 24) {
+            Some((offset, rest)) => (-offset, rest),
+            None => return None
+        }
+    };
+    let daylight = match local_type(name, offset, true) {
+        Some(t) => t, None => return None
+    };
+    if !starts_with(s, ',') {
+        return None;
+    }
+    let (start, s) = match read_rule_change(s.slice_from(1)) {
+        Some(x) => x, None => return None
+    };
+    if !starts_with(s, ',') {
+        return None;
+    }
+    let (end, s) = match read_rule_change(s.slice_from(1)) {
+        Some(x) => x, None => return None
+    };
+    if !s.is_empty() {
+        return None;
+    }
+    Some(PosixRule {
+        standard: standard,
+        daylight: Some((daylight, start, end))
+    })
+}
+
+/// Read the footer of version 2 and later TZif data, which is a POSIX TZ
+/// string between newlines.  Returns None if the footer is invalid, and
+/// Some(None) if it is empty.
+fn read_tzif_footer(s: &[u8]) -> Option<Option<PosixRule>> {
+    if !starts_with(s, '\n') {
+        return None;
+    }
+    let s = s.slice_from(1);
+    match s.iter().position(|&c| c == '\n' as u8) {
+        Some(0) => Some(None),
+        Some(end) => parse_posix_rule(s.slice_to(end)).map(|r| Some(r)),
+        None => None
+    }
+}
+
+impl RuleDay {
+    /// Get the chronological Julian day of the rule in a year.
+    fn to_cjd(&self, year: int) -> int {
+        let start = calendar_iso8601::to_cjd(year, 1, 1);
+        match *self {
+            JulianDay(n) if n >= 60 && calendar_iso8601::is_leap_year(year) =>
+                start + n,
+            JulianDay(n) => start + n - 1,
+            YearDay(n) => start + n,
+            MonthWeekDay(month, week, day) => {
+                let first = calendar_iso8601::to_cjd(year, month, 1);
+                // The weekday of the first, from 0 for Sunday.
+                let first_day = calendar_iso8601::weekday(first) % 7;
+                let cjd = first + (day - first_day + 7) % 7 + (week - 1) * 7;
+                let len = calendar_iso8601::days_in_month(year, month);
+                if cjd - first >= len { cjd - 7 } else { cjd }
+            }
+        }
+    }
+}
+
+impl RuleChange {
+    /// Get the instant of the change in a year, in ticks, given the offset
+    /// in effect before the change.  Saturates on overflow.
+    fn at(&self, year: int, offset: i64) -> i64 {
+        let cjd = self.day.to_cjd(year) as i64;
+        cjd.checked_mul(&tick::DAY)
+            .and_then(|n| n.checked_add(&(self.time - offset)))
+            .unwrap_or(if cjd < 0 { i64::MIN } else { i64::MAX })
+    }
+}
+
+impl PosixRule {
+    /// Get the local time type in effect at a number of ticks since the
+    /// epoch.
+    fn type_at<'a>(&'a self, ticks: i64) -> &'a LocalTimeType {
+        let &(ref daylight, ref start, ref end) = match self.daylight {
+            Some(ref d) => d, None => return &self.standard
+        };
+        let local = ticks.saturating_add(self.standard.offset);
+        let (cjd, _) = div_mod(local, tick::DAY);
+        let (year, _, _) = calendar_iso8601::from_cjd(cjd as int);
+        let start = start.at(year, self.standard.offset);
+        let end = end.at(year, daylight.offset);
+        // In the southern hemisphere, daylight saving time spans the end
+        // of the year.
+        let is_dst = if start <= end {
+            ticks >= start && ticks < end
+        } else {
+            ticks >= start || ticks < end
+        };
+        if is_dst { daylight } else { &self.standard }
+    }
+}
+
+impl Zone {
+    /// Create a zone with a fixed offset from UTC, in ticks.
+    pub fn fixed(name: &str, offset: i64) -> Zone {
+        Zone {
+            name: String::from_str(name),
+            types: vec![LocalTimeType {
+                offset: offset,
+                is_dst: false,
+                abbreviation: String::from_str(name)
+            }],
+            transitions: Vec::new(),
+            rule: None,
+            source: Supplied
+        }
+    }
+
+    /// Create a zone from a list of local time types and a list of
+    /// transitions, each of which gives the index of the type in effect
+    /// from that instant on.  The first type is in effect before the
+    /// first transition.  Returns None if there are no types, if the
+    /// transitions are not in increasing order, if any index is out of
+    /// range, or if any offset is a day or more.
+    pub fn new(name: &str, types: Vec<LocalTimeType>,
+               transitions: &[(Instant, uint)]) -> Option<Zone> {
+        if types.is_empty() ||
+            types.iter().any(|t| t.offset <= -tick::DAY ||
+                             t.offset >= tick::DAY) {
+            return None;
+        }
+        let mut list: Vec<Transition> =
+            Vec::with_capacity(transitions.len());
+        for &(at, index) in transitions.iter() {
+            if index >= types.len() {
+                return None;
+            }
+            match list.last() {
+                Some(prev) if prev.at >= at.ticks() => return None,
+                _ => ()
+            }
+            list.push(Transition { at: at.ticks(), index: index });
+        }
+        Some(Zone {
+            name: String::from_str(name),
+            types: types,
+            transitions: list,
+            rule: None,
+            source: Supplied
+        })
+    }
+
+    /// Create a zone from TZif data, the format of the compiled tz
+    /// database described in RFC 8536.  Transitions outside the range of an
+    /// instant are ignored.  In version 2 and later, the POSIX TZ string in
+    /// the footer gives the local time types after the last transition.
+    /// Returns None if the data or the TZ string is invalid.
+    pub fn from_tzif(name: &str, data: &[u8]) -> Option<Zone> {
+        let v1 = match read_tzif_block(data, 4) {
+            Some(b) => b, None => return None
+        };
+        // Version 2 and later repeat the data with 64-bit times, followed
+        // by the footer.
+        let (block, rule) = if data[4] >= '2' as u8 {
+            let data = data.slice_from(v1.len);
+            let block = match read_tzif_block(data, 8) {
+                Some(b) => b, None => return None
+            };
+            match read_tzif_footer(data.slice_from(block.len)) {
+                Some(rule) => (block, rule),
+                None => return None
+            }
+        } else {
+            (v1, None)
+        };
+        let min = tick::to_larger_unit_ceil(i64::MIN - UNIX_EPOCH.ticks(),
+                                            tick::SECOND);
+        let max = tick::to_larger_unit_floor(i64::MAX + UNIX_EPOCH.ticks(),
+                                             tick::SECOND);
+        let mut types = block.types;
+        let mut initial = None;
+        let mut transitions = Vec::with_capacity(block.transitions.len());
+        for &(at, index) in block.transitions.iter() {
+            if index >= types.len() {
+                return None;
+            }
+            if at < min {
+                initial = Some(index);
+            } else if at <= max {
+                let t = UNIX_EPOCH.ticks() + at * tick::SECOND;
+                transitions.push((Instant::from_ticks(t), index));
+            }
+        }
+        // A transition before the range of instants gives the type in
+        // effect from the start of the range, which must be first.
+        match initial {
+            Some(index) => {
+                let first = types[index].clone();
+                types.insert(0, first);
+                for t in transitions.mut_iter() {
+                    let (at, index) = *t;
+                    *t = (at, index + 1);
+                }
+            }
+            None => ()
+        }
+        Zone::new(name, types, transitions.as_slice()).map(|mut z| {
+            z.rule = rule;
+            z
+        })
+    }
+
+    /// Get the current name of a zone in the tz database, following common
+    /// links from old names, such as "Asia/Calcutta" to "Asia/Kolkata".
+    /// Other names are returned unchanged.
+    pub fn canonical_name<'a>(name: &'a str) -> &'a str {
+        match LINKS.iter().find(|&&(old, _)| old == name) {
+            Some(&(_, new)) => new,
+            None => name
+        }
+    }
+
+    /// Load a zone from the system's tz database by name, such as
+    /// "Europe/Berlin".  Old names are resolved to the current name, both
+    /// from common links and from symbolic links in the database, and the
+    /// zone has the current name.  The database is read from the directory
+    /// in the ZONEINFO environment variable, or from /usr/share/zoneinfo.
+    /// Returns None if there is no such zone or its file is invalid.
+    pub fn from_name(name: &str) -> Option<Zone> {
+        if name.is_empty() || name.starts_with("/") ||
+            name.split('/').any(|part| part == "..") {
+            return None;
+        }
+        let dir = zoneinfo_dir();
+        let name = Zone::canonical_name(name);
+        let path = dir.join(name);
+        let linked = match fs::readlink(&path) {
+            Ok(target) => path.dir_path().join(target)
+                .path_relative_from(&dir),
+            Err(_) => None
+        };
+        let name = match linked.as_ref().and_then(|p| p.as_str()) {
+            Some(n) if !n.starts_with("..") => String::from_str(n),
+            _ => String::from_str(name)
+        };
+        let data = match File::open(&path).read_to_end() {
+            Ok(data) => data, Err(_) => return None
+        };
+        Zone::from_tzif(name.as_slice(), data.as_slice()).map(|mut z| {
+            z.source = System(path.display().to_string());
+            z
+        })
+    }
+
+    /// List the names of the zones in the system's tz database, which is
+    /// found in the same way as by from_name(), in sorted order.  Both
+    /// current names and old names which link to them are included, but
+    /// not the copies of the database in the "posix" and "right"
+    /// directories.  Returns an empty list if there is no database.
+    pub fn available_names() -> Vec<String> {
+        let dir = zoneinfo_dir();
+        let paths = match fs::walk_dir(&dir) {
+            Ok(paths) => paths, Err(_) => return Vec::new()
+        };
+        let mut names: Vec<String> = paths
+            .filter_map(|p| p.path_relative_from(&dir))
+            .filter(|p| {
+                let top = p.components().next();
+                top != Some("posix".as_bytes()) &&
+                    top != Some("right".as_bytes())
+            })
+            .filter(|p| is_tzif_file(&dir.join(p)))
+            .filter_map(|p| p.as_str().map(|n| String::from_str(n)))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Get the zone's name.
+    pub fn name<'a>(&'a self) -> &'a str {
+        self.name.as_slice()
+    }
+
+    /// Get where the zone's rules came from.
+    pub fn source<'a>(&'a self) -> &'a ZoneSource {
+        &self.source
+    }
+
+    /// Get the local time type in effect at an instant.
+    fn type_at<'a>(&'a self, t: Instant) -> &'a LocalTimeType {
+        match self.rule {
+            Some(ref rule) => match self.transitions.last() {
+                Some(last) if last.at > t.ticks() => (),
+                _ => return rule.type_at(t.ticks())
+            },
+            None => ()
+        }
+        // Find the number of transitions at or before the instant.
+        let (mut lo, mut hi) = (0u, self.transitions.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.transitions[mid].at <= t.ticks() {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        if lo == 0 {
+            &self.types[0]
+        } else {
+            &self.types[self.transitions[lo - 1].index]
+        }
+    }
+
+    /// Get the offset from UTC in effect at an instant, in ticks.
+    pub fn offset_at(&self, t: Instant) -> i64 {
+        self.type_at(t).offset
+    }
+
+    /// Test whether daylight saving time is in effect at an instant.
+    pub fn is_dst_at(&self, t: Instant) -> bool {
+        self.type_at(t).is_dst
+    }
+
+    /// Get the abbreviation in effect at an instant, such as "CEST".
+    pub fn abbreviation_at<'a>(&'a self, t: Instant) -> &'a str {
+        self.type_at(t).abbreviation.as_slice()
+    }
+
+    /// Get the offset in effect at a number of ticks since the epoch,
+    /// saturating on overflow.
+    fn offset_near(&self, ticks: i64, delta: i64) -> i64 {
+        self.offset_at(Instant::from_ticks(ticks.saturating_add(delta)))
+    }
+
+    /// Convert a local time, as a number of ticks since the epoch on the
+    /// local time scale, to an instant.
+    pub fn from_local(&self, local: i64) -> LocalResult {
+        // Transitions are assumed to be more than two days apart, so only
+        // the offsets a day either side can apply.
+        let before = self.offset_near(local, -tick::DAY);
+        let after = self.offset_near(local, tick::DAY);
+        let before_valid = self.offset_near(local, -before) == before;
+        let after_valid = self.offset_near(local, -after) == after;
+        if before == after || (before_valid && !after_valid) {
+            Single(Instant::from_ticks(local - before))
+        } else if after_valid && !before_valid {
+            Single(Instant::from_ticks(local - after))
+        } else if before_valid {
+            Ambiguous(Instant::from_ticks(local - before),
+                      Instant::from_ticks(local - after))
+        } else {
+            Skipped(Instant::from_ticks(local - before))
+        }
+    }
+}
+
+/// A zone similar to Europe/Berlin, with transitions only in 2014.
+#[cfg(test)]
+pub fn test_zone() -> Zone {
+    use calendar_iso8601;
+    let cet = LocalTimeType {
+        offset: tick::HOUR,
+        is_dst: false,
+        abbreviation: String::from_str("CET")
+    };
+    let cest = LocalTimeType {
+        offset: tick::HOUR * 2,
+        is_dst: true,
+        abbreviation: String::from_str("CEST")
+    };
+    let at = |m: int, d: int| Instant::from_ticks(
+        calendar_iso8601::to_cjd(2014, m, d) as i64 * tick::DAY + tick::HOUR);
+    Zone::new("Test/Berlin", vec![cet, cest],
+              &[(at(3, 30), 1), (at(10, 26), 0)]).unwrap()
+}
+
+#[test]
+fn offsets() {
+    let z = test_zone();
+    let spring = 5202 * tick::DAY + tick::HOUR;
+    assert_eq!(z.name(), "Test/Berlin");
+    assert_eq!(z.offset_at(Instant::from_ticks(0)), tick::HOUR);
+    assert_eq!(z.offset_at(Instant::from_ticks(spring - 1)), tick::HOUR);
+    assert_eq!(z.offset_at(Instant::from_ticks(spring)), tick::HOUR * 2);
+    assert_eq!(z.offset_at(Instant::from_ticks(spring * 2)), tick::HOUR);
+    let f = Zone::fixed("UTC", 0);
+    assert_eq!(f.offset_at(Instant::from_ticks(spring)), 0);
+    assert!(!z.is_dst_at(Instant::from_ticks(spring - 1)));
+    assert!(z.is_dst_at(Instant::from_ticks(spring)));
+    assert!(!f.is_dst_at(Instant::from_ticks(spring)));
+    assert_eq!(z.abbreviation_at(Instant::from_ticks(spring - 1)), "CET");
+    assert_eq!(z.abbreviation_at(Instant::from_ticks(spring)), "CEST");
+    assert_eq!(f.abbreviation_at(Instant::from_ticks(spring)), "UTC");
+    assert!(Zone::new("X", Vec::new(), &[]).is_none());
+    assert!(Zone::new("X", vec![LocalTimeType {
+        offset: tick::DAY, is_dst: false, abbreviation: String::new()
+    }], &[]).is_none());
+}
+
+#[test]
+fn from_local() {
+    let z = test_zone();
+    // 2014-03-30 and 2014-10-26.
+    let spring = 5202 * tick::DAY;
+    let autumn = 5412 * tick::DAY;
+    let h = tick::HOUR;
+    assert_eq!(z.from_local(spring + h), Single(Instant::from_ticks(spring)));
+    assert_eq!(z.from_local(spring + h * 5 / 2),
+               Skipped(Instant::from_ticks(spring + h * 3 / 2)));
+    assert_eq!(z.from_local(spring + h * 3),
+               Single(Instant::from_ticks(spring + h)));
+    assert_eq!(z.from_local(autumn + h * 5 / 2),
+               Ambiguous(Instant::from_ticks(autumn + h / 2),
+                         Instant::from_ticks(autumn + h * 3 / 2)));
+    assert_eq!(z.from_local(autumn + h * 3),
+               Single(Instant::from_ticks(autumn + h * 2)));
+}
+
+/// Version 1 TZif data for CET and CEST, with transitions in 2014, the
+/// same as test_zone().
+#[cfg(test)]
+fn test_tzif() -> Vec<u8> {
+    fn be(v: &mut Vec<u8>, n: i64) {
+        for i in range(0u, 4).rev() {
+            v.push((n >> (i * 8)) as u8);
+        }
+    }
+    let mut data = Vec::new();
+    data.push_all("TZif".as_bytes());
+    data.grow(16, &0u8);
+    for &n in [0, 0, 0, 2, 2, 9].iter() {
+        be(&mut data, n);
+    }
+    be(&mut data, 1396141200);
+    be(&mut data, 1414285200);
+    data.push_all(&[1u8, 0]);
+    be(&mut data, 3600);
+    data.push_all(&[0u8, 0]);
+    be(&mut data, 7200);
+    data.push_all(&[1u8, 4]);
+    data.push_all("CET\0CEST\0".as_bytes());
+    data
+}
+
+#[test]
+fn tzif() {
+    let data = test_tzif();
+    let z = Zone::from_tzif("Test/Berlin", data.as_slice()).unwrap();
+    assert_eq!(z, test_zone());
+    assert_eq!(*z.source(), Supplied);
+    assert!(Zone::from_tzif("X", data.slice_to(50)).is_none());
+    assert!(Zone::from_tzif("X", "TZif".as_bytes()).is_none());
+}
+
+#[test]
+fn posix_rule() {
+    fn at(y: int, m: int, d: int, hour: i64) -> i64 {
+        calendar_iso8601::to_cjd(y, m, d) as i64 * tick::DAY +
+            hour * tick::HOUR
+    }
+    fn test(rule: &str, ticks: i64, expected: Option<(i64, &str)>) {
+        let out = parse_posix_rule(rule.as_bytes()).map(|r| {
+            let t = r.type_at(ticks);
+            (t.offset / tick::MINUTE, t.abbreviation.clone())
+        });
+        let expected = expected.map(|(minutes, abbr)| {
+            (minutes, String::from_str(abbr))
+        });
+        if out != expected {
+            fail!("input: {} {}, expected: {}, output: {}",
+                  rule, ticks, expected, out);
+        }
+    }
+    let berlin = "CET-1CEST,M3.5.0,M10.5.0/3";
+    test(berlin, at(2015, 3, 29, 1) - 1, Some((60, "CET")));
+    test(berlin, at(2015, 3, 29, 1), Some((120, "CEST")));
+    test(berlin, at(2015, 10, 25, 1) - 1, Some((120, "CEST")));
+    test(berlin, at(2015, 10, 25, 1), Some((60, "CET")));
+    test(berlin, at(2100, 7, 1, 0), Some((120, "CEST")));
+    let sydney = "AEST-10AEDT,M10.1.0,M4.1.0/3";
+    test(sydney, at(2015, 1, 15, 0), Some((660, "AEDT")));
+    test(sydney, at(2015, 7, 1, 0), Some((600, "AEST")));
+    test(sydney, at(2015, 4, 4, 16) - 1, Some((660, "AEDT")));
+    test(sydney, at(2015, 4, 4, 16), Some((600, "AEST")));
+    test(sydney, at(2015, 10, 3, 16), Some((660, "AEDT")));
+    let new_york = "EST5EDT,M3.2.0,M11.1.0";
+    test(new_york, at(2015, 3, 8, 7) - 1, Some((-300, "EST")));
+    test(new_york, at(2015, 3, 8, 7), Some((-240, "EDT")));
+    test("<+0330>-3:30", at(2015, 7, 1, 0), Some((210, "+0330")));
+    test("<-03>3", at(2015, 7, 1, 0), Some((-180, "-03")));
+    let julian = "XXX3YYY,J60,300/1:30";
+    test(julian, at(2015, 3, 1, 5) - 1, Some((-180, "XXX")));
+    test(julian, at(2016, 3, 1, 5), Some((-120, "YYY")));
+    let zero_based = "XXX3YYY,59,300/1:30";
+    let end = at(2016, 10, 27, 3) + 30 * tick::MINUTE;
+    test(zero_based, at(2016, 2, 29, 5), Some((-120, "YYY")));
+    test(zero_based, end - 1, Some((-120, "YYY")));
+    test(zero_based, end, Some((-180, "XXX")));
+    test("EST5EDT,0/0,J365/25", at(2015, 7, 1, 0), Some((-240, "EDT")));
+    test("EST5EDT", 0, None);
+    test("EST", 0, None);
+    test("EST5EDT,M3.2.0", 0, None);
+    test("EST5EDT,M13.2.0,M11.1.0", 0, None);
+    test("EST5EDT,M3.6.0,M11.1.0", 0, None);
+    test("EST5EDT,J0,J365", 0, None);
+    test("EST25", 0, None);
+    test("EST5 ", 0, None);
+    test("<+03", 0, None);
+}
+
+#[test]
+fn tzif_footer() {
+    // Version 2 data for CET and CEST, with transitions in 2014.
+    fn tzif(footer: &str) -> Vec<u8> {
+        fn be(v: &mut Vec<u8>, n: i64, size: uint) {
+            for i in range(0u, size).rev() {
+                v.push((n >> (i * 8)) as u8);
+            }
+        }
+        fn block(data: &mut Vec<u8>, size: uint) {
+            data.push_all("TZif2".as_bytes());
+            data.grow(15, &0u8);
+            for &n in [0, 0, 0, 2, 2, 9].iter() {
+                be(data, n, 4);
+            }
+            be(data, 1396141200, size);
+            be(data, 1414285200, size);
+            data.push_all(&[1u8, 0]);
+            be(data, 3600, 4);
+            data.push_all(&[0u8, 0]);
+            be(data, 7200, 4);
+            data.push_all(&[1u8, 4]);
+            data.push_all("CET\0CEST\0".as_bytes());
+        }
+        let mut data = Vec::new();
+        block(&mut data, 4);
+        block(&mut data, 8);
+        data.push_all(footer.as_bytes());
+        data
+    }
+    let at = |y: int, m: int, d: int, hour: i64| Instant::from_ticks(
+        calendar_iso8601::to_cjd(y, m, d) as i64 * tick::DAY +
+            hour * tick::HOUR);
+    let z = Zone::from_tzif("Test/Berlin", tzif("\n\n").as_slice())
+        .unwrap();
+    assert_eq!(z.offset_at(at(2014, 7, 1, 0)), tick::HOUR * 2);
+    assert_eq!(z.offset_at(at(2015, 7, 1, 0)), tick::HOUR);
+    let data = tzif("\nCET-1CEST,M3.5.0,M10.5.0/3\n");
+    let z = Zone::from_tzif("Test/Berlin", data.as_slice()).unwrap();
+    assert_eq!(z.offset_at(at(2013, 7, 1, 0)), tick::HOUR);
+    assert_eq!(z.offset_at(at(2014, 7, 1, 0)), tick::HOUR * 2);
+    assert_eq!(z.offset_at(at(2014, 12, 1, 0)), tick::HOUR);
+    assert_eq!(z.offset_at(at(2015, 7, 1, 0)), tick::HOUR * 2);
+    assert_eq!(z.abbreviation_at(at(2015, 7, 1, 0)), "CEST");
+    assert!(z.is_dst_at(at(2015, 7, 1, 0)));
+    assert_eq!(z.offset_at(at(2015, 12, 1, 0)), tick::HOUR);
+    assert_eq!(z.offset_at(at(2040, 7, 1, 0)), tick::HOUR * 2);
+    let half = tick::HOUR / 2;
+    let skipped = Instant::from_ticks(at(2015, 3, 29, 1).ticks() + half);
+    assert_eq!(z.from_local(at(2015, 3, 29, 2).ticks() + half),
+               Skipped(skipped));
+    assert!(Zone::from_tzif("X", tzif("").as_slice()).is_none());
+    assert!(Zone::from_tzif("X", tzif("\n").as_slice()).is_none());
+    assert!(Zone::from_tzif("X", tzif("\nCET-1CEST\n").as_slice())
+            .is_none());
+}
+
+#[cfg(test)]
+fn names() {
+    assert_eq!(Zone::canonical_name("Asia/Calcutta"), "Asia/Kolkata");
+    assert_eq!(Zone::canonical_name("US/Pacific"), "America/Los_Angeles");
+    assert_eq!(Zone::canonical_name("Europe/Berlin"), "Europe/Berlin");
+    assert!(Zone::from_name("../etc/passwd").is_none());
+    assert!(Zone::from_name("/etc/passwd").is_none());
+    assert!(Zone::from_name("No/Such_Zone").is_none());
+    if !Path::new(ZONEINFO_DIR).join("Asia/Kolkata").exists() {
+        return;
+    }
+    let z = Zone::from_name("Asia/Calcutta").unwrap();
+    assert_eq!(z.name(), "Asia/Kolkata");
+    match *z.source() {
+        System(ref path) => assert!(path.as_slice().ends_with("Asia/Kolkata")),
+        Supplied => fail!("expected a system zone")
+    }
+    let t = Instant::from_ticks(5274 * tick::DAY);
+    assert_eq!(z.offset_at(t), tick::HOUR * 11 / 2);
+    assert_eq!(z.abbreviation_at(t), "IST");
+    // Years after the last transition in the file use the TZ string.
+    let z = Zone::from_name("Europe/Berlin").unwrap();
+    let summer = Instant::from_ticks(
+        calendar_iso8601::to_cjd(2050, 7, 1) as i64 * tick::DAY);
+    assert_eq!(z.abbreviation_at(summer), "CEST");
+    assert_eq!(z.offset_at(summer), tick::HOUR * 2);
+}
+
+#[cfg(test)]
+fn available_names() {
+    let names = Zone::available_names();
+    if !Path::new(ZONEINFO_DIR).join("Europe/Berlin").exists() {
+        return;
+    }
+    let has = |name: &str| names.iter().any(|n| n.as_slice() == name);
+    assert!(has("Europe/Berlin"));
+    assert!(has("America/Argentina/Buenos_Aires"));
+    assert!(!has("zone.tab"));
+    assert!(!names.iter().any(|n| n.as_slice().starts_with("posix/")));
+    assert!(names.as_slice().windows(2).all(|w| w[0] < w[1]));
+}
+
+#[cfg(test)]
+fn version() {
+    let version = tzdb_version();
+    if !Path::new(ZONEINFO_DIR).join("tzdata.zi").exists() {
+        return;
+    }
+    let version = version.unwrap();
+    assert!(version.as_slice().len() >= 5);
+    assert!(version.as_slice().starts_with("20"));
+}
+
+/// Test that the ZONEINFO environment variable selects the database.
+#[cfg(test)]
+fn zoneinfo_variable() {
+    use std::io;
+    let dir = os::tmpdir().join(format!("datetime-zoneinfo-{}",
+                                        os::getpid()));
+    fs::mkdir_recursive(&dir.join("Test"), io::UserRWX).unwrap();
+    File::create(&dir.join("Test/Berlin"))
+        .write(test_tzif().as_slice()).unwrap();
+    File::create(&dir.join("+VERSION")).write_str("2099z\n").unwrap();
+    let old = os::getenv("ZONEINFO");
+    os::setenv("ZONEINFO", dir.as_str().unwrap());
+    let zone = Zone::from_name("Test/Berlin");
+    let names = Zone::available_names();
+    let version = tzdb_version();
+    match old {
+        Some(d) => os::setenv("ZONEINFO", d.as_slice()),
+        None => os::unsetenv("ZONEINFO")
+    }
+    fs::rmdir_recursive(&dir).unwrap();
+    let zone = zone.unwrap();
+    assert_eq!(zone.name(), "Test/Berlin");
+    assert_eq!(*zone.source(),
+               System(dir.join("Test/Berlin").display().to_string()));
+    assert_eq!(names, vec![String::from_str("Test/Berlin")]);
+    assert_eq!(version, Some(String::from_str("2099z")));
+}
+
+#[test]
+fn database() {
+    // These read the ZONEINFO environment variable, which one of them
+    // changes, so they cannot run in parallel.
+    names();
+    available_names();
+    version();
+    zoneinfo_variable();
+}
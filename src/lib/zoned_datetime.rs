@@ -0,0 +1,104 @@
+//! Instants in a time zone.
+//!
+//! A ZonedDateTime follows the rules of its zone, so its offset from UTC
+//! is whatever offset the zone has at its instant.  Use an OffsetDateTime
+//! to keep a fixed offset instead.
+
+use std::fmt::{Show, Formatter, FormatError};
+use instant::Instant;
+use datetime::DateTime;
+use offset_datetime::OffsetDateTime;
+use zone::Zone;
+
+/// An instant with the zone used to display it.
+#[deriving(PartialEq, Eq, Clone)]
+pub struct ZonedDateTime<'a> {
+    instant: Instant,
+    zone: &'a Zone
+}
+
+impl<'a> ZonedDateTime<'a> {
+    /// Create a value from an instant and a zone.
+    pub fn new(instant: Instant, zone: &'a Zone) -> ZonedDateTime<'a> {
+        ZonedDateTime { instant: instant, zone: zone }
+    }
+
+    /// Get the instant.
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+
+    /// Get the zone.
+    pub fn zone(&self) -> &'a Zone {
+        self.zone
+    }
+
+    /// Get the offset from UTC in effect, in ticks.
+    pub fn offset(&self) -> i64 {
+        self.zone.offset_at(self.instant)
+    }
+
+    /// Test whether daylight saving time is in effect, as given by the
+    /// tm_isdst field of the C struct tm.
+    pub fn is_dst(&self) -> bool {
+        self.zone.is_dst_at(self.instant)
+    }
+
+    /// Get the same instant with the offset currently in effect.  Returns
+    /// None if the local time is out of range, or if the offset is not a
+    /// whole number of seconds, which zones read from the tz database
+    /// never have.
+    pub fn to_offset_datetime(&self) -> Option<OffsetDateTime> {
+        OffsetDateTime::new(self.instant, self.offset())
+    }
+
+    /// Get the local date and time.  Returns None if the local time is out
+    /// of range.
+    pub fn to_datetime(&self) -> Option<DateTime> {
+        self.to_offset_datetime().map(|t| t.to_datetime())
+    }
+}
+
+impl<'a> Show for ZonedDateTime<'a> {
+    /// Uses the ISO 8601 extended format with the offset, followed by the
+    /// name of the zone in brackets, such as
+    /// "2014-06-10T13:12:13+02:00[Europe/Berlin]".
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        match self.to_offset_datetime() {
+            Some(t) => write!(f, "{}[{}]", t, self.zone.name()),
+            None => write!(f, "{}[{}]", self.instant, self.zone.name())
+        }
+    }
+}
+
+#[test]
+fn dst() {
+    use tick;
+    use zone;
+    let z = zone::test_zone();
+    let winter = ZonedDateTime::new(from_str("2014-01-10T12:00:00Z").unwrap(),
+                                    &z);
+    let summer = ZonedDateTime::new(from_str("2014-06-10T12:00:00Z").unwrap(),
+                                    &z);
+    assert!(!winter.is_dst());
+    assert!(summer.is_dst());
+    assert_eq!(summer.offset(), tick::HOUR * 2);
+    assert_eq!(format!("{}", winter).as_slice(),
+               "2014-01-10T13:00:00+01:00[Test/Berlin]");
+    assert_eq!(format!("{}", summer).as_slice(),
+               "2014-06-10T14:00:00+02:00[Test/Berlin]");
+    assert_eq!(summer.to_datetime(),
+               Some(DateTime::new(2014, 6, 10, 14, 0, 0, 0)));
+}
+
+#[test]
+fn local_mean_time() {
+    use tick;
+    use zone::Zone;
+    // Amsterdam used local mean time, 00:19:32 ahead of UTC, until 1937.
+    let z = Zone::fixed("LMT", tick::MINUTE * 19 + tick::SECOND * 32);
+    let t = ZonedDateTime::new(from_str("1900-01-01T00:00:00Z").unwrap(), &z);
+    assert!(t.to_offset_datetime().is_some());
+    assert_eq!(format!("{}", t).as_slice(),
+               "1900-01-01T00:19:32+00:19:32[LMT]");
+}